@@ -1,15 +1,36 @@
 //! Main application coordinator
 
-use crate::clipboard::spawn_clipboard_thread;
-use crate::settings::Settings;
-use crate::tray::{process_menu_event, MenuAction, TrayManager};
-use crate::tts::{spawn_tts_thread, TTSCommand, TTSEvent};
+use crate::audio::{EqBand, OutputLevel};
+use crate::clipboard::{self, read_text, spawn_clipboard_thread, ClipboardEvent, CopyLimits};
+use crate::dialog;
+use crate::drop_window::{self, DropWindow};
+use crate::ducking::Ducker;
+use crate::filter::TextFilter;
+use crate::hotkey::{spawn_hotkey_thread, Hotkey, HotkeyAction, HotkeyThread};
+use crate::icon::{taskbar_dpi, IconAlert};
+use crate::instance::spawn_instance_window;
+use crate::player_window::{self, PlayerAction, PlayerState};
+use crate::reading::ReadingList;
+use crate::settings::{find_model, recordings_dir, voices_dir, HotkeySettings, Settings};
+use crate::settings_window;
+use crate::source::{foreground_app, AppFilter};
+use crate::startup;
+use crate::tail::LogTail;
+use crate::toast;
+use crate::tray::{double_click_time, process_menu_event, ClickAction, MenuAction, TrayManager};
+use crate::tts::{spawn_tts_thread, CrashedEngine, TTSCommand, TTSEvent};
+use crate::update;
+use crate::voice_info::{describe_voice, describe_voices};
+use crate::wheel::{spawn_wheel_thread, IconArea};
 use anyhow::Result;
 use muda::MenuEvent;
-use std::sync::atomic::{AtomicBool, Ordering};
+use tray_icon::{MouseButton, MouseButtonState, TrayIconEvent};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::mpsc::{self, TryRecvError};
-use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
 use winit::application::ApplicationHandler;
 use winit::event::WindowEvent;
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
@@ -18,152 +39,2004 @@ use winit::window::WindowId;
 /// Animation frame interval (120ms = ~8.3 FPS)
 const ANIMATION_INTERVAL: Duration = Duration::from_millis(120);
 
+/// Most recently seen source apps offered in the tray, besides those already listed
+const RECENT_APPS: usize = 8;
+
+/// How often the foreground app is checked against the quiet apps
+const FOCUS_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often the taskbar theme and display scaling are checked, to keep the icon
+/// readable and sharp on it
+const TASKBAR_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often the settings file is checked for changes made outside the app
+const SETTINGS_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Settings problems listed in their notification; the log has them all
+const MAX_PROBLEMS_SHOWN: usize = 3;
+
+/// How long the same error isn't notified of again
+const ERROR_NOTIFY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Volume change per notch of the mouse wheel over the tray icon, in percent
+const VOLUME_STEP: i32 = 5;
+
+/// How long the tooltip shows the volume after it's changed with the wheel
+const VOLUME_FLASH: Duration = Duration::from_millis(1500);
+
+/// How long the tray icon flashes after an error
+const ERROR_FLASH: Duration = Duration::from_secs(5);
+
+/// Times the TTS engine is restarted after crashing before giving up
+const MAX_TTS_RESTARTS: u32 = 3;
+
+/// How long a restarted TTS engine has to run for its crashes to be forgotten
+const TTS_STABLE_AFTER: Duration = Duration::from_secs(10 * 60);
+
 /// Main application state
 pub struct App {
     settings: Settings,
     tray: Option<TrayManager>,
     tts_tx: mpsc::Sender<TTSCommand>,
     tts_event_rx: mpsc::Receiver<TTSEvent>,
+    /// Kept to hand to a restarted TTS thread
+    tts_event_tx: mpsc::Sender<TTSEvent>,
     monitor_enabled: Arc<AtomicBool>,
-    #[allow(dead_code)]
+    interrupt_on_copy: Arc<AtomicBool>,
+    read_images: Arc<AtomicBool>,
+    ask_first: Arc<AtomicBool>,
+    collect_copies: Arc<AtomicBool>,
+    /// Copies collected to be spoken together
+    collected: Vec<String>,
     is_speaking: Arc<AtomicBool>,
+    /// Level of the speech playing, drives the tray animation
+    output_level: OutputLevel,
     shutdown: Arc<AtomicBool>,
     model_loaded: bool,
+    /// Lowers other applications while speaking, if enabled
+    ducker: Ducker,
+    /// Tooltip shown while idle, naming the engine in use once known
+    ready_tooltip: String,
+    /// When monitoring paused from the tray turns itself back on
+    monitor_paused_until: Option<Instant>,
+    /// Minutes left of the pause, as last shown in the tooltip
+    pause_minutes_shown: u64,
+    /// Quiet app in the foreground, which monitoring is off for
+    quiet_app: Option<String>,
+    /// Part of a long text being spoken, and of how many, for the tooltip
+    speaking_part: Option<(usize, usize)>,
+    /// Whether speech is paused, for the tray's Pause/Resume item
+    speech_paused: bool,
+    /// Texts waiting after the one being spoken
+    queued_texts: usize,
+    /// Quit once the text being spoken is done
+    quit_after_speech: bool,
+    /// Whether speech was paused when a quiet app came to the foreground
+    paused_for_quiet_app: bool,
+    last_focus_check: Instant,
+    last_taskbar_check: Instant,
+    last_settings_check: Instant,
+    /// When the settings file was last seen written, by the app or anyone else
+    settings_modified: Option<SystemTime>,
     last_animation_tick: Instant,
-    _tts_thread: std::thread::JoinHandle<()>,
+    /// When the tray icon was clicked, while waiting to see if it's a double-click
+    pending_click: Option<Instant>,
+    /// The TTS thread, None once it has exited for good
+    tts_thread: Option<JoinHandle<Option<CrashedEngine>>>,
+    /// Restarts since the engine last ran for `TTS_STABLE_AFTER` without crashing
+    tts_restarts: u32,
+    last_tts_restart: Option<Instant>,
+    /// Last error shown in a notification, and when
+    last_error_notified: Option<(String, Instant)>,
+    /// Problems found in the settings file at startup, reported once the tray is up
+    settings_problems: Vec<String>,
     _clipboard_thread: std::thread::JoinHandle<()>,
+    /// Thread waiting for the hotkeys, if any are set
+    hotkey_thread: Option<HotkeyThread>,
+    /// Handed to the hotkey thread, whenever it's started again
+    copying_selection: Arc<AtomicU32>,
+    clipboard_tx: mpsc::Sender<ClipboardEvent>,
+    /// Where the tray icon is while the cursor is over it, for the wheel thread
+    icon_area: Arc<IconArea>,
+    _wheel_thread: Option<JoinHandle<()>>,
+    /// Thread with the window a second launch and other programs signal
+    _instance_thread: Option<JoinHandle<()>>,
+    /// Log file being followed, stopped when dropped
+    log_tail: Option<LogTail>,
+    /// Long texts put aside
+    reading_list: ReadingList,
+    /// Which applications copies are spoken from, shared with the clipboard thread
+    app_filter: Arc<Mutex<AppFilter>>,
+    /// Applications copies came from this session, most recent first
+    recent_apps: Vec<String>,
+    /// Which copied text is spoken, shared with the clipboard thread
+    text_filter: Arc<Mutex<TextFilter>>,
+    /// Which copied files are read, and when copies are repeated or put aside,
+    /// shared with the clipboard thread
+    copy_limits: Arc<Mutex<CopyLimits>>,
+    /// Voices of the engine in use, offered in the settings window
+    voices: Vec<String>,
+    /// The settings window, while it's open
+    settings_window: Option<JoinHandle<()>>,
+    /// The update check, while it runs
+    update_check: Option<JoinHandle<()>>,
+    /// Settings edited in the settings window
+    edited_settings_tx: mpsc::Sender<settings_window::Edit>,
+    edited_settings_rx: mpsc::Receiver<settings_window::Edit>,
+    /// The player window, while it's open
+    player_window: Option<JoinHandle<()>>,
+    /// What the player window shows, shared with it
+    player: Arc<Mutex<PlayerState>>,
+    /// Buttons pressed in the player window
+    player_tx: mpsc::Sender<PlayerAction>,
+    player_rx: mpsc::Receiver<PlayerAction>,
+    /// The drop target, while it's shown
+    drop_window: Option<DropWindow>,
+    /// Text dropped on the drop target
+    dropped_tx: mpsc::Sender<String>,
+    dropped_rx: mpsc::Receiver<String>,
+    clipboard_rx: mpsc::Receiver<ClipboardEvent>,
 }
 
 impl App {
     /// Create a new application instance
-    pub fn new(settings: Settings) -> Result<Self> {
+    pub fn new(mut settings: Settings, settings_problems: Vec<String>) -> Result<Self> {
         // Shared state
-        let monitor_enabled = Arc::new(AtomicBool::new(settings.monitor_enabled));
+        let monitor_enabled = Arc::new(AtomicBool::new(settings.clipboard.monitor_enabled));
+        let interrupt_on_copy = Arc::new(AtomicBool::new(settings.clipboard.interrupt_on_copy));
+        let read_images = Arc::new(AtomicBool::new(settings.clipboard.read_images));
+        let ask_first = Arc::new(AtomicBool::new(settings.clipboard.ask_before_speaking));
+        let collect_copies = Arc::new(AtomicBool::new(settings.clipboard.collect_copies));
+        let copying_selection = Arc::new(AtomicU32::new(0));
         let is_speaking = Arc::new(AtomicBool::new(false));
+        let output_level = OutputLevel::default();
+        let app_filter = Arc::new(Mutex::new(settings.app_filter()));
+        let text_filter = Arc::new(Mutex::new(settings.text_filter()));
+        let copy_limits = Arc::new(Mutex::new(settings.copy_limits()));
         let shutdown = Arc::new(AtomicBool::new(false));
 
-        // Channels
-        let (tts_tx, tts_rx) = mpsc::channel::<TTSCommand>();
-        let (tts_event_tx, tts_event_rx) = mpsc::channel::<TTSEvent>();
+        // Channels
+        let (tts_tx, tts_rx) = mpsc::channel::<TTSCommand>();
+        let (tts_event_tx, tts_event_rx) = mpsc::channel::<TTSEvent>();
+        let (clipboard_tx, clipboard_rx) = mpsc::channel::<ClipboardEvent>();
+        let (edited_settings_tx, edited_settings_rx) = mpsc::channel::<settings_window::Edit>();
+        let (player_tx, player_rx) = mpsc::channel::<PlayerAction>();
+        let (dropped_tx, dropped_rx) = mpsc::channel::<String>();
+
+        // Spawn TTS thread
+        let tts_thread = spawn_tts_thread(
+            settings.speech.current_model.clone(),
+            settings.speech.current_voice.clone(),
+            settings.text_options(),
+            settings.speech.voice_prosody.clone(),
+            Arc::clone(&is_speaking),
+            output_level.clone(),
+            VecDeque::new(),
+            tts_rx,
+            tts_event_tx.clone(),
+        );
+        send_engine_settings(&tts_tx, &settings);
+
+        // Spawn clipboard monitor thread
+        let clipboard_thread = spawn_clipboard_thread(
+            Arc::clone(&monitor_enabled),
+            Arc::clone(&interrupt_on_copy),
+            Arc::clone(&read_images),
+            Arc::clone(&ask_first),
+            Arc::clone(&collect_copies),
+            Arc::clone(&copying_selection),
+            Arc::clone(&shutdown),
+            tts_tx.clone(),
+            Arc::clone(&copy_limits),
+            Arc::clone(&app_filter),
+            Arc::clone(&text_filter),
+            clipboard_tx.clone(),
+        );
+
+        // Listen for the hotkeys
+        let hotkey_thread = start_hotkeys(&settings.hotkeys, &copying_selection, &tts_tx, &clipboard_tx);
+
+        // Watch the mouse wheel over the tray icon, for the volume
+        let icon_area = Arc::new(IconArea::default());
+        let wheel_thread = spawn_wheel_thread(Arc::clone(&icon_area), clipboard_tx.clone())
+            .map_err(|e| log::warn!("Mouse wheel over the tray icon not available: {}", e))
+            .ok();
+
+        // Hear from launches of the executable while it's running, and from other
+        // programs sending commands
+        let instance_thread = spawn_instance_window(clipboard_tx.clone())
+            .map_err(|e| log::warn!("A second launch can't reach this one: {}", e))
+            .ok();
+
+        // Point the Run key at this executable, in case it was moved
+        if settings.ui.start_with_windows {
+            if let Err(e) = startup::set_enabled(true) {
+                log::warn!("Failed to update starting with Windows: {}", e);
+            }
+        }
+
+        // Keep following the log file from last time, if it's still there
+        let log_tail = settings.clipboard.follow_log.clone().and_then(|path| {
+            LogTail::start(path.clone(), tts_tx.clone())
+                .map_err(|e| log::warn!("Failed to follow {}: {}", path.display(), e))
+                .ok()
+        });
+        if log_tail.is_none() {
+            settings.clipboard.follow_log = None;
+        }
+
+        Ok(Self {
+            settings,
+            tray: None,
+            tts_tx,
+            tts_event_rx,
+            tts_event_tx,
+            monitor_enabled,
+            interrupt_on_copy,
+            read_images,
+            ask_first,
+            collect_copies,
+            collected: Vec::new(),
+            is_speaking,
+            output_level,
+            shutdown,
+            model_loaded: false,
+            ducker: Ducker::new(),
+            ready_tooltip: "Pocket-Tray TTS - Ready".to_string(),
+            monitor_paused_until: None,
+            pause_minutes_shown: 0,
+            quiet_app: None,
+            speaking_part: None,
+            speech_paused: false,
+            queued_texts: 0,
+            quit_after_speech: false,
+            paused_for_quiet_app: false,
+            last_focus_check: Instant::now(),
+            last_taskbar_check: Instant::now(),
+            last_settings_check: Instant::now(),
+            settings_modified: Settings::modified(),
+            last_animation_tick: Instant::now(),
+            pending_click: None,
+            tts_thread: Some(tts_thread),
+            tts_restarts: 0,
+            last_tts_restart: None,
+            last_error_notified: None,
+            settings_problems,
+            _clipboard_thread: clipboard_thread,
+            hotkey_thread,
+            copying_selection,
+            clipboard_tx,
+            icon_area,
+            _wheel_thread: wheel_thread,
+            _instance_thread: instance_thread,
+            log_tail,
+            reading_list: ReadingList::load_or_default(),
+            app_filter,
+            recent_apps: Vec::new(),
+            text_filter,
+            copy_limits,
+            voices: Vec::new(),
+            settings_window: None,
+            update_check: None,
+            edited_settings_tx,
+            edited_settings_rx,
+            player_window: None,
+            player: Arc::new(Mutex::new(PlayerState::default())),
+            player_tx,
+            player_rx,
+            drop_window: None,
+            dropped_tx,
+            dropped_rx,
+            clipboard_rx,
+        })
+    }
+
+    /// Run the application event loop
+    pub fn run(mut self) -> Result<()> {
+        let event_loop = EventLoop::new()?;
+        // Use Poll mode for animation ticking
+        event_loop.set_control_flow(ControlFlow::Poll);
+
+        event_loop.run_app(&mut self)?;
+
+        Ok(())
+    }
+
+    /// Handle menu events
+    fn handle_menu_event(&mut self, event: &MenuEvent) {
+        self.handle_action(process_menu_event(event));
+    }
+
+    /// Carry out a tray menu action, or the same change made in the settings window
+    fn handle_action(&mut self, action: MenuAction) {
+        match action {
+            MenuAction::ToggleMonitor => self.set_monitor(!(self.settings.clipboard.monitor_enabled && self.monitor_paused_until.is_none())),
+            MenuAction::PauseMonitoring(minutes) => {
+                log::info!("Monitoring paused for {} minutes", minutes);
+                self.monitor_paused_until = Some(Instant::now() + Duration::from_secs(minutes as u64 * 60));
+                self.apply_monitoring();
+                if let Some(tray) = &self.tray {
+                    tray.set_monitor_checked(false);
+                }
+                self.show_idle_tooltip();
+            }
+            MenuAction::ToggleInterrupt => self.set_interrupt(!self.interrupt_on_copy.load(Ordering::SeqCst)),
+            MenuAction::ToggleReadImages => self.set_read_images(!self.read_images.load(Ordering::SeqCst)),
+            MenuAction::ToggleCollect => self.set_collect(!self.collect_copies.load(Ordering::SeqCst)),
+            MenuAction::SpeakCollected => self.speak_collected(),
+            MenuAction::ClearCollected => {
+                log::info!("Collected copies cleared");
+                self.collected.clear();
+                self.collected_changed();
+            }
+            MenuAction::ToggleAskFirst => self.set_ask_first(!self.ask_first.load(Ordering::SeqCst)),
+            MenuAction::ToggleDucking => self.set_ducking(!self.settings.audio.duck_others),
+            MenuAction::ToggleSkim => self.set_skim(!self.settings.speech.skim_long_texts),
+            MenuAction::ReadLastInFull => {
+                let _ = self.tts_tx.send(TTSCommand::ReadLastInFull);
+            }
+            MenuAction::ChangeAppFilter(mode) => {
+                log::info!("Source app filter changed to: {}", mode.label());
+                self.settings.filters.app_filter = mode;
+                self.apply_app_filter();
+                if let Some(tray) = &self.tray {
+                    tray.set_app_filter_checked(mode);
+                }
+            }
+            MenuAction::ToggleSourceApp(app) => {
+                let mut filter = self.settings.app_filter();
+                filter.toggle(&app);
+                log::info!(
+                    "{} {} the source app list",
+                    app,
+                    if filter.is_listed(&app) { "added to" } else { "removed from" }
+                );
+                self.settings.filters.denied_apps = filter.denied;
+                self.settings.filters.allowed_apps = filter.allowed;
+                self.apply_app_filter();
+            }
+            MenuAction::Stop => {
+                log::info!("Stop requested");
+                let _ = self.tts_tx.send(TTSCommand::Stop);
+                self.ducker.restore();
+                // Stop animation immediately
+                if let Some(tray) = &mut self.tray {
+                    tray.stop_animation();
+                }
+            }
+            MenuAction::TogglePause => {
+                if self.speech_paused {
+                    log::info!("Resume requested");
+                    let _ = self.tts_tx.send(TTSCommand::Resume);
+                } else {
+                    log::info!("Pause requested");
+                    let _ = self.tts_tx.send(TTSCommand::Pause);
+                }
+            }
+            MenuAction::NextSentence => {
+                log::info!("Next sentence requested");
+                let _ = self.tts_tx.send(TTSCommand::NextSentence);
+            }
+            MenuAction::PreviousSentence => {
+                log::info!("Previous sentence requested");
+                let _ = self.tts_tx.send(TTSCommand::PreviousSentence);
+            }
+            MenuAction::ClearQueue => {
+                log::info!("Clear queue requested");
+                let _ = self.tts_tx.send(TTSCommand::ClearQueue);
+            }
+            MenuAction::MoveQueuedToFront(id) => {
+                log::info!("Move queued text to front requested");
+                let _ = self.tts_tx.send(TTSCommand::MoveQueuedToFront { id });
+            }
+            MenuAction::RemoveQueued(id) => {
+                log::info!("Remove queued text requested");
+                let _ = self.tts_tx.send(TTSCommand::RemoveQueued { id });
+            }
+            MenuAction::OpenFile => {
+                log::info!("Open text file requested");
+                self.speak_text_file();
+            }
+            MenuAction::SaveLast => {
+                log::info!("Save last speech requested");
+                match recordings_dir() {
+                    Ok(dir) => {
+                        let timestamp = std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .map(|d| d.as_secs())
+                            .unwrap_or_default();
+                        let format = self.settings.audio.export_format;
+                        let path = dir.join(format!("speech-{}.{}", timestamp, format.extension()));
+                        let _ = self.tts_tx.send(TTSCommand::SaveLast { path, format });
+                    }
+                    Err(e) => log::warn!("Failed to locate recordings directory: {}", e),
+                }
+            }
+            MenuAction::ToggleFollowLog => {
+                self.toggle_follow_log();
+            }
+            MenuAction::ReadFromStart(id) => {
+                self.read(id, false);
+            }
+            MenuAction::ResumeReading(id) => {
+                self.read(id, true);
+            }
+            MenuAction::DeleteReading(id) => {
+                log::info!("Deleted reading list text {}", id);
+                self.reading_list.remove(id);
+                self.reading_list_changed();
+            }
+            MenuAction::ToggleArchive => self.set_archive(!self.settings.audio.archive_enabled),
+            MenuAction::ToggleStartWithWindows => self.set_start_with_windows(!self.settings.ui.start_with_windows),
+            MenuAction::CheckForUpdates => {
+                log::info!("Update check requested");
+                self.check_for_updates(true);
+            }
+            MenuAction::ClearCache => {
+                log::info!("Clear audio cache requested");
+                let _ = self.tts_tx.send(TTSCommand::ClearCache);
+            }
+            MenuAction::ChangeExportFormat(format) => {
+                log::info!("Export format changed to: {}", format.label());
+                self.settings.audio.export_format = format;
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                let _ = self.tts_tx.send(TTSCommand::SetArchive(self.settings.archive()));
+                if let Some(tray) = &self.tray {
+                    tray.set_export_format_checked(format);
+                }
+            }
+            MenuAction::ChangeChime(mode) => {
+                log::info!("Chime changed to: {}", mode.label());
+                self.settings.audio.chime = mode;
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                let _ = self.tts_tx.send(TTSCommand::SetChimes(self.settings.chimes()));
+                if let Some(tray) = &self.tray {
+                    tray.set_chime_checked(mode);
+                }
+            }
+            MenuAction::ChangeAudioLatency(latency) => {
+                log::info!("Audio latency changed to: {}", latency.label());
+                self.settings.audio.audio_latency = latency;
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                let _ = self.tts_tx.send(TTSCommand::SetAudioLatency(latency));
+                if let Some(tray) = &self.tray {
+                    tray.set_audio_latency_checked(latency);
+                }
+            }
+            MenuAction::ChangeOutputPreset(preset) => {
+                log::info!(
+                    "Output changed to: {}",
+                    preset.as_deref().unwrap_or("System Default")
+                );
+                self.settings.audio.output_preset = preset;
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                let _ = self.tts_tx.send(TTSCommand::SetOutputDevice(self.settings.output_device()));
+                if let Some(tray) = &self.tray {
+                    tray.set_output_preset_checked(self.settings.audio.output_preset.as_deref());
+                }
+            }
+            MenuAction::ChangeIconColor(color) => {
+                log::info!("Icon color changed to: {}", color.as_deref().unwrap_or("taskbar"));
+                self.settings.ui.icon_color = color;
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                if let Some(tray) = &mut self.tray {
+                    if let Err(e) = tray.set_icon_color(self.settings.icon_color()) {
+                        log::warn!("Failed to recolor the tray icon: {}", e);
+                    }
+                    tray.set_icon_color_checked(self.settings.ui.icon_color.as_deref());
+                }
+            }
+            MenuAction::ChangeDevice(device) => {
+                log::info!("Device changed to: {}", device.label());
+                self.settings.speech.device = device;
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                let _ = self.tts_tx.send(TTSCommand::SetDevice(device));
+                if let Some(tray) = &self.tray {
+                    tray.set_device_checked(device);
+                }
+            }
+            MenuAction::ChangePrecision(precision) => {
+                log::info!("Precision changed to: {}", precision.label());
+                self.settings.speech.precision = precision;
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                let _ = self.tts_tx.send(TTSCommand::SetPrecision(precision));
+                if let Some(tray) = &self.tray {
+                    tray.set_precision_checked(precision);
+                }
+            }
+            MenuAction::ChangeUnloadAfter(minutes) => {
+                log::info!("Unload model when idle: {} minutes", minutes);
+                self.settings.speech.unload_after_minutes = minutes;
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                let _ = self
+                    .tts_tx
+                    .send(TTSCommand::SetIdleUnload(self.settings.idle_unload()));
+                if let Some(tray) = &self.tray {
+                    tray.set_unload_after_checked(minutes);
+                }
+            }
+            MenuAction::ChangePitch(semitones) => {
+                log::info!("Pitch changed to: {} semitones", semitones);
+                self.settings.audio.pitch_semitones = semitones;
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                let _ = self.tts_tx.send(TTSCommand::SetPitch(semitones));
+                if let Some(tray) = &self.tray {
+                    tray.set_pitch_checked(semitones);
+                }
+            }
+            MenuAction::ChangeEqualizer(band, db) => {
+                log::info!("{} changed to: {} dB", band.label(), db);
+                self.settings.audio.equalizer.set(band, db);
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                let _ = self.tts_tx.send(TTSCommand::SetEqualizer(self.settings.audio.equalizer));
+                if let Some(tray) = &self.tray {
+                    tray.set_equalizer_checked(band, db);
+                }
+            }
+            MenuAction::ChangeSoftVoice(mode) => {
+                log::info!("Soft voice changed to: {}", mode.label());
+                self.settings.audio.soft_voice = mode;
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                let _ = self.tts_tx.send(TTSCommand::SetSoftVoice(self.settings.soft_schedule()));
+                if let Some(tray) = &self.tray {
+                    tray.set_soft_voice_checked(mode);
+                }
+            }
+            MenuAction::ChangeUrlMode(mode) => {
+                log::info!("Link handling changed to: {}", mode.label());
+                self.settings.speech.url_mode = mode;
+                self.apply_text_options();
+                if let Some(tray) = &self.tray {
+                    tray.set_url_mode_checked(mode);
+                }
+            }
+            MenuAction::ChangeEmojiMode(mode) => {
+                log::info!("Emoji handling changed to: {}", mode.label());
+                self.settings.speech.emoji_mode = mode;
+                self.apply_text_options();
+                if let Some(tray) = &self.tray {
+                    tray.set_emoji_mode_checked(mode);
+                }
+            }
+            MenuAction::ChangeCodeMode(mode) => {
+                log::info!("Code handling changed to: {}", mode.label());
+                self.settings.speech.code_mode = mode;
+                self.apply_text_options();
+                if let Some(tray) = &self.tray {
+                    tray.set_code_mode_checked(mode);
+                }
+            }
+            MenuAction::ChangeListMode(mode) => {
+                log::info!("List pacing changed to: {}", mode.label());
+                self.settings.speech.list_mode = mode;
+                self.apply_text_options();
+                if let Some(tray) = &self.tray {
+                    tray.set_list_mode_checked(mode);
+                }
+            }
+            MenuAction::ChangeJsonMode(mode) => {
+                log::info!("JSON handling changed to: {}", mode.label());
+                self.settings.speech.json_mode = mode;
+                self.apply_text_options();
+                if let Some(tray) = &self.tray {
+                    tray.set_json_mode_checked(mode);
+                }
+            }
+            MenuAction::ChangeVoice(voice) => {
+                log::info!("Voice change requested: {}", voice);
+                let previous = std::mem::replace(&mut self.settings.speech.current_voice, voice.clone());
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                let _ = self.tts_tx.send(TTSCommand::ChangeVoice { voice: voice.clone() });
+                // A hidden voice is only listed while it's selected
+                let hidden = |name: &str| self.settings.speech.hidden_voices.iter().any(|hidden| hidden == name);
+                if hidden(&previous) || hidden(&voice) {
+                    self.show_voices(&voice);
+                } else if let Some(tray) = &mut self.tray {
+                    tray.set_voice_checked(&voice);
+                }
+            }
+            MenuAction::ChangeVoiceGrouping(grouping) => {
+                log::info!("Voices grouped by: {}", grouping.label());
+                self.settings.ui.voice_grouping = grouping;
+                if let Err(e) = self.settings.save() {
+                    log::warn!("Failed to save settings: {}", e);
+                }
+                if let Some(tray) = &mut self.tray {
+                    if let Err(e) = tray.set_voice_grouping(grouping) {
+                        log::warn!("Failed to update voices menu: {}", e);
+                    }
+                }
+            }
+            MenuAction::ChangeModel(model) => {
+                log::info!("Model change requested: {}", model);
+                if model == self.settings.speech.current_model && self.model_loaded {
+                    if let Some(tray) = &self.tray {
+                        tray.set_model_checked(&model);
+                    }
+                    return;
+                }
+                if let Some(tray) = &self.tray {
+                    // Keep the old selection checked until the new model has loaded
+                    tray.set_model_checked(&self.settings.speech.current_model);
+                    tray.set_tooltip(&format!("Pocket-Tray TTS - Loading model {}...", model));
+                }
+                let _ = self.tts_tx.send(TTSCommand::ChangeModel { model });
+            }
+            MenuAction::PreviewVoice(voice) => {
+                log::info!("Voice preview requested: {}", voice);
+                let _ = self.tts_tx.send(TTSCommand::PreviewVoice { voice });
+            }
+            MenuAction::ReplayLastWith(voice) => {
+                log::info!("Replay with voice {} requested", voice);
+                let _ = self.tts_tx.send(TTSCommand::ReplayLastWith { voice });
+            }
+            MenuAction::AddVoice => {
+                log::info!("Add voice from audio requested");
+                self.add_voice_from_audio();
+            }
+            MenuAction::OpenSettings => {
+                self.open_settings_window();
+            }
+            MenuAction::ShowPlayer => {
+                self.open_player_window();
+            }
+            MenuAction::ToggleDropWindow => self.set_drop_window(!self.settings.ui.drop_window),
+            MenuAction::Quit => {
+                log::info!("Quit requested");
+                self.quit();
+            }
+            MenuAction::Unknown => {}
+        }
+    }
+
+    /// Turn monitoring on or off, ending any pause
+    fn set_monitor(&mut self, enabled: bool) {
+        self.settings.clipboard.monitor_enabled = enabled;
+        // Turning monitoring back on ends a pause early
+        if self.monitor_paused_until.take().is_some() {
+            self.show_idle_tooltip();
+        }
+        self.apply_monitoring();
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        if let Some(tray) = &self.tray {
+            tray.set_monitor_checked(enabled);
+            let status = if enabled { "ON" } else { "OFF" };
+            log::info!("Monitor toggled: {}", status);
+        }
+    }
+
+    /// Turn interrupting speech with new copies on or off
+    fn set_interrupt(&mut self, enabled: bool) {
+        self.interrupt_on_copy.store(enabled, Ordering::SeqCst);
+        self.settings.clipboard.interrupt_on_copy = enabled;
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        if let Some(tray) = &self.tray {
+            tray.set_interrupt_checked(enabled);
+        }
+        log::info!("Interrupt on new copy: {}", if enabled { "ON" } else { "OFF" });
+    }
+
+    /// Turn reading the text in copied images on or off
+    fn set_read_images(&mut self, enabled: bool) {
+        self.read_images.store(enabled, Ordering::SeqCst);
+        self.settings.clipboard.read_images = enabled;
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        if let Some(tray) = &self.tray {
+            tray.set_read_images_checked(enabled);
+        }
+        log::info!("Read text in copied images: {}", if enabled { "ON" } else { "OFF" });
+    }
+
+    /// Turn collecting copies on or off
+    fn set_collect(&mut self, enabled: bool) {
+        self.collect_copies.store(enabled, Ordering::SeqCst);
+        self.settings.clipboard.collect_copies = enabled;
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        if let Some(tray) = &self.tray {
+            tray.set_collect_checked(enabled);
+        }
+        log::info!("Collect copies: {}", if enabled { "ON" } else { "OFF" });
+    }
+
+    /// Turn asking before speaking each copy on or off
+    fn set_ask_first(&mut self, enabled: bool) {
+        self.ask_first.store(enabled, Ordering::SeqCst);
+        self.settings.clipboard.ask_before_speaking = enabled;
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        if let Some(tray) = &self.tray {
+            tray.set_ask_first_checked(enabled);
+        }
+        log::info!("Ask before speaking: {}", if enabled { "ON" } else { "OFF" });
+    }
+
+    /// Turn lowering other audio while speaking on or off
+    fn set_ducking(&mut self, enabled: bool) {
+        self.settings.audio.duck_others = enabled;
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        if let Some(tray) = &self.tray {
+            tray.set_duck_checked(enabled);
+        }
+        if !enabled {
+            self.ducker.restore();
+        }
+        log::info!("Lower other audio while speaking: {}", if enabled { "ON" } else { "OFF" });
+    }
+
+    /// Turn skimming long texts on or off
+    fn set_skim(&mut self, enabled: bool) {
+        self.settings.speech.skim_long_texts = enabled;
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        let _ = self.tts_tx.send(TTSCommand::SetSkim(enabled));
+        if let Some(tray) = &self.tray {
+            tray.set_skim_checked(enabled);
+        }
+        log::info!("Skim long texts: {}", if enabled { "ON" } else { "OFF" });
+    }
+
+    /// Turn archiving everything spoken on or off
+    fn set_archive(&mut self, enabled: bool) {
+        self.settings.audio.archive_enabled = enabled;
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        let _ = self.tts_tx.send(TTSCommand::SetArchive(self.settings.archive()));
+        if let Some(tray) = &self.tray {
+            tray.set_archive_checked(enabled);
+        }
+        log::info!("Archive everything spoken: {}", if enabled { "ON" } else { "OFF" });
+    }
+
+    /// Turn starting with Windows on or off
+    fn set_start_with_windows(&mut self, enabled: bool) {
+        if let Err(e) = startup::set_enabled(enabled) {
+            log::warn!("Failed to change starting with Windows: {}", e);
+            if let Some(tray) = &self.tray {
+                tray.set_start_with_windows_checked(self.settings.ui.start_with_windows);
+            }
+            return;
+        }
+        self.settings.ui.start_with_windows = enabled;
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        if let Some(tray) = &self.tray {
+            tray.set_start_with_windows_checked(enabled);
+        }
+        log::info!("Start with Windows: {}", if enabled { "ON" } else { "OFF" });
+    }
+
+    /// Show or hide the drop target
+    fn set_drop_window(&mut self, enabled: bool) {
+        self.settings.ui.drop_window = enabled;
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        self.show_drop_window(enabled);
+        log::info!("Drop target: {}", if enabled { "ON" } else { "OFF" });
+    }
+
+    /// Quit, first asking whether to finish the text being spoken if there is one
+    /// Asked again while waiting for that, it quits at once
+    fn quit(&mut self) {
+        let busy = self.is_speaking.load(Ordering::SeqCst) || self.queued_texts > 0;
+        if busy && !self.quit_after_speech {
+            let answer = dialog::ask(
+                "Pocket-Tray - Quit",
+                "Speech is still playing.\n\n\
+                 Yes: finish the current text, then quit\n\
+                 No: quit now\n\
+                 Cancel: keep running",
+            );
+            match answer {
+                Some(true) => {
+                    // Checked again, it may have finished while the box was open
+                    if self.is_speaking.load(Ordering::SeqCst) {
+                        log::info!("Quitting once the current text is done");
+                        self.quit_after_speech = true;
+                        let _ = self.tts_tx.send(TTSCommand::ClearQueueAfterCurrent);
+                        if let Some(tray) = &self.tray {
+                            tray.set_tooltip("Pocket-Tray TTS - Quitting after this text...");
+                        }
+                        return;
+                    }
+                }
+                Some(false) => {}
+                None => {
+                    log::info!("Quit cancelled");
+                    return;
+                }
+            }
+        }
+        self.shutdown.store(true, Ordering::SeqCst);
+        let _ = self.tts_tx.send(TTSCommand::Shutdown);
+    }
+
+    /// Save settings and send the current text processing options to the TTS thread
+    fn apply_text_options(&mut self) {
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        let _ = self
+            .tts_tx
+            .send(TTSCommand::SetTextOptions(self.settings.text_options()));
+    }
+
+    /// Ask for a text file and speak its contents
+    fn speak_text_file(&mut self) {
+        let Some(path) = dialog::open_file("Open Text File", dialog::TEXT_FILE_FILTER) else {
+            return;
+        };
+
+        let text = match std::fs::read(&path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).trim().to_string(),
+            Err(e) => {
+                log::error!("Failed to read {}: {}", path.display(), e);
+                if let Some(tray) = &self.tray {
+                    tray.set_tooltip(&format!("Pocket-Tray TTS - Error: Cannot read {}", path.display()));
+                }
+                return;
+            }
+        };
+
+        if text.is_empty() {
+            log::warn!("File is empty: {}", path.display());
+            return;
+        }
+
+        log::info!("Speaking file {} ({} chars)", path.display(), text.len());
+        let _ = self.tts_tx.send(TTSCommand::Speak { text, voice: None });
+    }
+
+    /// Read a reading list text from the start, or from where it was left
+    fn read(&mut self, id: u64, resume: bool) {
+        let Some(item) = self.reading_list.get(id) else {
+            return;
+        };
+        let from_part = if resume { item.part } else { 0 };
+        log::info!("Reading '{}' from part {}", item.title, from_part + 1);
+        let _ = self.tts_tx.send(TTSCommand::Read {
+            id,
+            text: item.text.clone(),
+            from_part,
+        });
+    }
+
+    /// Turn monitoring back on once a pause is over, and keep the time left in the tooltip
+    fn check_monitor_pause(&mut self) {
+        let Some(until) = self.monitor_paused_until else {
+            return;
+        };
+        let now = Instant::now();
+        if now >= until {
+            log::info!("Monitoring pause over");
+            self.monitor_paused_until = None;
+            self.settings.clipboard.monitor_enabled = true;
+            self.apply_monitoring();
+            if let Err(e) = self.settings.save() {
+                log::warn!("Failed to save settings: {}", e);
+            }
+            if let Some(tray) = &self.tray {
+                tray.set_monitor_checked(true);
+            }
+            self.show_idle_tooltip();
+        } else if minutes_left(until - now) != self.pause_minutes_shown {
+            self.show_idle_tooltip();
+        }
+    }
+
+    /// Watch copies unless monitoring is off, paused, or a quiet app is in front
+    /// The tray icon is greyed out whenever copies aren't spoken
+    fn apply_monitoring(&mut self) {
+        let enabled =
+            self.settings.clipboard.monitor_enabled && self.monitor_paused_until.is_none() && self.quiet_app.is_none();
+        self.monitor_enabled.store(enabled, Ordering::SeqCst);
+        if let Some(tray) = &mut self.tray {
+            if let Err(e) = tray.set_monitoring(enabled) {
+                log::warn!("Failed to redraw the tray icon: {}", e);
+            }
+        }
+    }
+
+    /// Redraw the tray icon in a contrasting color when the taskbar switches
+    /// between the light and dark theme, unless the icon has a color of its own,
+    /// and in another size when the display scaling changes
+    /// The DPI change messages go to windows, which the tray icon isn't, so the
+    /// scaling is read again like the theme
+    fn check_taskbar(&mut self) {
+        if self.last_taskbar_check.elapsed() < TASKBAR_CHECK_INTERVAL {
+            return;
+        }
+        self.last_taskbar_check = Instant::now();
+
+        if let Some(tray) = &mut self.tray {
+            if let Err(e) = tray.set_icon_color(self.settings.icon_color()) {
+                log::warn!("Failed to recolor the tray icon: {}", e);
+            }
+            if let Err(e) = tray.set_icon_dpi(taskbar_dpi()) {
+                log::warn!("Failed to resize the tray icon: {}", e);
+            }
+        }
+    }
+
+    /// Go quiet while one of the quiet apps is in the foreground, pausing speech,
+    /// and pick up again once focus leaves it
+    fn check_quiet_apps(&mut self) {
+        if self.settings.filters.quiet_apps.is_empty() && self.quiet_app.is_none() {
+            return;
+        }
+        if self.last_focus_check.elapsed() < FOCUS_CHECK_INTERVAL {
+            return;
+        }
+        self.last_focus_check = Instant::now();
+
+        let quiet_app = foreground_app().filter(|app| {
+            self.settings
+                .filters.quiet_apps
+                .iter()
+                .any(|quiet| quiet.eq_ignore_ascii_case(app))
+        });
+        if quiet_app == self.quiet_app {
+            return;
+        }
+
+        match &quiet_app {
+            Some(app) => {
+                log::info!("{} is in front, staying quiet", app);
+                if self.quiet_app.is_none() && self.is_speaking.load(Ordering::SeqCst) {
+                    let _ = self.tts_tx.send(TTSCommand::Pause);
+                    self.paused_for_quiet_app = true;
+                }
+            }
+            None => {
+                log::info!("Quiet app left, monitoring again");
+                if std::mem::take(&mut self.paused_for_quiet_app) {
+                    let _ = self.tts_tx.send(TTSCommand::Resume);
+                }
+            }
+        }
+        self.quiet_app = quiet_app;
+        self.apply_monitoring();
+        self.show_idle_tooltip();
+    }
+
+    /// Tooltip while nothing is being spoken: ready, how long monitoring stays
+    /// paused, or which quiet app has it off
+    fn idle_tooltip(&mut self) -> String {
+        if let Some(app) = &self.quiet_app {
+            return format!("Pocket-Tray TTS - Quiet while {} is in front", app);
+        }
+        match self.monitor_paused_until {
+            Some(until) => {
+                self.pause_minutes_shown = minutes_left(until.saturating_duration_since(Instant::now()));
+                format!(
+                    "Pocket-Tray TTS - Monitoring paused ({} min left)",
+                    self.pause_minutes_shown
+                )
+            }
+            None => self.ready_tooltip.clone(),
+        }
+    }
+
+    /// Show the idle tooltip, unless speech is playing
+    fn show_idle_tooltip(&mut self) {
+        let tooltip = self.idle_tooltip();
+        if let Some(tray) = &self.tray {
+            if !tray.is_animating() {
+                tray.set_tooltip(&tooltip);
+            }
+        }
+    }
+
+    /// Ask about long texts the clipboard thread passed on, and note the apps copies
+    /// came from
+    fn check_clipboard_events(&mut self) {
+        let mut added = false;
+        let mut apps_changed = false;
+        while let Ok(event) = self.clipboard_rx.try_recv() {
+            match event {
+                ClipboardEvent::LongText { text, voice } => {
+                    if self.ask_read_later(&text) {
+                        log::info!("Long text added to the reading list");
+                        self.reading_list.add(text);
+                        added = true;
+                    } else {
+                        self.take_copy(text, voice);
+                    }
+                }
+                ClipboardEvent::BigFiles { paths, size, voice } => {
+                    if !clipboard::confirm_files(&paths, size, "Copied") {
+                        continue;
+                    }
+                    // Their text goes the way of any other copy
+                    let text = clipboard::read_files(&paths, "Copied");
+                    if text.is_empty() || !clipboard::text_filter_allows(&self.text_filter, &text) {
+                        continue;
+                    }
+                    let reading_threshold = self.settings.clipboard.reading_list_chars;
+                    if reading_threshold > 0 && text.chars().count() > reading_threshold && self.ask_read_later(&text) {
+                        log::info!("Long text added to the reading list");
+                        self.reading_list.add(text);
+                        added = true;
+                    } else {
+                        self.take_copy(text, voice);
+                    }
+                }
+                ClipboardEvent::Collected(text) => {
+                    self.collected.push(text);
+                    self.collected_changed();
+                }
+                ClipboardEvent::SpeakCollected => self.speak_collected(),
+                ClipboardEvent::NextVoice => self.next_voice(),
+                ClipboardEvent::Stop => self.handle_action(MenuAction::Stop),
+                ClipboardEvent::ToggleMonitor => self.handle_action(MenuAction::ToggleMonitor),
+                ClipboardEvent::Quit => self.handle_action(MenuAction::Quit),
+                ClipboardEvent::ShowPlayer => self.handle_action(MenuAction::ShowPlayer),
+                ClipboardEvent::Wheel(notches) => self.change_volume(notches * VOLUME_STEP),
+                ClipboardEvent::SpeakClipboard => self.speak_clipboard(),
+                ClipboardEvent::Speak { text, now: true } => {
+                    let _ = self.tts_tx.send(TTSCommand::SpeakNow { text, voice: None });
+                }
+                ClipboardEvent::Speak { text, now: false } => {
+                    let _ = self.tts_tx.send(TTSCommand::Speak { text, voice: None });
+                }
+                ClipboardEvent::TogglePause => self.handle_action(MenuAction::TogglePause),
+                ClipboardEvent::ChangeVoice(voice) => {
+                    // Named by another program, so matched loosely; any name goes until
+                    // the voices have loaded
+                    let known = self.voices.iter().find(|known| known.eq_ignore_ascii_case(&voice)).cloned();
+                    match known {
+                        Some(voice) => self.handle_action(MenuAction::ChangeVoice(voice)),
+                        None if self.voices.is_empty() => self.handle_action(MenuAction::ChangeVoice(voice)),
+                        None => log::warn!("No voice '{}' to switch to", voice),
+                    }
+                }
+                ClipboardEvent::Copied { app } => {
+                    if self.recent_apps.first().is_some_and(|recent| recent.eq_ignore_ascii_case(&app)) {
+                        continue;
+                    }
+                    self.recent_apps.retain(|recent| !recent.eq_ignore_ascii_case(&app));
+                    self.recent_apps.insert(0, app);
+                    self.recent_apps.truncate(RECENT_APPS);
+                    apps_changed = true;
+                }
+            }
+        }
+        if added {
+            self.reading_list_changed();
+        }
+        if apps_changed {
+            self.source_apps_changed();
+        }
+    }
+
+    /// Whether a long copied text is to go on the reading list instead of being read now
+    fn ask_read_later(&self, text: &str) -> bool {
+        let message = format!(
+            "The copied text is {} characters long.\n\nAdd it to the reading list to read later, instead of reading it now?",
+            text.chars().count()
+        );
+        dialog::confirm("Pocket-Tray - Long Text", &message)
+    }
+
+    /// Collect or speak a copy the clipboard thread left to this one; it's been asked
+    /// about already, so it isn't asked about again in a notification
+    fn take_copy(&mut self, text: String, voice: Option<String>) {
+        if self.collect_copies.load(Ordering::SeqCst) {
+            log::info!("Copy collected");
+            self.collected.push(text);
+            self.collected_changed();
+        } else if self.interrupt_on_copy.load(Ordering::SeqCst) {
+            let _ = self.tts_tx.send(TTSCommand::SpeakNow { text, voice });
+        } else {
+            let _ = self.tts_tx.send(TTSCommand::Speak { text, voice });
+        }
+    }
+
+    /// Speak the collected copies as one text, and start collecting afresh
+    fn speak_collected(&mut self) {
+        if self.collected.is_empty() {
+            log::info!("No copies collected to speak");
+            return;
+        }
+        log::info!("Speaking {} collected copies", self.collected.len());
+        let text = std::mem::take(&mut self.collected).join("\n\n");
+        let _ = self.tts_tx.send(TTSCommand::SpeakNow { text, voice: None });
+        self.collected_changed();
+    }
+
+    /// Speak whatever text is on the clipboard now, even if it was spoken before
+    fn speak_clipboard(&mut self) {
+        let text = arboard::Clipboard::new()
+            .ok()
+            .and_then(|mut clipboard| read_text(&mut clipboard))
+            .filter(|text| !text.is_empty());
+        match text {
+            Some(text) => {
+                log::info!("Speaking the clipboard");
+                let _ = self.tts_tx.send(TTSCommand::SpeakNow { text, voice: None });
+            }
+            None => log::info!("No text on the clipboard to speak"),
+        }
+    }
+
+    /// Switch to the voice after the current one, going round to the first, and
+    /// say its name
+    fn next_voice(&mut self) {
+        if self.voices.is_empty() {
+            log::info!("No voices loaded yet to switch between");
+            return;
+        }
+        let current = &self.settings.speech.current_voice;
+        let voices: Vec<&String> = self
+            .voices
+            .iter()
+            .filter(|voice| self.settings.is_voice_shown(voice, current))
+            .collect();
+        let next = voices
+            .iter()
+            .position(|voice| *voice == current)
+            .map_or(0, |i| (i + 1) % voices.len());
+        let voice = voices[next].clone();
+        self.handle_action(MenuAction::ChangeVoice(voice.clone()));
+        let _ = self.tts_tx.send(TTSCommand::AnnounceVoice { voice });
+    }
+
+    /// List the voices of the engine in the tray, with `selected` checked
+    fn show_voices(&mut self, selected: &str) {
+        let voices = describe_voices(&self.voices, &self.settings.speech.current_model);
+        let voices = self.settings.menu_voices(voices, selected);
+        if let Some(tray) = &mut self.tray {
+            if let Err(e) = tray.set_voices(&voices, selected) {
+                log::warn!("Failed to update voices menu: {}", e);
+            }
+        }
+    }
+
+    /// Show how many copies are collected in the tray
+    fn collected_changed(&self) {
+        if let Some(tray) = &self.tray {
+            tray.set_collected_count(self.collected.len());
+        }
+    }
+
+    /// Run the click and double-click actions; a click waits out the double-click
+    /// time, so a double-click doesn't also count as a click
+    fn check_tray_clicks(&mut self) {
+        while let Ok(event) = TrayIconEvent::receiver().try_recv() {
+            match event {
+                TrayIconEvent::Click {
+                    button: MouseButton::Left,
+                    button_state: MouseButtonState::Down,
+                    ..
+                } => {
+                    self.pending_click = Some(Instant::now());
+                }
+                TrayIconEvent::DoubleClick {
+                    button: MouseButton::Left,
+                    ..
+                } => {
+                    self.pending_click = None;
+                    self.run_click_action(self.settings.ui.double_click_action);
+                }
+                TrayIconEvent::Enter { rect, .. } | TrayIconEvent::Move { rect, .. } => {
+                    let left = rect.position.x as i32;
+                    let top = rect.position.y as i32;
+                    self.icon_area
+                        .set(Some((left, top, left + rect.size.width as i32, top + rect.size.height as i32)));
+                }
+                TrayIconEvent::Leave { .. } => {
+                    self.icon_area.set(None);
+                }
+                _ => {}
+            }
+        }
+
+        if self.pending_click.is_some_and(|clicked| clicked.elapsed() > double_click_time()) {
+            self.pending_click = None;
+            self.run_click_action(self.settings.ui.click_action);
+        }
+    }
+
+    fn run_click_action(&mut self, action: ClickAction) {
+        match action {
+            // The menu is shown by the tray icon itself
+            ClickAction::None | ClickAction::Menu => {}
+            ClickAction::Replay => {
+                log::info!("Replay requested");
+                let _ = self.tts_tx.send(TTSCommand::ReplayLast);
+            }
+            ClickAction::Stop => self.handle_action(MenuAction::Stop),
+            ClickAction::PauseResume => self.handle_action(MenuAction::TogglePause),
+            ClickAction::ToggleMonitor => self.handle_action(MenuAction::ToggleMonitor),
+        }
+    }
+
+    /// Show an error in a notification, unless the same one was shown just now
+    fn notify_error(&mut self, message: &str) {
+        if let Some((last, at)) = &self.last_error_notified {
+            if last == message && at.elapsed() < ERROR_NOTIFY_INTERVAL {
+                return;
+            }
+        }
+        self.last_error_notified = Some((message.to_string(), Instant::now()));
+        if let Err(e) = toast::show_error(message) {
+            log::warn!("Failed to show error notification: {}", e);
+        }
+    }
+
+    /// Log problems with the settings file, and show them in a notification
+    fn report_settings_problems(&mut self, problems: &[String]) {
+        if problems.is_empty() {
+            return;
+        }
+        for problem in problems {
+            log::warn!("Settings: {}", problem);
+        }
+        let mut message = problems
+            .iter()
+            .take(MAX_PROBLEMS_SHOWN)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n");
+        if problems.len() > MAX_PROBLEMS_SHOWN {
+            message.push_str(&format!(
+                "\n...and {} more, see the log",
+                problems.len() - MAX_PROBLEMS_SHOWN
+            ));
+        }
+        if let Err(e) = toast::show_notice("Problems in pocket-tray.json", &message) {
+            log::warn!("Failed to show settings notification: {}", e);
+        }
+    }
+
+    /// Color the tray icon red or amber while something is wrong, or back again with None
+    /// Errors also flash it for a few seconds
+    fn set_icon_alert(&mut self, alert: Option<IconAlert>) {
+        if let Some(tray) = &mut self.tray {
+            if let Err(e) = tray.set_alert(alert) {
+                log::warn!("Failed to redraw the tray icon: {}", e);
+            }
+            // Flashing, so an error during unattended reading isn't missed
+            if alert == Some(IconAlert::Error) {
+                tray.flash_icon(ERROR_FLASH);
+            }
+        }
+    }
+
+    /// Look for a newer release in the background, unless a check is running already
+    /// `manual` checks also tell when there's nothing new
+    fn check_for_updates(&mut self, manual: bool) {
+        if self.update_check.as_ref().is_some_and(|check| !check.is_finished()) {
+            log::info!("Already checking for updates");
+            return;
+        }
+        match update::spawn_update_check(manual) {
+            Ok(check) => self.update_check = Some(check),
+            Err(e) => log::error!("Failed to check for updates: {}", e),
+        }
+    }
+
+    /// Open the settings window, unless it's open already
+    fn open_settings_window(&mut self) {
+        if self.settings_window.as_ref().is_some_and(|window| !window.is_finished()) {
+            log::info!("Settings window is already open");
+            return;
+        }
+        // The voices the menus list
+        let voices = describe_voices(&self.voices, &self.settings.speech.current_model);
+        let voices = self
+            .settings
+            .menu_voices(voices, &self.settings.speech.current_voice)
+            .into_iter()
+            .map(|voice| voice.name)
+            .collect();
+        match settings_window::open(
+            self.settings.clone(),
+            voices,
+            self.edited_settings_tx.clone(),
+        ) {
+            Ok(window) => self.settings_window = Some(window),
+            Err(e) => log::error!("Failed to open the settings window: {}", e),
+        }
+    }
+
+    /// Open the player window, unless it's open already
+    fn open_player_window(&mut self) {
+        if self.player_window.as_ref().is_some_and(|window| !window.is_finished()) {
+            log::info!("Player window is already open");
+            return;
+        }
+        match player_window::open(Arc::clone(&self.player), self.player_tx.clone()) {
+            Ok(window) => self.player_window = Some(window),
+            Err(e) => log::error!("Failed to open the player window: {}", e),
+        }
+    }
 
-        // Spawn TTS thread
-        let tts_thread = spawn_tts_thread(
-            settings.current_voice.clone(),
-            Arc::clone(&is_speaking),
-            tts_rx,
-            tts_event_tx,
-        );
+    /// Act on the buttons pressed in the player window
+    fn check_player_actions(&mut self) {
+        while let Ok(action) = self.player_rx.try_recv() {
+            match action {
+                PlayerAction::PlayPause if self.is_speaking.load(Ordering::SeqCst) => {
+                    self.handle_action(MenuAction::TogglePause)
+                }
+                PlayerAction::PlayPause => self.run_click_action(ClickAction::Replay),
+                PlayerAction::Stop => self.handle_action(MenuAction::Stop),
+                PlayerAction::Skip => self.handle_action(MenuAction::NextSentence),
+            }
+        }
+    }
 
-        // Spawn clipboard monitor thread
-        let clipboard_thread = spawn_clipboard_thread(
-            Arc::clone(&monitor_enabled),
-            Arc::clone(&is_speaking),
-            Arc::clone(&shutdown),
-            tts_tx.clone(),
-        );
+    /// Turn the volume up or down by `percent`, showing the new level in the tooltip
+    fn change_volume(&mut self, percent: i32) {
+        let volume = (self.settings.audio.volume_percent.min(100) as i32 + percent).clamp(0, 100) as u32;
+        if volume != self.settings.audio.volume_percent {
+            self.settings.audio.volume_percent = volume;
+            if let Err(e) = self.settings.save() {
+                log::warn!("Failed to save settings: {}", e);
+            }
+            let _ = self.tts_tx.send(TTSCommand::SetVolume(self.settings.volume()));
+            log::info!("Volume: {}%", volume);
+        }
+        if let Some(tray) = &self.tray {
+            tray.flash_tooltip(&format!("Pocket-Tray TTS - Volume {}%", volume), VOLUME_FLASH);
+        }
+    }
 
-        Ok(Self {
-            settings,
-            tray: None,
-            tts_tx,
-            tts_event_rx,
-            monitor_enabled,
-            is_speaking,
-            shutdown,
-            model_loaded: false,
-            last_animation_tick: Instant::now(),
-            _tts_thread: tts_thread,
-            _clipboard_thread: clipboard_thread,
-        })
+    /// Open or close the drop target
+    fn show_drop_window(&mut self, shown: bool) {
+        if !shown {
+            if let Some(window) = self.drop_window.take() {
+                window.close();
+            }
+        } else if !self.drop_window.as_ref().is_some_and(DropWindow::is_open) {
+            match drop_window::open(self.settings.file_limits(), self.dropped_tx.clone()) {
+                Ok(window) => self.drop_window = Some(window),
+                Err(e) => log::error!("Failed to open the drop target: {}", e),
+            }
+        }
+        if let Some(tray) = &self.tray {
+            tray.set_drop_window_checked(self.drop_window.is_some());
+        }
     }
 
-    /// Run the application event loop
-    pub fn run(mut self) -> Result<()> {
-        let event_loop = EventLoop::new()?;
-        // Use Poll mode for animation ticking
-        event_loop.set_control_flow(ControlFlow::Poll);
+    /// Speak text dropped on the drop target, and notice it being closed from its menu
+    fn check_drop_window(&mut self) {
+        while let Ok(text) = self.dropped_rx.try_recv() {
+            let _ = self.tts_tx.send(TTSCommand::SpeakNow { text, voice: None });
+        }
+        if self.drop_window.as_ref().is_some_and(|window| !window.is_open()) {
+            log::info!("Drop target closed");
+            self.drop_window = None;
+            self.settings.ui.drop_window = false;
+            if let Err(e) = self.settings.save() {
+                log::warn!("Failed to save settings: {}", e);
+            }
+            if let Some(tray) = &self.tray {
+                tray.set_drop_window_checked(false);
+            }
+        }
+    }
 
-        event_loop.run_app(&mut self)?;
+    /// Change what the player window shows
+    fn update_player(&self, update: impl FnOnce(&mut PlayerState)) {
+        if let Ok(mut player) = self.player.lock() {
+            update(&mut player);
+        }
+    }
 
-        Ok(())
+    /// Apply settings changed in the settings window
+    fn check_edited_settings(&mut self) {
+        while let Ok(edit) = self.edited_settings_rx.try_recv() {
+            log::info!("Settings changed in the settings window");
+            self.apply_edited_settings(&edit.opened, edit.edited);
+        }
     }
 
-    /// Handle menu events
-    fn handle_menu_event(&mut self, event: &MenuEvent) {
-        match process_menu_event(event) {
-            MenuAction::ToggleMonitor => {
-                let new_state = !self.monitor_enabled.load(Ordering::SeqCst);
-                self.monitor_enabled.store(new_state, Ordering::SeqCst);
-                self.settings.monitor_enabled = new_state;
-                if let Err(e) = self.settings.save() {
-                    log::warn!("Failed to save settings: {}", e);
-                }
-                if let Some(tray) = &self.tray {
-                    tray.set_monitor_checked(new_state);
-                    let status = if new_state { "ON" } else { "OFF" };
-                    log::info!("Monitor toggled: {}", status);
+    /// Apply the settings the settings window edits, those that differ from `opened`
+    fn apply_edited_settings(&mut self, opened: &Settings, edited: Settings) {
+        // The changes the tray also makes go the same way
+        if edited.speech.current_voice != opened.speech.current_voice {
+            self.handle_action(MenuAction::ChangeVoice(edited.speech.current_voice.clone()));
+        }
+        if edited.speech.device != opened.speech.device {
+            self.handle_action(MenuAction::ChangeDevice(edited.speech.device));
+        }
+        if edited.audio.output_preset != opened.audio.output_preset {
+            self.handle_action(MenuAction::ChangeOutputPreset(edited.audio.output_preset.clone()));
+        }
+        if edited.filters.app_filter != opened.filters.app_filter {
+            self.handle_action(MenuAction::ChangeAppFilter(edited.filters.app_filter));
+        }
+
+        // Voice by voice, so another voice's prosody changed meanwhile stays
+        if edited.speech.voice_prosody != opened.speech.voice_prosody {
+            for voice in opened.speech.voice_prosody.keys() {
+                if !edited.speech.voice_prosody.contains_key(voice) {
+                    self.settings.speech.voice_prosody.remove(voice);
                 }
             }
-            MenuAction::Stop => {
-                log::info!("Stop requested");
-                let _ = self.tts_tx.send(TTSCommand::Stop);
-                // Stop animation immediately
-                if let Some(tray) = &mut self.tray {
-                    tray.stop_animation();
+            for (voice, prosody) in edited.speech.voice_prosody {
+                if opened.speech.voice_prosody.get(&voice) != Some(&prosody) {
+                    self.settings.speech.voice_prosody.insert(voice, prosody);
                 }
             }
-            MenuAction::ChangeVoice(voice) => {
-                log::info!("Voice change requested: {}", voice);
-                self.settings.current_voice = voice.clone();
-                if let Err(e) = self.settings.save() {
-                    log::warn!("Failed to save settings: {}", e);
-                }
-                let _ = self.tts_tx.send(TTSCommand::ChangeVoice { voice: voice.clone() });
-                if let Some(tray) = &self.tray {
-                    tray.set_voice_checked(&voice);
+            let _ = self
+                .tts_tx
+                .send(TTSCommand::SetVoiceProsody(self.settings.speech.voice_prosody.clone()));
+        }
+        if edited.audio.volume_percent != opened.audio.volume_percent {
+            self.settings.audio.volume_percent = edited.audio.volume_percent;
+            let _ = self.tts_tx.send(TTSCommand::SetVolume(self.settings.volume()));
+            log::info!("Volume: {}%", self.settings.audio.volume_percent);
+        }
+
+        let filters = &opened.filters;
+        if edited.filters.exclude_patterns != filters.exclude_patterns
+            || edited.filters.include_patterns != filters.include_patterns
+            || edited.filters.skip_machine_strings != filters.skip_machine_strings
+        {
+            self.settings.filters.exclude_patterns = edited.filters.exclude_patterns;
+            self.settings.filters.include_patterns = edited.filters.include_patterns;
+            self.settings.filters.skip_machine_strings = edited.filters.skip_machine_strings;
+            if let Ok(mut filter) = self.text_filter.lock() {
+                *filter = self.settings.text_filter();
+            }
+        }
+        if edited.filters.quiet_apps != filters.quiet_apps {
+            self.settings.filters.quiet_apps = edited.filters.quiet_apps;
+        }
+        if edited.filters.denied_apps != filters.denied_apps {
+            self.settings.filters.denied_apps = edited.filters.denied_apps;
+        }
+        if edited.filters.allowed_apps != filters.allowed_apps {
+            self.settings.filters.allowed_apps = edited.filters.allowed_apps;
+        }
+
+        let hotkeys = [
+            (&mut self.settings.hotkeys.read_selection_hotkey, edited.hotkeys.read_selection_hotkey, &opened.hotkeys.read_selection_hotkey),
+            (&mut self.settings.hotkeys.speak_collected_hotkey, edited.hotkeys.speak_collected_hotkey, &opened.hotkeys.speak_collected_hotkey),
+            (&mut self.settings.hotkeys.next_voice_hotkey, edited.hotkeys.next_voice_hotkey, &opened.hotkeys.next_voice_hotkey),
+            (&mut self.settings.hotkeys.stop_hotkey, edited.hotkeys.stop_hotkey, &opened.hotkeys.stop_hotkey),
+            (&mut self.settings.hotkeys.monitor_hotkey, edited.hotkeys.monitor_hotkey, &opened.hotkeys.monitor_hotkey),
+            (&mut self.settings.hotkeys.quit_hotkey, edited.hotkeys.quit_hotkey, &opened.hotkeys.quit_hotkey),
+            (&mut self.settings.hotkeys.player_hotkey, edited.hotkeys.player_hotkey, &opened.hotkeys.player_hotkey),
+        ];
+        let mut hotkeys_changed = false;
+        for (current, edited, opened) in hotkeys {
+            if edited != *opened {
+                *current = edited;
+                hotkeys_changed = true;
+            }
+        }
+        // The old hotkeys are unregistered before the new ones are registered
+        if hotkeys_changed {
+            log::info!("Hotkeys changed, registering them again");
+            if let Some(thread) = self.hotkey_thread.take() {
+                thread.stop();
+            }
+            self.hotkey_thread =
+                start_hotkeys(&self.settings.hotkeys, &self.copying_selection, &self.tts_tx, &self.clipboard_tx);
+            if let Some(tray) = &self.tray {
+                tray.set_hotkeys(&self.settings.hotkeys);
+            }
+        }
+
+        // Saves the rest too
+        self.apply_app_filter();
+    }
+
+    /// Apply changes made to the settings file outside the app, e.g. in a text editor
+    fn check_settings_file(&mut self) {
+        if self.last_settings_check.elapsed() < SETTINGS_CHECK_INTERVAL {
+            return;
+        }
+        self.last_settings_check = Instant::now();
+
+        let modified = Settings::modified();
+        if modified == self.settings_modified {
+            return;
+        }
+        self.settings_modified = modified;
+        let reloaded = match Settings::load() {
+            Ok(reloaded) => reloaded,
+            Err(e) => {
+                // The next save is tried again
+                self.report_settings_problems(&[format!(
+                    "The changed settings file couldn't be read ({:#}), so it's ignored",
+                    e
+                )]);
+                return;
+            }
+        };
+        // The app's own saves read back the same
+        if serde_json::to_value(&reloaded).ok() == serde_json::to_value(&self.settings).ok() {
+            return;
+        }
+
+        log::info!("Settings file changed, applying it");
+        let problems = reloaded.problems();
+        self.apply_reloaded_settings(reloaded);
+        self.report_settings_problems(&problems);
+        self.settings_modified = Settings::modified();
+    }
+
+    /// Apply settings read back from the file, the way the same changes made in the
+    /// tray and settings window are
+    fn apply_reloaded_settings(&mut self, reloaded: Settings) {
+        // Switches are set to the file's values, whatever they are now
+        let switches = [
+            (reloaded.clipboard.monitor_enabled, self.settings.clipboard.monitor_enabled, Self::set_monitor as fn(&mut Self, bool)),
+            (reloaded.clipboard.interrupt_on_copy, self.settings.clipboard.interrupt_on_copy, Self::set_interrupt),
+            (reloaded.clipboard.read_images, self.settings.clipboard.read_images, Self::set_read_images),
+            (reloaded.clipboard.ask_before_speaking, self.settings.clipboard.ask_before_speaking, Self::set_ask_first),
+            (reloaded.clipboard.collect_copies, self.settings.clipboard.collect_copies, Self::set_collect),
+            (reloaded.audio.duck_others, self.settings.audio.duck_others, Self::set_ducking),
+            (reloaded.speech.skim_long_texts, self.settings.speech.skim_long_texts, Self::set_skim),
+            (reloaded.audio.archive_enabled, self.settings.audio.archive_enabled, Self::set_archive),
+            (reloaded.ui.start_with_windows, self.settings.ui.start_with_windows, Self::set_start_with_windows),
+            (reloaded.ui.drop_window, self.settings.ui.drop_window, Self::set_drop_window),
+        ];
+        for (value, old, set) in switches {
+            if value != old {
+                set(self, value);
+            }
+        }
+
+        let old = &self.settings;
+        let mut actions: Vec<MenuAction> = [
+            (reloaded.filters.app_filter != old.filters.app_filter, MenuAction::ChangeAppFilter(reloaded.filters.app_filter)),
+            (reloaded.audio.export_format != old.audio.export_format, MenuAction::ChangeExportFormat(reloaded.audio.export_format)),
+            (reloaded.audio.chime != old.audio.chime, MenuAction::ChangeChime(reloaded.audio.chime)),
+            (reloaded.audio.audio_latency != old.audio.audio_latency, MenuAction::ChangeAudioLatency(reloaded.audio.audio_latency)),
+            (reloaded.ui.icon_color != old.ui.icon_color, MenuAction::ChangeIconColor(reloaded.ui.icon_color.clone())),
+            (reloaded.speech.precision != old.speech.precision, MenuAction::ChangePrecision(reloaded.speech.precision)),
+            (
+                reloaded.speech.unload_after_minutes != old.speech.unload_after_minutes,
+                MenuAction::ChangeUnloadAfter(reloaded.speech.unload_after_minutes),
+            ),
+            (reloaded.audio.pitch_semitones != old.audio.pitch_semitones, MenuAction::ChangePitch(reloaded.audio.pitch_semitones)),
+            (reloaded.audio.soft_voice != old.audio.soft_voice, MenuAction::ChangeSoftVoice(reloaded.audio.soft_voice)),
+            (reloaded.speech.url_mode != old.speech.url_mode, MenuAction::ChangeUrlMode(reloaded.speech.url_mode)),
+            (reloaded.speech.emoji_mode != old.speech.emoji_mode, MenuAction::ChangeEmojiMode(reloaded.speech.emoji_mode)),
+            (reloaded.speech.code_mode != old.speech.code_mode, MenuAction::ChangeCodeMode(reloaded.speech.code_mode)),
+            (reloaded.speech.list_mode != old.speech.list_mode, MenuAction::ChangeListMode(reloaded.speech.list_mode)),
+            (reloaded.speech.json_mode != old.speech.json_mode, MenuAction::ChangeJsonMode(reloaded.speech.json_mode)),
+            (reloaded.ui.voice_grouping != old.ui.voice_grouping, MenuAction::ChangeVoiceGrouping(reloaded.ui.voice_grouping)),
+            // The voices in the menu are the new model's once it has loaded
+            (reloaded.speech.current_model != old.speech.current_model, MenuAction::ChangeModel(reloaded.speech.current_model.clone())),
+        ]
+        .into_iter()
+        .filter_map(|(changed, action)| changed.then_some(action))
+        .collect();
+        for band in EqBand::ALL {
+            if reloaded.audio.equalizer.get(band) != old.audio.equalizer.get(band) {
+                actions.push(MenuAction::ChangeEqualizer(band, reloaded.audio.equalizer.get(band)));
+            }
+        }
+        for action in actions {
+            self.handle_action(action);
+        }
+        // Voice, volume, device, output, filters, quiet apps and hotkeys
+        let current = self.settings.clone();
+        self.apply_edited_settings(&current, reloaded.clone());
+
+        if reloaded.clipboard.follow_log != self.settings.clipboard.follow_log {
+            self.log_tail = reloaded.clipboard.follow_log.clone().and_then(|path| {
+                LogTail::start(path.clone(), self.tts_tx.clone())
+                    .map_err(|e| log::warn!("Failed to follow {}: {}", path.display(), e))
+                    .ok()
+            });
+            if let Some(tray) = &self.tray {
+                tray.set_follow_log_checked(self.log_tail.is_some());
+            }
+        }
+        if let Some(tray) = &self.tray {
+            tray.set_click_action(reloaded.ui.click_action);
+        }
+        if let Ok(mut limits) = self.copy_limits.lock() {
+            *limits = reloaded.copy_limits();
+        }
+        if reloaded.ui.log != self.settings.ui.log
+            || serde_json::to_value(&reloaded.audio.output_presets).ok()
+                != serde_json::to_value(&self.settings.audio.output_presets).ok()
+        {
+            log::info!("Output preset and log settings take effect after a restart");
+        }
+        let cache_changed = reloaded.speech.audio_cache_mb != self.settings.speech.audio_cache_mb;
+        let voices_renamed = reloaded.speech.voice_aliases != self.settings.speech.voice_aliases
+            || reloaded.speech.hidden_voices != self.settings.speech.hidden_voices;
+
+        // The rest are read when they're needed, or passed on as at startup; the model
+        // becomes current once it has loaded, and starting with Windows once it's set
+        let current_model = std::mem::take(&mut self.settings.speech.current_model);
+        let start_with_windows = self.settings.ui.start_with_windows;
+        self.settings = reloaded;
+        self.settings.speech.current_model = current_model;
+        self.settings.ui.start_with_windows = start_with_windows;
+        self.settings.clipboard.follow_log = self.log_tail.as_ref().map(|tail| tail.path().to_path_buf());
+        if cache_changed {
+            let _ = self.tts_tx.send(TTSCommand::SetCacheSize(self.settings.speech.audio_cache_mb));
+        }
+        // Until the engine has loaded, the tray lists the voices found on disk
+        if voices_renamed && !self.voices.is_empty() {
+            let current_voice = self.settings.speech.current_voice.clone();
+            self.show_voices(&current_voice);
+        }
+        let _ = self.tts_tx.send(TTSCommand::SetArchive(self.settings.archive()));
+        let _ = self.tts_tx.send(TTSCommand::SetChimes(self.settings.chimes()));
+        let _ = self.tts_tx.send(TTSCommand::SetSoftVoice(self.settings.soft_schedule()));
+        let _ = self.tts_tx.send(TTSCommand::SetNightVolume(self.settings.night_volume()));
+        let _ = self.tts_tx.send(TTSCommand::SetVolume(self.settings.volume()));
+        let _ = self
+            .tts_tx
+            .send(TTSCommand::SetLanguageVoices(self.settings.speech.language_voices.clone()));
+        self.apply_app_filter();
+    }
+
+    /// Save settings, hand the app filter to the clipboard thread and show it in the tray
+    fn apply_app_filter(&mut self) {
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        if let Ok(mut filter) = self.app_filter.lock() {
+            *filter = self.settings.app_filter();
+        }
+        self.source_apps_changed();
+    }
+
+    /// Show the recently seen apps, and those on the current list, in the tray
+    fn source_apps_changed(&mut self) {
+        let filter = self.settings.app_filter();
+        let mut apps = self.recent_apps.clone();
+        for app in filter.listed() {
+            if !apps.iter().any(|seen| seen.eq_ignore_ascii_case(app)) {
+                apps.push(app.clone());
+            }
+        }
+        if let Some(tray) = &mut self.tray {
+            if let Err(e) = tray.set_source_apps(&apps, &filter) {
+                log::warn!("Failed to update source apps menu: {}", e);
+            }
+        }
+    }
+
+    /// Save the reading list and show it in the tray
+    fn reading_list_changed(&mut self) {
+        if let Err(e) = self.reading_list.save() {
+            log::warn!("Failed to save reading list: {}", e);
+        }
+        if let Some(tray) = &mut self.tray {
+            if let Err(e) = tray.set_reading_list(self.reading_list.items()) {
+                log::warn!("Failed to update reading list menu: {}", e);
+            }
+        }
+    }
+
+    /// Stop following the log file, or ask for one and start following it
+    fn toggle_follow_log(&mut self) {
+        if self.log_tail.is_some() {
+            // Dropping it stops the thread
+            self.log_tail = None;
+        } else if let Some(path) = dialog::open_file("Follow Log File", dialog::LOG_FILE_FILTER) {
+            match LogTail::start(path.clone(), self.tts_tx.clone()) {
+                Ok(tail) => self.log_tail = Some(tail),
+                Err(e) => {
+                    log::error!("Failed to follow {}: {}", path.display(), e);
+                    if let Some(tray) = &self.tray {
+                        tray.set_tooltip(&format!("Pocket-Tray TTS - Error: Cannot read {}", path.display()));
+                    }
                 }
             }
-            MenuAction::Quit => {
-                log::info!("Quit requested");
-                self.shutdown.store(true, Ordering::SeqCst);
-                let _ = self.tts_tx.send(TTSCommand::Shutdown);
+        }
+
+        self.settings.clipboard.follow_log = self.log_tail.as_ref().map(|tail| tail.path().to_path_buf());
+        if let Err(e) = self.settings.save() {
+            log::warn!("Failed to save settings: {}", e);
+        }
+        // Clicking the item toggles its check mark, whatever happened
+        if let Some(tray) = &self.tray {
+            tray.set_follow_log_checked(self.log_tail.is_some());
+        }
+    }
+
+    /// Ask for a WAV file, keep a copy in the voices directory and clone a voice from it
+    fn add_voice_from_audio(&mut self) {
+        let Some(source) = dialog::open_file("Add Voice from Audio", dialog::AUDIO_FILE_FILTER) else {
+            return;
+        };
+
+        let dir = match voices_dir() {
+            Ok(dir) => dir,
+            Err(e) => {
+                log::warn!("Failed to locate voices directory: {}", e);
+                return;
             }
-            MenuAction::Unknown => {}
+        };
+
+        // Name the voice after the file, keeping it usable as a menu ID and file name
+        let stem = source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let base: String = stem
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        let base = if base.is_empty() { "custom".to_string() } else { base };
+
+        let taken: Vec<String> = find_model(&self.settings.speech.current_model)
+            .map(|model| model.voices())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while taken.contains(&name) || dir.join(format!("{}.wav", name)).exists() {
+            name = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+
+        let path = dir.join(format!("{}.wav", name));
+        let copied = std::fs::create_dir_all(&dir).and_then(|_| std::fs::copy(&source, &path));
+        if let Err(e) = copied {
+            log::error!("Failed to copy {}: {}", source.display(), e);
+            if let Some(tray) = &self.tray {
+                tray.set_tooltip(&format!("Pocket-Tray TTS - Error: Cannot read {}", source.display()));
+            }
+            return;
+        }
+
+        log::info!("Cloning voice '{}' from {}", name, source.display());
+        if let Some(tray) = &self.tray {
+            tray.set_tooltip("Pocket-Tray TTS - Cloning voice...");
         }
+        let _ = self.tts_tx.send(TTSCommand::AddVoice { name, path });
     }
 
     /// Check for TTS events and update UI
     fn check_tts_events(&mut self) {
         loop {
             match self.tts_event_rx.try_recv() {
-                Ok(TTSEvent::ModelLoaded) => {
-                    log::info!("Model loaded, ready for TTS");
-                    self.model_loaded = true;
+                Ok(TTSEvent::ModelLoading { model }) => {
+                    log::info!("Loading model '{}'", model);
+                    self.model_loaded = false;
+                    if let Some(tray) = &mut self.tray {
+                        tray.set_tooltip(&format!("Pocket-Tray TTS - Loading model {}...", model));
+                        tray.start_loading();
+                        self.last_animation_tick = Instant::now();
+                    }
+                }
+                Ok(TTSEvent::ModelUnloaded) => {
+                    log::info!("Model unloaded to free memory");
+                    self.model_loaded = false;
                     if let Some(tray) = &self.tray {
-                        tray.set_tooltip("Pocket-Tray TTS - Ready");
+                        tray.set_active_device(None);
+                    }
+                }
+                Ok(TTSEvent::ModelLoaded {
+                    model,
+                    voices,
+                    voice,
+                    device,
+                }) => {
+                    log::info!("Model '{}' loaded on {}, ready for TTS", model, device.label());
+                    self.model_loaded = true;
+                    self.voices = voices.clone();
+                    // The engine falls back to its first voice for one the model lacks
+                    if self.settings.speech.current_model == model
+                        && !voices.contains(&self.settings.speech.current_voice)
+                    {
+                        self.report_settings_problems(&[format!(
+                            "current_voice: model '{}' has no voice '{}', so '{}' is used",
+                            model, self.settings.speech.current_voice, voice
+                        )]);
+                    }
+                    if self.settings.speech.current_model != model || self.settings.speech.current_voice != voice {
+                        self.settings.speech.current_model = model.clone();
+                        self.settings.speech.current_voice = voice.clone();
+                        if let Err(e) = self.settings.save() {
+                            log::warn!("Failed to save settings: {}", e);
+                        }
+                    }
+                    self.ready_tooltip = format!("Pocket-Tray TTS - Ready ({})", device.label());
+                    let tooltip = self.idle_tooltip();
+                    self.show_voices(&voice);
+                    if let Some(tray) = &mut self.tray {
+                        tray.set_model_checked(&model);
+                        tray.set_active_device(Some(device));
+                        tray.set_tooltip(&tooltip);
+                        tray.stop_loading();
+                    }
+                    self.set_icon_alert(None);
+                }
+                Ok(TTSEvent::FallbackLoaded { voices, voice }) => {
+                    log::warn!("Model unavailable, speaking with Windows voices");
+                    self.voices = voices;
+                    self.model_loaded = false;
+                    self.ready_tooltip = "Pocket-Tray TTS - Ready (Windows voices, model not loaded)".to_string();
+                    let tooltip = self.idle_tooltip();
+                    self.show_voices(&voice);
+                    if let Some(tray) = &mut self.tray {
+                        tray.set_active_device(None);
+                        tray.set_tooltip(&tooltip);
+                        tray.stop_loading();
                     }
+                    self.set_icon_alert(Some(IconAlert::Warning));
                 }
                 Ok(TTSEvent::StartedSpeaking) => {
                     log::info!("Started speaking - starting animation");
+                    self.duck_others();
+                    self.speaking_part = None;
+                    self.update_player(|player| {
+                        *player = PlayerState {
+                            speaking: true,
+                            ..PlayerState::default()
+                        }
+                    });
                     if let Some(tray) = &mut self.tray {
                         tray.set_tooltip("Pocket-Tray TTS - Speaking...");
                         tray.start_animation();
                         self.last_animation_tick = Instant::now();
                     }
                 }
-                Ok(TTSEvent::FinishedSpeaking) => {
+                Ok(TTSEvent::Progress { part, total, reading }) => {
+                    log::info!("Speaking part {} of {}", part, total);
+                    self.speaking_part = Some((part, total));
+                    if let Some(tray) = &self.tray {
+                        tray.set_tooltip(&format!("Pocket-Tray TTS - Speaking part {} of {}...", part, total));
+                    }
+                    // Resuming restarts the part being read
+                    if let Some(id) = reading {
+                        self.reading_list.set_progress(id, part - 1, total);
+                        self.reading_list_changed();
+                    }
+                }
+                Ok(TTSEvent::Playing {
+                    paragraph,
+                    paragraphs,
+                    remaining,
+                    sentence,
+                    progress,
+                }) => {
+                    if let Some(tray) = &self.tray {
+                        tray.set_tooltip(&playing_tooltip(self.speaking_part, paragraph, paragraphs, remaining));
+                    }
+                    // Across all parts of a long text
+                    let progress = match self.speaking_part {
+                        Some((part, total)) => (part - 1) as f32 / total as f32 + progress / total as f32,
+                        None => progress,
+                    };
+                    self.update_player(|player| {
+                        player.sentence = sentence;
+                        player.progress = progress;
+                    });
+                    // Speech playing again clears an error, but not running on Windows voices
+                    if self.tray.as_ref().is_some_and(|tray| tray.alert() == Some(IconAlert::Error)) {
+                        self.set_icon_alert(None);
+                    }
+                }
+                Ok(TTSEvent::ReadingFinished { id }) => {
+                    log::info!("Finished reading list text {}", id);
+                    self.reading_list.remove(id);
+                    self.reading_list_changed();
+                }
+                Ok(TTSEvent::Paused) => {
+                    log::info!("Speech paused - freezing animation");
+                    self.ducker.restore();
+                    self.speech_paused = true;
+                    self.update_player(|player| player.paused = true);
+                    if let Some(tray) = &mut self.tray {
+                        tray.set_paused(true);
+                        tray.set_tooltip("Pocket-Tray TTS - Paused");
+                        tray.pause_animation();
+                    }
+                }
+                Ok(TTSEvent::Resumed) => {
+                    log::info!("Speech resumed - resuming animation");
+                    self.duck_others();
+                    self.speech_paused = false;
+                    self.update_player(|player| player.paused = false);
+                    if let Some(tray) = &mut self.tray {
+                        tray.set_paused(false);
+                        tray.set_tooltip("Pocket-Tray TTS - Speaking...");
+                        tray.resume_animation();
+                        self.last_animation_tick = Instant::now();
+                    }
+                }
+                Ok(TTSEvent::FinishedSpeaking { more_parts }) => {
                     log::info!("Finished speaking - stopping animation");
+                    // Other audio stays ducked between the parts of a long text
+                    if !more_parts {
+                        self.ducker.restore();
+                    }
+                    self.speech_paused = false;
+                    self.update_player(|player| *player = PlayerState::default());
+                    let tooltip = self.idle_tooltip();
                     if let Some(tray) = &mut self.tray {
-                        tray.set_tooltip("Pocket-Tray TTS - Ready");
+                        tray.set_paused(false);
+                        tray.set_tooltip(&tooltip);
                         tray.stop_animation();
                     }
+                    // Not before the last part of a long text
+                    if self.quit_after_speech && !more_parts {
+                        self.quit();
+                    }
+                }
+                Ok(TTSEvent::QueueChanged(texts)) => {
+                    self.queued_texts = texts.len();
+                    if let Some(tray) = &mut self.tray {
+                        if let Err(e) = tray.set_queue(&texts) {
+                            log::warn!("Failed to update queue menu: {}", e);
+                        }
+                    }
+                }
+                Ok(TTSEvent::VoiceAdded(name)) => {
+                    log::info!("Voice added: {}", name);
+                    self.voices.push(name.clone());
+                    self.settings.speech.current_voice = name.clone();
+                    if let Err(e) = self.settings.save() {
+                        log::warn!("Failed to save settings: {}", e);
+                    }
+                    let tooltip = self.idle_tooltip();
+                    if let Some(tray) = &mut self.tray {
+                        let voice = self.settings.menu_voice(describe_voice(&name, &self.settings.speech.current_model));
+                        if let Err(e) = tray.add_voice(voice) {
+                            log::warn!("Failed to add voice to menu: {}", e);
+                        }
+                        tray.set_voice_checked(&name);
+                        tray.set_tooltip(&tooltip);
+                    }
                 }
                 Ok(TTSEvent::Error(e)) => {
                     log::error!("TTS error: {}", e);
+                    self.ducker.restore();
+                    self.update_player(|player| *player = PlayerState::default());
                     if let Some(tray) = &mut self.tray {
                         tray.set_tooltip(&format!("Pocket-Tray TTS - Error: {}", e));
+                        tray.stop_loading();
                         tray.stop_animation();
                     }
+                    self.set_icon_alert(Some(IconAlert::Error));
+                    self.notify_error(&e);
                 }
                 Err(TryRecvError::Empty) => break,
                 Err(TryRecvError::Disconnected) => {
@@ -174,13 +2047,115 @@ impl App {
         }
     }
 
+    /// Lower other applications' volume, if enabled
+    fn duck_others(&mut self) {
+        if !self.settings.audio.duck_others {
+            return;
+        }
+        if let Err(e) = self.ducker.duck(self.settings.duck_level()) {
+            log::warn!("Failed to lower other audio: {}", e);
+        }
+    }
+
+    /// Restart the TTS thread if it crashed, with the same voice and the texts it hadn't spoken
+    fn check_tts_thread(&mut self) {
+        if !self.tts_thread.as_ref().is_some_and(|thread| thread.is_finished()) {
+            return;
+        }
+        let Some(thread) = self.tts_thread.take() else {
+            return;
+        };
+        if self.shutdown.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let crashed = match thread.join() {
+            Ok(Some(crashed)) => crashed,
+            Ok(None) => {
+                log::error!("TTS engine stopped");
+                return;
+            }
+            Err(_) => {
+                log::error!("TTS engine crashed and can't be restarted");
+                if let Some(tray) = &mut self.tray {
+                    tray.set_tooltip("Pocket-Tray TTS - Error: Speech engine crashed, please restart");
+                    tray.stop_loading();
+                    tray.stop_animation();
+                }
+                self.set_icon_alert(Some(IconAlert::Error));
+                self.notify_error("Speech engine crashed, please restart");
+                return;
+            }
+        };
+
+        self.model_loaded = false;
+        self.ducker.restore();
+        self.speech_paused = false;
+        self.update_player(|player| *player = PlayerState::default());
+        if let Some(tray) = &mut self.tray {
+            tray.stop_loading();
+            tray.stop_animation();
+            tray.set_paused(false);
+            tray.set_active_device(None);
+            // The new engine reports the queue once it's speaking again
+            if let Err(e) = tray.set_queue(&[]) {
+                log::warn!("Failed to update queue menu: {}", e);
+            }
+        }
+        self.queued_texts = 0;
+        self.set_icon_alert(Some(IconAlert::Error));
+
+        // Crashes far apart don't add up to giving up
+        if self.last_tts_restart.is_some_and(|at| at.elapsed() >= TTS_STABLE_AFTER) {
+            self.tts_restarts = 0;
+        }
+        if self.tts_restarts >= MAX_TTS_RESTARTS {
+            log::error!("TTS engine crashed {} times, not restarting", self.tts_restarts + 1);
+            let message = format!("Speech engine keeps crashing ({}), please restart", crashed.error);
+            if let Some(tray) = &self.tray {
+                tray.set_tooltip(&format!("Pocket-Tray TTS - Error: {}", message));
+            }
+            self.notify_error(&message);
+            return;
+        }
+        self.tts_restarts += 1;
+        self.last_tts_restart = Some(Instant::now());
+
+        log::warn!(
+            "Restarting TTS engine with voice '{}' and {} queued texts",
+            crashed.voice,
+            crashed.queue.len()
+        );
+        if let Some(tray) = &self.tray {
+            tray.set_tooltip(&format!(
+                "Pocket-Tray TTS - Error: Speech engine crashed ({}), restarted",
+                crashed.error
+            ));
+        }
+
+        // Sent before the new engine starts, so it's set up before it speaks the texts
+        // handed over to it
+        send_engine_settings(&self.tts_tx, &self.settings);
+        self.tts_thread = Some(spawn_tts_thread(
+            self.settings.speech.current_model.clone(),
+            crashed.voice,
+            self.settings.text_options(),
+            self.settings.speech.voice_prosody.clone(),
+            Arc::clone(&self.is_speaking),
+            self.output_level.clone(),
+            crashed.queue,
+            crashed.cmd_rx,
+            self.tts_event_tx.clone(),
+        ));
+    }
+
     /// Update animation if needed
     fn tick_animation(&mut self) {
         if let Some(tray) = &mut self.tray {
             if tray.is_animating() {
                 let now = Instant::now();
                 if now.duration_since(self.last_animation_tick) >= ANIMATION_INTERVAL {
-                    tray.tick_animation();
+                    tray.tick_animation(self.output_level.get());
                     self.last_animation_tick = now;
                 }
             }
@@ -192,11 +2167,25 @@ impl ApplicationHandler for App {
     fn resumed(&mut self, _event_loop: &ActiveEventLoop) {
         // Create tray icon when the application is ready
         if self.tray.is_none() {
-            match TrayManager::new(self.settings.monitor_enabled, &self.settings.current_voice) {
-                Ok(tray) => {
-                    tray.set_tooltip("Pocket-Tray TTS - Loading model...");
+            match TrayManager::new(&self.settings) {
+                Ok(mut tray) => {
+                    if let Err(e) = tray.set_reading_list(self.reading_list.items()) {
+                        log::warn!("Failed to fill reading list menu: {}", e);
+                    }
+                    // The model loads on first use
+                    tray.set_tooltip("Pocket-Tray TTS - Ready");
                     self.tray = Some(tray);
+                    self.apply_monitoring();
+                    self.source_apps_changed();
                     log::info!("Tray icon created");
+                    if self.settings.ui.check_for_updates {
+                        self.check_for_updates(false);
+                    }
+                    if self.settings.ui.drop_window {
+                        self.show_drop_window(true);
+                    }
+                    let problems = std::mem::take(&mut self.settings_problems);
+                    self.report_settings_problems(&problems);
                 }
                 Err(e) => {
                     log::error!("Failed to create tray icon: {}", e);
@@ -223,8 +2212,23 @@ impl ApplicationHandler for App {
             self.handle_menu_event(&event);
         }
 
+        self.check_tray_clicks();
+        if let Some(tray) = &mut self.tray {
+            tray.end_tooltip_flash();
+            tray.tick_icon_flash();
+        }
+
         // Check for TTS events
         self.check_tts_events();
+        self.check_tts_thread();
+        self.check_clipboard_events();
+        self.check_edited_settings();
+        self.check_settings_file();
+        self.check_player_actions();
+        self.check_drop_window();
+        self.check_monitor_pause();
+        self.check_quiet_apps();
+        self.check_taskbar();
 
         // Tick animation if active
         self.tick_animation();
@@ -242,3 +2246,76 @@ impl ApplicationHandler for App {
         }
     }
 }
+
+/// Start the thread listening for the hotkeys set, None if none are or it can't start
+fn start_hotkeys(
+    settings: &HotkeySettings,
+    copying_selection: &Arc<AtomicU32>,
+    tts_tx: &mpsc::Sender<TTSCommand>,
+    clipboard_tx: &mpsc::Sender<ClipboardEvent>,
+) -> Option<HotkeyThread> {
+    let hotkeys: Vec<(HotkeyAction, Hotkey)> = [
+        (HotkeyAction::ReadSelection, &settings.read_selection_hotkey),
+        (HotkeyAction::SpeakCollected, &settings.speak_collected_hotkey),
+        (HotkeyAction::NextVoice, &settings.next_voice_hotkey),
+        (HotkeyAction::Stop, &settings.stop_hotkey),
+        (HotkeyAction::ToggleMonitor, &settings.monitor_hotkey),
+        (HotkeyAction::Quit, &settings.quit_hotkey),
+        (HotkeyAction::ShowPlayer, &settings.player_hotkey),
+    ]
+    .into_iter()
+    .filter_map(|(action, text)| {
+        let hotkey = Hotkey::parse(text.as_deref()?)
+            .map_err(|e| log::warn!("{} hotkey not available: {}", action.label(), e))
+            .ok()?;
+        Some((action, hotkey))
+    })
+    .collect();
+    if hotkeys.is_empty() {
+        return None;
+    }
+    spawn_hotkey_thread(hotkeys, Arc::clone(copying_selection), tts_tx.clone(), clipboard_tx.clone())
+        .map_err(|e| log::warn!("Hotkeys not available: {}", e))
+        .ok()
+}
+
+/// Send the settings the TTS engine doesn't take at startup
+fn send_engine_settings(tts_tx: &mpsc::Sender<TTSCommand>, settings: &Settings) {
+    let _ = tts_tx.send(TTSCommand::SetDevice(settings.speech.device));
+    let _ = tts_tx.send(TTSCommand::SetPrecision(settings.speech.precision));
+    let _ = tts_tx.send(TTSCommand::SetIdleUnload(settings.idle_unload()));
+    let _ = tts_tx.send(TTSCommand::SetCacheSize(settings.speech.audio_cache_mb));
+    let _ = tts_tx.send(TTSCommand::SetArchive(settings.archive()));
+    let _ = tts_tx.send(TTSCommand::SetChimes(settings.chimes()));
+    let _ = tts_tx.send(TTSCommand::SetPitch(settings.audio.pitch_semitones));
+    let _ = tts_tx.send(TTSCommand::SetAudioLatency(settings.audio.audio_latency));
+    let _ = tts_tx.send(TTSCommand::SetOutputDevice(settings.output_device()));
+    let _ = tts_tx.send(TTSCommand::SetEqualizer(settings.audio.equalizer));
+    let _ = tts_tx.send(TTSCommand::SetSoftVoice(settings.soft_schedule()));
+    let _ = tts_tx.send(TTSCommand::SetNightVolume(settings.night_volume()));
+    let _ = tts_tx.send(TTSCommand::SetVolume(settings.volume()));
+    let _ = tts_tx.send(TTSCommand::SetLanguageVoices(settings.speech.language_voices.clone()));
+    let _ = tts_tx.send(TTSCommand::SetSkim(settings.speech.skim_long_texts));
+}
+
+/// Tooltip while speaking, e.g. "Speaking 2/7 paragraphs — ~1:40 remaining"
+fn playing_tooltip(part: Option<(usize, usize)>, paragraph: usize, paragraphs: usize, remaining: Duration) -> String {
+    let mut tooltip = "Pocket-Tray TTS - Speaking".to_string();
+    if let Some((part, total)) = part {
+        tooltip.push_str(&format!(" part {} of {}", part, total));
+        if paragraphs > 1 {
+            tooltip.push(',');
+        }
+    }
+    if paragraphs > 1 {
+        tooltip.push_str(&format!(" {}/{} paragraphs", paragraph, paragraphs));
+    }
+    let seconds = remaining.as_secs();
+    tooltip.push_str(&format!(" — ~{}:{:02} remaining", seconds / 60, seconds % 60));
+    tooltip
+}
+
+/// Whole minutes left of `duration`, rounded up
+fn minutes_left(duration: Duration) -> u64 {
+    duration.as_secs().div_ceil(60)
+}
@@ -0,0 +1,115 @@
+//! Archive of everything spoken, kept as timestamped audio files
+
+use crate::export::{write_audio, ExportFormat};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Words of the text used in file names
+const NAME_WORDS: usize = 6;
+
+/// Digits of the timestamp file names start with, seconds since 1970 as of 2001
+const TIMESTAMP_DIGITS: usize = 10;
+
+/// Writes every utterance to its own file, deleting the oldest beyond a limit
+#[derive(Debug, Clone)]
+pub struct Archive {
+    dir: PathBuf,
+    format: ExportFormat,
+    /// Most files kept (0 keeps all)
+    keep: usize,
+}
+
+impl Archive {
+    pub fn new(dir: PathBuf, format: ExportFormat, keep: usize) -> Self {
+        Self { dir, format, keep }
+    }
+
+    /// Write a spoken text as `<timestamp>-<first words>.<ext>`, then apply the retention limit
+    pub fn save(&self, samples: &[f32], sample_rate: u32, text: &str) {
+        let path = self.file_path(text);
+        match write_audio(&path, samples, sample_rate, self.format) {
+            Ok(()) => log::info!("Archived speech to {}", path.display()),
+            Err(e) => {
+                log::warn!("Failed to archive speech to {}: {}", path.display(), e);
+                return;
+            }
+        }
+        self.prune();
+    }
+
+    fn file_path(&self, text: &str) -> PathBuf {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .take(NAME_WORDS)
+            .map(str::to_lowercase)
+            .collect();
+        let base = if words.is_empty() {
+            format!("{}", timestamp)
+        } else {
+            format!("{}-{}", timestamp, words.join("-"))
+        };
+
+        let extension = self.format.extension();
+        let mut path = self.dir.join(format!("{}.{}", base, extension));
+        let mut suffix = 2;
+        while path.exists() {
+            path = self.dir.join(format!("{}-{}.{}", base, suffix, extension));
+            suffix += 1;
+        }
+        path
+    }
+
+    /// Delete the oldest archived files beyond the limit; only files named the way
+    /// the archive names them count, as the folder may hold the user's own
+    fn prune(&self) {
+        if self.keep == 0 {
+            return;
+        }
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        let mut files: Vec<(u64, PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .filter_map(|path| Some((archived_timestamp(&path)?, path)))
+            .collect();
+        if files.len() <= self.keep {
+            return;
+        }
+
+        files.sort();
+        let excess = files.len() - self.keep;
+        for (_, path) in files.into_iter().take(excess) {
+            if let Err(e) = std::fs::remove_file(&path) {
+                log::warn!("Failed to remove archived {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// When an archived file was saved, from its `<timestamp>-<first words>.<ext>` name;
+/// None for any other file
+fn archived_timestamp(path: &Path) -> Option<u64> {
+    let extension = path.extension()?.to_str()?;
+    if !ExportFormat::ALL.iter().any(|format| extension == format.extension()) {
+        return None;
+    }
+    let mut parts = path.file_stem()?.to_str()?.split('-');
+    let timestamp = parts.next()?;
+    if timestamp.len() < TIMESTAMP_DIGITS || !timestamp.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    // Lowercased words, and a number added when the name was taken
+    let words_match = parts.all(|word| !word.is_empty() && word.chars().all(|c| c.is_alphanumeric() && !c.is_uppercase()));
+    if !words_match {
+        return None;
+    }
+    timestamp.parse().ok()
+}
@@ -0,0 +1,1153 @@
+//! Post-processing of generated audio before it reaches the sink
+
+use rodio::buffer::SamplesBuffer;
+use rodio::Source;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Samples quieter than this count as silence
+const SILENCE_THRESHOLD: f32 = 0.01;
+
+/// Silence kept before the first sound of a segment
+const LEAD_IN: Duration = Duration::from_millis(30);
+
+/// Longest pause kept inside a segment; longer ones are shortened to this
+const MAX_GAP: Duration = Duration::from_millis(350);
+
+/// Silence kept after the last sound of a segment
+const TAIL: Duration = Duration::from_millis(120);
+
+/// Trims leading and trailing silence from a segment's audio and shortens long
+/// pauses inside it, working on chunks as they are generated
+///
+/// Silence is held back until it's known whether more sound follows, so the
+/// output lags the input by up to `MAX_GAP`.
+pub struct SilenceTrimmer {
+    lead_in: usize,
+    max_gap: usize,
+    tail: usize,
+    /// Whether any sound has been seen yet
+    started: bool,
+    /// Silence held back since the last sound, up to `max_gap` samples
+    pending: VecDeque<f32>,
+}
+
+impl SilenceTrimmer {
+    pub fn new(sample_rate: u32) -> Self {
+        let samples = |duration: Duration| (duration.as_secs_f64() * sample_rate as f64) as usize;
+        Self {
+            lead_in: samples(LEAD_IN),
+            max_gap: samples(MAX_GAP),
+            tail: samples(TAIL),
+            started: false,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Feed the next chunk, returning the samples ready to play
+    pub fn push(&mut self, chunk: &[f32]) -> Vec<f32> {
+        let mut out = Vec::with_capacity(chunk.len() + self.pending.len());
+        for &sample in chunk {
+            if sample.abs() >= SILENCE_THRESHOLD {
+                out.extend(self.pending.drain(..));
+                out.push(sample);
+                self.started = true;
+                continue;
+            }
+
+            self.pending.push_back(sample);
+            let limit = if self.started { self.max_gap } else { self.lead_in };
+            if self.pending.len() > limit {
+                // Keep the silence closest to the next sound
+                self.pending.pop_front();
+            }
+        }
+        out
+    }
+
+    /// End the segment, returning the remaining samples and resetting for the next one
+    pub fn finish(&mut self) -> Vec<f32> {
+        let kept = if self.started { self.tail } else { 0 };
+        let out = self.pending.drain(..).take(kept).collect();
+        self.started = false;
+        out
+    }
+
+    /// Drop held-back audio, e.g. after skipping to another segment
+    pub fn reset(&mut self) {
+        self.started = false;
+        self.pending.clear();
+    }
+}
+
+/// Length of the frames the time-stretcher splices together
+const STRETCH_FRAME: Duration = Duration::from_millis(20);
+
+/// Changes the speed of audio without changing its pitch (WSOLA)
+///
+/// Overlapping frames are taken from the input at the sped-up (or slowed-down)
+/// rate and cross-faded at the normal rate. Each frame is shifted slightly to
+/// where it best lines up with the previous one, which avoids the warble of
+/// plain overlap-add.
+pub struct TimeStretcher {
+    rate: f64,
+    frame: usize,
+    hop: usize,
+    /// How far a frame may be shifted from its nominal position
+    tolerance: usize,
+    window: Vec<f32>,
+    /// Input not yet consumed; `input[0]` is sample number `offset` of the segment
+    input: Vec<f32>,
+    offset: usize,
+    /// Nominal input position of the next frame
+    position: f64,
+    /// Input position of the previous frame
+    previous: Option<usize>,
+    /// Second half of the previous windowed frame, to overlap with the next one
+    overlap: Vec<f32>,
+}
+
+impl TimeStretcher {
+    pub fn new(rate: f32, sample_rate: u32) -> Self {
+        let frame = ((STRETCH_FRAME.as_secs_f64() * sample_rate as f64) as usize / 2 * 2).max(2);
+        let hop = frame / 2;
+        // Periodic Hann window; at 50% overlap the windows sum to one
+        let window = (0..frame)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / frame as f32).cos())
+            .collect();
+        Self {
+            rate: rate as f64,
+            frame,
+            hop,
+            tolerance: hop / 2,
+            window,
+            input: Vec::new(),
+            offset: 0,
+            position: 0.0,
+            previous: None,
+            overlap: vec![0.0; hop],
+        }
+    }
+
+    /// Whether audio passes through unchanged
+    fn is_identity(&self) -> bool {
+        (self.rate - 1.0).abs() < 0.01
+    }
+
+    /// Feed the next chunk, returning the stretched samples ready to play
+    pub fn push(&mut self, chunk: &[f32]) -> Vec<f32> {
+        if self.is_identity() {
+            return chunk.to_vec();
+        }
+
+        self.input.extend_from_slice(chunk);
+        let mut out = Vec::new();
+        while self.end() >= self.position as usize + self.tolerance + self.frame {
+            self.add_frame(&mut out);
+        }
+        self.discard_consumed();
+        out
+    }
+
+    /// End the segment, returning the remaining samples and resetting for the next one
+    pub fn finish(&mut self) -> Vec<f32> {
+        if self.is_identity() {
+            return Vec::new();
+        }
+
+        // Pad with silence so frames covering the last of the input can be taken
+        let end = self.end();
+        let mut out = Vec::new();
+        while (self.position as usize) < end {
+            let needed = self.position as usize + self.tolerance + self.frame;
+            if needed > self.end() {
+                self.input.resize(needed - self.offset, 0.0);
+            }
+            self.add_frame(&mut out);
+        }
+        if self.previous.is_some() {
+            out.extend_from_slice(&self.overlap);
+        }
+        self.reset();
+        out
+    }
+
+    /// Drop buffered audio, e.g. after skipping to another segment
+    pub fn reset(&mut self) {
+        self.input.clear();
+        self.offset = 0;
+        self.position = 0.0;
+        self.previous = None;
+        self.overlap.iter_mut().for_each(|s| *s = 0.0);
+    }
+
+    /// Sample number just past the buffered input
+    fn end(&self) -> usize {
+        self.offset + self.input.len()
+    }
+
+    /// Overlap-add the next frame, emitting one hop of output
+    fn add_frame(&mut self, out: &mut Vec<f32>) {
+        let nominal = self.position as usize;
+        let start = match self.previous {
+            Some(previous) => self.best_start(nominal, previous),
+            None => nominal,
+        };
+
+        let frame = &self.input[start - self.offset..start - self.offset + self.frame];
+        out.extend((0..self.hop).map(|i| self.overlap[i] + frame[i] * self.window[i]));
+        for i in 0..self.hop {
+            self.overlap[i] = frame[self.hop + i] * self.window[self.hop + i];
+        }
+
+        self.previous = Some(start);
+        self.position += self.hop as f64 * self.rate;
+    }
+
+    /// Start of the frame near `nominal` whose beginning best continues the
+    /// frame taken at `previous`
+    fn best_start(&self, nominal: usize, previous: usize) -> usize {
+        let continuation = previous + self.hop - self.offset;
+        let target = &self.input[continuation..continuation + self.hop];
+
+        let low = nominal.saturating_sub(self.tolerance).max(self.offset);
+        let high = nominal + self.tolerance;
+        let score = |start: usize| {
+            let candidate = &self.input[start - self.offset..start - self.offset + self.hop];
+            candidate.iter().zip(target).map(|(c, t)| c * t).sum::<f32>()
+        };
+        (low..=high)
+            .map(|start| (start, score(start)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(start, _)| start)
+            .unwrap_or(nominal)
+    }
+
+    /// Free input that no later frame can use
+    fn discard_consumed(&mut self) {
+        let mut keep_from = (self.position as usize).saturating_sub(self.tolerance);
+        if let Some(previous) = self.previous {
+            keep_from = keep_from.min(previous + self.hop);
+        }
+        if keep_from > self.offset {
+            self.input.drain(..keep_from - self.offset);
+            self.offset = keep_from;
+        }
+    }
+}
+
+/// Changes the speed and pitch of audio independently
+///
+/// Audio is time-stretched to make up for the length change of resampling,
+/// then resampled to move the pitch.
+pub struct PitchShifter {
+    stretcher: TimeStretcher,
+    /// Input samples consumed per output sample
+    step: f64,
+    /// Input waiting to be resampled
+    input: Vec<f32>,
+    /// Position of the next output sample within `input`
+    position: f64,
+}
+
+impl PitchShifter {
+    /// `speed` is a rate multiplier, `semitones` moves the pitch up or down
+    pub fn new(speed: f32, semitones: f32, sample_rate: u32) -> Self {
+        let factor = 2f32.powf(semitones / 12.0);
+        Self {
+            stretcher: TimeStretcher::new(speed / factor, sample_rate),
+            step: factor as f64,
+            input: Vec::new(),
+            position: 0.0,
+        }
+    }
+
+    /// Whether the pitch is left alone
+    fn keeps_pitch(&self) -> bool {
+        (self.step - 1.0).abs() < 0.001
+    }
+
+    /// Feed the next chunk, returning the samples ready to play
+    pub fn push(&mut self, chunk: &[f32]) -> Vec<f32> {
+        let stretched = self.stretcher.push(chunk);
+        self.resample(&stretched)
+    }
+
+    /// End the segment, returning the remaining samples and resetting for the next one
+    pub fn finish(&mut self) -> Vec<f32> {
+        let tail = self.stretcher.finish();
+        let out = self.resample(&tail);
+        self.reset();
+        out
+    }
+
+    /// Resample by linear interpolation, keeping the last input sample for the next call
+    fn resample(&mut self, samples: &[f32]) -> Vec<f32> {
+        if self.keeps_pitch() {
+            return samples.to_vec();
+        }
+
+        self.input.extend_from_slice(samples);
+        let mut out = Vec::new();
+        while self.position + 1.0 < self.input.len() as f64 {
+            let index = self.position as usize;
+            let frac = (self.position - index as f64) as f32;
+            out.push(self.input[index] * (1.0 - frac) + self.input[index + 1] * frac);
+            self.position += self.step;
+        }
+
+        let consumed = (self.position as usize).min(self.input.len());
+        self.input.drain(..consumed);
+        self.position -= consumed as f64;
+        out
+    }
+
+    /// Drop buffered audio, e.g. after skipping to another segment
+    pub fn reset(&mut self) {
+        self.stretcher.reset();
+        self.input.clear();
+        self.position = 0.0;
+    }
+}
+
+/// Convert audio between sample rates by linear interpolation
+pub fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let step = from as f64 / to as f64;
+    let len = (samples.len() as f64 / step) as usize;
+    (0..len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let frac = (position - index as f64) as f32;
+            let next = samples.get(index + 1).copied().unwrap_or(samples[index]);
+            samples[index] * (1.0 - frac) + next * frac
+        })
+        .collect()
+}
+
+/// Channel count and sample rate the output device plays at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceFormat {
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+/// Converts mono audio to the output device's sample rate and channel layout
+///
+/// Interpolation carries over from one chunk to the next, so chunks join
+/// without clicks.
+pub struct OutputConverter {
+    format: DeviceFormat,
+    level: OutputLevel,
+    step: f64,
+    /// Position of the next output sample, in input samples from the start of the next chunk
+    /// (negative values fall between the previous chunk's last sample and the next chunk)
+    position: f64,
+    /// Last sample of the previous chunk
+    previous: f32,
+}
+
+impl OutputConverter {
+    pub fn new(sample_rate: u32, format: DeviceFormat, level: OutputLevel) -> Self {
+        Self {
+            format,
+            level,
+            step: sample_rate as f64 / format.sample_rate as f64,
+            position: 0.0,
+            previous: 0.0,
+        }
+    }
+
+    /// Convert the next chunk into a buffer ready for the sink, metered as it plays
+    pub fn buffer(&mut self, samples: &[f32]) -> LevelMeter<SamplesBuffer<f32>> {
+        let buffer = SamplesBuffer::new(self.format.channels, self.format.sample_rate, self.push(samples));
+        LevelMeter::new(buffer, self.level.clone())
+    }
+
+    /// Convert the next chunk, returning interleaved samples
+    fn push(&mut self, samples: &[f32]) -> Vec<f32> {
+        let channels = self.format.channels.max(1) as usize;
+        let len = samples.len() as f64;
+        let mut output = Vec::with_capacity((len / self.step) as usize * channels + channels);
+        while self.position < len - 1.0 {
+            let index = self.position.floor();
+            let frac = (self.position - index) as f32;
+            let (current, next) = if index < 0.0 {
+                (self.previous, samples[0])
+            } else {
+                (samples[index as usize], samples[index as usize + 1])
+            };
+            let sample = current * (1.0 - frac) + next * frac;
+            output.resize(output.len() + channels, sample);
+            self.position += self.step;
+        }
+
+        if let Some(&last) = samples.last() {
+            self.position -= len;
+            self.previous = last;
+        }
+        output
+    }
+
+    /// Forget the previous chunk, before converting unrelated audio
+    pub fn reset(&mut self) {
+        self.position = 0.0;
+        self.previous = 0.0;
+    }
+}
+
+/// A band of the equalizer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqBand {
+    Bass,
+    Mid,
+    Treble,
+}
+
+impl EqBand {
+    /// All bands, in menu order
+    pub const ALL: [EqBand; 3] = [EqBand::Bass, EqBand::Mid, EqBand::Treble];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            EqBand::Bass => "bass",
+            EqBand::Mid => "mid",
+            EqBand::Treble => "treble",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            EqBand::Bass => "Bass",
+            EqBand::Mid => "Mid",
+            EqBand::Treble => "Treble",
+        }
+    }
+}
+
+/// Boost or cut per equalizer band, in dB
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EqGains {
+    pub bass: i32,
+    pub mid: i32,
+    pub treble: i32,
+}
+
+impl EqGains {
+    pub fn get(&self, band: EqBand) -> i32 {
+        match band {
+            EqBand::Bass => self.bass,
+            EqBand::Mid => self.mid,
+            EqBand::Treble => self.treble,
+        }
+    }
+
+    pub fn set(&mut self, band: EqBand, db: i32) {
+        match band {
+            EqBand::Bass => self.bass = db,
+            EqBand::Mid => self.mid = db,
+            EqBand::Treble => self.treble = db,
+        }
+    }
+}
+
+/// Corner frequency of the bass shelf
+const BASS_FREQUENCY: f32 = 250.0;
+
+/// Center frequency of the mid band
+const MID_FREQUENCY: f32 = 1000.0;
+
+/// Corner frequency of the treble shelf
+const TREBLE_FREQUENCY: f32 = 4000.0;
+
+/// Three-band equalizer: a bass shelf, a mid peak and a treble shelf
+/// Bands left at 0 dB are skipped.
+pub struct Equalizer {
+    filters: Vec<Biquad>,
+}
+
+impl Equalizer {
+    pub fn new(gains: EqGains, sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f32;
+        let filters = EqBand::ALL
+            .into_iter()
+            .filter(|&band| gains.get(band) != 0)
+            .map(|band| {
+                let db = gains.get(band).clamp(-12, 12) as f32;
+                match band {
+                    EqBand::Bass => Biquad::low_shelf(BASS_FREQUENCY, db, sample_rate),
+                    EqBand::Mid => Biquad::peak(MID_FREQUENCY, db, sample_rate),
+                    EqBand::Treble => Biquad::high_shelf(TREBLE_FREQUENCY, db, sample_rate),
+                }
+            })
+            .collect();
+        Self { filters }
+    }
+
+    /// Filter the next chunk in place
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for filter in &mut self.filters {
+            samples.iter_mut().for_each(|s| *s = filter.process(*s));
+        }
+    }
+
+    /// Clear the filters' memory, before processing unrelated audio
+    pub fn reset(&mut self) {
+        for filter in &mut self.filters {
+            filter.z1 = 0.0;
+            filter.z2 = 0.0;
+        }
+    }
+}
+
+/// Second-order filter, coefficients from the RBJ Audio EQ Cookbook
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    fn low_shelf(frequency: f32, db: f32, sample_rate: f32) -> Self {
+        let (a, cos, alpha) = Self::params(frequency, db, sample_rate, std::f32::consts::FRAC_1_SQRT_2);
+        let beta = 2.0 * a.sqrt() * alpha;
+        Self::new(
+            a * ((a + 1.0) - (a - 1.0) * cos + beta),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+            a * ((a + 1.0) - (a - 1.0) * cos - beta),
+            (a + 1.0) + (a - 1.0) * cos + beta,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+            (a + 1.0) + (a - 1.0) * cos - beta,
+        )
+    }
+
+    fn high_shelf(frequency: f32, db: f32, sample_rate: f32) -> Self {
+        let (a, cos, alpha) = Self::params(frequency, db, sample_rate, std::f32::consts::FRAC_1_SQRT_2);
+        let beta = 2.0 * a.sqrt() * alpha;
+        Self::new(
+            a * ((a + 1.0) + (a - 1.0) * cos + beta),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - beta),
+            (a + 1.0) - (a - 1.0) * cos + beta,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - beta,
+        )
+    }
+
+    fn low_pass(frequency: f32, sample_rate: f32) -> Self {
+        let (_, cos, alpha) = Self::params(frequency, 0.0, sample_rate, std::f32::consts::FRAC_1_SQRT_2);
+        Self::new(
+            (1.0 - cos) / 2.0,
+            1.0 - cos,
+            (1.0 - cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    fn peak(frequency: f32, db: f32, sample_rate: f32) -> Self {
+        let (a, cos, alpha) = Self::params(frequency, db, sample_rate, 0.9);
+        Self::new(
+            1.0 + alpha * a,
+            -2.0 * cos,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos,
+            1.0 - alpha / a,
+        )
+    }
+
+    /// Amplitude, cosine of the angular frequency and bandwidth term
+    fn params(frequency: f32, db: f32, sample_rate: f32, q: f32) -> (f32, f32, f32) {
+        let a = 10f32.powf(db / 40.0);
+        let w0 = 2.0 * std::f32::consts::PI * frequency.min(sample_rate * 0.45) / sample_rate;
+        (a, w0.cos(), w0.sin() / (2.0 * q))
+    }
+
+    /// Normalize coefficients by `a0`
+    fn new(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    /// Transposed direct form II
+    fn process(&mut self, input: f32) -> f32 {
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+}
+
+/// Frequencies above this are muffled by the soft voice
+const SOFT_CUTOFF: f32 = 3000.0;
+
+/// Volume of the soft voice (about -10 dB)
+const SOFT_GAIN: f32 = 0.32;
+
+/// Loudness of the soft voice's breath noise, relative to the voice
+const SOFT_BREATH: f32 = 0.12;
+
+/// Time the breath noise takes to follow the voice's loudness
+const SOFT_ATTACK: Duration = Duration::from_millis(10);
+
+/// Quieter, duller speech with a little breath in it, for late-night listening
+///
+/// Noise shaped by the voice's envelope is mixed in before the low-pass, so
+/// it only sounds while words are spoken.
+pub struct SoftEffect {
+    enabled: bool,
+    filter: Biquad,
+    envelope: f32,
+    /// Share of the distance to the current level the envelope moves per sample
+    smoothing: f32,
+    /// xorshift state for the noise
+    noise: u32,
+}
+
+impl SoftEffect {
+    /// A soft voice filter, or a pass-through if not `enabled`
+    pub fn new(enabled: bool, sample_rate: u32) -> Self {
+        let attack_samples = SOFT_ATTACK.as_secs_f32() * sample_rate as f32;
+        Self {
+            enabled,
+            filter: Biquad::low_pass(SOFT_CUTOFF, sample_rate as f32),
+            envelope: 0.0,
+            smoothing: 1.0 - (-1.0 / attack_samples).exp(),
+            noise: 0x2545_f491,
+        }
+    }
+
+    /// Soften the next chunk in place
+    pub fn process(&mut self, samples: &mut [f32]) {
+        if !self.enabled {
+            return;
+        }
+        for sample in samples {
+            self.envelope += (sample.abs() - self.envelope) * self.smoothing;
+            let breath = self.next_noise() * self.envelope * SOFT_BREATH;
+            *sample = self.filter.process(*sample + breath) * SOFT_GAIN;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.filter.z1 = 0.0;
+        self.filter.z2 = 0.0;
+        self.envelope = 0.0;
+    }
+
+    /// White noise between -1 and 1
+    fn next_noise(&mut self) -> f32 {
+        self.noise ^= self.noise << 13;
+        self.noise ^= self.noise >> 17;
+        self.noise ^= self.noise << 5;
+        self.noise as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+/// Level the limiter keeps peaks under (about -0.5 dBFS)
+const LIMITER_CEILING: f32 = 0.944;
+
+/// Time the limiter takes to let the level recover after a peak
+const LIMITER_RELEASE: Duration = Duration::from_millis(80);
+
+/// Keeps loud audio from clipping
+///
+/// The gain drops at once to fit a peak under the ceiling and recovers
+/// smoothly, so boosted voices get quieter instead of distorting.
+pub struct Limiter {
+    gain: f32,
+    /// Share of the remaining distance to full gain recovered per sample
+    release: f32,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: u32) -> Self {
+        let release_samples = LIMITER_RELEASE.as_secs_f32() * sample_rate as f32;
+        Self {
+            gain: 1.0,
+            release: 1.0 - (-1.0 / release_samples).exp(),
+        }
+    }
+
+    /// Limit the next chunk in place
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            let peak = sample.abs();
+            if peak * self.gain > LIMITER_CEILING {
+                self.gain = LIMITER_CEILING / peak;
+            }
+            *sample *= self.gain;
+            self.gain += (1.0 - self.gain) * self.release;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+}
+
+/// Window the playback level is measured over
+const LEVEL_WINDOW: Duration = Duration::from_millis(30);
+
+/// Loudness of the audio playing right now (RMS), shared with the UI thread
+#[derive(Debug, Clone, Default)]
+pub struct OutputLevel(Arc<AtomicU32>);
+
+impl OutputLevel {
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, level: f32) {
+        self.0.store(level.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Passes audio through unchanged, updating an `OutputLevel` as it's played
+pub struct LevelMeter<S> {
+    source: S,
+    level: OutputLevel,
+    window: usize,
+    sum_squares: f32,
+    count: usize,
+}
+
+impl<S: Source<Item = f32>> LevelMeter<S> {
+    pub fn new(source: S, level: OutputLevel) -> Self {
+        let window = LEVEL_WINDOW.as_secs_f32() * source.sample_rate() as f32 * source.channels() as f32;
+        Self {
+            source,
+            level,
+            window: (window as usize).max(1),
+            sum_squares: 0.0,
+            count: 0,
+        }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for LevelMeter<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.source.next()?;
+        self.sum_squares += sample * sample;
+        self.count += 1;
+        if self.count == self.window {
+            self.level.set((self.sum_squares / self.count as f32).sqrt());
+            self.sum_squares = 0.0;
+            self.count = 0;
+        }
+        Some(sample)
+    }
+}
+
+impl<S: Source<Item = f32>> Source for LevelMeter<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sample rate of the test tones
+    const RATE: u32 = 16_000;
+
+    /// Length of a time-stretcher frame at `RATE`
+    const FRAME: f32 = 320.0;
+
+    /// Trim `chunks` as one segment, returning everything played
+    fn trim(trimmer: &mut SilenceTrimmer, chunks: &[&[f32]]) -> Vec<f32> {
+        let mut out: Vec<f32> = chunks.iter().flat_map(|chunk| trimmer.push(chunk)).collect();
+        out.extend(trimmer.finish());
+        out
+    }
+
+    /// `len` samples of a sine wave
+    fn tone(len: usize, amplitude: f32, frequency: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    /// Stretch `input` as one segment fed `chunk` samples at a time, returning everything played
+    fn stretch(stretcher: &mut TimeStretcher, input: &[f32], chunk: usize) -> Vec<f32> {
+        let mut out: Vec<f32> = input.chunks(chunk).flat_map(|chunk| stretcher.push(chunk)).collect();
+        out.extend(stretcher.finish());
+        out
+    }
+
+    /// Shift `input` as one segment fed `chunk` samples at a time, returning everything played
+    fn shift(shifter: &mut PitchShifter, input: &[f32], chunk: usize) -> Vec<f32> {
+        let mut out: Vec<f32> = input.chunks(chunk).flat_map(|chunk| shifter.push(chunk)).collect();
+        out.extend(shifter.finish());
+        out
+    }
+
+    /// Convert `input` fed `chunk` samples at a time
+    fn convert(converter: &mut OutputConverter, input: &[f32], chunk: usize) -> Vec<f32> {
+        input.chunks(chunk).flat_map(|chunk| converter.push(chunk)).collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn trims_silence_around_a_segment() {
+        let mut trimmer = SilenceTrimmer::new(1000);
+        let out = trim(&mut trimmer, &[&[0.0; 500], &[0.5; 100], &[0.0; 500]]);
+        // 30 ms lead-in and 120 ms tail, at 1000 samples a second
+        assert_eq!(out.len(), 30 + 100 + 120);
+        assert!(out[..30].iter().all(|&s| s == 0.0));
+        assert!(out[30..130].iter().all(|&s| s == 0.5));
+    }
+
+    #[test]
+    fn shortens_long_pauses_only() {
+        let mut trimmer = SilenceTrimmer::new(1000);
+        assert_eq!(trim(&mut trimmer, &[&[0.5; 10], &[0.0; 1000], &[0.5; 10]]).len(), 10 + 350 + 10);
+        assert_eq!(trim(&mut trimmer, &[&[0.5; 10], &[0.0; 200], &[0.5; 10]]).len(), 10 + 200 + 10);
+    }
+
+    #[test]
+    fn trims_empty_and_one_sample_chunks() {
+        let mut trimmer = SilenceTrimmer::new(1000);
+        assert!(trimmer.push(&[]).is_empty());
+        assert_eq!(trimmer.push(&[0.5]), vec![0.5]);
+        assert!(trimmer.push(&[0.0]).is_empty());
+        assert!(trimmer.push(&[]).is_empty());
+        assert_eq!(trimmer.finish(), vec![0.0]);
+    }
+
+    #[test]
+    fn trimming_ignores_chunk_sizes() {
+        let mut input = vec![0.0; 400];
+        input.extend([0.5; 50]);
+        input.extend([0.0; 600]);
+        input.extend([-0.5; 50]);
+        input.extend([0.0; 200]);
+        let whole = trim(&mut SilenceTrimmer::new(1000), &[&input]);
+        assert_eq!(whole.len(), 30 + 50 + 350 + 50 + 120);
+        for size in [1, 37] {
+            let chunks: Vec<&[f32]> = input.chunks(size).collect();
+            assert_eq!(trim(&mut SilenceTrimmer::new(1000), &chunks), whole, "{} samples a chunk", size);
+        }
+    }
+
+    #[test]
+    fn finish_plays_the_held_back_silence() {
+        let mut trimmer = SilenceTrimmer::new(1000);
+        assert_eq!(trimmer.push(&[0.5; 5]).len(), 5);
+        assert!(trimmer.push(&[0.0; 50]).is_empty());
+        assert_eq!(trimmer.finish().len(), 50);
+        // The next segment gets a lead-in of its own
+        assert!(trimmer.push(&[0.0; 100]).is_empty());
+        assert_eq!(trimmer.push(&[0.5]).len(), 30 + 1);
+    }
+
+    #[test]
+    fn silence_alone_plays_nothing() {
+        let mut trimmer = SilenceTrimmer::new(1000);
+        assert!(trim(&mut trimmer, &[&[0.0; 2000], &[0.005; 10]]).is_empty());
+    }
+
+    #[test]
+    fn stretched_length_follows_the_rate() {
+        let input = tone(RATE as usize, 0.5, 220.0);
+        for rate in [0.5, 0.75, 1.5, 2.0] {
+            let out = stretch(&mut TimeStretcher::new(rate, RATE), &input, 1000);
+            let expected = input.len() as f32 / rate;
+            assert!((out.len() as f32 - expected).abs() <= FRAME, "rate {}: {} samples", rate, out.len());
+        }
+    }
+
+    #[test]
+    fn stretching_at_the_limits_stays_in_range() {
+        let input = tone(RATE as usize / 2, 0.5, 220.0);
+        for rate in [0.5, 2.0] {
+            let out = stretch(&mut TimeStretcher::new(rate, RATE), &input, 512);
+            assert!(out.iter().all(|s| s.is_finite() && s.abs() <= 0.5 + 1e-4), "rate {}", rate);
+        }
+    }
+
+    #[test]
+    fn stretches_empty_and_one_sample_chunks() {
+        let input = tone(4000, 0.5, 220.0);
+        let whole = stretch(&mut TimeStretcher::new(1.5, RATE), &input, input.len());
+        let mut stretcher = TimeStretcher::new(1.5, RATE);
+        assert!(stretcher.push(&[]).is_empty());
+        assert_eq!(stretch(&mut stretcher, &input, 1), whole);
+    }
+
+    #[test]
+    fn finish_stretches_a_partial_frame() {
+        let mut stretcher = TimeStretcher::new(0.5, RATE);
+        // Less than a frame can't be played until the segment ends
+        assert!(stretcher.push(&tone(100, 0.5, 220.0)).is_empty());
+        let tail = stretcher.finish();
+        assert!(tail.len() as f32 >= 200.0 && tail.len() as f32 <= 200.0 + FRAME, "{}", tail.len());
+        assert!(tail.iter().all(|s| s.is_finite()));
+        // Nothing is left over for the next segment
+        assert!(stretcher.finish().is_empty());
+    }
+
+    #[test]
+    fn normal_speed_passes_audio_through() {
+        let input = tone(1000, 0.5, 220.0);
+        let mut stretcher = TimeStretcher::new(1.0, RATE);
+        assert_eq!(stretcher.push(&input), input);
+        assert!(stretcher.finish().is_empty());
+    }
+
+    #[test]
+    fn pitch_shifts_keep_the_length() {
+        let input = tone(RATE as usize, 0.5, 220.0);
+        for semitones in [-12.0, -3.0, 5.0, 12.0] {
+            let out = shift(&mut PitchShifter::new(1.0, semitones, RATE), &input, 1000);
+            let difference = (out.len() as f32 - input.len() as f32).abs();
+            assert!(difference <= 2.0 * FRAME, "{} semitones: {} samples", semitones, out.len());
+        }
+    }
+
+    #[test]
+    fn speed_and_pitch_at_the_limits() {
+        let input = tone(RATE as usize, 0.5, 220.0);
+        for speed in [0.5, 2.0] {
+            for semitones in [-12.0, 12.0] {
+                let out = shift(&mut PitchShifter::new(speed, semitones, RATE), &input, 777);
+                let expected = input.len() as f32 / speed;
+                assert!(
+                    (out.len() as f32 - expected).abs() <= 2.0 * FRAME,
+                    "speed {}, {} semitones: {} samples",
+                    speed,
+                    semitones,
+                    out.len()
+                );
+                assert!(out.iter().all(|s| s.is_finite() && s.abs() <= 0.5 + 1e-4));
+            }
+        }
+    }
+
+    #[test]
+    fn shifts_empty_and_one_sample_chunks() {
+        let input = tone(4000, 0.5, 220.0);
+        let whole = shift(&mut PitchShifter::new(1.25, 4.0, RATE), &input, input.len());
+        let mut shifter = PitchShifter::new(1.25, 4.0, RATE);
+        assert!(shifter.push(&[]).is_empty());
+        let samples = shift(&mut shifter, &input, 1);
+        assert_eq!(samples.len(), whole.len());
+        assert!(samples.iter().zip(&whole).all(|(a, b)| (a - b).abs() < 1e-4));
+    }
+
+    #[test]
+    fn finish_shifts_a_partial_frame() {
+        let mut shifter = PitchShifter::new(1.0, 7.0, RATE);
+        assert!(shifter.push(&tone(100, 0.5, 220.0)).is_empty());
+        let tail = shifter.finish();
+        assert!(!tail.is_empty() && tail.iter().all(|s| s.is_finite()));
+        assert!(shifter.finish().is_empty());
+    }
+
+    #[test]
+    fn converted_length_follows_the_sample_rates() {
+        let input = tone(RATE as usize, 0.5, 220.0);
+        for (device_rate, channels) in [(16_000, 1), (48_000, 2), (44_100, 2), (8_000, 1)] {
+            let format = DeviceFormat {
+                channels,
+                sample_rate: device_rate,
+            };
+            let out = convert(&mut OutputConverter::new(RATE, format, OutputLevel::default()), &input, 1000);
+            let frames = out.len() / channels as usize;
+            // Within a couple of input samples
+            let ratio = device_rate as f32 / RATE as f32;
+            let expected = input.len() as f32 * ratio;
+            assert!((frames as f32 - expected).abs() <= 2.0 * ratio + 1.0, "{} Hz: {} frames", device_rate, frames);
+            assert!(out.iter().all(|s| s.is_finite() && s.abs() <= 0.5));
+        }
+    }
+
+    #[test]
+    fn converts_empty_and_one_sample_chunks() {
+        let format = DeviceFormat {
+            channels: 2,
+            sample_rate: 44_100,
+        };
+        let input = tone(2000, 0.5, 220.0);
+        let whole = convert(&mut OutputConverter::new(RATE, format, OutputLevel::default()), &input, input.len());
+        let mut converter = OutputConverter::new(RATE, format, OutputLevel::default());
+        assert!(converter.push(&[]).is_empty());
+        let samples = convert(&mut converter, &input, 1);
+        assert!(converter.push(&[]).is_empty());
+        assert_eq!(samples.len(), whole.len());
+        assert!(samples.iter().zip(&whole).all(|(a, b)| (a - b).abs() < 1e-4));
+    }
+
+    #[test]
+    fn channels_get_the_same_sample() {
+        let format = DeviceFormat {
+            channels: 6,
+            sample_rate: 48_000,
+        };
+        let out = convert(&mut OutputConverter::new(RATE, format, OutputLevel::default()), &tone(500, 0.5, 220.0), 64);
+        assert!(out.chunks(6).all(|frame| frame.iter().all(|&s| s == frame[0])));
+    }
+
+    #[test]
+    fn reset_forgets_the_previous_chunk() {
+        let format = DeviceFormat {
+            channels: 1,
+            sample_rate: 22_050,
+        };
+        let input = tone(1000, 0.5, 220.0);
+        let fresh = convert(&mut OutputConverter::new(RATE, format, OutputLevel::default()), &input, 100);
+        let mut converter = OutputConverter::new(RATE, format, OutputLevel::default());
+        convert(&mut converter, &tone(333, 0.9, 440.0), 100);
+        converter.reset();
+        assert_eq!(convert(&mut converter, &input, 100), fresh);
+    }
+
+    #[test]
+    fn flat_equalizer_leaves_audio_alone() {
+        let input = tone(1000, 0.5, 220.0);
+        let mut samples = input.clone();
+        Equalizer::new(EqGains::default(), RATE).process(&mut samples);
+        assert_eq!(samples, input);
+    }
+
+    #[test]
+    fn bands_boost_and_cut_their_frequencies() {
+        let bass = tone(RATE as usize, 0.1, 100.0);
+        // Past the filters settling
+        let level = |gains: EqGains| {
+            let mut samples = bass.clone();
+            Equalizer::new(gains, RATE).process(&mut samples);
+            rms(&samples[RATE as usize / 2..]) / rms(&bass[RATE as usize / 2..])
+        };
+        // 12 dB is about four times the amplitude
+        let boosted = level(EqGains { bass: 12, ..EqGains::default() });
+        assert!((3.5..4.5).contains(&boosted), "{}", boosted);
+        let cut = level(EqGains { bass: -12, ..EqGains::default() });
+        assert!((0.22..0.29).contains(&cut), "{}", cut);
+        let treble = level(EqGains { treble: 12, ..EqGains::default() });
+        assert!((0.95..1.1).contains(&treble), "{}", treble);
+        // Gains past 12 dB are held to it
+        assert_eq!(level(EqGains { bass: 30, ..EqGains::default() }), boosted);
+    }
+
+    #[test]
+    fn equalizes_empty_and_one_sample_chunks() {
+        let gains = EqGains {
+            bass: 6,
+            mid: -4,
+            treble: 9,
+        };
+        let mut whole = tone(2000, 0.5, 440.0);
+        let mut samples = whole.clone();
+        Equalizer::new(gains, RATE).process(&mut whole);
+        let mut equalizer = Equalizer::new(gains, RATE);
+        equalizer.process(&mut []);
+        for sample in samples.chunks_mut(1) {
+            equalizer.process(sample);
+        }
+        assert_eq!(samples, whole);
+    }
+
+    #[test]
+    fn equalizer_stays_finite_at_low_sample_rates() {
+        let gains = EqGains {
+            bass: 12,
+            mid: 12,
+            treble: 12,
+        };
+        // The treble corner is past the Nyquist frequency here
+        let mut samples: Vec<f32> = (0..8000).map(|i| if i % 2 == 0 { 0.5 } else { -0.5 }).collect();
+        Equalizer::new(gains, 8000).process(&mut samples);
+        assert!(samples.iter().all(|s| s.is_finite()));
+    }
+
+    #[test]
+    fn quiet_audio_isnt_limited() {
+        let input = tone(1000, 0.5, 220.0);
+        let mut samples = input.clone();
+        Limiter::new(RATE).process(&mut samples);
+        assert_eq!(samples, input);
+    }
+
+    #[test]
+    fn loud_audio_stays_under_the_ceiling() {
+        // A voice at 400% gain
+        let mut samples = tone(RATE as usize, 4.0, 220.0);
+        Limiter::new(RATE).process(&mut samples);
+        assert!(samples.iter().all(|s| s.is_finite() && s.abs() <= LIMITER_CEILING + 1e-6));
+        let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak > LIMITER_CEILING * 0.9, "{}", peak);
+    }
+
+    #[test]
+    fn limits_empty_and_one_sample_chunks() {
+        let mut whole = tone(2000, 2.0, 220.0);
+        let mut samples = whole.clone();
+        Limiter::new(RATE).process(&mut whole);
+        let mut limiter = Limiter::new(RATE);
+        limiter.process(&mut []);
+        for sample in samples.chunks_mut(1) {
+            limiter.process(sample);
+        }
+        assert_eq!(samples, whole);
+        let mut one = [3.0];
+        Limiter::new(RATE).process(&mut one);
+        assert!((one[0] - LIMITER_CEILING).abs() < 1e-6);
+    }
+
+    #[test]
+    fn level_recovers_after_a_peak() {
+        let mut limiter = Limiter::new(RATE);
+        limiter.process(&mut [2.0]);
+        // A second later, well past the release time
+        let mut quiet = vec![0.1; RATE as usize];
+        limiter.process(&mut quiet);
+        assert!((quiet[quiet.len() - 1] - 0.1).abs() < 1e-4);
+        limiter.process(&mut [2.0]);
+        limiter.reset();
+        let mut after = [0.5];
+        limiter.process(&mut after);
+        assert_eq!(after, [0.5]);
+    }
+}
@@ -0,0 +1,107 @@
+//! Speech synthesis backends driven by the TTS engine
+
+use anyhow::Result;
+use std::path::Path;
+
+/// Audio of one text as chunks of mono samples, in playback order
+pub type SampleStream<'a> = Box<dyn Iterator<Item = Result<Vec<f32>>> + 'a>;
+
+/// A speech synthesizer offering a set of voices
+///
+/// The engine handles queueing, playback, caching and post-processing, so a
+/// backend only turns text into audio.
+pub trait TtsBackend {
+    /// Sample rate of the generated audio
+    fn sample_rate(&self) -> u32;
+
+    /// Names of the voices, in menu order
+    fn voices(&self) -> Vec<String>;
+
+    /// Whether `voice` is one of the voices, for picking the backend that speaks it
+    fn has_voice(&self, voice: &str) -> bool {
+        self.voices().iter().any(|name| name == voice)
+    }
+
+    /// Start generating `text` spoken by `voice`
+    /// Audio is produced lazily, chunk by chunk, as the stream is iterated
+    fn synth_stream<'a>(&'a self, text: &'a str, voice: &str) -> Result<SampleStream<'a>>;
+
+    /// Create a voice named `name` from prompt audio
+    fn add_voice(&mut self, name: &str, _path: &Path) -> Result<()> {
+        anyhow::bail!("Voice '{}' can't be created, these voices don't support cloning", name)
+    }
+}
+
+/// A backend with the given voices that speaks a sample per letter, a word a chunk,
+/// for testing the engine without a model
+#[cfg(test)]
+pub struct MockBackend {
+    pub voices: Vec<String>,
+    /// Texts containing this word make it panic, like a model hitting a bug
+    pub panic_on: Option<String>,
+}
+
+#[cfg(test)]
+impl MockBackend {
+    pub const SAMPLE_RATE: u32 = 10;
+
+    pub fn new(voices: &[&str]) -> Self {
+        Self {
+            voices: voices.iter().map(|voice| voice.to_string()).collect(),
+            panic_on: None,
+        }
+    }
+
+    pub fn panicking_on(self, word: &str) -> Self {
+        Self {
+            panic_on: Some(word.to_string()),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+impl TtsBackend for MockBackend {
+    fn sample_rate(&self) -> u32 {
+        Self::SAMPLE_RATE
+    }
+
+    fn voices(&self) -> Vec<String> {
+        self.voices.clone()
+    }
+
+    fn synth_stream<'a>(&'a self, text: &'a str, voice: &str) -> Result<SampleStream<'a>> {
+        if !self.has_voice(voice) {
+            anyhow::bail!("Unknown voice '{}'", voice);
+        }
+        if let Some(word) = self.panic_on.as_deref().filter(|word| text.contains(word)) {
+            panic!("Mock backend crashed on '{}'", word);
+        }
+        Ok(Box::new(
+            text.split_whitespace()
+                .map(|word| Ok(vec![0.5; word.chars().count()])),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_speaks_a_sample_per_letter() -> Result<()> {
+        let backend = MockBackend::new(&["alba"]);
+        let chunks: Vec<Vec<f32>> = backend.synth_stream("Hi there", "alba")?.collect::<Result<_>>()?;
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 5]);
+        Ok(())
+    }
+
+    #[test]
+    fn voices_are_checked() {
+        let mut backend = MockBackend::new(&["alba"]);
+        assert!(backend.has_voice("alba"));
+        assert!(!backend.has_voice("marius"));
+        assert!(backend.synth_stream("Hi", "marius").is_err());
+        assert!(backend.add_voice("mine", Path::new("mine.wav")).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! Bounded on-disk cache of generated speech, so repeated sentences play instantly
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Generated audio stored as 32-bit float WAV files named after the hash of
+/// what produced them. Speed and gain are applied on playback, so one entry
+/// serves every speed setting.
+pub struct AudioCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl AudioCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self { dir, max_bytes }
+    }
+
+    /// Cache key for a sentence spoken by a voice of a given model
+    pub fn key(model: &str, voice: &str, text: &str) -> u64 {
+        // FNV-1a, stable across Rust versions unlike DefaultHasher
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for part in [model, voice, text] {
+            for byte in part.bytes().chain(std::iter::once(0)) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+
+    fn path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.wav", key))
+    }
+
+    /// Cached samples for `key`, if present and recorded at `sample_rate`
+    pub fn get(&self, key: u64, sample_rate: u32) -> Option<Vec<f32>> {
+        let path = self.path(key);
+        let reader = hound::WavReader::open(&path).ok()?;
+        if reader.spec().sample_rate != sample_rate {
+            return None;
+        }
+        let samples: Vec<f32> = reader.into_samples::<f32>().collect::<Result<_, _>>().ok()?;
+
+        // Mark as recently used so eviction keeps it
+        if let Ok(file) = std::fs::File::options().append(true).open(&path) {
+            let _ = file.set_modified(std::time::SystemTime::now());
+        }
+        Some(samples)
+    }
+
+    /// Store samples for `key`, then evict the least recently used entries
+    /// until the cache fits its size limit
+    pub fn put(&self, key: u64, samples: &[f32], sample_rate: u32) {
+        if let Err(e) = self.write(&self.path(key), samples, sample_rate) {
+            log::warn!("Failed to cache audio: {}", e);
+            return;
+        }
+        self.evict();
+    }
+
+    fn write(&self, path: &Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(path, spec)?;
+        for &sample in samples {
+            writer.write_sample(sample)?;
+        }
+        writer.finalize()?;
+        Ok(())
+    }
+
+    fn evict(&self) {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        let mut files: Vec<(std::time::SystemTime, u64, PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+            })
+            .collect();
+
+        let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+        files.sort();
+        for (_, len, path) in files {
+            if total <= self.max_bytes {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                total -= len;
+            }
+        }
+    }
+
+    /// Delete every cached entry
+    pub fn clear(&self) {
+        if self.dir.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&self.dir) {
+                log::warn!("Failed to clear audio cache: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Short chimes played before and after speech, built in or from the user's WAV files
+
+use crate::audio::resample;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::path::Path;
+
+/// Sample rate the built-in chimes are rendered at
+const CHIME_RATE: u32 = 24000;
+
+/// Length of each note of the built-in chimes
+const NOTE_SECONDS: f32 = 0.09;
+
+/// Peak amplitude of the built-in chimes, kept below typical speech
+const CHIME_AMPLITUDE: f32 = 0.25;
+
+/// Notes of the built-in chimes in Hz: rising before speech, falling after
+const START_NOTES: [f32; 2] = [659.25, 880.0];
+const END_NOTES: [f32; 2] = [880.0, 659.25];
+
+/// When a chime is played around each text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChimeMode {
+    #[default]
+    Off,
+    Before,
+    After,
+    Both,
+}
+
+impl ChimeMode {
+    /// All options, in menu order
+    pub const ALL: [ChimeMode; 4] = [ChimeMode::Off, ChimeMode::Before, ChimeMode::After, ChimeMode::Both];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            ChimeMode::Off => "off",
+            ChimeMode::Before => "before",
+            ChimeMode::After => "after",
+            ChimeMode::Both => "both",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            ChimeMode::Off => "Off",
+            ChimeMode::Before => "Before Speaking",
+            ChimeMode::After => "After Speaking",
+            ChimeMode::Both => "Before and After",
+        }
+    }
+}
+
+/// Mono samples and their sample rate
+#[derive(Debug, Clone)]
+struct Sound {
+    samples: Vec<f32>,
+    sample_rate: u32,
+}
+
+/// The chimes to play before and after each text, if any
+#[derive(Debug, Clone, Default)]
+pub struct Chimes {
+    start: Option<Sound>,
+    end: Option<Sound>,
+}
+
+impl Chimes {
+    /// Chimes for `mode`, read from the given WAV files or built in when a file isn't
+    /// set or can't be read
+    pub fn new(mode: ChimeMode, start_wav: Option<&Path>, end_wav: Option<&Path>) -> Self {
+        let before = matches!(mode, ChimeMode::Before | ChimeMode::Both);
+        let after = matches!(mode, ChimeMode::After | ChimeMode::Both);
+        Self {
+            start: before.then(|| load_or_build(start_wav, &START_NOTES)),
+            end: after.then(|| load_or_build(end_wav, &END_NOTES)),
+        }
+    }
+
+    /// Chime played before a text, at `sample_rate`
+    pub fn start(&self, sample_rate: u32) -> Option<Vec<f32>> {
+        self.start.as_ref().map(|sound| sound.at_rate(sample_rate))
+    }
+
+    /// Chime played after a text, at `sample_rate`
+    pub fn end(&self, sample_rate: u32) -> Option<Vec<f32>> {
+        self.end.as_ref().map(|sound| sound.at_rate(sample_rate))
+    }
+}
+
+impl Sound {
+    fn at_rate(&self, sample_rate: u32) -> Vec<f32> {
+        resample(&self.samples, self.sample_rate, sample_rate)
+    }
+}
+
+fn load_or_build(path: Option<&Path>, notes: &[f32]) -> Sound {
+    if let Some(path) = path {
+        match load_wav(path) {
+            Ok(sound) => return sound,
+            Err(e) => log::warn!("Failed to load chime {}, using the built-in one: {}", path.display(), e),
+        }
+    }
+    Sound {
+        samples: build_chime(notes),
+        sample_rate: CHIME_RATE,
+    }
+}
+
+/// Read a WAV file, mixed down to mono
+fn load_wav(path: &Path) -> Result<Sound> {
+    let reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    let interleaved = match spec.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<std::result::Result<Vec<f32>, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample.max(1) - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 / scale))
+                .collect::<std::result::Result<Vec<f32>, _>>()?
+        }
+    };
+
+    let channels = spec.channels.max(1) as usize;
+    let samples = interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+    Ok(Sound {
+        samples,
+        sample_rate: spec.sample_rate,
+    })
+}
+
+/// Render a bell-like note for each frequency, one after the other
+fn build_chime(notes: &[f32]) -> Vec<f32> {
+    let note_len = (NOTE_SECONDS * CHIME_RATE as f32) as usize;
+    // A few milliseconds of fade-in and a fade-out avoid clicks at each note's ends
+    let attack = CHIME_RATE as usize / 500;
+
+    let mut samples = Vec::with_capacity(note_len * notes.len());
+    for &frequency in notes {
+        for i in 0..note_len {
+            let t = i as f32 / CHIME_RATE as f32;
+            let fade_out = 1.0 - i as f32 / note_len as f32;
+            let envelope = (i as f32 / attack as f32).min(1.0) * (-t * 30.0).exp() * fade_out;
+            // A quiet octave above gives the tone some shimmer
+            let tone = (2.0 * PI * frequency * t).sin() + 0.3 * (4.0 * PI * frequency * t).sin();
+            samples.push(CHIME_AMPLITUDE * envelope * tone / 1.3);
+        }
+    }
+    samples
+}
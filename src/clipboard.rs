@@ -1,38 +1,259 @@
-//! Clipboard monitoring thread
+//! Clipboard monitoring thread, woken by Windows' clipboard change notifications
 
+use crate::dialog;
+use crate::filter::TextFilter;
+use crate::ocr;
+use crate::source::{self, AppFilter};
+use crate::text;
+use crate::toast::SpeakPrompt;
 use crate::tts::TTSCommand;
 use arboard::Clipboard;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::mpsc::Sender;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-/// Clipboard monitor configuration
+/// How often the clipboard is polled where change notifications aren't available
+#[cfg(not(windows))]
 const POLL_INTERVAL_MS: u64 = 500;
 
+/// What the read-selection hotkey's clipboard sequence number is while it copies
+pub const COPYING_SELECTION: u32 = u32::MAX;
+
+/// Tries at opening the clipboard while another app holds it, and the wait between
+#[cfg(windows)]
+const OPEN_ATTEMPTS: u32 = 5;
+#[cfg(windows)]
+const OPEN_RETRY_DELAY: Duration = Duration::from_millis(20);
+
+/// Extensions of copied files that are read aloud
+const TEXT_FILE_EXTENSIONS: [&str; 2] = ["txt", "md"];
+
+/// Sizes of copied text files that are read aloud, in bytes
+#[derive(Debug, Clone, Copy)]
+pub struct FileLimits {
+    /// Bigger files are never read (0 never reads copied files)
+    pub max_bytes: u64,
+    /// Bigger files are only read after asking (0 never asks)
+    pub confirm_bytes: u64,
+}
+
+/// Whether text files of some size are read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileCheck {
+    TooLarge,
+    /// Read only after asking
+    Ask,
+    Read,
+}
+
+impl FileLimits {
+    /// Whether files of `size` bytes in all are read
+    pub fn check(&self, size: u64) -> FileCheck {
+        if size > self.max_bytes {
+            FileCheck::TooLarge
+        } else if self.confirm_bytes > 0 && size > self.confirm_bytes {
+            FileCheck::Ask
+        } else {
+            FileCheck::Read
+        }
+    }
+
+    /// The text of the files, if they're small enough, asking first when they're big
+    /// `origin` says where they came from in messages, e.g. "Dropped"
+    pub fn read(&self, paths: &[PathBuf], origin: &str) -> Option<String> {
+        let size = total_size(paths);
+        match self.check(size) {
+            FileCheck::TooLarge => {
+                log::info!("{} files are too large to read ({} KB)", origin, size / 1024);
+                None
+            }
+            FileCheck::Ask if !confirm_files(paths, size, origin) => None,
+            _ => Some(read_files(paths, origin)),
+        }
+    }
+}
+
+/// Size of the files in all, in bytes
+fn total_size(paths: &[PathBuf]) -> u64 {
+    paths
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Ask whether big files are to be read aloud
+pub fn confirm_files(paths: &[PathBuf], size: u64, origin: &str) -> bool {
+    let message = format!(
+        "The {} {} {} KB of text.\n\nRead {} aloud?",
+        origin.to_lowercase(),
+        if paths.len() == 1 { "file has" } else { "files have" },
+        size.div_ceil(1024),
+        if paths.len() == 1 { "it" } else { "them" }
+    );
+    dialog::confirm(&format!("Pocket-Tray - {} Files", origin), &message)
+}
+
+/// The text of the files, each trimmed, a blank line between them
+pub fn read_files(paths: &[PathBuf], origin: &str) -> String {
+    let mut texts = Vec::new();
+    for path in paths {
+        match std::fs::read(path) {
+            Ok(bytes) => texts.push(String::from_utf8_lossy(&bytes).trim().to_string()),
+            Err(e) => log::warn!("Failed to read {}: {}", path.display(), e),
+        }
+    }
+    log::info!("Reading {} {} file(s)", texts.len(), origin.to_lowercase());
+    texts.join("\n\n")
+}
+
+/// Whether the filter patterns let a copied text be spoken
+/// The text itself isn't logged, it may be what the patterns keep quiet
+pub fn text_filter_allows(filter: &Mutex<TextFilter>, text: &str) -> bool {
+    let Ok(filter) = filter.lock() else {
+        return true;
+    };
+    if !filter.allows(text) {
+        log::info!("Skipped text caught by the filter patterns");
+        return false;
+    }
+    if let Some(kind) = filter.machine_string(text) {
+        log::info!("Skipped copied {}", kind);
+        return false;
+    }
+    true
+}
+
+/// Limits on which copies are spoken, changed when the settings file is
+#[derive(Debug, Clone, Copy)]
+pub struct CopyLimits {
+    /// Which copied text files are read
+    pub files: FileLimits,
+    /// Copying the last text again after this long speaks it again
+    pub repeat_after: Option<Duration>,
+    /// Texts longer than this many characters may go to the reading list (0 never asks)
+    pub reading_threshold: usize,
+}
+
+/// What the clipboard thread tells the main thread
+pub enum ClipboardEvent {
+    /// New text was copied from this application, whether or not it's spoken
+    Copied { app: String },
+    /// A long text was copied, for the main thread to ask whether it goes on the
+    /// reading list, with the voice it's read in otherwise
+    LongText { text: String, voice: Option<String> },
+    /// Big text files were copied, `size` bytes in all, for the main thread to ask
+    /// whether they're read, in `voice`
+    BigFiles {
+        paths: Vec<PathBuf>,
+        size: u64,
+        voice: Option<String>,
+    },
+    /// A copy was collected instead of spoken
+    Collected(String),
+    /// The collected copies are to be spoken (sent by the hotkey thread)
+    SpeakCollected,
+    /// The next voice is to be switched to (sent by the hotkey thread)
+    NextVoice,
+    /// Speech is to be stopped (sent by the hotkey thread)
+    Stop,
+    /// Monitoring is to be turned on or off (sent by the hotkey thread)
+    ToggleMonitor,
+    /// The app is to quit (sent by the hotkey thread)
+    Quit,
+    /// The player window is to be shown (sent by the hotkey thread)
+    ShowPlayer,
+    /// The mouse wheel turned over the tray icon by this many notches, positive
+    /// away from the user (sent by the wheel thread)
+    Wheel(i32),
+    /// The clipboard is to be spoken, as when Pocket-Tray is launched again while
+    /// running (sent by the instance window)
+    SpeakClipboard,
+    /// A text is to be spoken, right away or after what's queued (sent by the
+    /// instance window)
+    Speak { text: String, now: bool },
+    /// Speech is to be paused or resumed (sent by the instance window)
+    TogglePause,
+    /// This voice is to be switched to (sent by the instance window)
+    ChangeVoice(String),
+}
+
 /// Clipboard monitor running in a dedicated thread
 pub struct ClipboardMonitor {
     enabled: Arc<AtomicBool>,
-    is_speaking: Arc<AtomicBool>,
+    interrupt: Arc<AtomicBool>,
+    /// Read the text in copied images
+    read_images: Arc<AtomicBool>,
+    /// Ask in a notification before speaking each copy
+    ask_first: Arc<AtomicBool>,
+    /// Collect copies for the main thread to speak together later
+    collect: Arc<AtomicBool>,
+    /// `COPYING_SELECTION` while the read-selection hotkey copies the selection,
+    /// then the clipboard sequence number it left; changes up to that are its own
+    /// to handle, and it's cleared (to 0) on the first change after
+    copying_selection: Arc<AtomicU32>,
     shutdown: Arc<AtomicBool>,
     tts_tx: Sender<TTSCommand>,
+    /// Which copied files are read, when the same text is spoken again and which
+    /// texts may go to the reading list, changed from the settings file
+    limits: Arc<Mutex<CopyLimits>>,
+    /// Which applications copies are spoken from, changed from the tray
+    app_filter: Arc<Mutex<AppFilter>>,
+    /// Which texts are spoken, by content, changed from the settings window
+    text_filter: Arc<Mutex<TextFilter>>,
+    events_tx: Sender<ClipboardEvent>,
     last_text: String,
+    /// When `last_text` was last found on the clipboard
+    last_seen: Instant,
+    /// Hash of the last image read and the text found in it, so an image isn't
+    /// read again for every clipboard update
+    last_image: Option<(u64, String)>,
+    /// The last files copied and their text, None if they weren't to be read, so
+    /// they aren't asked about again for every clipboard update
+    last_files: Option<(Vec<PathBuf>, Option<String>)>,
+    /// Notification asking whether to speak the last copy, replaced by the next one
+    speak_prompt: Option<SpeakPrompt>,
 }
 
 impl ClipboardMonitor {
     /// Create a new clipboard monitor
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         enabled: Arc<AtomicBool>,
-        is_speaking: Arc<AtomicBool>,
+        interrupt: Arc<AtomicBool>,
+        read_images: Arc<AtomicBool>,
+        ask_first: Arc<AtomicBool>,
+        collect: Arc<AtomicBool>,
+        copying_selection: Arc<AtomicU32>,
         shutdown: Arc<AtomicBool>,
         tts_tx: Sender<TTSCommand>,
+        limits: Arc<Mutex<CopyLimits>>,
+        app_filter: Arc<Mutex<AppFilter>>,
+        text_filter: Arc<Mutex<TextFilter>>,
+        events_tx: Sender<ClipboardEvent>,
     ) -> Self {
         Self {
             enabled,
-            is_speaking,
+            interrupt,
+            read_images,
+            ask_first,
+            collect,
+            copying_selection,
             shutdown,
             tts_tx,
+            limits,
+            app_filter,
+            text_filter,
+            events_tx,
             last_text: String::new(),
+            last_seen: Instant::now(),
+            last_image: None,
+            last_files: None,
+            speak_prompt: None,
         }
     }
 
@@ -47,75 +268,441 @@ impl ClipboardMonitor {
         };
 
         // Initialize last_text with current clipboard content to avoid speaking it at launch
-        if let Ok(text) = clipboard.get_text() {
-            self.last_text = text.trim().to_string();
+        if let Some(text) = read_text(&mut clipboard) {
+            self.last_text = text;
             log::info!("Initialized with existing clipboard content ({} chars)", self.last_text.len());
         }
 
         log::info!("Clipboard monitor started");
+        self.listen(&mut clipboard);
+        log::info!("Clipboard monitor shutting down");
+    }
 
-        loop {
-            // Check for shutdown
-            if self.shutdown.load(Ordering::Relaxed) {
-                log::info!("Clipboard monitor shutting down");
-                break;
+    /// Wait for clipboard changes on a hidden message-only window, checking the
+    /// clipboard only when Windows reports one
+    /// The thread sleeps in GetMessageW meanwhile; it isn't joined, so it ends with the process
+    #[cfg(windows)]
+    fn listen(&mut self, clipboard: &mut Clipboard) {
+        use windows::core::{w, PCWSTR};
+        use windows::Win32::System::DataExchange::{AddClipboardFormatListener, RemoveClipboardFormatListener};
+        use windows::Win32::UI::WindowsAndMessaging::{
+            CreateWindowExW, DestroyWindow, GetMessageW, HWND_MESSAGE, MSG, WINDOW_EX_STYLE,
+            WINDOW_STYLE, WM_CLIPBOARDUPDATE,
+        };
+
+        unsafe {
+            // The predefined STATIC class saves registering one; posted messages are
+            // read straight off the queue, never dispatched to it
+            let hwnd = match CreateWindowExW(
+                WINDOW_EX_STYLE(0),
+                w!("STATIC"),
+                PCWSTR::null(),
+                WINDOW_STYLE(0),
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                None,
+                None,
+                None,
+            ) {
+                Ok(hwnd) => hwnd,
+                Err(e) => {
+                    log::error!("Failed to create clipboard listener window: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = AddClipboardFormatListener(hwnd) {
+                log::error!("Failed to listen for clipboard changes: {}", e);
+                let _ = DestroyWindow(hwnd);
+                return;
             }
 
+            let mut msg = MSG::default();
+            // 0 is WM_QUIT, -1 an error
+            while GetMessageW(&mut msg, None, 0, 0).0 > 0 {
+                if self.shutdown.load(Ordering::Relaxed) {
+                    break;
+                }
+                if msg.message == WM_CLIPBOARDUPDATE && !self.check(clipboard) {
+                    break;
+                }
+            }
+
+            let _ = RemoveClipboardFormatListener(hwnd);
+            let _ = DestroyWindow(hwnd);
+        }
+    }
+
+    /// Poll the clipboard, where change notifications aren't available
+    #[cfg(not(windows))]
+    fn listen(&mut self, clipboard: &mut Clipboard) {
+        while !self.shutdown.load(Ordering::Relaxed) {
             std::thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+            if !self.check(clipboard) {
+                break;
+            }
+        }
+    }
+
+    /// The current limits
+    fn limits(&self) -> CopyLimits {
+        *self.limits.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Read the copied text files, if any; big ones are left to the main thread to
+    /// ask about, so copies aren't missed while the question is open
+    fn read_files(&mut self, clipboard: &mut Clipboard) -> Option<String> {
+        let file_limits = self.limits().files;
+        if file_limits.max_bytes == 0 {
+            return None;
+        }
+        let paths: Vec<PathBuf> = clipboard
+            .get()
+            .file_list()
+            .ok()?
+            .into_iter()
+            .filter(|path| is_text_file(path))
+            .collect();
+        if paths.is_empty() {
+            return None;
+        }
+        if let Some((last_paths, text)) = &self.last_files {
+            if *last_paths == paths {
+                return text.clone();
+            }
+        }
 
-            // Check if monitoring is enabled
-            if !self.enabled.load(Ordering::Relaxed) {
-                continue;
+        let size = total_size(&paths);
+        let text = match file_limits.check(size) {
+            FileCheck::TooLarge => {
+                log::info!("Copied files are too large to read ({} KB)", size / 1024);
+                None
+            }
+            FileCheck::Ask => {
+                let app = source::source_app();
+                let (allowed, voice) = self.app_verdict(app.as_deref());
+                if allowed {
+                    let _ = self.events_tx.send(ClipboardEvent::BigFiles {
+                        paths: paths.clone(),
+                        size,
+                        voice,
+                    });
+                } else {
+                    log::info!("Not reading files copied from {}", app.as_deref().unwrap_or("unknown app"));
+                }
+                None
             }
+            FileCheck::Read => Some(read_files(&paths, "Copied")),
+        };
+        self.last_files = Some((paths, text.clone()));
+        text
+    }
 
-            // Check if currently speaking (ignore new text per user requirement)
-            if self.is_speaking.load(Ordering::Relaxed) {
-                continue;
+    /// Whether the clipboard holds what the read-selection hotkey copied or put back
+    fn is_selection_copy(&self) -> bool {
+        let own = self.copying_selection.load(Ordering::SeqCst);
+        match own {
+            0 => false,
+            COPYING_SELECTION => true,
+            own if clipboard_sequence() <= own => true,
+            own => {
+                // Unless the hotkey has started on another copy meanwhile
+                let _ = self
+                    .copying_selection
+                    .compare_exchange(own, 0, Ordering::SeqCst, Ordering::SeqCst);
+                false
             }
+        }
+    }
 
-            // Get clipboard text
-            let text = match clipboard.get_text() {
-                Ok(t) => t,
-                Err(_) => continue, // Not text content or clipboard error
-            };
+    /// Whether copies from `app` are spoken, and in which voice
+    fn app_verdict(&self, app: Option<&str>) -> (bool, Option<String>) {
+        self.app_filter
+            .lock()
+            .map_or((true, None), |filter| (filter.allows(app), filter.voice(app)))
+    }
+
+    /// Recognize the text in the clipboard's image, if it holds one
+    fn read_image(&mut self, clipboard: &mut Clipboard) -> Option<String> {
+        let image = clipboard.get_image().ok()?;
+
+        let mut hasher = DefaultHasher::new();
+        (image.width, image.height).hash(&mut hasher);
+        image.bytes.hash(&mut hasher);
+        let hash = hasher.finish();
+        if let Some((last_hash, text)) = &self.last_image {
+            if *last_hash == hash {
+                return Some(text.clone());
+            }
+        }
 
-            // Check if it's new text and not empty
-            let text = text.trim().to_string();
-            if text == self.last_text || text.is_empty() {
-                continue;
+        let text = match ocr::recognize(image.width, image.height, &image.bytes) {
+            Ok(text) => text.trim().to_string(),
+            Err(e) => {
+                log::warn!("Failed to read text in copied image: {}", e);
+                return None;
             }
+        };
+        log::info!(
+            "Read {} chars of text in copied {}x{} image",
+            text.len(),
+            image.width,
+            image.height
+        );
+        self.last_image = Some((hash, text.clone()));
+        Some(text)
+    }
+
+    /// Speak the clipboard's text if it's new
+    /// Returns false once the TTS thread is gone
+    fn check(&mut self, clipboard: &mut Clipboard) -> bool {
+        // Check if monitoring is enabled
+        if !self.enabled.load(Ordering::Relaxed) || self.is_selection_copy() {
+            return true;
+        }
+
+        // Never read out what password managers and the like mark as private
+        if is_private() {
+            log::info!("Skipped clipboard content marked as private");
+            return true;
+        }
+
+        // Get clipboard text, the contents of copied text files, or the text in a
+        // copied image
+        let text = match read_text(clipboard).or_else(|| self.read_files(clipboard)) {
+            Some(text) => text,
+            None if self.read_images.load(Ordering::Relaxed) => match self.read_image(clipboard) {
+                Some(text) => text,
+                None => return true,
+            },
+            None => return true, // Not text content or clipboard error
+        };
+
+        if text.is_empty() {
+            return true;
+        }
 
-            // Check text is reasonable length (avoid giant pastes)
-            if text.len() > 10000 {
-                log::warn!("Clipboard text too long ({} chars), ignoring", text.len());
-                self.last_text = text;
-                continue;
+        // The same text copied again is spoken again only after a while, so the
+        // several updates some apps make per copy don't repeat it (and polling,
+        // which sees it all along, never does)
+        let now = Instant::now();
+        let since_seen = now.duration_since(self.last_seen);
+        self.last_seen = now;
+        if text == self.last_text {
+            if self.limits().repeat_after.is_none_or(|after| since_seen < after) {
+                return true;
             }
+            log::info!("Same text copied again, speaking it again");
+        }
+
+        // Store and speak
+        log::info!("New clipboard text detected ({} chars)", text.len());
+        self.last_text = text.clone();
+
+        // Skip copies from apps filtered out in the tray
+        let app = source::source_app();
+        if let Some(app) = &app {
+            let _ = self.events_tx.send(ClipboardEvent::Copied { app: app.clone() });
+        }
+        let (allowed, voice) = self.app_verdict(app.as_deref());
+        if !allowed {
+            log::info!("Not speaking copy from {}", app.as_deref().unwrap_or("unknown app"));
+            return true;
+        }
 
-            // Store and speak
-            log::info!("New clipboard text detected ({} chars)", text.len());
-            self.last_text = text.clone();
+        if !text_filter_allows(&self.text_filter, &text) {
+            return true;
+        }
 
-            // Send to TTS thread
-            if let Err(e) = self.tts_tx.send(TTSCommand::Speak { text }) {
-                log::error!("Failed to send TTS command: {}", e);
-                break; // Channel closed
+        // Offer to put long texts aside instead; the main thread asks, so copies
+        // aren't missed while the question is open
+        let reading_threshold = self.limits().reading_threshold;
+        if reading_threshold > 0 && text.chars().count() > reading_threshold {
+            return self.events_tx.send(ClipboardEvent::LongText { text, voice }).is_ok();
+        }
+
+        // Keep it with the others collected, to be spoken together
+        if self.collect.load(Ordering::Relaxed) {
+            log::info!("Copy collected");
+            return self.events_tx.send(ClipboardEvent::Collected(text)).is_ok();
+        }
+
+        // Send to TTS thread (queued if something is already being spoken,
+        // unless interrupt mode wants it spoken right away); long texts are
+        // split into parts there
+        let interrupt = self.interrupt.load(Ordering::Relaxed);
+        let command = move |text| {
+            let voice = voice.clone();
+            if interrupt {
+                TTSCommand::SpeakNow { text, voice }
+            } else {
+                TTSCommand::Speak { text, voice }
+            }
+        };
+
+        // Or only once the notification's "Speak" is clicked
+        if self.ask_first.load(Ordering::Relaxed) {
+            let tts_tx = self.tts_tx.clone();
+            let spoken = text.clone();
+            let command = command.clone();
+            // Dropping the previous prompt takes it off the screen
+            self.speak_prompt = None;
+            match SpeakPrompt::show(&text, move || {
+                let _ = tts_tx.send(command(spoken));
+            }) {
+                Ok(prompt) => {
+                    log::info!("Asking before speaking the copy");
+                    self.speak_prompt = Some(prompt);
+                    return true;
+                }
+                Err(e) => log::warn!("Failed to ask before speaking, speaking right away: {}", e),
             }
         }
+
+        if let Err(e) = self.tts_tx.send(command(text)) {
+            log::error!("Failed to send TTS command: {}", e);
+            return false; // Channel closed
+        }
+        true
+    }
+}
+
+/// The clipboard's text, trimmed
+/// Text copied from a browser is read from its HTML, which keeps paragraphs apart
+/// and leaves out link targets and page furniture the plain text version includes
+pub fn read_text(clipboard: &mut Clipboard) -> Option<String> {
+    if let Ok(html) = clipboard.get().html() {
+        let text = text::html_to_text(&html);
+        if !text.is_empty() {
+            return Some(text);
+        }
     }
+    clipboard.get_text().ok().map(|text| text.trim().to_string())
+}
+
+/// Whether a copied or dropped file is one that's read aloud
+pub fn is_text_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| TEXT_FILE_EXTENSIONS.iter().any(|known| ext.eq_ignore_ascii_case(known)))
+}
+
+/// Whether the clipboard holds content its owner asked monitors and clipboard
+/// history to leave alone, as password managers do for passwords
+#[cfg(windows)]
+fn is_private() -> bool {
+    use windows::core::w;
+    use windows::Win32::Foundation::HGLOBAL;
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, GetClipboardData, IsClipboardFormatAvailable, RegisterClipboardFormatW,
+    };
+    use windows::Win32::System::Memory::{GlobalLock, GlobalUnlock};
+
+    unsafe {
+        // Flags whose mere presence marks the content
+        for name in [
+            w!("ExcludeClipboardContentFromMonitorProcessing"),
+            w!("Clipboard Viewer Ignore"),
+        ] {
+            let format = RegisterClipboardFormatW(name);
+            if format != 0 && IsClipboardFormatAvailable(format).is_ok() {
+                return true;
+            }
+        }
+
+        // A DWORD, 0 keeps the content out of clipboard history
+        let format = RegisterClipboardFormatW(w!("CanIncludeInClipboardHistory"));
+        if format == 0 || IsClipboardFormatAvailable(format).is_err() {
+            return false;
+        }
+        // Another app may have it open for a moment; if it stays unreadable the flag
+        // is taken as absent, rather than silencing every copy
+        if !open_clipboard() {
+            log::debug!("Clipboard history flag couldn't be read");
+            return false;
+        }
+        let allowed = GetClipboardData(format).ok().and_then(|data| {
+            let data = HGLOBAL(data.0);
+            let value = GlobalLock(data) as *const u32;
+            if value.is_null() {
+                return None;
+            }
+            let allowed = *value != 0;
+            let _ = GlobalUnlock(data);
+            Some(allowed)
+        });
+        let _ = CloseClipboard();
+        allowed != Some(true)
+    }
+}
+
+#[cfg(not(windows))]
+fn is_private() -> bool {
+    false
+}
+
+/// Open the clipboard, trying a few times while another app holds it
+#[cfg(windows)]
+fn open_clipboard() -> bool {
+    use windows::Win32::System::DataExchange::OpenClipboard;
+
+    for attempt in 0..OPEN_ATTEMPTS {
+        if attempt > 0 {
+            std::thread::sleep(OPEN_RETRY_DELAY);
+        }
+        if unsafe { OpenClipboard(None) }.is_ok() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Number of the clipboard's latest change, counting up from the start of the session
+#[cfg(windows)]
+pub fn clipboard_sequence() -> u32 {
+    unsafe { windows::Win32::System::DataExchange::GetClipboardSequenceNumber() }
+}
+
+#[cfg(not(windows))]
+pub fn clipboard_sequence() -> u32 {
+    0
 }
 
 /// Spawn the clipboard monitor in a separate thread
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_clipboard_thread(
     enabled: Arc<AtomicBool>,
-    is_speaking: Arc<AtomicBool>,
+    interrupt: Arc<AtomicBool>,
+    read_images: Arc<AtomicBool>,
+    ask_first: Arc<AtomicBool>,
+    collect: Arc<AtomicBool>,
+    copying_selection: Arc<AtomicU32>,
     shutdown: Arc<AtomicBool>,
     tts_tx: Sender<TTSCommand>,
+    limits: Arc<Mutex<CopyLimits>>,
+    app_filter: Arc<Mutex<AppFilter>>,
+    text_filter: Arc<Mutex<TextFilter>>,
+    events_tx: Sender<ClipboardEvent>,
 ) -> std::thread::JoinHandle<()> {
     std::thread::Builder::new()
         .name("clipboard-monitor".into())
         .spawn(move || {
-            let mut monitor = ClipboardMonitor::new(enabled, is_speaking, shutdown, tts_tx);
+            let mut monitor = ClipboardMonitor::new(
+                enabled,
+                interrupt,
+                read_images,
+                ask_first,
+                collect,
+                copying_selection,
+                shutdown,
+                tts_tx,
+                limits,
+                app_filter,
+                text_filter,
+                events_tx,
+            );
             monitor.run();
         })
         .expect("Failed to spawn clipboard thread")
@@ -0,0 +1,140 @@
+//! Native file dialogs and message boxes
+
+use std::path::PathBuf;
+
+/// Filter for .txt and .md files, as (description, pattern) pairs
+pub const TEXT_FILE_FILTER: &[(&str, &str)] = &[
+    ("Text files (*.txt, *.md)", "*.txt;*.md"),
+    ("All files (*.*)", "*.*"),
+];
+
+/// Filter for log files followed as they grow
+pub const LOG_FILE_FILTER: &[(&str, &str)] = &[
+    ("Log files (*.log, *.txt)", "*.log;*.txt"),
+    ("All files (*.*)", "*.*"),
+];
+
+/// Filter for WAV files used as voice prompts
+pub const AUDIO_FILE_FILTER: &[(&str, &str)] = &[
+    ("WAV audio (*.wav)", "*.wav"),
+    ("All files (*.*)", "*.*"),
+];
+
+/// Show a Windows "Open" dialog and return the chosen file, if any
+#[cfg(windows)]
+pub fn open_file(title: &str, filter: &[(&str, &str)]) -> Option<PathBuf> {
+    use std::ffi::{OsStr, OsString};
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use windows::core::{PCWSTR, PWSTR};
+    use windows::Win32::UI::Controls::Dialogs::{
+        GetOpenFileNameW, OFN_FILEMUSTEXIST, OFN_NOCHANGEDIR, OFN_PATHMUSTEXIST, OPENFILENAMEW,
+    };
+
+    let title: Vec<u16> = OsStr::new(title)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    // Filter is a sequence of NUL-separated description/pattern pairs ending in a double NUL
+    let filter: Vec<u16> = filter
+        .iter()
+        .flat_map(|(description, pattern)| [*description, *pattern])
+        .flat_map(|part| OsStr::new(part).encode_wide().chain(std::iter::once(0)))
+        .chain(std::iter::once(0))
+        .collect();
+
+    let mut file_buffer = [0u16; 1024];
+
+    let mut ofn = OPENFILENAMEW {
+        lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
+        lpstrFilter: PCWSTR::from_raw(filter.as_ptr()),
+        nFilterIndex: 1,
+        lpstrFile: PWSTR::from_raw(file_buffer.as_mut_ptr()),
+        nMaxFile: file_buffer.len() as u32,
+        lpstrTitle: PCWSTR::from_raw(title.as_ptr()),
+        Flags: OFN_FILEMUSTEXIST | OFN_PATHMUSTEXIST | OFN_NOCHANGEDIR,
+        ..Default::default()
+    };
+
+    let chosen = unsafe { GetOpenFileNameW(&mut ofn) }.as_bool();
+    if !chosen {
+        return None;
+    }
+
+    let len = file_buffer.iter().position(|&c| c == 0).unwrap_or(file_buffer.len());
+    Some(PathBuf::from(OsString::from_wide(&file_buffer[..len])))
+}
+
+#[cfg(not(windows))]
+pub fn open_file(title: &str, _filter: &[(&str, &str)]) -> Option<PathBuf> {
+    log::warn!("File dialogs are not supported on this platform ({})", title);
+    None
+}
+
+/// Ask a yes/no question in a message box, returning true for "Yes"
+#[cfg(windows)]
+pub fn confirm(title: &str, message: &str) -> bool {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        MessageBoxW, IDYES, MB_ICONQUESTION, MB_SETFOREGROUND, MB_TOPMOST, MB_YESNO,
+    };
+
+    let title: Vec<u16> = OsStr::new(title)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let message: Vec<u16> = OsStr::new(message)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    // There's no window to own the box, so bring it to the front
+    let answer = unsafe {
+        MessageBoxW(
+            None,
+            PCWSTR::from_raw(message.as_ptr()),
+            PCWSTR::from_raw(title.as_ptr()),
+            MB_YESNO | MB_ICONQUESTION | MB_TOPMOST | MB_SETFOREGROUND,
+        )
+    };
+    answer == IDYES
+}
+
+/// Ask a yes/no/cancel question in a message box, returning Some(true) for "Yes",
+/// Some(false) for "No" and None for "Cancel"
+#[cfg(windows)]
+pub fn ask(title: &str, message: &str) -> Option<bool> {
+    use windows::core::HSTRING;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        MessageBoxW, IDNO, IDYES, MB_ICONQUESTION, MB_SETFOREGROUND, MB_TOPMOST, MB_YESNOCANCEL,
+    };
+
+    // There's no window to own the box, so bring it to the front
+    let answer = unsafe {
+        MessageBoxW(
+            None,
+            &HSTRING::from(message),
+            &HSTRING::from(title),
+            MB_YESNOCANCEL | MB_ICONQUESTION | MB_TOPMOST | MB_SETFOREGROUND,
+        )
+    };
+    match answer {
+        IDYES => Some(true),
+        IDNO => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(not(windows))]
+pub fn ask(title: &str, _message: &str) -> Option<bool> {
+    log::warn!("Message boxes are not supported on this platform ({})", title);
+    None
+}
+
+#[cfg(not(windows))]
+pub fn confirm(title: &str, _message: &str) -> bool {
+    log::warn!("Message boxes are not supported on this platform ({})", title);
+    false
+}
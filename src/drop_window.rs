@@ -0,0 +1,461 @@
+//! Drop target, a tiny always-on-top window that speaks whatever text or text
+//! file is dropped on it, without going through the clipboard
+//!
+//! The window runs on a thread of its own and sends the dropped text to the app,
+//! which speaks it right away. It stays open until the app closes it or it's closed
+//! from its system menu.
+
+use crate::clipboard::FileLimits;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// The open drop target window
+pub struct DropWindow {
+    thread: JoinHandle<()>,
+    /// Set to have the window close itself
+    closing: Arc<AtomicBool>,
+}
+
+impl DropWindow {
+    /// Whether the window is still open, which it isn't once closed from its menu
+    pub fn is_open(&self) -> bool {
+        !self.thread.is_finished()
+    }
+
+    /// Close the window, shortly
+    pub fn close(&self) {
+        self.closing.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Open the drop target on its own thread
+/// Dropped text files are read within `limits`; the text is sent on `text_tx`
+#[cfg(windows)]
+pub fn open(limits: FileLimits, text_tx: Sender<String>) -> std::io::Result<DropWindow> {
+    let closing = Arc::new(AtomicBool::new(false));
+    let thread = std::thread::Builder::new()
+        .name("drop-window".to_string())
+        .spawn({
+            let closing = Arc::clone(&closing);
+            move || win::run(limits, text_tx, closing)
+        })?;
+    Ok(DropWindow { thread, closing })
+}
+
+#[cfg(not(windows))]
+pub fn open(_limits: FileLimits, _text_tx: Sender<String>) -> std::io::Result<DropWindow> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "the drop target is not supported on this platform",
+    ))
+}
+
+#[cfg(windows)]
+mod win {
+    use crate::clipboard::{is_text_file, FileLimits};
+    use std::cell::{Cell, RefCell};
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::Sender;
+    use std::sync::Arc;
+    use windows::core::{implement, w, HSTRING, PCWSTR};
+    use windows::Win32::Foundation::{HGLOBAL, HWND, LPARAM, LRESULT, POINTL, RECT, WPARAM};
+    use windows::Win32::Graphics::Gdi::{GetStockObject, COLOR_BTNFACE, DEFAULT_GUI_FONT, HBRUSH};
+    use windows::Win32::System::Com::{IDataObject, DVASPECT_CONTENT, FORMATETC, TYMED_HGLOBAL};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::System::Memory::{GlobalLock, GlobalSize, GlobalUnlock};
+    use windows::Win32::System::Ole::{
+        IDropTarget, IDropTarget_Impl, OleInitialize, OleUninitialize, RegisterDragDrop,
+        ReleaseStgMedium, RevokeDragDrop, CF_HDROP, CF_UNICODETEXT, CLIPBOARD_FORMAT, DROPEFFECT,
+        DROPEFFECT_COPY, DROPEFFECT_NONE,
+    };
+    use windows::Win32::System::SystemServices::MODIFIERKEYS_FLAGS;
+    use windows::Win32::UI::Shell::{DragQueryFileW, HDROP};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        AdjustWindowRectEx, CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW,
+        GetDlgItem, GetMessageW, KillTimer, LoadCursorW, PostMessageW, PostQuitMessage,
+        RegisterClassW, SendMessageW, SetTimer, SetWindowTextW, ShowWindow, SystemParametersInfoW,
+        TranslateMessage, HMENU, HTCAPTION, IDC_ARROW, MSG, SPI_GETWORKAREA, SW_SHOWNOACTIVATE,
+        SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, WINDOW_EX_STYLE, WINDOW_STYLE, WM_APP, WM_CLOSE,
+        WM_DESTROY, WM_NCHITTEST, WM_SETFONT, WM_TIMER, WNDCLASSW, WS_BORDER, WS_CHILD,
+        WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP, WS_SYSMENU, WS_VISIBLE,
+    };
+
+    const CLASS_NAME: PCWSTR = w!("PocketTrayDropTarget");
+
+    /// Size of the window's client area
+    const WIDTH: i32 = 132;
+    const HEIGHT: i32 = 40;
+
+    /// Gap between the window and the right edge of the work area
+    const MARGIN: i32 = 12;
+
+    /// How often the window checks whether the app closed it, in milliseconds
+    const CLOSE_CHECK_MS: u32 = 250;
+    const CLOSE_TIMER: usize = 1;
+
+    /// Posted to the window after a drop, to read it once the drag is over
+    const WM_DROPPED: u32 = WM_APP + 1;
+
+    /// Static control styles: centered on both axes, showing "&" as is
+    const SS_CENTER: u32 = 0x1;
+    const SS_CENTERIMAGE: u32 = 0x200;
+    const SS_NOPREFIX: u32 = 0x80;
+
+    const ID_LABEL: i32 = 100;
+
+    const IDLE_LABEL: &str = "Drop text to speak";
+    const HOVER_LABEL: &str = "Release to speak";
+
+    /// What was dropped, before it's read
+    enum Dropped {
+        Text(String),
+        /// The text files among the dropped files
+        Files(Vec<PathBuf>),
+    }
+
+    /// Where dropped text goes, kept for the window procedure
+    struct DropState {
+        limits: FileLimits,
+        text_tx: Sender<String>,
+        closing: Arc<AtomicBool>,
+        /// The latest drop, read when WM_DROPPED arrives
+        pending: Option<Dropped>,
+    }
+
+    thread_local! {
+        static STATE: RefCell<Option<DropState>> = const { RefCell::new(None) };
+    }
+
+    pub(super) fn run(limits: FileLimits, text_tx: Sender<String>, closing: Arc<AtomicBool>) {
+        // Drag and drop needs OLE on the thread that owns the window
+        if let Err(e) = unsafe { OleInitialize(None) } {
+            log::error!("Failed to set up drag and drop: {}", e);
+            return;
+        }
+        match unsafe { create() } {
+            Ok(hwnd) => {
+                STATE.with(|state| {
+                    *state.borrow_mut() = Some(DropState {
+                        limits,
+                        text_tx,
+                        closing,
+                        pending: None,
+                    })
+                });
+                let target: IDropTarget = TextDropTarget {
+                    hwnd,
+                    accepted: Cell::new(false),
+                }
+                .into();
+
+                unsafe {
+                    match RegisterDragDrop(hwnd, &target) {
+                        Ok(()) => {
+                            SetTimer(hwnd, CLOSE_TIMER, CLOSE_CHECK_MS, None);
+                            // Don't take focus from the app text is dragged out of
+                            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+
+                            let mut msg = MSG::default();
+                            while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                                let _ = TranslateMessage(&msg);
+                                DispatchMessageW(&msg);
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("Failed to accept drops on the drop target: {}", e);
+                            let _ = DestroyWindow(hwnd);
+                        }
+                    }
+                }
+                STATE.with(|state| state.borrow_mut().take());
+            }
+            Err(e) => log::error!("Failed to open the drop target: {}", e),
+        }
+        unsafe { OleUninitialize() };
+    }
+
+    /// Create the window, at the right edge of the work area
+    unsafe fn create() -> windows::core::Result<HWND> {
+        let instance = GetModuleHandleW(None)?;
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: instance.into(),
+            hCursor: LoadCursorW(None, IDC_ARROW)?,
+            hbrBackground: HBRUSH((COLOR_BTNFACE.0 + 1) as isize as _),
+            lpszClassName: CLASS_NAME,
+            ..Default::default()
+        };
+        // Fails harmlessly when the window was opened before
+        RegisterClassW(&class);
+
+        // No caption, it's moved by dragging it and closed from its system menu
+        let style = WS_POPUP | WS_BORDER | WS_SYSMENU;
+        let ex_style = WS_EX_TOPMOST | WS_EX_TOOLWINDOW;
+        let mut rect = RECT {
+            left: 0,
+            top: 0,
+            right: WIDTH,
+            bottom: HEIGHT,
+        };
+        AdjustWindowRectEx(&mut rect, style, false, ex_style)?;
+        let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+
+        let mut work_area = RECT::default();
+        SystemParametersInfoW(
+            SPI_GETWORKAREA,
+            0,
+            Some((&mut work_area as *mut RECT).cast()),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )?;
+
+        let hwnd = CreateWindowExW(
+            ex_style,
+            CLASS_NAME,
+            w!("Pocket-Tray Drop Target"),
+            style,
+            work_area.right - width - MARGIN,
+            (work_area.top + work_area.bottom - height) / 2,
+            width,
+            height,
+            None,
+            None,
+            instance,
+            None,
+        )?;
+
+        let label = CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("STATIC"),
+            &HSTRING::from(IDLE_LABEL),
+            WS_CHILD | WS_VISIBLE | WINDOW_STYLE(SS_CENTER | SS_CENTERIMAGE | SS_NOPREFIX),
+            0,
+            0,
+            WIDTH,
+            HEIGHT,
+            hwnd,
+            HMENU(ID_LABEL as isize as _),
+            None,
+            None,
+        )?;
+        // Controls start in the bitmap system font otherwise
+        let font = GetStockObject(DEFAULT_GUI_FONT);
+        SendMessageW(label, WM_SETFONT, WPARAM(font.0 as usize), LPARAM(1));
+
+        Ok(hwnd)
+    }
+
+    unsafe extern "system" fn window_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        match msg {
+            // Dragging anywhere in the window moves it, and right-clicking opens
+            // its system menu
+            WM_NCHITTEST => LRESULT(HTCAPTION as isize),
+            WM_DROPPED => {
+                speak_pending();
+                LRESULT(0)
+            }
+            WM_TIMER => {
+                let closing = STATE.with(|state| {
+                    state
+                        .borrow()
+                        .as_ref()
+                        .is_some_and(|state| state.closing.load(Ordering::SeqCst))
+                });
+                if closing {
+                    let _ = DestroyWindow(hwnd);
+                }
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                let _ = DestroyWindow(hwnd);
+                LRESULT(0)
+            }
+            WM_DESTROY => {
+                let _ = KillTimer(hwnd, CLOSE_TIMER);
+                let _ = RevokeDragDrop(hwnd);
+                PostQuitMessage(0);
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+
+    /// Read the latest drop and hand its text to the app
+    /// This runs after the drag is over, so asking about big files doesn't hold up
+    /// the app the text was dragged from
+    fn speak_pending() {
+        STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            let Some(state) = state.as_mut() else {
+                return;
+            };
+            let text = match state.pending.take() {
+                Some(Dropped::Text(text)) => Some(text),
+                Some(Dropped::Files(paths)) => state.limits.read(&paths, "Dropped"),
+                None => None,
+            };
+            match text.map(|text| text.trim().to_string()) {
+                Some(text) if !text.is_empty() => {
+                    log::info!("Speaking dropped text ({} chars)", text.len());
+                    let _ = state.text_tx.send(text);
+                }
+                Some(_) => log::info!("Dropped text is empty"),
+                None => {}
+            }
+        });
+    }
+
+    /// Accepts text, and files among which there are text files
+    #[implement(IDropTarget)]
+    struct TextDropTarget {
+        hwnd: HWND,
+        /// Whether what's being dragged over can be dropped
+        accepted: Cell<bool>,
+    }
+
+    impl TextDropTarget {
+        /// Copy if what's dragged can be dropped and its source allows copying;
+        /// anything else could have the source delete the text dragged out of it
+        fn effect(&self, allowed: DROPEFFECT) -> DROPEFFECT {
+            if self.accepted.get() && allowed.0 & DROPEFFECT_COPY.0 != 0 {
+                DROPEFFECT_COPY
+            } else {
+                DROPEFFECT_NONE
+            }
+        }
+
+        fn set_label(&self, text: &str) {
+            unsafe {
+                if let Ok(label) = GetDlgItem(self.hwnd, ID_LABEL) {
+                    let _ = SetWindowTextW(label, &HSTRING::from(text));
+                }
+            }
+        }
+    }
+
+    impl IDropTarget_Impl for TextDropTarget_Impl {
+        fn DragEnter(
+            &self,
+            data: Option<&IDataObject>,
+            _keys: MODIFIERKEYS_FLAGS,
+            _point: &POINTL,
+            effect: *mut DROPEFFECT,
+        ) -> windows::core::Result<()> {
+            self.accepted
+                .set(data.and_then(|data| unsafe { dropped(data) }).is_some());
+            if self.accepted.get() {
+                self.set_label(HOVER_LABEL);
+            }
+            unsafe { *effect = self.effect(*effect) };
+            Ok(())
+        }
+
+        fn DragOver(
+            &self,
+            _keys: MODIFIERKEYS_FLAGS,
+            _point: &POINTL,
+            effect: *mut DROPEFFECT,
+        ) -> windows::core::Result<()> {
+            unsafe { *effect = self.effect(*effect) };
+            Ok(())
+        }
+
+        fn DragLeave(&self) -> windows::core::Result<()> {
+            self.accepted.set(false);
+            self.set_label(IDLE_LABEL);
+            Ok(())
+        }
+
+        fn Drop(
+            &self,
+            data: Option<&IDataObject>,
+            _keys: MODIFIERKEYS_FLAGS,
+            _point: &POINTL,
+            effect: *mut DROPEFFECT,
+        ) -> windows::core::Result<()> {
+            unsafe { *effect = self.effect(*effect) };
+            self.accepted.set(false);
+            self.set_label(IDLE_LABEL);
+
+            let dropped = data.and_then(|data| unsafe { dropped(data) });
+            if dropped.is_some() {
+                STATE.with(|state| {
+                    if let Some(state) = state.borrow_mut().as_mut() {
+                        state.pending = dropped;
+                    }
+                });
+                unsafe {
+                    let _ = PostMessageW(self.hwnd, WM_DROPPED, WPARAM(0), LPARAM(0));
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// The text, or else the text files, in dragged data
+    unsafe fn dropped(data: &IDataObject) -> Option<Dropped> {
+        if let Some(text) = with_global(data, CF_UNICODETEXT, |global| read_text(global)).flatten()
+        {
+            return Some(Dropped::Text(text));
+        }
+        let paths: Vec<PathBuf> =
+            with_global(data, CF_HDROP, |global| file_paths(HDROP(global.0)))?
+                .into_iter()
+                .filter(|path| is_text_file(path))
+                .collect();
+        (!paths.is_empty()).then_some(Dropped::Files(paths))
+    }
+
+    /// Run `read` on the memory holding `format`, if the data has it
+    unsafe fn with_global<T>(
+        data: &IDataObject,
+        format: CLIPBOARD_FORMAT,
+        read: impl FnOnce(HGLOBAL) -> T,
+    ) -> Option<T> {
+        let format = FORMATETC {
+            cfFormat: format.0,
+            ptd: std::ptr::null_mut(),
+            dwAspect: DVASPECT_CONTENT.0,
+            lindex: -1,
+            tymed: TYMED_HGLOBAL.0 as u32,
+        };
+        let mut medium = data.GetData(&format).ok()?;
+        let result = read(medium.u.hGlobal);
+        ReleaseStgMedium(&mut medium);
+        Some(result)
+    }
+
+    /// The null-terminated UTF-16 text in `global`
+    unsafe fn read_text(global: HGLOBAL) -> Option<String> {
+        let ptr = GlobalLock(global) as *const u16;
+        if ptr.is_null() {
+            return None;
+        }
+        let chars = std::slice::from_raw_parts(ptr, GlobalSize(global) / 2);
+        let end = chars.iter().position(|&c| c == 0).unwrap_or(chars.len());
+        let text = String::from_utf16_lossy(&chars[..end]);
+        let _ = GlobalUnlock(global);
+        Some(text)
+    }
+
+    unsafe fn file_paths(hdrop: HDROP) -> Vec<PathBuf> {
+        let count = DragQueryFileW(hdrop, u32::MAX, None);
+        (0..count)
+            .map(|i| {
+                let len = DragQueryFileW(hdrop, i, None) as usize;
+                let mut path = vec![0u16; len + 1];
+                DragQueryFileW(hdrop, i, Some(&mut path));
+                PathBuf::from(OsString::from_wide(&path[..len]))
+            })
+            .collect()
+    }
+}
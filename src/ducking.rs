@@ -0,0 +1,108 @@
+//! Lowering the volume of other applications while speaking ("ducking")
+
+use anyhow::Result;
+
+/// Other applications' audio sessions, lowered while speech plays
+#[cfg(windows)]
+pub struct Ducker {
+    /// Sessions that were lowered, with the volume to restore and the one they were
+    /// lowered to
+    ducked: Vec<(windows::Win32::Media::Audio::ISimpleAudioVolume, f32, f32)>,
+}
+
+/// How far a session's volume may be from the lowered one and still count as untouched
+#[cfg(windows)]
+const VOLUME_TOLERANCE: f32 = 0.001;
+
+#[cfg(windows)]
+impl Ducker {
+    pub fn new() -> Self {
+        Self { ducked: Vec::new() }
+    }
+
+    /// Scale the volume of every other application playing on the default output by `level`
+    /// Does nothing if they are already lowered
+    pub fn duck(&mut self, level: f32) -> Result<()> {
+        use windows::core::Interface;
+        use windows::Win32::Media::Audio::{
+            eMultimedia, eRender, IAudioSessionControl2, IAudioSessionManager2, IMMDeviceEnumerator,
+            ISimpleAudioVolume, MMDeviceEnumerator,
+        };
+        use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_APARTMENTTHREADED};
+
+        if !self.ducked.is_empty() {
+            return Ok(());
+        }
+
+        unsafe {
+            // Fails harmlessly if COM is already initialized on this thread
+            let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+
+            let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+            let device = enumerator.GetDefaultAudioEndpoint(eRender, eMultimedia)?;
+            let manager: IAudioSessionManager2 = device.Activate(CLSCTX_ALL, None)?;
+            let sessions = manager.GetSessionEnumerator()?;
+
+            let own_process = std::process::id();
+            for index in 0..sessions.GetCount()? {
+                let session = sessions.GetSession(index)?;
+                let Ok(control) = session.cast::<IAudioSessionControl2>() else {
+                    continue;
+                };
+                if control.GetProcessId().is_ok_and(|pid| pid == own_process) {
+                    continue;
+                }
+                let Ok(volume) = session.cast::<ISimpleAudioVolume>() else {
+                    continue;
+                };
+                let Ok(original) = volume.GetMasterVolume() else {
+                    continue;
+                };
+                let lowered = original * level;
+                if volume.SetMasterVolume(lowered, std::ptr::null()).is_ok() {
+                    self.ducked.push((volume, original, lowered));
+                }
+            }
+        }
+
+        log::debug!("Lowered {} audio sessions", self.ducked.len());
+        Ok(())
+    }
+
+    /// Put lowered applications back at their previous volume, except those whose
+    /// volume was changed meanwhile, by the user or the application itself
+    pub fn restore(&mut self) {
+        for (volume, original, lowered) in self.ducked.drain(..) {
+            // The application may have closed meanwhile
+            let untouched = unsafe { volume.GetMasterVolume() }
+                .is_ok_and(|current| (current - lowered).abs() <= VOLUME_TOLERANCE);
+            if untouched {
+                let _ = unsafe { volume.SetMasterVolume(original, std::ptr::null()) };
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for Ducker {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+/// Ducking needs the Windows Core Audio session APIs
+#[cfg(not(windows))]
+pub struct Ducker;
+
+#[cfg(not(windows))]
+impl Ducker {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn duck(&mut self, _level: f32) -> Result<()> {
+        anyhow::bail!("Lowering other audio is not available on this platform")
+    }
+
+    pub fn restore(&mut self) {}
+}
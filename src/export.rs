@@ -0,0 +1,154 @@
+//! Audio export - encodes generated speech to WAV, MP3, OGG Vorbis or FLAC
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// MP3 bitrate used for exports (speech doesn't need more)
+const MP3_BITRATE: mp3lame_encoder::Bitrate = mp3lame_encoder::Bitrate::Kbps128;
+
+/// Audio file formats speech can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Wav,
+    Mp3,
+    Ogg,
+    Flac,
+}
+
+impl ExportFormat {
+    /// All supported formats, in menu order
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Wav,
+        ExportFormat::Mp3,
+        ExportFormat::Ogg,
+        ExportFormat::Flac,
+    ];
+
+    /// File extension for this format (without the dot)
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Wav => "wav",
+            ExportFormat::Mp3 => "mp3",
+            ExportFormat::Ogg => "ogg",
+            ExportFormat::Flac => "flac",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Wav => "WAV",
+            ExportFormat::Mp3 => "MP3",
+            ExportFormat::Ogg => "OGG Vorbis",
+            ExportFormat::Flac => "FLAC",
+        }
+    }
+}
+
+/// Encode mono f32 samples and write them to `path` in the given format
+pub fn write_audio(path: &Path, samples: &[f32], sample_rate: u32, format: ExportFormat) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    match format {
+        ExportFormat::Wav => write_wav(path, samples, sample_rate),
+        ExportFormat::Mp3 => write_mp3(path, samples, sample_rate),
+        ExportFormat::Ogg => write_ogg(path, samples, sample_rate),
+        ExportFormat::Flac => write_flac(path, samples, sample_rate),
+    }
+}
+
+/// Convert a f32 sample to 16-bit PCM
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// Write a 16-bit PCM WAV file
+fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for &sample in samples {
+        writer.write_sample(to_i16(sample))?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Write a constant-bitrate MP3 file using LAME
+fn write_mp3(path: &Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+    use mp3lame_encoder::{Builder, FlushNoGap, MonoPcm};
+
+    let mut builder = Builder::new().ok_or_else(|| anyhow::anyhow!("Failed to create MP3 encoder"))?;
+    builder
+        .set_num_channels(1)
+        .map_err(|e| anyhow::anyhow!("MP3 encoder setup failed: {}", e))?;
+    builder
+        .set_sample_rate(sample_rate)
+        .map_err(|e| anyhow::anyhow!("MP3 encoder setup failed: {}", e))?;
+    builder
+        .set_brate(MP3_BITRATE)
+        .map_err(|e| anyhow::anyhow!("MP3 encoder setup failed: {}", e))?;
+    let mut encoder = builder
+        .build()
+        .map_err(|e| anyhow::anyhow!("MP3 encoder setup failed: {}", e))?;
+
+    let pcm: Vec<i16> = samples.iter().map(|&s| to_i16(s)).collect();
+    let mut out = Vec::with_capacity(mp3lame_encoder::max_required_buffer_size(pcm.len()));
+    encoder
+        .encode_to_vec(MonoPcm(&pcm), &mut out)
+        .map_err(|e| anyhow::anyhow!("MP3 encoding failed: {}", e))?;
+    encoder
+        .flush_to_vec::<FlushNoGap>(&mut out)
+        .map_err(|e| anyhow::anyhow!("MP3 encoding failed: {}", e))?;
+
+    std::fs::write(path, out)?;
+    Ok(())
+}
+
+/// Write an OGG Vorbis file
+fn write_ogg(path: &Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+    use std::num::{NonZeroU32, NonZeroU8};
+    use vorbis_rs::VorbisEncoderBuilder;
+
+    let sample_rate =
+        NonZeroU32::new(sample_rate).ok_or_else(|| anyhow::anyhow!("Invalid sample rate"))?;
+    let channels = NonZeroU8::new(1).expect("1 is non-zero");
+
+    let file = std::fs::File::create(path)?;
+    let mut encoder = VorbisEncoderBuilder::new(sample_rate, channels, file)?.build()?;
+    encoder.encode_audio_block([samples])?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Write a 16-bit FLAC file
+fn write_flac(path: &Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+    use flacenc::component::BitRepr;
+    use flacenc::error::Verify;
+
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| anyhow::anyhow!("FLAC encoder setup failed: {:?}", e))?;
+
+    let pcm: Vec<i32> = samples.iter().map(|&s| to_i16(s) as i32).collect();
+    let source = flacenc::source::MemSource::from_samples(&pcm, 1, 16, sample_rate as usize);
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| anyhow::anyhow!("FLAC encoding failed: {:?}", e))?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|e| anyhow::anyhow!("FLAC encoding failed: {:?}", e))?;
+
+    std::fs::write(path, sink.as_slice())?;
+    Ok(())
+}
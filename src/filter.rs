@@ -0,0 +1,202 @@
+//! Regex filters deciding which copied texts are spoken
+
+use regex::Regex;
+
+/// Shortest hex string taken for a hash
+const MIN_HASH_CHARS: usize = 16;
+
+/// Shortest string taken for a base64 blob or token
+const MIN_BASE64_CHARS: usize = 32;
+
+/// Shortest file path skipped
+const MIN_PATH_CHARS: usize = 40;
+
+/// Copied text is spoken if it matches no exclude pattern and, when there are any
+/// include patterns, at least one of them
+#[derive(Debug, Clone, Default)]
+pub struct TextFilter {
+    exclude: Vec<Regex>,
+    /// None without include patterns; with only invalid ones, nothing matches
+    include: Option<Vec<Regex>>,
+    /// Skip hashes, UUIDs, base64 blobs and long file paths
+    skip_machine_strings: bool,
+}
+
+impl TextFilter {
+    /// Compile the patterns; invalid ones are logged and ignored
+    pub fn new(exclude: &[String], include: &[String], skip_machine_strings: bool) -> Self {
+        Self {
+            exclude: compile(exclude),
+            include: (!include.is_empty()).then(|| compile(include)),
+            skip_machine_strings,
+        }
+    }
+
+    /// What kind of machine-generated string `text` is, if it's one and those are
+    /// skipped; "a3f9c2..." read character by character helps no one
+    pub fn machine_string(&self, text: &str) -> Option<&'static str> {
+        if !self.skip_machine_strings {
+            return None;
+        }
+        if is_uuid(text) {
+            Some("UUID")
+        } else if is_hash(text) {
+            Some("hash")
+        } else if is_base64(text) {
+            Some("base64 data")
+        } else if is_long_path(text) {
+            Some("file path")
+        } else {
+            None
+        }
+    }
+
+    /// Whether `text` should be spoken
+    pub fn allows(&self, text: &str) -> bool {
+        if self.exclude.iter().any(|pattern| pattern.is_match(text)) {
+            return false;
+        }
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.iter().any(|pattern| pattern.is_match(text)))
+    }
+}
+
+fn compile(patterns: &[String]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter_map(|pattern| match Regex::new(pattern) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                log::warn!("Ignoring invalid filter pattern '{}': {}", pattern, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// "123e4567-e89b-12d3-a456-426614174000", optionally in braces
+fn is_uuid(text: &str) -> bool {
+    let text = text
+        .strip_prefix('{')
+        .and_then(|text| text.strip_suffix('}'))
+        .unwrap_or(text);
+    text.len() == 36
+        && text.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// A long run of hex digits mixing digits and letters, like a SHA or git commit
+fn is_hash(text: &str) -> bool {
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    text.len() >= MIN_HASH_CHARS
+        && text.chars().all(|c| c.is_ascii_hexdigit())
+        && text.chars().any(|c| c.is_ascii_digit())
+        && text.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+/// Base64 (or base64url, or a JWT) with no spaces, which may be wrapped over lines
+/// Random data switches between capitals, small letters and digits far more often
+/// than a long identifier does
+fn is_base64(text: &str) -> bool {
+    let text: String = text.lines().map(str::trim).collect();
+    if text.len() < MIN_BASE64_CHARS
+        || !text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=' | '-' | '_' | '.'))
+    {
+        return false;
+    }
+
+    let class = |c: char| {
+        if c.is_ascii_uppercase() {
+            0
+        } else if c.is_ascii_lowercase() {
+            1
+        } else if c.is_ascii_digit() {
+            2
+        } else {
+            3
+        }
+    };
+    let classes: Vec<u8> = text.chars().map(class).collect();
+    if ![0, 1, 2].iter().all(|wanted| classes.contains(wanted)) {
+        return false;
+    }
+    let switches = classes.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    switches * 10 > classes.len() * 4
+}
+
+/// A long absolute path on its own: "C:\...", "\\server\..." or "/home/..."
+fn is_long_path(text: &str) -> bool {
+    if text.len() < MIN_PATH_CHARS || text.contains('\n') {
+        return false;
+    }
+    let bytes = text.as_bytes();
+    let drive = bytes.len() > 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/');
+    let windows = drive || text.starts_with("\\\\");
+    // Unix paths can't be told from prose if they contain spaces
+    let unix =
+        (text.starts_with('/') || text.starts_with("~/")) && !text.contains(char::is_whitespace);
+    let separators = text.chars().filter(|c| matches!(c, '\\' | '/')).count();
+    (windows || unix) && separators >= 3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    #[test]
+    fn allows_everything_without_patterns() {
+        assert!(TextFilter::default().allows("anything"));
+    }
+
+    #[test]
+    fn exclude_patterns_win() {
+        let filter = TextFilter::new(&patterns(&["secret"]), &patterns(&["text"]), false);
+        assert!(filter.allows("some text"));
+        assert!(!filter.allows("secret text"));
+        assert!(!filter.allows("other"));
+    }
+
+    #[test]
+    fn invalid_patterns_are_ignored() {
+        let filter = TextFilter::new(&patterns(&["("]), &[], false);
+        assert!(filter.allows("anything"));
+    }
+
+    #[test]
+    fn only_invalid_include_patterns_match_nothing() {
+        let filter = TextFilter::new(&[], &patterns(&["("]), false);
+        assert!(!filter.allows("anything"));
+    }
+
+    #[test]
+    fn recognizes_machine_strings() {
+        let filter = TextFilter::new(&[], &[], true);
+        assert_eq!(filter.machine_string("123e4567-e89b-12d3-a456-426614174000"), Some("UUID"));
+        assert_eq!(filter.machine_string("9e71955a3f9c2b7d4e81"), Some("hash"));
+        assert_eq!(filter.machine_string("eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxMjM0In0.aB3dE5"), Some("base64 data"));
+        assert_eq!(
+            filter.machine_string(r"C:\Users\someone\Documents\Projects\report-final.docx"),
+            Some("file path")
+        );
+        assert_eq!(filter.machine_string("A sentence someone might copy."), None);
+        assert_eq!(filter.machine_string("deadbeefdeadbeefdeadbeef"), None);
+    }
+
+    #[test]
+    fn machine_strings_are_kept_unless_skipped() {
+        let filter = TextFilter::new(&[], &[], false);
+        assert_eq!(filter.machine_string("123e4567-e89b-12d3-a456-426614174000"), None);
+    }
+}
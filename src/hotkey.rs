@@ -0,0 +1,482 @@
+//! Global hotkeys: one copies the selection in any app and speaks it, putting the
+//! clipboard back as it was, another speaks the copies collected so far, another
+//! switches to the next voice, and the rest do what the tray's Stop, Monitoring
+//! and Quit items do
+
+use crate::clipboard::ClipboardEvent;
+use crate::tts::TTSCommand;
+use std::sync::atomic::AtomicU32;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// A key combination such as "Ctrl+Alt+R"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub win: bool,
+    /// Windows virtual-key code of the key pressed with the modifiers
+    pub key: u32,
+}
+
+impl Hotkey {
+    /// Parse modifiers and a key (a letter, digit, F1-F24 or Space) joined by '+'
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut hotkey = Hotkey {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            win: false,
+            key: 0,
+        };
+
+        for part in text.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => hotkey.ctrl = true,
+                "alt" => hotkey.alt = true,
+                "shift" => hotkey.shift = true,
+                "win" | "windows" => hotkey.win = true,
+                key => {
+                    if hotkey.key != 0 {
+                        anyhow::bail!("More than one key in hotkey '{}'", text);
+                    }
+                    hotkey.key = virtual_key(key)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown key '{}' in hotkey '{}'", part, text))?;
+                }
+            }
+        }
+
+        if hotkey.key == 0 {
+            anyhow::bail!("No key in hotkey '{}'", text);
+        }
+        if !(hotkey.ctrl || hotkey.alt || hotkey.win) {
+            anyhow::bail!("Hotkey '{}' needs Ctrl, Alt or Win", text);
+        }
+        Ok(hotkey)
+    }
+}
+
+/// What a hotkey does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    ReadSelection,
+    SpeakCollected,
+    NextVoice,
+    Stop,
+    ToggleMonitor,
+    Quit,
+    ShowPlayer,
+}
+
+impl HotkeyAction {
+    /// Name for log messages
+    pub fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::ReadSelection => "Read-selection",
+            HotkeyAction::SpeakCollected => "Speak-collected",
+            HotkeyAction::NextVoice => "Next-voice",
+            HotkeyAction::Stop => "Stop",
+            HotkeyAction::ToggleMonitor => "Monitoring",
+            HotkeyAction::Quit => "Quit",
+            HotkeyAction::ShowPlayer => "Player",
+        }
+    }
+}
+
+/// Virtual-key code of a lowercase key name
+fn virtual_key(name: &str) -> Option<u32> {
+    if name == "space" {
+        return Some(0x20);
+    }
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.clone().next()) {
+        if c.is_ascii_alphanumeric() {
+            return Some(c.to_ascii_uppercase() as u32);
+        }
+    }
+    // F1 is 0x70, up to F24
+    let number: u32 = name.strip_prefix('f')?.parse().ok()?;
+    (1..=24).contains(&number).then(|| 0x70 + number - 1)
+}
+
+/// The thread listening for the hotkeys, which holds them while it runs
+pub struct HotkeyThread {
+    thread: JoinHandle<()>,
+    /// Win32 ID of the thread, for posting it WM_QUIT
+    #[cfg_attr(not(windows), allow(dead_code))]
+    thread_id: u32,
+}
+
+impl HotkeyThread {
+    /// Unregister the hotkeys and wait for the thread to end, so they can be registered again
+    pub fn stop(self) {
+        #[cfg(windows)]
+        unsafe {
+            use windows::Win32::Foundation::{LPARAM, WPARAM};
+            use windows::Win32::UI::WindowsAndMessaging::{PostThreadMessageW, WM_QUIT};
+            // Fails harmlessly when the thread has ended, having registered none
+            let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+        let _ = self.thread.join();
+    }
+}
+
+/// Spawn the thread listening for the hotkeys
+/// `copying` is set while the selection is being copied, then to the clipboard
+/// sequence number left once it's put back, for the clipboard monitor to leave
+/// those changes alone; collected copies, the next voice and the
+/// tray's actions are asked for on `events_tx`
+#[cfg(windows)]
+pub fn spawn_hotkey_thread(
+    hotkeys: Vec<(HotkeyAction, Hotkey)>,
+    copying: Arc<AtomicU32>,
+    tts_tx: Sender<TTSCommand>,
+    events_tx: Sender<ClipboardEvent>,
+) -> std::io::Result<HotkeyThread> {
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::WindowsAndMessaging::{PeekMessageW, MSG, PM_NOREMOVE};
+
+    let (id_tx, id_rx) = std::sync::mpsc::channel();
+    let thread = std::thread::Builder::new().name("hotkey".into()).spawn(move || {
+        // The message queue is made on first use; it must exist for WM_QUIT to be posted to it
+        let mut msg = MSG::default();
+        let _ = unsafe { PeekMessageW(&mut msg, None, 0, 0, PM_NOREMOVE) };
+        let _ = id_tx.send(unsafe { GetCurrentThreadId() });
+        listen(&hotkeys, &copying, &tts_tx, &events_tx)
+    })?;
+    let thread_id = id_rx
+        .recv()
+        .map_err(|_| std::io::Error::other("the hotkey thread ended before it started listening"))?;
+    Ok(HotkeyThread { thread, thread_id })
+}
+
+#[cfg(not(windows))]
+pub fn spawn_hotkey_thread(
+    _hotkeys: Vec<(HotkeyAction, Hotkey)>,
+    _copying: Arc<AtomicU32>,
+    _tts_tx: Sender<TTSCommand>,
+    _events_tx: Sender<ClipboardEvent>,
+) -> std::io::Result<HotkeyThread> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Global hotkeys are not supported on this platform",
+    ))
+}
+
+/// Wait for the hotkeys in GetMessageW, until WM_QUIT is posted by `HotkeyThread::stop`
+#[cfg(windows)]
+fn listen(
+    hotkeys: &[(HotkeyAction, Hotkey)],
+    copying: &AtomicU32,
+    tts_tx: &Sender<TTSCommand>,
+    events_tx: &Sender<ClipboardEvent>,
+) {
+    use crate::clipboard::{clipboard_sequence, COPYING_SELECTION};
+    use std::sync::atomic::Ordering;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT,
+        MOD_SHIFT, MOD_WIN,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetMessageW, MSG, WM_HOTKEY};
+
+    unsafe {
+        // Hotkeys are told apart by their position in the list, from 1
+        let mut registered = Vec::new();
+        for (id, (action, hotkey)) in (1..).zip(hotkeys) {
+            let mut modifiers = MOD_NOREPEAT;
+            for (held, modifier) in [
+                (hotkey.ctrl, MOD_CONTROL),
+                (hotkey.alt, MOD_ALT),
+                (hotkey.shift, MOD_SHIFT),
+                (hotkey.win, MOD_WIN),
+            ] {
+                if held {
+                    modifiers |= modifier;
+                }
+            }
+
+            // Without a window, WM_HOTKEY is posted to this thread's queue
+            match RegisterHotKey(None, id, HOT_KEY_MODIFIERS(modifiers.0), hotkey.key) {
+                Ok(()) => {
+                    log::info!("{} hotkey registered", action.label());
+                    registered.push((id, *action));
+                }
+                Err(e) => log::error!(
+                    "Failed to register the {} hotkey (is it used by another app?): {}",
+                    action.label().to_lowercase(),
+                    e
+                ),
+            }
+        }
+        if registered.is_empty() {
+            return;
+        }
+
+        let mut msg = MSG::default();
+        // 0 is WM_QUIT, -1 an error
+        while GetMessageW(&mut msg, None, 0, 0).0 > 0 {
+            if msg.message != WM_HOTKEY {
+                continue;
+            }
+            let action = registered
+                .iter()
+                .find(|(id, _)| *id as usize == msg.wParam.0)
+                .map(|(_, action)| *action);
+
+            let sent = match action {
+                Some(HotkeyAction::ReadSelection) => {
+                    copying.store(COPYING_SELECTION, Ordering::SeqCst);
+                    let text = copy_selection();
+                    // The monitor skips changes up to here, and clears it on the next
+                    copying.store(clipboard_sequence(), Ordering::SeqCst);
+
+                    match text {
+                        Some(text) => {
+                            log::info!("Reading selection ({} chars)", text.len());
+                            tts_tx.send(TTSCommand::SpeakNow { text, voice: None }).is_ok()
+                        }
+                        None => {
+                            log::info!("Nothing selected to read");
+                            true
+                        }
+                    }
+                }
+                Some(HotkeyAction::SpeakCollected) => events_tx.send(ClipboardEvent::SpeakCollected).is_ok(),
+                Some(HotkeyAction::NextVoice) => events_tx.send(ClipboardEvent::NextVoice).is_ok(),
+                Some(HotkeyAction::Stop) => events_tx.send(ClipboardEvent::Stop).is_ok(),
+                Some(HotkeyAction::ToggleMonitor) => events_tx.send(ClipboardEvent::ToggleMonitor).is_ok(),
+                Some(HotkeyAction::Quit) => events_tx.send(ClipboardEvent::Quit).is_ok(),
+                Some(HotkeyAction::ShowPlayer) => events_tx.send(ClipboardEvent::ShowPlayer).is_ok(),
+                None => true,
+            };
+            if !sent {
+                break;
+            }
+        }
+
+        for (id, _) in registered {
+            let _ = UnregisterHotKey(None, id);
+        }
+    }
+}
+
+/// Copy the selection of the focused app with Ctrl+C and return its text, putting
+/// back what the clipboard held before
+#[cfg(windows)]
+fn copy_selection() -> Option<String> {
+    use std::time::{Duration, Instant};
+    use windows::Win32::System::DataExchange::GetClipboardSequenceNumber;
+
+    wait_for_modifiers_released();
+
+    let saved = clipboard_state::save();
+    let sequence = unsafe { GetClipboardSequenceNumber() };
+    send_copy();
+
+    // Apps that have nothing selected leave the clipboard alone
+    let deadline = Instant::now() + Duration::from_millis(500);
+    while unsafe { GetClipboardSequenceNumber() } == sequence {
+        if Instant::now() > deadline {
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    // Give the app a moment to add all its formats
+    std::thread::sleep(Duration::from_millis(50));
+    // Not a copy of the user's, for clipboard managers to record
+    clipboard_state::exclude_from_monitors();
+
+    let text = arboard::Clipboard::new()
+        .ok()
+        .and_then(|mut clipboard| crate::clipboard::read_text(&mut clipboard));
+
+    match saved {
+        Some(saved) => clipboard_state::restore(&saved),
+        None => log::warn!("Clipboard could not be saved, it now holds the selection"),
+    }
+    text.filter(|text| !text.is_empty())
+}
+
+/// Wait (up to a second) for the hotkey's modifiers to be let go, so the app sees
+/// a plain Ctrl+C
+#[cfg(windows)]
+fn wait_for_modifiers_released() {
+    use std::time::{Duration, Instant};
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        GetAsyncKeyState, VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT,
+    };
+
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while Instant::now() < deadline {
+        let held = [VK_CONTROL, VK_MENU, VK_SHIFT, VK_LWIN, VK_RWIN]
+            .into_iter()
+            .any(|key| unsafe { GetAsyncKeyState(key.0 as i32) } < 0);
+        if !held {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Press and release Ctrl+C in the focused app
+#[cfg(windows)]
+fn send_copy() {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        VIRTUAL_KEY, VK_C, VK_CONTROL,
+    };
+
+    let key = |key: VIRTUAL_KEY, up: bool| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: key,
+                dwFlags: if up { KEYEVENTF_KEYUP } else { KEYBD_EVENT_FLAGS(0) },
+                ..Default::default()
+            },
+        },
+    };
+    let inputs = [
+        key(VK_CONTROL, false),
+        key(VK_C, false),
+        key(VK_C, true),
+        key(VK_CONTROL, true),
+    ];
+    unsafe {
+        SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+    }
+}
+
+/// Saving and restoring everything on the clipboard, format by format
+#[cfg(windows)]
+mod clipboard_state {
+    use windows::core::w;
+    use windows::Win32::Foundation::{GlobalFree, HANDLE, HGLOBAL};
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, EmptyClipboard, EnumClipboardFormats, GetClipboardData, OpenClipboard,
+        RegisterClipboardFormatW, SetClipboardData,
+    };
+    use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE};
+
+    /// Formats held as GDI handles rather than memory, which can't be copied this
+    /// way; Windows recreates them from the memory formats
+    const GDI_FORMATS: [u32; 8] = [2, 3, 9, 14, 0x80, 0x82, 0x83, 0x8E];
+
+    /// Clipboard contents as (format, data) pairs
+    pub struct Saved(Vec<(u32, Vec<u8>)>);
+
+    pub fn save() -> Option<Saved> {
+        let mut formats = Vec::new();
+        unsafe {
+            OpenClipboard(None).ok()?;
+            let mut format = EnumClipboardFormats(0);
+            while format != 0 {
+                if !GDI_FORMATS.contains(&format) {
+                    if let Ok(handle) = GetClipboardData(format) {
+                        let data = HGLOBAL(handle.0);
+                        let bytes = GlobalLock(data) as *const u8;
+                        if !bytes.is_null() {
+                            let len = GlobalSize(data);
+                            formats.push((format, std::slice::from_raw_parts(bytes, len).to_vec()));
+                            let _ = GlobalUnlock(data);
+                        }
+                    }
+                }
+                format = EnumClipboardFormats(format);
+            }
+            let _ = CloseClipboard();
+        }
+        Some(Saved(formats))
+    }
+
+    /// Mark what's on the clipboard for clipboard monitors to leave alone
+    pub fn exclude_from_monitors() {
+        unsafe {
+            let format = RegisterClipboardFormatW(w!("ExcludeClipboardContentFromMonitorProcessing"));
+            if format == 0 || OpenClipboard(None).is_err() {
+                return;
+            }
+            // Only the format's presence counts
+            if let Ok(data) = GlobalAlloc(GMEM_MOVEABLE, 4) {
+                if SetClipboardData(format, HANDLE(data.0)).is_err() {
+                    let _ = GlobalFree(data);
+                }
+            }
+            let _ = CloseClipboard();
+        }
+    }
+
+    /// Put saved contents back, kept out of clipboard history and away from
+    /// clipboard monitors where they already are
+    pub fn restore(saved: &Saved) {
+        unsafe {
+            let history = RegisterClipboardFormatW(w!("CanIncludeInClipboardHistory"));
+            let monitors = RegisterClipboardFormatW(w!("ExcludeClipboardContentFromMonitorProcessing"));
+            if let Err(e) = OpenClipboard(None) {
+                log::warn!("Failed to restore the clipboard: {}", e);
+                return;
+            }
+            let _ = EmptyClipboard();
+
+            let excluded = 0u32.to_ne_bytes();
+            let entries = saved
+                .0
+                .iter()
+                .filter(|(format, _)| *format != history && *format != monitors)
+                .map(|(format, bytes)| (*format, bytes.as_slice()))
+                .chain((history != 0).then_some((history, excluded.as_slice())))
+                .chain((monitors != 0).then_some((monitors, excluded.as_slice())));
+            for (format, bytes) in entries {
+                let Ok(data) = GlobalAlloc(GMEM_MOVEABLE, bytes.len()) else {
+                    continue;
+                };
+                let target = GlobalLock(data) as *mut u8;
+                if target.is_null() {
+                    let _ = GlobalFree(data);
+                    continue;
+                }
+                std::ptr::copy_nonoverlapping(bytes.as_ptr(), target, bytes.len());
+                let _ = GlobalUnlock(data);
+                // The clipboard owns the memory once it's set
+                if SetClipboardData(format, HANDLE(data.0)).is_err() {
+                    let _ = GlobalFree(data);
+                }
+            }
+
+            let _ = CloseClipboard();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_and_key() {
+        assert_eq!(
+            Hotkey::parse("Ctrl+Alt+R").unwrap(),
+            Hotkey {
+                ctrl: true,
+                alt: true,
+                shift: false,
+                win: false,
+                key: 'R' as u32,
+            }
+        );
+        let hotkey = Hotkey::parse(" win + shift + f12 ").unwrap();
+        assert!(hotkey.win && hotkey.shift && !hotkey.ctrl);
+        assert_eq!(hotkey.key, 0x7B);
+        assert_eq!(Hotkey::parse("Control+Space").unwrap().key, 0x20);
+        assert_eq!(Hotkey::parse("Alt+7").unwrap().key, '7' as u32);
+    }
+
+    #[test]
+    fn rejects_bad_hotkeys() {
+        for text in ["Ctrl+Alt", "Ctrl+A+B", "Shift+A", "A", "Ctrl+F25", "Ctrl+Enter", ""] {
+            assert!(Hotkey::parse(text).is_err(), "{}", text);
+        }
+    }
+}
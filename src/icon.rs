@@ -1,19 +1,85 @@
 //! Icon generation and animation for the system tray
 //!
 //! Creates a 3-vertical-bars icon design that animates during speech playback.
+//! The bars follow the level of the audio being played. While the model loads,
+//! the bars rise one after another instead. The icon is drawn at the size that
+//! suits the taskbar's display scaling, so it stays sharp above 100%.
 
 use image::{Rgba, RgbaImage};
 use tray_icon::Icon;
 
-/// DodgerBlue color (#1E90FF)
-const ICON_COLOR: Rgba<u8> = Rgba([30, 144, 255, 255]);
+/// DodgerBlue (#1E90FF), for dark taskbars
+pub const DARK_TASKBAR_COLOR: Rgba<u8> = Rgba([30, 144, 255, 255]);
+
+/// A deeper blue (#0050A0), for light taskbars where DodgerBlue washes out
+pub const LIGHT_TASKBAR_COLOR: Rgba<u8> = Rgba([0, 80, 160, 255]);
+
+/// Colors offered in the tray, besides matching the taskbar
+pub const ICON_COLOR_PRESETS: [(&str, &str); 7] = [
+    ("Blue", "#1E90FF"),
+    ("Green", "#2ECC71"),
+    ("Orange", "#FF8C00"),
+    ("Red", "#E74C3C"),
+    ("Purple", "#9B59B6"),
+    ("White", "#FFFFFF"),
+    ("Black", "#000000"),
+];
+
+/// Something the static icon warns about, drawn in its own color over any other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconAlert {
+    /// Speech works, but not as configured (e.g. Windows voices instead of the model)
+    Warning,
+    /// The engine failed (e.g. it couldn't start or lost the audio device)
+    Error,
+}
+
+impl IconAlert {
+    /// Amber for warnings, red for errors
+    pub fn color(&self) -> Rgba<u8> {
+        match self {
+            IconAlert::Warning => Rgba([255, 176, 0, 255]),
+            IconAlert::Error => Rgba([224, 48, 32, 255]),
+        }
+    }
+}
+
+/// Alpha of the greyed-out bars shown while monitoring is off
+const DIMMED_ALPHA: u8 = 140;
+
+/// Alpha of the bar caps, slightly transparent for an anti-aliasing effect
+const CAP_ALPHA: u8 = 180;
 
-/// Icon dimensions
-const ICON_SIZE: u32 = 16;
+/// Size the icon's geometry below is laid out for, scaled to the size drawn
+const BASE_SIZE: u32 = 16;
 
-/// Number of animation frames
+/// Sizes the icon is drawn in, for 100%, 125%, 150% and 200% display scaling
+pub const ICON_SIZES: [u32; 4] = [16, 20, 24, 32];
+
+/// DPI of 100% display scaling
+const BASE_DPI: u32 = 96;
+
+/// Number of animation frames in one cycle of the bars' wave
 const FRAME_COUNT: usize = 8;
 
+/// Animation frames each bar stays raised while loading
+const LOADING_STEP_FRAMES: usize = 3;
+
+/// Number of animation frames in one cycle of the loading chase
+const LOADING_FRAME_COUNT: usize = LOADING_STEP_FRAMES * 3;
+
+/// Alpha of the lowered bars while loading
+const LOADING_ALPHA: u8 = 110;
+
+/// Levels at or below this (dBFS) keep the bars at their minimum height
+const SILENT_DB: f64 = -50.0;
+
+/// Levels at or above this (dBFS) raise the bars to their full height
+const LOUD_DB: f64 = -10.0;
+
+/// How much of the previous level is kept per frame, so the bars fall smoothly
+const RELEASE: f64 = 0.7;
+
 /// X positions for the 3 vertical lines (evenly spaced)
 const LINE_X_POSITIONS: [u32; 3] = [3, 7, 11];
 
@@ -29,34 +95,44 @@ const MAX_HEIGHT: f64 = 10.0;
 /// Static line heights for the non-animated icon
 const STATIC_HEIGHTS: [u32; 3] = [6, 10, 8];
 
-/// Generate the static (non-animated) tray icon
-pub fn create_static_icon() -> anyhow::Result<Icon> {
-    let mut img = RgbaImage::new(ICON_SIZE, ICON_SIZE);
+/// The icon size for a display scaling of `dpi`: the smallest that isn't scaled
+/// up, or the largest
+pub fn icon_size_for_dpi(dpi: u32) -> u32 {
+    let wanted = BASE_SIZE * dpi / BASE_DPI;
+    ICON_SIZES
+        .iter()
+        .copied()
+        .find(|&size| size >= wanted)
+        .unwrap_or(ICON_SIZES[ICON_SIZES.len() - 1])
+}
+
+/// `value`, laid out for BASE_SIZE, scaled to an icon of `size`
+fn scaled(value: f64, size: u32) -> f64 {
+    value * size as f64 / BASE_SIZE as f64
+}
+
+/// Generate the static (non-animated) tray icon, `size` pixels square
+pub fn create_static_icon(color: Rgba<u8>, size: u32) -> anyhow::Result<Icon> {
+    let mut img = RgbaImage::new(size, size);
 
     // Draw 3 vertical lines with static heights
     for (i, &x) in LINE_X_POSITIONS.iter().enumerate() {
         let height = STATIC_HEIGHTS[i];
-        draw_vertical_line(&mut img, x, height);
+        draw_vertical_line(&mut img, x, height as f64, color);
     }
 
     image_to_icon(&img)
 }
 
-/// Generate all animation frames (8 frames for smooth sine wave animation)
-pub fn create_animation_frames() -> anyhow::Result<Vec<Icon>> {
-    let mut frames = Vec::with_capacity(FRAME_COUNT);
-
-    for frame_index in 0..FRAME_COUNT {
-        let img = create_animation_frame(frame_index);
-        frames.push(image_to_icon(&img)?);
-    }
-
-    Ok(frames)
-}
-
 /// Create a single animation frame
-fn create_animation_frame(frame_index: usize) -> RgbaImage {
-    let mut img = RgbaImage::new(ICON_SIZE, ICON_SIZE);
+/// `amount` (0 to 1) scales the bars between their minimum and maximum height
+fn create_animation_frame(
+    frame_index: usize,
+    amount: f64,
+    color: Rgba<u8>,
+    size: u32,
+) -> RgbaImage {
+    let mut img = RgbaImage::new(size, size);
 
     let frame_progress = frame_index as f64 / FRAME_COUNT as f64;
 
@@ -66,46 +142,83 @@ fn create_animation_frame(frame_index: usize) -> RgbaImage {
         let phase = (frame_progress + line_index as f64 * 0.33) * std::f64::consts::PI * 2.0;
         let wave_value = phase.sin();
 
-        // Calculate height based on sine wave
-        let height_range = (MAX_HEIGHT - MIN_HEIGHT) / 2.0;
-        let center_height = MIN_HEIGHT + height_range;
-        let line_height = (center_height + wave_value * height_range).round() as u32;
+        // The wave keeps the bars apart, the level sets how far they rise
+        let line_amount = amount * (0.6 + 0.4 * wave_value);
+        let line_height = MIN_HEIGHT + line_amount * (MAX_HEIGHT - MIN_HEIGHT);
+
+        draw_vertical_line(&mut img, x, line_height, color);
+    }
+
+    img
+}
+
+/// The grey of the same brightness as `color`, partly transparent
+fn dimmed(color: Rgba<u8>) -> Rgba<u8> {
+    let [r, g, b, _] = color.0;
+    let grey = (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64).round() as u8;
+    Rgba([grey, grey, grey, DIMMED_ALPHA])
+}
+
+/// Create a single frame of the loading chase: one bar raised, the others low and faint
+fn create_loading_frame(frame_index: usize, color: Rgba<u8>, size: u32) -> RgbaImage {
+    let mut img = RgbaImage::new(size, size);
 
-        draw_vertical_line(&mut img, x, line_height);
+    let raised = frame_index / LOADING_STEP_FRAMES % LINE_X_POSITIONS.len();
+    let faint = Rgba([color[0], color[1], color[2], LOADING_ALPHA]);
+    for (line_index, &x) in LINE_X_POSITIONS.iter().enumerate() {
+        if line_index == raised {
+            draw_vertical_line(&mut img, x, MAX_HEIGHT, color);
+        } else {
+            draw_vertical_line(&mut img, x, MIN_HEIGHT, faint);
+        }
     }
 
     img
 }
 
+/// Map an RMS level (linear, 0 to 1) to how far the bars rise (0 to 1)
+fn level_to_amount(level: f32) -> f64 {
+    if level <= 0.0 {
+        return 0.0;
+    }
+    let db = 20.0 * (level as f64).log10();
+    ((db - SILENT_DB) / (LOUD_DB - SILENT_DB)).clamp(0.0, 1.0)
+}
+
 /// Draw a vertical line centered on the icon
-fn draw_vertical_line(img: &mut RgbaImage, x: u32, height: u32) {
-    let center_y = ICON_SIZE / 2;
+/// `x` and `height` are laid out for BASE_SIZE and scaled to the image's size
+fn draw_vertical_line(img: &mut RgbaImage, x: u32, height: f64, color: Rgba<u8>) {
+    let size = img.width();
+    let x = scaled(x as f64, size).round() as u32;
+    let height = scaled(height, size).round() as u32;
+    let line_width = scaled(LINE_WIDTH as f64, size).round().max(1.0) as u32;
+
+    let center_y = size / 2;
     let half_height = height / 2;
 
     let y_start = center_y.saturating_sub(half_height);
-    let y_end = (center_y + half_height).min(ICON_SIZE - 1);
+    let y_end = (center_y + half_height).min(size - 1);
 
     // Draw with rounded caps by filling multiple columns for line width
-    for dx in 0..LINE_WIDTH {
+    for dx in 0..line_width {
         let px = x + dx;
-        if px >= ICON_SIZE {
+        if px >= size {
             continue;
         }
 
         for y in y_start..=y_end {
-            img.put_pixel(px, y, ICON_COLOR);
+            img.put_pixel(px, y, color);
         }
 
         // Round the caps by adding pixels at ends
+        let cap = Rgba([color[0], color[1], color[2], color[3].min(CAP_ALPHA)]);
         if y_start > 0 {
             // Top cap
-            let alpha = 180u8; // Slightly transparent for anti-aliasing effect
-            img.put_pixel(px, y_start.saturating_sub(1), Rgba([30, 144, 255, alpha]));
+            img.put_pixel(px, y_start.saturating_sub(1), cap);
         }
-        if y_end < ICON_SIZE - 1 {
+        if y_end < size - 1 {
             // Bottom cap
-            let alpha = 180u8;
-            img.put_pixel(px, y_end + 1, Rgba([30, 144, 255, alpha]));
+            img.put_pixel(px, y_end + 1, cap);
         }
     }
 }
@@ -120,23 +233,42 @@ fn image_to_icon(img: &RgbaImage) -> anyhow::Result<Icon> {
 
 /// Animation state manager
 pub struct IconAnimator {
+    color: Rgba<u8>,
+    /// Width and height in pixels
+    size: u32,
+    /// Whether the static icon is greyed out, while monitoring is off
+    dimmed: bool,
+    /// What the static icon warns about, if anything
+    alert: Option<IconAlert>,
     static_icon: Icon,
-    animation_frames: Vec<Icon>,
+    /// Last rendered animation frame
+    frame_icon: Option<Icon>,
     current_frame: usize,
+    /// Smoothed bar amount, follows the level up at once and down gradually
+    envelope: f64,
     is_animating: bool,
+    is_paused: bool,
+    /// Whether the model is loading, shown when no speech animation is running
+    is_loading: bool,
 }
 
 impl IconAnimator {
-    /// Create a new icon animator
-    pub fn new() -> anyhow::Result<Self> {
-        let static_icon = create_static_icon()?;
-        let animation_frames = create_animation_frames()?;
+    /// Create a new icon animator drawing in the given color and size
+    pub fn new(color: Rgba<u8>, size: u32) -> anyhow::Result<Self> {
+        let static_icon = create_static_icon(color, size)?;
 
         Ok(Self {
+            color,
+            size,
+            dimmed: false,
+            alert: None,
             static_icon,
-            animation_frames,
+            frame_icon: None,
             current_frame: 0,
+            envelope: 0.0,
             is_animating: false,
+            is_paused: false,
+            is_loading: false,
         })
     }
 
@@ -145,41 +277,254 @@ impl IconAnimator {
         &self.static_icon
     }
 
+    /// The color the bars are drawn in
+    pub fn color(&self) -> Rgba<u8> {
+        self.color
+    }
+
+    /// Draw the bars in another color from now on
+    /// The next animation frame picks it up, the static icon is redrawn at once
+    pub fn set_color(&mut self, color: Rgba<u8>) -> anyhow::Result<()> {
+        self.color = color;
+        self.redraw_static_icon()
+    }
+
+    /// The size the icon is drawn in
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Draw the icon in another size from now on, e.g. after the display scaling changed
+    /// The next animation frame picks it up, the static icon is redrawn at once
+    pub fn set_size(&mut self, size: u32) -> anyhow::Result<()> {
+        self.size = size;
+        self.redraw_static_icon()
+    }
+
+    /// Grey out the static icon, or bring its color back
+    /// Animation frames keep the color: speech is playing either way
+    pub fn set_dimmed(&mut self, dimmed: bool) -> anyhow::Result<()> {
+        self.dimmed = dimmed;
+        self.redraw_static_icon()
+    }
+
+    /// Check if the static icon is greyed out
+    pub fn is_dimmed(&self) -> bool {
+        self.dimmed
+    }
+
+    /// Draw the static icon in the alert's color until it's cleared with None
+    pub fn set_alert(&mut self, alert: Option<IconAlert>) -> anyhow::Result<()> {
+        self.alert = alert;
+        self.redraw_static_icon()
+    }
+
+    /// What the static icon warns about, if anything
+    pub fn alert(&self) -> Option<IconAlert> {
+        self.alert
+    }
+
+    fn redraw_static_icon(&mut self) -> anyhow::Result<()> {
+        let color = match self.alert {
+            Some(alert) => alert.color(),
+            None if self.dimmed => dimmed(self.color),
+            None => self.color,
+        };
+        self.static_icon = create_static_icon(color, self.size)?;
+        Ok(())
+    }
+
     /// Start the animation
     pub fn start_animation(&mut self) {
         self.is_animating = true;
+        self.is_paused = false;
         self.current_frame = 0;
+        self.envelope = 0.0;
     }
 
     /// Stop the animation and return to static icon
     pub fn stop_animation(&mut self) {
         self.is_animating = false;
+        self.is_paused = false;
         self.current_frame = 0;
+        self.frame_icon = None;
     }
 
-    /// Check if animation is currently running
+    /// Start the loading animation, unless speech is animating already
+    pub fn start_loading(&mut self) {
+        self.is_loading = true;
+        if !self.is_animating {
+            self.current_frame = 0;
+        }
+    }
+
+    /// Stop the loading animation
+    pub fn stop_loading(&mut self) {
+        self.is_loading = false;
+        if !self.is_animating {
+            self.frame_icon = None;
+        }
+    }
+
+    /// Freeze the animation on its current frame
+    pub fn pause_animation(&mut self) {
+        if self.is_animating {
+            self.is_paused = true;
+        }
+    }
+
+    /// Continue a frozen animation from its current frame
+    pub fn resume_animation(&mut self) {
+        self.is_paused = false;
+    }
+
+    /// Check if animation is currently running (frozen animations don't count)
     pub fn is_animating(&self) -> bool {
-        self.is_animating
+        if self.is_animating {
+            !self.is_paused
+        } else {
+            self.is_loading
+        }
     }
 
-    /// Advance to the next animation frame and return it
+    /// Advance to the next animation frame, drawn for the given playback level, and return it
     /// Returns None if not animating
-    pub fn next_frame(&mut self) -> Option<&Icon> {
-        if !self.is_animating || self.animation_frames.is_empty() {
+    pub fn next_frame(&mut self, level: f32) -> Option<&Icon> {
+        if !self.is_animating() {
             return None;
         }
 
-        self.current_frame = (self.current_frame + 1) % self.animation_frames.len();
-        Some(&self.animation_frames[self.current_frame])
+        let img = if self.is_animating {
+            self.current_frame = (self.current_frame + 1) % FRAME_COUNT;
+            self.envelope = level_to_amount(level).max(self.envelope * RELEASE);
+            create_animation_frame(self.current_frame, self.envelope, self.color, self.size)
+        } else {
+            self.current_frame = (self.current_frame + 1) % LOADING_FRAME_COUNT;
+            create_loading_frame(self.current_frame, self.color, self.size)
+        };
+        match image_to_icon(&img) {
+            Ok(icon) => self.frame_icon = Some(icon),
+            Err(e) => log::warn!("Failed to draw animation frame: {}", e),
+        }
+        self.frame_icon.as_ref()
     }
 
     /// Get the current icon (animated frame if animating, static otherwise)
-    #[allow(dead_code)]
     pub fn current_icon(&self) -> &Icon {
-        if self.is_animating && !self.animation_frames.is_empty() {
-            &self.animation_frames[self.current_frame]
-        } else {
-            &self.static_icon
+        match &self.frame_icon {
+            Some(icon) if self.is_animating || self.is_loading => icon,
+            _ => &self.static_icon,
+        }
+    }
+
+    /// A fully transparent icon of the current size, shown between flashes
+    pub fn blank_icon(&self) -> anyhow::Result<Icon> {
+        image_to_icon(&RgbaImage::new(self.size, self.size))
+    }
+}
+
+/// Parse a "#RRGGBB" color (the "#" is optional)
+pub fn parse_color(hex: &str) -> Option<Rgba<u8>> {
+    let hex = hex.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Rgba([channel(0)?, channel(2)?, channel(4)?, 255]))
+}
+
+/// The bar color that stands out on the current taskbar
+pub fn taskbar_color() -> Rgba<u8> {
+    if light_taskbar() {
+        LIGHT_TASKBAR_COLOR
+    } else {
+        DARK_TASKBAR_COLOR
+    }
+}
+
+/// Whether the taskbar uses the light theme
+/// Windows broadcasts a theme change as WM_SETTINGCHANGE after updating this value,
+/// so reading it again picks the change up
+#[cfg(windows)]
+pub fn light_taskbar() -> bool {
+    use windows::core::w;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!(r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize"),
+            w!("SystemUsesLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some((&mut value as *mut u32).cast()),
+            Some(&mut size),
+        )
+    };
+    // Missing on Windows versions without a light taskbar
+    result.is_ok() && value != 0
+}
+
+/// Whether the taskbar uses the light theme (always dark off Windows)
+#[cfg(not(windows))]
+pub fn light_taskbar() -> bool {
+    false
+}
+
+/// DPI of the primary monitor, where the taskbar's notification area is
+/// It follows changes to the display scaling, as the process is per-monitor DPI aware
+#[cfg(windows)]
+pub fn taskbar_dpi() -> u32 {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::Graphics::Gdi::{MonitorFromPoint, MONITOR_DEFAULTTOPRIMARY};
+    use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+
+    let monitor = unsafe { MonitorFromPoint(POINT::default(), MONITOR_DEFAULTTOPRIMARY) };
+    let (mut dpi_x, mut dpi_y) = (0, 0);
+    match unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) } {
+        Ok(()) if dpi_x > 0 => dpi_x,
+        _ => BASE_DPI,
+    }
+}
+
+/// DPI of the taskbar's monitor (always 100% scaling off Windows)
+#[cfg(not(windows))]
+pub fn taskbar_dpi() -> u32 {
+    BASE_DPI
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse_color("#FF8000"), Some(Rgba([255, 128, 0, 255])));
+        assert_eq!(parse_color(" 0a0b0c "), Some(Rgba([10, 11, 12, 255])));
+    }
+
+    #[test]
+    fn rejects_bad_colors() {
+        for hex in ["", "#FFF", "#FF80001", "#GG0000", "#ÄÄÄ"] {
+            assert_eq!(parse_color(hex), None, "{}", hex);
         }
     }
+
+    #[test]
+    fn picks_the_smallest_icon_that_isnt_scaled_up() {
+        assert_eq!(icon_size_for_dpi(96), 16);
+        assert_eq!(icon_size_for_dpi(120), 20);
+        assert_eq!(icon_size_for_dpi(144), 24);
+        assert_eq!(icon_size_for_dpi(168), 32);
+        assert_eq!(icon_size_for_dpi(192), 32);
+    }
+
+    #[test]
+    fn uses_the_largest_icon_past_it() {
+        assert_eq!(icon_size_for_dpi(480), 32);
+        assert_eq!(icon_size_for_dpi(72), 16);
+    }
 }
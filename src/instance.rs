@@ -0,0 +1,293 @@
+//! A single running instance: a named mutex tells a second launch that Pocket-Tray
+//! is already running, which then has the running one speak the clipboard, through
+//! a hidden window of its own, and exits
+//!
+//! Without it, two tray icons and two clipboard monitors would read every copy twice.
+//!
+//! The same window takes commands from other programs, such as AutoHotkey scripts,
+//! sent as text in WM_COPYDATA messages: "speak <text>", "speak-now <text>",
+//! "speak-clipboard", "stop", "pause", "voice <name>", "next-voice", "monitor",
+//! "player" and "quit".
+
+use crate::clipboard::ClipboardEvent;
+use std::sync::mpsc::Sender;
+use std::thread::JoinHandle;
+
+/// Held by the running instance for as long as it runs
+pub struct Instance {
+    #[cfg(windows)]
+    mutex: windows::Win32::Foundation::HANDLE,
+}
+
+#[cfg(windows)]
+impl Drop for Instance {
+    fn drop(&mut self) {
+        let _ = unsafe { windows::Win32::Foundation::CloseHandle(self.mutex) };
+    }
+}
+
+/// Become the running instance, None if another one already is
+#[cfg(windows)]
+pub fn claim() -> anyhow::Result<Option<Instance>> {
+    use windows::Win32::Foundation::{CloseHandle, GetLastError, ERROR_ALREADY_EXISTS};
+    use windows::Win32::System::Threading::CreateMutexW;
+
+    unsafe {
+        let mutex = CreateMutexW(None, false, win::MUTEX_NAME)?;
+        if GetLastError() == ERROR_ALREADY_EXISTS {
+            let _ = CloseHandle(mutex);
+            return Ok(None);
+        }
+        Ok(Some(Instance { mutex }))
+    }
+}
+
+#[cfg(not(windows))]
+pub fn claim() -> anyhow::Result<Option<Instance>> {
+    Ok(Some(Instance {}))
+}
+
+/// Have the running instance speak the clipboard; false if its window wasn't found,
+/// as while it's still starting
+#[cfg(windows)]
+pub fn signal_running() -> bool {
+    win::signal()
+}
+
+#[cfg(not(windows))]
+pub fn signal_running() -> bool {
+    false
+}
+
+/// Send a command to the running instance as other programs do; None if it isn't
+/// running, else whether it took the command
+#[cfg(windows)]
+pub fn send_command(command: &str) -> Option<bool> {
+    win::send_command(command)
+}
+
+#[cfg(not(windows))]
+pub fn send_command(_command: &str) -> Option<bool> {
+    None
+}
+
+/// Spawn the thread with the hidden window a second launch and other programs
+/// signal; what they ask for is sent on `events_tx`
+#[cfg(windows)]
+pub fn spawn_instance_window(events_tx: Sender<ClipboardEvent>) -> std::io::Result<JoinHandle<()>> {
+    std::thread::Builder::new()
+        .name("instance".into())
+        .spawn(move || win::run(events_tx))
+}
+
+#[cfg(not(windows))]
+pub fn spawn_instance_window(_events_tx: Sender<ClipboardEvent>) -> std::io::Result<JoinHandle<()>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "signalling the running instance is not supported on this platform",
+    ))
+}
+
+#[cfg(windows)]
+mod win {
+    use crate::clipboard::ClipboardEvent;
+    use std::cell::RefCell;
+    use std::sync::mpsc::Sender;
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::DataExchange::COPYDATASTRUCT;
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DispatchMessageW, FindWindowW, GetMessageW,
+        PostMessageW, RegisterClassW, SendMessageTimeoutW, TranslateMessage, MSG,
+        SMTO_ABORTIFHUNG, WINDOW_STYLE, WM_APP, WM_COPYDATA, WNDCLASSW, WS_EX_TOOLWINDOW,
+    };
+
+    /// Per session, so other users signed in run their own
+    pub(super) const MUTEX_NAME: PCWSTR = w!("Local\\PocketTray.TTS");
+
+    const CLASS_NAME: PCWSTR = w!("PocketTrayInstance");
+    const WINDOW_NAME: PCWSTR = w!("Pocket-Tray TTS");
+
+    /// Posted by a second launch
+    const WM_LAUNCHED_AGAIN: u32 = WM_APP + 1;
+
+    /// `dwData` of a command sent as UTF-8; anything else is taken as UTF-16, which
+    /// is what AutoHotkey and most Windows programs have their strings in
+    const UTF8_COMMAND: usize = 1;
+
+    /// How long a command sent waits for the running instance to take it
+    const SEND_TIMEOUT_MS: u32 = 5000;
+
+    thread_local! {
+        static EVENTS_TX: RefCell<Option<Sender<ClipboardEvent>>> = const { RefCell::new(None) };
+    }
+
+    /// Create the window and handle its messages; the thread isn't joined, it ends
+    /// with the process
+    pub(super) fn run(events_tx: Sender<ClipboardEvent>) {
+        EVENTS_TX.with(|tx| *tx.borrow_mut() = Some(events_tx));
+        if let Err(e) = unsafe { create() } {
+            log::warn!("Failed to create the window a second launch signals: {}", e);
+            return;
+        }
+        unsafe {
+            let mut msg = MSG::default();
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+    }
+
+    /// A window that's never shown, found by its class and title
+    unsafe fn create() -> windows::core::Result<HWND> {
+        let instance = GetModuleHandleW(None)?;
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: instance.into(),
+            lpszClassName: CLASS_NAME,
+            ..Default::default()
+        };
+        RegisterClassW(&class);
+
+        CreateWindowExW(
+            WS_EX_TOOLWINDOW,
+            CLASS_NAME,
+            WINDOW_NAME,
+            WINDOW_STYLE::default(),
+            0,
+            0,
+            0,
+            0,
+            None,
+            None,
+            instance,
+            None,
+        )
+    }
+
+    pub(super) fn signal() -> bool {
+        unsafe {
+            FindWindowW(CLASS_NAME, WINDOW_NAME)
+                .and_then(|hwnd| PostMessageW(hwnd, WM_LAUNCHED_AGAIN, WPARAM(0), LPARAM(0)))
+                .is_ok()
+        }
+    }
+
+    pub(super) fn send_command(command: &str) -> Option<bool> {
+        unsafe {
+            let hwnd = FindWindowW(CLASS_NAME, WINDOW_NAME).ok()?;
+            let data = COPYDATASTRUCT {
+                dwData: UTF8_COMMAND,
+                cbData: command.len() as u32,
+                lpData: command.as_ptr() as *mut _,
+            };
+            let mut taken = 0;
+            let sent = SendMessageTimeoutW(
+                hwnd,
+                WM_COPYDATA,
+                WPARAM(0),
+                LPARAM(&data as *const COPYDATASTRUCT as isize),
+                SMTO_ABORTIFHUNG,
+                SEND_TIMEOUT_MS,
+                Some(&mut taken),
+            );
+            // A hung instance is as good as not running
+            (sent.0 != 0).then_some(taken != 0)
+        }
+    }
+
+    fn send(event: ClipboardEvent) {
+        EVENTS_TX.with(|tx| {
+            if let Some(tx) = tx.borrow().as_ref() {
+                let _ = tx.send(event);
+            }
+        });
+    }
+
+    /// The command a WM_COPYDATA message carries
+    unsafe fn copied_text(data: &COPYDATASTRUCT) -> Option<String> {
+        if data.lpData.is_null() {
+            return None;
+        }
+        let bytes = std::slice::from_raw_parts(data.lpData as *const u8, data.cbData as usize);
+        let text = if data.dwData == UTF8_COMMAND {
+            String::from_utf8_lossy(bytes).into_owned()
+        } else if bytes.len() % 2 != 0 {
+            // Not whole UTF-16 units, so not a command
+            return None;
+        } else {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        };
+        // Senders often count the terminating null in
+        Some(text.trim_end_matches('\0').to_string())
+    }
+
+    /// What a command asks for: a word, then for some, the rest of the text
+    fn parse_command(command: &str) -> Option<ClipboardEvent> {
+        let (verb, argument) = match command.trim_start().split_once(char::is_whitespace) {
+            Some((verb, argument)) => (verb, argument.trim()),
+            None => (command.trim(), ""),
+        };
+        let event = match (verb.to_ascii_lowercase().as_str(), argument) {
+            ("speak", text) if !text.is_empty() => ClipboardEvent::Speak {
+                text: text.to_string(),
+                now: false,
+            },
+            ("speak-now", text) if !text.is_empty() => ClipboardEvent::Speak {
+                text: text.to_string(),
+                now: true,
+            },
+            ("voice", name) if !name.is_empty() => ClipboardEvent::ChangeVoice(name.to_string()),
+            ("speak-clipboard", "") => ClipboardEvent::SpeakClipboard,
+            ("stop", "") => ClipboardEvent::Stop,
+            ("pause", "") => ClipboardEvent::TogglePause,
+            ("next-voice", "") => ClipboardEvent::NextVoice,
+            ("monitor", "") => ClipboardEvent::ToggleMonitor,
+            ("player", "") => ClipboardEvent::ShowPlayer,
+            ("quit", "") => ClipboardEvent::Quit,
+            _ => return None,
+        };
+        Some(event)
+    }
+
+    unsafe extern "system" fn window_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        match msg {
+            WM_LAUNCHED_AGAIN => {
+                send(ClipboardEvent::SpeakClipboard);
+                LRESULT(0)
+            }
+            // Nonzero tells the sender the command was taken
+            WM_COPYDATA => {
+                if lparam.0 == 0 {
+                    return LRESULT(0);
+                }
+                let data = &*(lparam.0 as *const COPYDATASTRUCT);
+                let command = copied_text(data);
+                match command.as_deref().and_then(parse_command) {
+                    Some(event) => {
+                        send(event);
+                        LRESULT(1)
+                    }
+                    None => {
+                        // Only the first word, the rest may be text that's kept private
+                        let verb = command.as_deref().and_then(|command| command.split_whitespace().next());
+                        log::warn!("Ignoring unknown command: {}", verb.unwrap_or("(none)"));
+                        LRESULT(0)
+                    }
+                }
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+}
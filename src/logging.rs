@@ -0,0 +1,88 @@
+//! Logging setup: how much is logged, and whether to a file as well as the console,
+//! which a `windows_subsystem = "windows"` app doesn't have
+
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+
+/// How much is logged, from nothing to everything
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn filter(self) -> log::LevelFilter {
+        match self {
+            LogLevel::Off => log::LevelFilter::Off,
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// The `log` section of the settings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSettings {
+    /// Least severe messages logged; `RUST_LOG`, if set, takes precedence
+    pub level: LogLevel,
+    /// File the log is appended to; relative paths are next to the executable
+    pub file: Option<PathBuf>,
+}
+
+impl LogSettings {
+    /// Where the log file is, if there's one
+    fn file_path(&self) -> Option<PathBuf> {
+        let file = self.file.as_ref()?;
+        if file.is_absolute() {
+            return Some(file.clone());
+        }
+        let exe = std::env::current_exe().ok()?;
+        Some(exe.parent()?.join(file))
+    }
+}
+
+/// Start logging as `settings` say; a log file that can't be opened is left out,
+/// the error returned to be reported once logging has started
+pub fn init(settings: &LogSettings) -> std::io::Result<()> {
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(settings.level.filter());
+    if let Ok(filters) = std::env::var("RUST_LOG") {
+        builder.parse_filters(&filters);
+    }
+
+    let file = settings
+        .file_path()
+        .map(|path| OpenOptions::new().create(true).append(true).open(path))
+        .transpose();
+    let result = match file {
+        // Lines in a file are read later, when the time they were logged matters
+        Ok(Some(file)) => {
+            builder
+                .format_timestamp_secs()
+                .target(env_logger::Target::Pipe(Box::new(file)));
+            Ok(())
+        }
+        Ok(None) => {
+            builder.format_timestamp(None);
+            Ok(())
+        }
+        Err(e) => {
+            builder.format_timestamp(None);
+            Err(e)
+        }
+    };
+    builder.init();
+    result
+}
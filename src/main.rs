@@ -6,33 +6,112 @@
 #![windows_subsystem = "windows"]
 
 mod app;
+mod archive;
+mod audio;
+mod backend;
+mod cache;
+mod chime;
 mod clipboard;
+mod dialog;
+mod drop_window;
+mod ducking;
+mod export;
+mod filter;
+mod hotkey;
 mod icon;
+mod instance;
+mod logging;
+mod native_messaging;
+mod ocr;
+mod onecore;
+mod output;
+mod piper;
+mod player_window;
+mod pocket;
+mod reading;
+mod sapi;
 mod settings;
+mod settings_window;
+mod soft;
+mod source;
+mod startup;
+mod tail;
+mod text;
+mod toast;
 mod tray;
 mod tts;
+mod update;
+mod voice_info;
+mod wheel;
 
 use app::App;
 use settings::Settings;
 
 fn main() {
+    // Load settings first, as they say how to log
+    let (settings, mut settings_problems) = Settings::load_checked();
+
     // Initialize logging
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .format_timestamp(None)
-        .init();
+    if let Err(e) = logging::init(&settings.ui.log) {
+        settings_problems.push(format!("log: the log file couldn't be opened ({}), so it's not written", e));
+    }
+
+    // Started by a browser, only to pass its extension's messages on to the running instance
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if native_messaging::is_requested(&args) {
+        if let Err(e) = native_messaging::run() {
+            log::error!("Native messaging failed: {}", e);
+        }
+        return;
+    }
 
     log::info!("Pocket-Tray starting...");
 
-    // Load settings
-    let settings = Settings::load_or_default();
+    // Only one instance runs; launching it again has the running one speak the clipboard
+    let instance = match instance::claim() {
+        Ok(Some(instance)) => Some(instance),
+        Ok(None) => {
+            log::info!("Pocket-Tray is already running, asking it to speak the clipboard");
+            if !instance::signal_running() {
+                log::warn!("The running instance couldn't be reached");
+            }
+            return;
+        }
+        Err(e) => {
+            log::warn!("Failed to check for a running instance: {}", e);
+            None
+        }
+    };
+
+    // A downloaded update replaces this executable, which then hands over to it
+    let _instance = match update::apply_staged() {
+        Ok(Some(installed)) => {
+            log::info!("Update installed, restarting");
+            // Let the updated executable be the running instance
+            drop(instance);
+            match installed.start() {
+                Ok(()) => return,
+                Err(e) => {
+                    log::error!("The update didn't start, keeping this version: {:#}", e);
+                    instance::claim().ok().flatten()
+                }
+            }
+        }
+        Ok(None) => instance,
+        Err(e) => {
+            log::error!("Failed to install update: {}", e);
+            instance
+        }
+    };
+
     log::info!(
         "Settings loaded: monitor={}, voice={}",
-        settings.monitor_enabled,
-        settings.current_voice
+        settings.clipboard.monitor_enabled,
+        settings.speech.current_voice
     );
 
     // Create and run application
-    match App::new(settings) {
+    match App::new(settings, settings_problems) {
         Ok(app) => {
             if let Err(e) = app.run() {
                 log::error!("Application error: {}", e);
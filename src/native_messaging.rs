@@ -0,0 +1,201 @@
+//! Native messaging host: started by a browser for its Pocket-Tray extension, or as
+//! `pocket-tray --native-messaging`, it reads the extension's messages on stdin and
+//! passes them on to the running instance, starting one if there's none
+//!
+//! Messages both ways are their length, as 4 bytes in native byte order, followed
+//! by that much JSON, the protocol Chrome and Firefox share. A message is the text
+//! to speak as a JSON string, or an object with a `command`, one of those the
+//! instance window takes (`speak` if left out), and its `text`. Each is answered
+//! with `{"ok": true}`, or `{"ok": false, "error": "..."}`.
+
+use crate::instance;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Longest message read; browsers send up to 4 GB, far more than is ever spoken
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// How long a started instance has to open its window
+const START_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How often a started instance is checked for
+const START_POLL: Duration = Duration::from_millis(250);
+
+/// A message from the extension
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Message {
+    /// Text to speak
+    Text(String),
+    Request {
+        command: Option<String>,
+        text: Option<String>,
+    },
+}
+
+impl Message {
+    /// The command as the instance window takes it
+    fn command_line(&self) -> String {
+        match self {
+            Message::Text(text) => format!("speak {}", text),
+            Message::Request { command, text } => {
+                let command = command.as_deref().unwrap_or("speak");
+                match text {
+                    Some(text) => format!("{} {}", command, text),
+                    None => command.to_string(),
+                }
+            }
+        }
+    }
+}
+
+/// The answer to a message
+#[derive(Debug, Serialize)]
+struct Response {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Whether Pocket-Tray was started as a native messaging host: Chrome passes the
+/// extension's origin, Firefox the full path of the host manifest and the
+/// extension's ID
+pub fn is_requested(args: &[String]) -> bool {
+    match args {
+        [flag, ..] if flag == "--native-messaging" => true,
+        [origin, ..] if origin.starts_with("chrome-extension://") => true,
+        [manifest, _extension_id] => {
+            manifest.to_ascii_lowercase().ends_with(".json") && Path::new(manifest).is_absolute()
+        }
+        _ => false,
+    }
+}
+
+/// Answer messages until the browser closes the connection
+pub fn run() -> Result<()> {
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+    let mut started = false;
+
+    while let Some(message) = read_message(&mut input)? {
+        let result = serde_json::from_slice::<Message>(&message)
+            .map_err(|e| anyhow!("Not a message Pocket-Tray understands: {}", e))
+            .and_then(|message| forward(&message.command_line(), &mut started));
+        let response = match result {
+            Ok(()) => Response { ok: true, error: None },
+            Err(e) => {
+                log::warn!("{}", e);
+                Response {
+                    ok: false,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+        write_message(&mut output, &response)?;
+    }
+    Ok(())
+}
+
+/// The next message, None once the browser has closed the connection
+fn read_message(input: &mut impl Read) -> Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    match input.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e).context("Failed to read from the browser"),
+    }
+    let len = u32::from_ne_bytes(len) as usize;
+    if len > MAX_MESSAGE_LEN {
+        bail!("Message of {} bytes is too long", len);
+    }
+    let mut message = vec![0; len];
+    input
+        .read_exact(&mut message)
+        .context("Failed to read from the browser")?;
+    Ok(Some(message))
+}
+
+fn write_message(output: &mut impl Write, response: &Response) -> Result<()> {
+    let json = serde_json::to_vec(response)?;
+    output.write_all(&(json.len() as u32).to_ne_bytes())?;
+    output.write_all(&json)?;
+    // Stdout is line buffered, and the browser waits for the whole message
+    output.flush()?;
+    Ok(())
+}
+
+/// Pass a command on to the running instance; the first time there's none, one is
+/// started and waited for
+fn forward(command: &str, started: &mut bool) -> Result<()> {
+    let mut taken = instance::send_command(command);
+    if taken.is_none() && !*started {
+        *started = true;
+        start_instance()?;
+        let deadline = Instant::now() + START_TIMEOUT;
+        while taken.is_none() && Instant::now() < deadline {
+            std::thread::sleep(START_POLL);
+            taken = instance::send_command(command);
+        }
+    }
+    match taken {
+        Some(true) => Ok(()),
+        Some(false) => bail!("Pocket-Tray didn't take the command"),
+        None => bail!("Pocket-Tray isn't running"),
+    }
+}
+
+fn start_instance() -> Result<()> {
+    log::info!("Pocket-Tray isn't running, starting it");
+    let exe = std::env::current_exe()?;
+    let mut command = Command::new(exe);
+    // Kept off the browser's pipes, which would otherwise stay open while it runs
+    command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    // Browsers run hosts in a job that ends everything in it when the host is
+    // closed, right after it answers
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        use windows::Win32::System::Threading::{CREATE_BREAKAWAY_FROM_JOB, DETACHED_PROCESS};
+        command.creation_flags((CREATE_BREAKAWAY_FROM_JOB | DETACHED_PROCESS).0);
+    }
+    command.spawn().context("Failed to start Pocket-Tray")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn framed(message: &[u8]) -> Vec<u8> {
+        let mut bytes = (message.len() as u32).to_ne_bytes().to_vec();
+        bytes.extend_from_slice(message);
+        bytes
+    }
+
+    #[test]
+    fn reads_messages_until_the_connection_closes() -> Result<()> {
+        let mut bytes = framed(br#"{"text":"hi"}"#);
+        bytes.extend(framed(b"{}"));
+        let mut input = bytes.as_slice();
+        assert_eq!(read_message(&mut input)?, Some(br#"{"text":"hi"}"#.to_vec()));
+        assert_eq!(read_message(&mut input)?, Some(b"{}".to_vec()));
+        assert_eq!(read_message(&mut input)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_oversized_messages() {
+        let bytes = ((MAX_MESSAGE_LEN + 1) as u32).to_ne_bytes();
+        assert!(read_message(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn rejects_cut_off_messages() {
+        let bytes = framed(b"{\"text\":\"hi\"}");
+        assert!(read_message(&mut &bytes[..8]).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+//! Text recognition in copied images (Windows.Media.Ocr), e.g. screenshots of error dialogs
+
+use anyhow::Result;
+
+/// Recognize the text in an RGBA image, one line of text per line
+/// Uses the OCR languages of the user's profile
+#[cfg(windows)]
+pub fn recognize(width: usize, height: usize, rgba: &[u8]) -> Result<String> {
+    use windows::Graphics::Imaging::{BitmapPixelFormat, SoftwareBitmap};
+    use windows::Media::Ocr::OcrEngine;
+    use windows::Storage::Streams::DataWriter;
+
+    let max = OcrEngine::MaxImageDimension()? as usize;
+    if width > max || height > max {
+        anyhow::bail!("Image is too large to read ({}x{}, at most {} pixels a side)", width, height, max);
+    }
+
+    // The clipboard gives RGBA, OCR takes BGRA
+    let bgra: Vec<u8> = rgba
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+        .collect();
+    let writer = DataWriter::new()?;
+    writer.WriteBytes(&bgra)?;
+    let bitmap = SoftwareBitmap::CreateCopyFromBuffer(
+        &writer.DetachBuffer()?,
+        BitmapPixelFormat::Bgra8,
+        width as i32,
+        height as i32,
+    )?;
+
+    let engine = OcrEngine::TryCreateFromUserProfileLanguages()
+        .map_err(|e| anyhow::anyhow!("No OCR language is installed ({})", e))?;
+    let result = engine.RecognizeAsync(&bitmap)?.get()?;
+
+    let mut lines = Vec::new();
+    for line in result.Lines()? {
+        lines.push(line.Text()?.to_string());
+    }
+    Ok(lines.join("\n"))
+}
+
+#[cfg(not(windows))]
+pub fn recognize(_width: usize, _height: usize, _rgba: &[u8]) -> Result<String> {
+    anyhow::bail!("Text recognition is not available on this platform")
+}
@@ -0,0 +1,116 @@
+//! Windows OneCore voices (Windows.Media.SpeechSynthesis), offered next to the model's voices
+
+use crate::backend::{SampleStream, TtsBackend};
+use anyhow::Result;
+
+/// Sample rate voices are rendered at, matching the Pocket TTS model
+const SAMPLE_RATE: u32 = 24000;
+
+/// Installed OneCore voices, including the natural voices of recent Windows versions
+#[cfg(windows)]
+pub struct OneCoreVoices {
+    synthesizer: windows::Media::SpeechSynthesis::SpeechSynthesizer,
+    /// Voices by display name, sorted by name
+    voices: Vec<(String, windows::Media::SpeechSynthesis::VoiceInformation)>,
+}
+
+#[cfg(windows)]
+impl OneCoreVoices {
+    /// List the installed voices
+    pub fn new() -> Result<Self> {
+        use windows::Media::SpeechSynthesis::SpeechSynthesizer;
+
+        let synthesizer = SpeechSynthesizer::new()?;
+        let mut voices = Vec::new();
+        for voice in SpeechSynthesizer::AllVoices()? {
+            voices.push((voice.DisplayName()?.to_string(), voice));
+        }
+        voices.sort_by(|a, b| a.0.cmp(&b.0));
+        voices.dedup_by(|a, b| a.0 == b.0);
+
+        if voices.is_empty() {
+            anyhow::bail!("No OneCore voices are installed");
+        }
+        Ok(Self { synthesizer, voices })
+    }
+
+    /// Display names of the installed voices, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.voices.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Render `text` with the named voice as mono samples at `sample_rate`
+    fn synthesize(&self, voice: &str, text: &str, sample_rate: u32) -> Result<Vec<f32>> {
+        use crate::audio::resample;
+        use windows::core::HSTRING;
+        use windows::Storage::Streams::DataReader;
+
+        let Some((_, info)) = self.voices.iter().find(|(name, _)| name == voice) else {
+            anyhow::bail!("Windows voice '{}' not found", voice);
+        };
+
+        self.synthesizer.SetVoice(info)?;
+        let stream = self
+            .synthesizer
+            .SynthesizeTextToStreamAsync(&HSTRING::from(text))?
+            .get()?;
+
+        // The stream holds a complete WAV file
+        let size = stream.Size()? as u32;
+        let reader = DataReader::CreateDataReader(&stream.GetInputStreamAt(0)?)?;
+        reader.LoadAsync(size)?.get()?;
+        let mut wav = vec![0u8; size as usize];
+        reader.ReadBytes(&mut wav)?;
+
+        let reader = hound::WavReader::new(std::io::Cursor::new(wav))?;
+        let spec = reader.spec();
+        let interleaved = reader
+            .into_samples::<i16>()
+            .map(|sample| sample.map(|s| s as f32 / i16::MAX as f32))
+            .collect::<std::result::Result<Vec<f32>, _>>()?;
+
+        // Mix down to mono
+        let channels = spec.channels.max(1) as usize;
+        let mono: Vec<f32> = interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect();
+
+        Ok(resample(&mono, spec.sample_rate, sample_rate))
+    }
+}
+
+/// OneCore voices are only available on Windows
+#[cfg(not(windows))]
+pub struct OneCoreVoices;
+
+#[cfg(not(windows))]
+impl OneCoreVoices {
+    pub fn new() -> Result<Self> {
+        anyhow::bail!("Windows voices are not available on this platform")
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn synthesize(&self, _voice: &str, _text: &str, _sample_rate: u32) -> Result<Vec<f32>> {
+        anyhow::bail!("Windows voices are not available on this platform")
+    }
+}
+
+impl TtsBackend for OneCoreVoices {
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn voices(&self) -> Vec<String> {
+        self.names()
+    }
+
+    /// The whole text is rendered up front, as a single chunk
+    fn synth_stream<'a>(&'a self, text: &'a str, voice: &str) -> Result<SampleStream<'a>> {
+        let samples = self.synthesize(voice, text, SAMPLE_RATE)?;
+        Ok(Box::new(std::iter::once(Ok(samples))))
+    }
+}
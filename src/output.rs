@@ -0,0 +1,222 @@
+//! The audio output device, opened at its own format
+
+use crate::audio::DeviceFormat;
+use anyhow::Result;
+use rodio::cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::cpal::{self, FromSample, SampleFormat, SizedSample, SupportedBufferSize};
+use rodio::dynamic_mixer::{self, DynamicMixer, DynamicMixerController};
+use rodio::{OutputStream, OutputStreamHandle, Sink};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Device buffer length in low-latency mode
+const LOW_LATENCY_BUFFER: Duration = Duration::from_millis(10);
+
+/// How the output device is opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioLatency {
+    /// The device's default buffer
+    #[default]
+    Normal,
+    /// A small WASAPI buffer so speech starts sooner, at the cost of more CPU wake-ups
+    Low,
+}
+
+impl AudioLatency {
+    /// All options, in menu order
+    pub const ALL: [AudioLatency; 2] = [AudioLatency::Normal, AudioLatency::Low];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            AudioLatency::Normal => "normal",
+            AudioLatency::Low => "low",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            AudioLatency::Normal => "Normal",
+            AudioLatency::Low => "Low (10 ms buffer)",
+        }
+    }
+}
+
+/// A named output device to route speech to, e.g. a virtual cable feeding a broadcast mix
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputPreset {
+    /// Name shown in the tray
+    pub name: String,
+    /// Output device name as Windows lists it, e.g. "CABLE Input (VB-Audio Virtual Cable)"
+    pub device: String,
+}
+
+/// The output device, playing whatever sinks are created on it
+pub struct AudioOutput {
+    stream: Stream,
+    format: DeviceFormat,
+}
+
+enum Stream {
+    /// Opened by rodio with the device's default buffer
+    Default {
+        _stream: OutputStream,
+        handle: OutputStreamHandle,
+    },
+    /// Opened with a small buffer, sinks are mixed into it
+    LowLatency {
+        _stream: cpal::Stream,
+        mixer: Arc<DynamicMixerController<f32>>,
+    },
+    /// No device, sinks play nothing
+    #[cfg(test)]
+    Silent,
+}
+
+impl AudioOutput {
+    /// Open the named output device (the default one if None) at its own sample rate
+    /// and channel count
+    /// Falls back to the default device if the named one isn't found, and to the normal
+    /// buffer if a low-latency one can't be set up
+    pub fn open(latency: AudioLatency, device_name: Option<&str>) -> Result<Self> {
+        let host = cpal::default_host();
+        let named = device_name.and_then(|name| {
+            let device = host
+                .output_devices()
+                .ok()?
+                .find(|device| device.name().is_ok_and(|n| n == name));
+            if device.is_none() {
+                log::warn!("Output device '{}' not found, using the default device", name);
+            }
+            device
+        });
+        let device = match named {
+            Some(device) => device,
+            None => host
+                .default_output_device()
+                .ok_or_else(|| anyhow::anyhow!("No audio output device found"))?,
+        };
+        let config = device.default_output_config()?;
+        let format = DeviceFormat {
+            channels: config.channels(),
+            sample_rate: config.sample_rate().0,
+        };
+
+        let stream = match latency {
+            AudioLatency::Low => match open_low_latency(&device, &config) {
+                Ok(stream) => Some(stream),
+                Err(e) => {
+                    log::warn!(
+                        "Low-latency output unavailable, using the normal buffer: {}",
+                        e
+                    );
+                    None
+                }
+            },
+            AudioLatency::Normal => None,
+        };
+        let stream = match stream {
+            Some(stream) => stream,
+            None => {
+                let (stream, handle) = OutputStream::try_from_device_config(&device, config)?;
+                Stream::Default {
+                    _stream: stream,
+                    handle,
+                }
+            }
+        };
+
+        log::info!(
+            "Audio output: {}, {} Hz, {} channels, {} latency",
+            device.name().unwrap_or_default(),
+            format.sample_rate,
+            format.channels,
+            match stream {
+                Stream::Default { .. } => "normal",
+                Stream::LowLatency { .. } => "low",
+                #[cfg(test)]
+                Stream::Silent => "none",
+            }
+        );
+        Ok(Self { stream, format })
+    }
+
+    /// An output without a device, for testing the engine
+    #[cfg(test)]
+    pub fn silent() -> Self {
+        Self {
+            stream: Stream::Silent,
+            format: DeviceFormat {
+                channels: 2,
+                sample_rate: 48000,
+            },
+        }
+    }
+
+    /// Sample rate and channel count of the device
+    pub fn format(&self) -> DeviceFormat {
+        self.format
+    }
+
+    /// A new sink playing on the device
+    pub fn new_sink(&self) -> Result<Sink> {
+        match &self.stream {
+            Stream::Default { handle, .. } => Ok(Sink::try_new(handle)?),
+            Stream::LowLatency { mixer, .. } => {
+                let (sink, output) = Sink::new_idle();
+                mixer.add(output);
+                Ok(sink)
+            }
+            #[cfg(test)]
+            Stream::Silent => Ok(Sink::new_idle().0),
+        }
+    }
+}
+
+/// Open the device with a buffer of `LOW_LATENCY_BUFFER`, or the smallest it allows
+fn open_low_latency(device: &cpal::Device, config: &cpal::SupportedStreamConfig) -> Result<Stream> {
+    let mut frames = (LOW_LATENCY_BUFFER.as_secs_f64() * config.sample_rate().0 as f64) as u32;
+    if let SupportedBufferSize::Range { min, max } = *config.buffer_size() {
+        frames = frames.clamp(min, max.max(min));
+    }
+    let mut stream_config = config.config();
+    stream_config.buffer_size = cpal::BufferSize::Fixed(frames);
+
+    let (controller, mixer) =
+        dynamic_mixer::mixer::<f32>(config.channels(), config.sample_rate().0);
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => build_stream::<f32>(device, &stream_config, mixer)?,
+        SampleFormat::I16 => build_stream::<i16>(device, &stream_config, mixer)?,
+        SampleFormat::I32 => build_stream::<i32>(device, &stream_config, mixer)?,
+        SampleFormat::U16 => build_stream::<u16>(device, &stream_config, mixer)?,
+        format => anyhow::bail!("Unsupported sample format: {}", format),
+    };
+    stream.play()?;
+
+    Ok(Stream::LowLatency {
+        _stream: stream,
+        mixer: controller,
+    })
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut mixer: DynamicMixer<f32>,
+) -> Result<cpal::Stream>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    Ok(device.build_output_stream(
+        config,
+        move |data: &mut [T], _| {
+            data.iter_mut()
+                .for_each(|sample| *sample = T::from_sample(mixer.next().unwrap_or(0.0)));
+        },
+        |e| log::error!("Audio output error: {}", e),
+        None,
+    )?)
+}
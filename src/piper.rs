@@ -0,0 +1,296 @@
+//! Piper backend - lightweight ONNX voices run with onnxruntime
+//!
+//! Text is turned into IPA phonemes by espeak-ng, which must be installed
+//! (on the PATH or in an `espeak-ng` folder next to the executable).
+
+use crate::audio::resample;
+use crate::backend::{SampleStream, TtsBackend};
+use crate::settings::ModelInfo;
+use crate::tts::ComputeDevice;
+use anyhow::{Context, Result};
+use ort::session::builder::SessionBuilder;
+use ort::session::Session;
+use ort::value::Tensor;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Sample rate all voices are rendered at, that of most Piper voices
+const SAMPLE_RATE: u32 = 22050;
+
+/// Phonemes marking the start and end of the text, and the padding between phonemes
+const BOS: char = '^';
+const EOS: char = '$';
+const PAD: char = '_';
+
+/// A voice's `<voice>.onnx.json` config, only the fields used for synthesis
+#[derive(Debug, Deserialize)]
+struct VoiceConfig {
+    audio: AudioConfig,
+    #[serde(default)]
+    espeak: EspeakConfig,
+    #[serde(default)]
+    inference: InferenceConfig,
+    #[serde(default)]
+    phoneme_type: PhonemeType,
+    phoneme_id_map: HashMap<char, Vec<i64>>,
+    #[serde(default = "default_num_speakers")]
+    num_speakers: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct AudioConfig {
+    sample_rate: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct EspeakConfig {
+    voice: String,
+}
+
+impl Default for EspeakConfig {
+    fn default() -> Self {
+        Self {
+            voice: "en-us".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct InferenceConfig {
+    noise_scale: f32,
+    length_scale: f32,
+    noise_w: f32,
+}
+
+impl Default for InferenceConfig {
+    fn default() -> Self {
+        Self {
+            noise_scale: 0.667,
+            length_scale: 1.0,
+            noise_w: 0.8,
+        }
+    }
+}
+
+/// How a voice expects its text: espeak-ng phonemes, or the characters themselves
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PhonemeType {
+    #[default]
+    Espeak,
+    Text,
+}
+
+fn default_num_speakers() -> u32 {
+    1
+}
+
+/// A loaded Piper voice
+struct PiperVoice {
+    session: Session,
+    config: VoiceConfig,
+}
+
+impl PiperVoice {
+    fn load(path: &Path, device: ComputeDevice) -> Result<Self> {
+        let config_path = PathBuf::from(format!("{}.json", path.display()));
+        let config = std::fs::read_to_string(&config_path)
+            .with_context(|| format!("Voice config not found at: {}", config_path.display()))?;
+        let config: VoiceConfig = serde_json::from_str(&config)?;
+        let builder = match device {
+            ComputeDevice::DirectMl => directml_session()?,
+            _ => Session::builder()?,
+        };
+        let session = builder.commit_from_file(path)?;
+        Ok(Self { session, config })
+    }
+
+    /// Render one sentence as mono samples at `SAMPLE_RATE`
+    fn synthesize(&self, text: &str) -> Result<Vec<f32>> {
+        let phonemes = match self.config.phoneme_type {
+            PhonemeType::Espeak => phonemize(text, &self.config.espeak.voice)?,
+            PhonemeType::Text => text.to_lowercase(),
+        };
+        let ids = self.phoneme_ids(&phonemes);
+        if ids.len() <= 3 {
+            return Ok(Vec::new());
+        }
+
+        let inference = &self.config.inference;
+        let length = ids.len();
+        let mut inputs = ort::inputs![
+            "input" => Tensor::from_array(([1, length], ids))?,
+            "input_lengths" => Tensor::from_array(([1], vec![length as i64]))?,
+            "scales" => Tensor::from_array((
+                [3],
+                vec![inference.noise_scale, inference.length_scale, inference.noise_w],
+            ))?,
+        ]?;
+        if self.config.num_speakers > 1 {
+            inputs.extend(ort::inputs!["sid" => Tensor::from_array(([1], vec![0i64]))?]?);
+        }
+
+        let outputs = self.session.run(inputs)?;
+        let (_, audio) = outputs[0].try_extract_raw_tensor::<f32>()?;
+        let samples: Vec<f32> = audio.iter().map(|s| s.clamp(-1.0, 1.0)).collect();
+        Ok(resample(&samples, self.config.audio.sample_rate, SAMPLE_RATE))
+    }
+
+    /// Map phonemes to the voice's ids, padded and wrapped in start and end markers
+    /// Phonemes the voice doesn't know are skipped
+    fn phoneme_ids(&self, phonemes: &str) -> Vec<i64> {
+        let map = &self.config.phoneme_id_map;
+        let ids_of = |c: char| map.get(&c).map(Vec::as_slice).unwrap_or_default();
+
+        let mut ids = ids_of(BOS).to_vec();
+        ids.extend_from_slice(ids_of(PAD));
+        for phoneme in phonemes.chars().filter(|c| map.contains_key(c)) {
+            ids.extend_from_slice(ids_of(phoneme));
+            ids.extend_from_slice(ids_of(PAD));
+        }
+        ids.extend_from_slice(ids_of(EOS));
+        ids
+    }
+}
+
+/// Piper voices of one model folder
+pub struct PiperBackend {
+    /// Voices by name, sorted by name
+    voices: Vec<(String, PiperVoice)>,
+}
+
+impl PiperBackend {
+    /// Load every voice of the model described by `info`, on DirectML if that's the
+    /// `device` and it's available, otherwise on the CPU
+    /// Returns the backend and the device it actually runs on
+    pub fn load(info: &ModelInfo, device: ComputeDevice) -> Result<(Self, ComputeDevice)> {
+        let device = match device {
+            ComputeDevice::DirectMl => match directml_session() {
+                Ok(_) => ComputeDevice::DirectMl,
+                Err(e) => {
+                    log::warn!("{} unavailable, falling back to CPU: {}", device.label(), e);
+                    ComputeDevice::Cpu
+                }
+            },
+            // Piper voices are light enough for the CPU; CUDA is used by Pocket TTS models
+            _ => ComputeDevice::Cpu,
+        };
+        log::info!(
+            "Loading Piper voices '{}' from: {} on {}",
+            info.name,
+            info.dir.display(),
+            device.label()
+        );
+
+        let mut voices = Vec::new();
+        for (voice_name, voice_path) in info.voices() {
+            match PiperVoice::load(&voice_path, device) {
+                Ok(voice) => {
+                    log::info!("Loaded voice: {}", voice_name);
+                    voices.push((voice_name, voice));
+                }
+                Err(e) => {
+                    log::warn!("Failed to load voice '{}': {}", voice_name, e);
+                }
+            }
+        }
+
+        if voices.is_empty() {
+            anyhow::bail!("No Piper voices could be loaded from: {}", info.dir.display());
+        }
+        Ok((Self { voices }, device))
+    }
+}
+
+/// A session builder running models on the default DirectX 12 GPU
+/// Fails if DirectML can't be set up, rather than quietly running on the CPU
+#[cfg(feature = "directml")]
+fn directml_session() -> Result<SessionBuilder> {
+    use ort::execution_providers::DirectMLExecutionProvider;
+
+    // DirectML supports neither memory patterns nor parallel execution
+    Ok(Session::builder()?
+        .with_memory_pattern(false)?
+        .with_parallel_execution(false)?
+        .with_execution_providers([DirectMLExecutionProvider::default().build().error_on_failure()])?)
+}
+
+#[cfg(not(feature = "directml"))]
+fn directml_session() -> Result<SessionBuilder> {
+    anyhow::bail!("DirectML needs a build with the `directml` feature")
+}
+
+impl TtsBackend for PiperBackend {
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn voices(&self) -> Vec<String> {
+        self.voices.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Sentences are rendered one at a time, as the stream is iterated
+    fn synth_stream<'a>(&'a self, text: &'a str, voice: &str) -> Result<SampleStream<'a>> {
+        let (_, voice) = self
+            .voices
+            .iter()
+            .find(|(name, _)| name == voice)
+            .ok_or_else(|| anyhow::anyhow!("Voice '{}' not loaded", voice))?;
+        Ok(Box::new(
+            text.split_inclusive(['.', '!', '?', '\n'])
+                .filter(|sentence| !sentence.trim().is_empty())
+                .map(|sentence| voice.synthesize(sentence)),
+        ))
+    }
+}
+
+/// Convert text to IPA phonemes with espeak-ng
+/// Each clause comes back on its own line; they're rejoined with the text's final punctuation
+fn phonemize(text: &str, voice: &str) -> Result<String> {
+    let mut command = Command::new(espeak_path());
+    command
+        .args(["-q", "-b", "1", "--ipa", "-v", voice, "--stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW: don't flash a console window
+        command.creation_flags(0x0800_0000);
+    }
+
+    let mut child = command
+        .spawn()
+        .context("espeak-ng is needed for Piper voices but couldn't be started")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!("espeak-ng failed to phonemize text");
+    }
+
+    let mut phonemes = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ");
+    if let Some(punctuation) = text.trim_end().chars().last().filter(|c| ".!?".contains(*c)) {
+        phonemes.push(punctuation);
+    }
+    Ok(phonemes)
+}
+
+/// espeak-ng bundled next to the executable, or the one on the PATH
+fn espeak_path() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join("espeak-ng").join("espeak-ng.exe")))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from("espeak-ng"))
+}
@@ -0,0 +1,374 @@
+//! Player window, a small always-on-top popup with playback controls, the
+//! sentence being read and how far into the text it is
+//!
+//! The window runs on a thread of its own. It shows the state the app keeps up to
+//! date and hands button presses back through a channel, which the app acts on.
+
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// What the player shows, kept up to date by the app
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlayerState {
+    pub speaking: bool,
+    pub paused: bool,
+    /// The sentence playing, empty between texts
+    pub sentence: String,
+    /// How far into the text playback is, 0 to 1
+    pub progress: f32,
+}
+
+/// A button pressed in the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerAction {
+    /// Pause or resume speech, or speak the last text again when nothing is playing
+    PlayPause,
+    Stop,
+    /// Go on to the next sentence
+    Skip,
+}
+
+/// Open the player window on its own thread
+#[cfg(windows)]
+pub fn open(
+    state: Arc<Mutex<PlayerState>>,
+    action_tx: Sender<PlayerAction>,
+) -> std::io::Result<JoinHandle<()>> {
+    std::thread::Builder::new()
+        .name("player-window".to_string())
+        .spawn(move || win::run(state, action_tx))
+}
+
+#[cfg(not(windows))]
+pub fn open(
+    _state: Arc<Mutex<PlayerState>>,
+    _action_tx: Sender<PlayerAction>,
+) -> std::io::Result<JoinHandle<()>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "the player window is not supported on this platform",
+    ))
+}
+
+#[cfg(windows)]
+mod win {
+    use super::{PlayerAction, PlayerState};
+    use std::cell::RefCell;
+    use std::sync::mpsc::Sender;
+    use std::sync::{Arc, Mutex};
+    use windows::core::{w, HSTRING, PCWSTR};
+    use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+    use windows::Win32::Graphics::Gdi::{GetStockObject, COLOR_BTNFACE, DEFAULT_GUI_FONT, HBRUSH};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::Controls::{
+        InitCommonControlsEx, ICC_PROGRESS_CLASS, INITCOMMONCONTROLSEX, PBM_SETPOS, PBM_SETRANGE32,
+        PROGRESS_CLASSW,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        AdjustWindowRectEx, CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW,
+        GetDlgItem, GetMessageW, IsDialogMessageW, KillTimer, LoadCursorW, PostQuitMessage,
+        RegisterClassW, SendMessageW, SetForegroundWindow, SetTimer, SetWindowTextW, ShowWindow,
+        SystemParametersInfoW, TranslateMessage, BS_PUSHBUTTON, HMENU, IDC_ARROW, MSG,
+        SPI_GETWORKAREA, SW_SHOW, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, WINDOW_EX_STYLE,
+        WINDOW_STYLE, WM_CLOSE, WM_COMMAND, WM_DESTROY, WM_SETFONT, WM_TIMER, WNDCLASSW,
+        WS_CAPTION, WS_CHILD, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_SYSMENU, WS_TABSTOP, WS_VISIBLE,
+    };
+
+    const CLASS_NAME: PCWSTR = w!("PocketTrayPlayer");
+
+    /// Size of the window's client area
+    const WIDTH: i32 = 360;
+    const HEIGHT: i32 = 118;
+
+    /// Gap between the window and the corner of the work area
+    const MARGIN: i32 = 12;
+
+    /// How often the window picks up the app's state, in milliseconds
+    const REFRESH_MS: u32 = 200;
+    const REFRESH_TIMER: usize = 1;
+
+    /// Steps of the progress bar
+    const PROGRESS_RANGE: f32 = 1000.0;
+
+    /// Characters of the sentence shown, which has room for about two lines
+    const SENTENCE_CHARS: usize = 120;
+
+    /// Static control style showing "&" as is, rather than underlining the next letter
+    const SS_NOPREFIX: u32 = 0x80;
+
+    // Control IDs
+    const ID_SENTENCE: i32 = 100;
+    const ID_PROGRESS: i32 = 101;
+    const ID_PLAY_PAUSE: i32 = 102;
+    const ID_STOP: i32 = 103;
+    const ID_SKIP: i32 = 104;
+
+    /// The app's state and where button presses go, kept for the window procedure
+    struct Player {
+        state: Arc<Mutex<PlayerState>>,
+        action_tx: Sender<PlayerAction>,
+        /// What the controls show, so they're only updated on changes
+        shown: Option<PlayerState>,
+    }
+
+    thread_local! {
+        static PLAYER: RefCell<Option<Player>> = const { RefCell::new(None) };
+    }
+
+    pub(super) fn run(state: Arc<Mutex<PlayerState>>, action_tx: Sender<PlayerAction>) {
+        let hwnd = match unsafe { create() } {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                log::error!("Failed to open the player window: {}", e);
+                return;
+            }
+        };
+        PLAYER.with(|player| {
+            *player.borrow_mut() = Some(Player {
+                state,
+                action_tx,
+                shown: None,
+            })
+        });
+
+        unsafe {
+            refresh(hwnd);
+            SetTimer(hwnd, REFRESH_TIMER, REFRESH_MS, None);
+            let _ = ShowWindow(hwnd, SW_SHOW);
+            let _ = SetForegroundWindow(hwnd);
+
+            let mut msg = MSG::default();
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                // Tab between the buttons
+                if !IsDialogMessageW(hwnd, &msg).as_bool() {
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+            }
+        }
+        PLAYER.with(|player| player.borrow_mut().take());
+    }
+
+    /// Create the window and its controls, above the bottom right of the work area
+    /// (where the tray usually is)
+    unsafe fn create() -> windows::core::Result<HWND> {
+        // The progress bar is a common control
+        let controls = INITCOMMONCONTROLSEX {
+            dwSize: std::mem::size_of::<INITCOMMONCONTROLSEX>() as u32,
+            dwICC: ICC_PROGRESS_CLASS,
+        };
+        let _ = InitCommonControlsEx(&controls);
+
+        let instance = GetModuleHandleW(None)?;
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: instance.into(),
+            hCursor: LoadCursorW(None, IDC_ARROW)?,
+            hbrBackground: HBRUSH((COLOR_BTNFACE.0 + 1) as isize as _),
+            lpszClassName: CLASS_NAME,
+            ..Default::default()
+        };
+        // Fails harmlessly when the window was opened before
+        RegisterClassW(&class);
+
+        // Always on top, and without a taskbar button
+        let style = WS_CAPTION | WS_SYSMENU;
+        let ex_style = WS_EX_TOPMOST | WS_EX_TOOLWINDOW;
+        let mut rect = RECT {
+            left: 0,
+            top: 0,
+            right: WIDTH,
+            bottom: HEIGHT,
+        };
+        AdjustWindowRectEx(&mut rect, style, false, ex_style)?;
+        let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+
+        let mut work_area = RECT::default();
+        SystemParametersInfoW(
+            SPI_GETWORKAREA,
+            0,
+            Some((&mut work_area as *mut RECT).cast()),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )?;
+
+        let hwnd = CreateWindowExW(
+            ex_style,
+            CLASS_NAME,
+            w!("Pocket-Tray Player"),
+            style,
+            work_area.right - width - MARGIN,
+            work_area.bottom - height - MARGIN,
+            width,
+            height,
+            None,
+            None,
+            instance,
+            None,
+        )?;
+
+        control(
+            hwnd,
+            w!("STATIC"),
+            "",
+            SS_NOPREFIX,
+            ID_SENTENCE,
+            (12, 10, WIDTH - 24, 36),
+        );
+        let progress = control(
+            hwnd,
+            PROGRESS_CLASSW,
+            "",
+            0,
+            ID_PROGRESS,
+            (12, 52, WIDTH - 24, 14),
+        );
+        SendMessageW(
+            progress,
+            PBM_SETRANGE32,
+            WPARAM(0),
+            LPARAM(PROGRESS_RANGE as isize),
+        );
+
+        let buttons = [
+            ("Play", ID_PLAY_PAUSE),
+            ("Stop", ID_STOP),
+            ("Skip", ID_SKIP),
+        ];
+        let button_width = (WIDTH - 24 - 2 * 12) / 3;
+        for (i, (text, id)) in buttons.into_iter().enumerate() {
+            control(
+                hwnd,
+                w!("BUTTON"),
+                text,
+                WS_TABSTOP.0 | BS_PUSHBUTTON as u32,
+                id,
+                (12 + i as i32 * (button_width + 12), 78, button_width, 28),
+            );
+        }
+
+        Ok(hwnd)
+    }
+
+    unsafe extern "system" fn window_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        match msg {
+            WM_COMMAND => {
+                let action = match (wparam.0 & 0xFFFF) as i32 {
+                    ID_PLAY_PAUSE => Some(PlayerAction::PlayPause),
+                    ID_STOP => Some(PlayerAction::Stop),
+                    ID_SKIP => Some(PlayerAction::Skip),
+                    _ => None,
+                };
+                if let Some(action) = action {
+                    PLAYER.with(|player| {
+                        if let Some(player) = player.borrow().as_ref() {
+                            let _ = player.action_tx.send(action);
+                        }
+                    });
+                }
+                LRESULT(0)
+            }
+            WM_TIMER => {
+                refresh(hwnd);
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                let _ = DestroyWindow(hwnd);
+                LRESULT(0)
+            }
+            WM_DESTROY => {
+                let _ = KillTimer(hwnd, REFRESH_TIMER);
+                PostQuitMessage(0);
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+
+    /// Show the app's latest state, if it changed since it was last shown
+    unsafe fn refresh(hwnd: HWND) {
+        PLAYER.with(|player| {
+            let mut player = player.borrow_mut();
+            let Some(player) = player.as_mut() else {
+                return;
+            };
+            let Some(state) = player.state.lock().ok().map(|state| state.clone()) else {
+                return;
+            };
+            if player.shown.as_ref() == Some(&state) {
+                return;
+            }
+
+            let sentence = if state.speaking {
+                shorten(&state.sentence)
+            } else {
+                "Nothing playing".to_string()
+            };
+            set_text(hwnd, ID_SENTENCE, &sentence);
+            set_text(
+                hwnd,
+                ID_PLAY_PAUSE,
+                match (state.speaking, state.paused) {
+                    (true, false) => "Pause",
+                    (true, true) => "Resume",
+                    (false, _) => "Play",
+                },
+            );
+            if let Ok(progress) = GetDlgItem(hwnd, ID_PROGRESS) {
+                let position = (state.progress.clamp(0.0, 1.0) * PROGRESS_RANGE) as usize;
+                SendMessageW(progress, PBM_SETPOS, WPARAM(position), LPARAM(0));
+            }
+            player.shown = Some(state);
+        });
+    }
+
+    /// `sentence`, cut to what fits the window
+    fn shorten(sentence: &str) -> String {
+        if sentence.chars().count() > SENTENCE_CHARS {
+            let cut: String = sentence.chars().take(SENTENCE_CHARS).collect();
+            format!("{}…", cut.trim_end())
+        } else {
+            sentence.to_string()
+        }
+    }
+
+    unsafe fn control(
+        parent: HWND,
+        class: PCWSTR,
+        text: &str,
+        style: u32,
+        id: i32,
+        (x, y, width, height): (i32, i32, i32, i32),
+    ) -> HWND {
+        let hwnd = CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            class,
+            &HSTRING::from(text),
+            WS_CHILD | WS_VISIBLE | WINDOW_STYLE(style),
+            x,
+            y,
+            width,
+            height,
+            parent,
+            HMENU(id as isize as _),
+            None,
+            None,
+        )
+        .unwrap_or_default();
+        // Controls start in the bitmap system font otherwise
+        let font = GetStockObject(DEFAULT_GUI_FONT);
+        SendMessageW(hwnd, WM_SETFONT, WPARAM(font.0 as usize), LPARAM(1));
+        hwnd
+    }
+
+    unsafe fn set_text(hwnd: HWND, id: i32, text: &str) {
+        if let Ok(control) = GetDlgItem(hwnd, id) {
+            let _ = SetWindowTextW(control, &HSTRING::from(text));
+        }
+    }
+}
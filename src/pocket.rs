@@ -0,0 +1,231 @@
+//! Pocket TTS backend - the neural model and its voices
+
+use crate::backend::{SampleStream, TtsBackend};
+use crate::settings::{custom_voices, embedded_config, expanded_weights_dir, ModelInfo};
+use crate::tts::{ComputeDevice, Precision};
+use anyhow::Result;
+use candle_core::quantized::gguf_file;
+use pocket_tts::{ModelState, TTSModel};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// A loaded Pocket TTS model with its voices
+pub struct PocketBackend {
+    model: TTSModel,
+    voice_states: HashMap<String, ModelState>,
+}
+
+impl PocketBackend {
+    /// Load the model described by `info`
+    /// Returns the backend and the device it actually runs on
+    pub fn load(info: &ModelInfo, device: ComputeDevice, precision: Precision) -> Result<(Self, ComputeDevice)> {
+        let (model, voice_states, device) = load_model(info, device, precision)?;
+        Ok((Self { model, voice_states }, device))
+    }
+}
+
+impl TtsBackend for PocketBackend {
+    fn sample_rate(&self) -> u32 {
+        self.model.sample_rate as u32
+    }
+
+    fn voices(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.voice_states.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn has_voice(&self, voice: &str) -> bool {
+        self.voice_states.contains_key(voice)
+    }
+
+    fn synth_stream<'a>(&'a self, text: &'a str, voice: &str) -> Result<SampleStream<'a>> {
+        let voice_state = self
+            .voice_states
+            .get(voice)
+            .ok_or_else(|| anyhow::anyhow!("Voice '{}' not loaded", voice))?;
+        Ok(Box::new(
+            self.model
+                .generate_stream_long(text, voice_state)
+                .map(|chunk| chunk.map(|tensor| tensor_to_samples(&tensor))),
+        ))
+    }
+
+    fn add_voice(&mut self, name: &str, path: &Path) -> Result<()> {
+        let state = self.model.get_voice_state_from_prompt_file(path)?;
+        self.voice_states.insert(name.to_string(), state);
+        Ok(())
+    }
+}
+
+/// Convert a tensor to f32 samples
+fn tensor_to_samples(tensor: &candle_core::Tensor) -> Vec<f32> {
+    // Tensor is [B, C, T] - squeeze batch and channel to get [T]
+    let squeezed = tensor
+        .squeeze(0)
+        .and_then(|t| t.squeeze(0))
+        .unwrap_or_else(|_| tensor.clone());
+
+    squeezed.to_vec1::<f32>().unwrap_or_default()
+}
+
+fn is_gguf(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gguf"))
+}
+
+/// Dequantize a model's GGUF weights into a safetensors file at `precision`, which
+/// the model loads like any other weights
+/// The file is kept in the expanded weights directory and reused until the GGUF file changes.
+fn expand_quantized(info: &ModelInfo, path: &Path, precision: Precision) -> Result<PathBuf> {
+    let modified = std::fs::metadata(path)?.modified()?.duration_since(UNIX_EPOCH)?.as_secs();
+    let dir = expanded_weights_dir()?;
+    let expanded = dir.join(format!("{}_{}.safetensors", info.name, modified));
+    if expanded.exists() {
+        return Ok(expanded);
+    }
+    // Expansions of an earlier version of the model's weights are of no more use,
+    // nor are ones left unfinished
+    let partial = expanded.with_extension("partial");
+    if let Ok(entries) = std::fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path != partial && is_expansion_of(&entry.file_name().to_string_lossy(), &info.name) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    log::info!("Expanding quantized weights {} to {}", path.display(), precision.dtype());
+    let device = candle_core::Device::Cpu;
+    let mut file = std::fs::File::open(path)?;
+    let content = gguf_file::Content::read(&mut file)?;
+    let mut tensors = HashMap::new();
+    for name in content.tensor_infos.keys() {
+        let tensor = content
+            .tensor(&mut file, name, &device)?
+            .dequantize(&device)?
+            .to_dtype(precision.to_candle())?;
+        tensors.insert(name.clone(), tensor);
+    }
+
+    // Written aside first, so an interrupted expansion isn't taken for a finished one
+    std::fs::create_dir_all(&dir)?;
+    candle_core::safetensors::save(&tensors, &partial)?;
+    std::fs::rename(&partial, &expanded)?;
+    log::info!("Quantized weights expanded to: {}", expanded.display());
+    Ok(expanded)
+}
+
+/// Whether `file_name` is an expansion of the model called `name`, finished or not:
+/// `{name}_{modified}.safetensors` or `.partial`
+fn is_expansion_of(file_name: &str, name: &str) -> bool {
+    file_name
+        .strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix('_'))
+        .and_then(|rest| rest.strip_suffix(".safetensors").or_else(|| rest.strip_suffix(".partial")))
+        .is_some_and(|modified| !modified.is_empty() && modified.bytes().all(|byte| byte.is_ascii_digit()))
+}
+
+/// Load a model's weights, the voices shipped with it and the user's custom voices
+/// Falls back to the CPU if the model can't be loaded on `device`
+fn load_model(
+    info: &ModelInfo,
+    device: ComputeDevice,
+    precision: Precision,
+) -> Result<(TTSModel, HashMap<String, ModelState>, ComputeDevice)> {
+    let weights_path = info
+        .weights_path(precision)
+        .ok_or_else(|| anyhow::anyhow!("Model weights not found in: {}", info.dir.display()))?;
+    let weights_path = if is_gguf(&weights_path) {
+        expand_quantized(info, &weights_path, precision)?
+    } else {
+        weights_path
+    };
+    let tokenizer_path = info.tokenizer_path();
+
+    // Verify required files exist
+    if !tokenizer_path.exists() {
+        anyhow::bail!("Tokenizer not found at: {}", tokenizer_path.display());
+    }
+
+    log::info!(
+        "Loading TTS model '{}' from: {} on {} at {}",
+        info.name,
+        weights_path.display(),
+        device.label(),
+        precision.dtype()
+    );
+
+    // Load model using offline method
+    let loaded = match device {
+        ComputeDevice::Cpu => TTSModel::load_offline(&weights_path, &tokenizer_path, embedded_config(precision)),
+        _ => device.to_candle().and_then(|candle_device| {
+            TTSModel::load_offline_on_device(
+                &weights_path,
+                &tokenizer_path,
+                embedded_config(precision),
+                &candle_device,
+            )
+        }),
+    };
+    let (model, device) = match loaded {
+        Ok(model) => (model, device),
+        Err(e) if device != ComputeDevice::Cpu => {
+            log::warn!("{} unavailable, falling back to CPU: {}", device.label(), e);
+            let model = TTSModel::load_offline(&weights_path, &tokenizer_path, embedded_config(precision))?;
+            (model, ComputeDevice::Cpu)
+        }
+        Err(e) => return Err(e),
+    };
+
+    log::info!("Model loaded successfully on {}", device.label());
+
+    // Pre-load every voice installed alongside the model
+    let mut voice_states = HashMap::new();
+    for (voice_name, voice_path) in info.voices() {
+        match model.get_voice_state_from_prompt_file(&voice_path) {
+            Ok(state) => {
+                log::info!("Loaded voice: {}", voice_name);
+                voice_states.insert(voice_name, state);
+            }
+            Err(e) => {
+                log::warn!("Failed to load voice '{}': {}", voice_name, e);
+            }
+        }
+    }
+
+    // User-added voices cloned from their prompt audio
+    for (voice_name, voice_path) in custom_voices() {
+        match model.get_voice_state_from_prompt_file(&voice_path) {
+            Ok(state) => {
+                log::info!("Loaded custom voice: {}", voice_name);
+                voice_states.insert(voice_name, state);
+            }
+            Err(e) => {
+                log::warn!("Failed to load custom voice '{}': {}", voice_name, e);
+            }
+        }
+    }
+
+    if voice_states.is_empty() {
+        anyhow::bail!("No voice files found in: {}", info.dir.display());
+    }
+
+    Ok((model, voice_states, device))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expansions_are_told_apart_by_model() {
+        assert!(is_expansion_of("en_1700000000.safetensors", "en"));
+        assert!(is_expansion_of("en_1700000000.partial", "en"));
+        assert!(!is_expansion_of("en_v2_1700000000.safetensors", "en"));
+        assert!(!is_expansion_of("en_1700000000.safetensors", "en_v2"));
+        assert!(!is_expansion_of("en_.safetensors", "en"));
+        assert!(!is_expansion_of("en_1700000000.gguf", "en"));
+    }
+}
@@ -0,0 +1,116 @@
+//! Reading list: long texts put aside to be read later, kept across restarts
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Words of the text used as its title in the tray
+const TITLE_WORDS: usize = 6;
+
+/// A long text waiting in the reading list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingItem {
+    /// Unique within the list, used in menu IDs
+    pub id: u64,
+    /// First words of the text
+    pub title: String,
+    pub text: String,
+    /// Part reading stopped at, counted from 0 (texts are read in parts of
+    /// about `LONG_TEXT_PART_CHARS`)
+    pub part: usize,
+    /// Number of parts, 0 until the text has been read from
+    pub parts: usize,
+}
+
+impl ReadingItem {
+    /// Title with how far it has been read, for menus
+    pub fn label(&self) -> String {
+        if self.part > 0 && self.parts > 0 {
+            format!("{} (part {} of {})", self.title, self.part + 1, self.parts)
+        } else {
+            self.title.clone()
+        }
+    }
+}
+
+/// Long texts put aside, stored in `reading-list.json` next to the executable
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReadingList {
+    items: Vec<ReadingItem>,
+}
+
+impl ReadingList {
+    fn path() -> anyhow::Result<PathBuf> {
+        let exe = std::env::current_exe()?;
+        let dir = exe.parent().ok_or_else(|| anyhow::anyhow!("No parent directory"))?;
+        Ok(dir.join("reading-list.json"))
+    }
+
+    /// Load the list from file, or start an empty one
+    pub fn load_or_default() -> Self {
+        Self::path()
+            .ok()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path()?, contents)?;
+        Ok(())
+    }
+
+    pub fn items(&self) -> &[ReadingItem] {
+        &self.items
+    }
+
+    pub fn get(&self, id: u64) -> Option<&ReadingItem> {
+        self.items.iter().find(|item| item.id == id)
+    }
+
+    /// Add a text to the end of the list
+    pub fn add(&mut self, text: String) {
+        // Timestamps in milliseconds, bumped past the newest item to stay unique
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let id = self
+            .items
+            .iter()
+            .map(|item| item.id + 1)
+            .max()
+            .map_or(now, |next| next.max(now));
+
+        self.items.push(ReadingItem {
+            id,
+            title: title(&text),
+            text,
+            part: 0,
+            parts: 0,
+        });
+    }
+
+    /// Remember the part being read
+    pub fn set_progress(&mut self, id: u64, part: usize, parts: usize) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            item.part = part;
+            item.parts = parts;
+        }
+    }
+
+    pub fn remove(&mut self, id: u64) {
+        self.items.retain(|item| item.id != id);
+    }
+}
+
+/// The first words of a text, to list it by in the tray
+pub fn title(text: &str) -> String {
+    let words: Vec<&str> = text.split_whitespace().take(TITLE_WORDS).collect();
+    let mut title = words.join(" ");
+    if text.split_whitespace().count() > TITLE_WORDS {
+        title.push_str("...");
+    }
+    title
+}
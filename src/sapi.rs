@@ -0,0 +1,154 @@
+//! Windows SAPI voices, used when the Pocket TTS model can't be loaded
+
+use crate::backend::{SampleStream, TtsBackend};
+use anyhow::Result;
+
+/// Sample rate voices are rendered at, matching the Pocket TTS model
+const SAMPLE_RATE: u32 = 24000;
+
+/// Installed SAPI voices and a synthesizer to render them
+#[cfg(windows)]
+pub struct SapiVoices {
+    voice: windows::Win32::Media::Speech::ISpVoice,
+    /// Voice tokens by display name, sorted by name
+    tokens: Vec<(String, windows::Win32::Media::Speech::ISpObjectToken)>,
+}
+
+#[cfg(windows)]
+impl SapiVoices {
+    /// Connect to SAPI and list the installed voices
+    /// Must be called on the thread that synthesizes, COM is initialized for it
+    pub fn new() -> Result<Self> {
+        use windows::core::PCWSTR;
+        use windows::Win32::Media::Speech::{
+            IEnumSpObjectTokens, ISpObjectTokenCategory, ISpVoice, SpObjectTokenCategory, SpVoice,
+            SPCAT_VOICES,
+        };
+        use windows::Win32::System::Com::{
+            CoCreateInstance, CoInitializeEx, CoTaskMemFree, CLSCTX_ALL, COINIT_MULTITHREADED,
+        };
+
+        unsafe {
+            // Fails harmlessly if the thread already joined another apartment
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+            let voice: ISpVoice = CoCreateInstance(&SpVoice, None, CLSCTX_ALL)?;
+
+            let category: ISpObjectTokenCategory = CoCreateInstance(&SpObjectTokenCategory, None, CLSCTX_ALL)?;
+            category.SetId(SPCAT_VOICES, false)?;
+            let enumerator: IEnumSpObjectTokens = category.EnumTokens(PCWSTR::null(), PCWSTR::null())?;
+
+            let mut count = 0;
+            enumerator.GetCount(&mut count)?;
+            let mut tokens = Vec::new();
+            for _ in 0..count {
+                let mut token = None;
+                enumerator.Next(1, &mut token, None)?;
+                let Some(token) = token else {
+                    break;
+                };
+                // The token's default value is its display name
+                let name = token.GetStringValue(PCWSTR::null())?;
+                let display_name = name.to_string();
+                CoTaskMemFree(Some(name.0 as *const _));
+                tokens.push((display_name?, token));
+            }
+            tokens.sort_by(|a, b| a.0.cmp(&b.0));
+
+            if tokens.is_empty() {
+                anyhow::bail!("No Windows voices are installed");
+            }
+            Ok(Self { voice, tokens })
+        }
+    }
+
+    /// Display names of the installed voices, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.tokens.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Render `text` with the named voice as mono samples at `sample_rate`
+    fn synthesize(&self, voice: &str, text: &str, sample_rate: u32) -> Result<Vec<f32>> {
+        use windows::core::{GUID, HSTRING};
+        use windows::Win32::Media::Audio::{WAVEFORMATEX, WAVE_FORMAT_PCM};
+        use windows::Win32::Media::Speech::{ISpStream, SpStream, SPFM_CREATE_ALWAYS, SPF_IS_NOT_XML};
+        use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
+
+        /// SPDFID_WaveFormatEx: stream format described by a WAVEFORMATEX
+        const WAVE_FORMAT_EX_ID: GUID = GUID::from_u128(0xc31adbae_527f_4ff5_a230_f62bb61ff70c);
+
+        let Some((_, token)) = self.tokens.iter().find(|(name, _)| name == voice) else {
+            anyhow::bail!("Windows voice '{}' not found", voice);
+        };
+
+        // SAPI renders into a WAV file, which is read back and removed
+        let path = std::env::temp_dir().join(format!("pocket-tray-sapi-{}.wav", std::process::id()));
+        let format = WAVEFORMATEX {
+            wFormatTag: WAVE_FORMAT_PCM as u16,
+            nChannels: 1,
+            nSamplesPerSec: sample_rate,
+            nAvgBytesPerSec: sample_rate * 2,
+            nBlockAlign: 2,
+            wBitsPerSample: 16,
+            cbSize: 0,
+        };
+
+        unsafe {
+            let stream: ISpStream = CoCreateInstance(&SpStream, None, CLSCTX_ALL)?;
+            stream.BindToFile(
+                &HSTRING::from(path.as_os_str()),
+                SPFM_CREATE_ALWAYS,
+                Some(&WAVE_FORMAT_EX_ID),
+                Some(&format),
+                0,
+            )?;
+            self.voice.SetVoice(token)?;
+            self.voice.SetOutput(&stream, true)?;
+            let spoken = self.voice.Speak(&HSTRING::from(text), SPF_IS_NOT_XML.0 as u32, None);
+            stream.Close()?;
+            spoken?;
+        }
+
+        let samples = hound::WavReader::open(&path)?
+            .into_samples::<i16>()
+            .map(|sample| sample.map(|s| s as f32 / i16::MAX as f32))
+            .collect::<std::result::Result<Vec<f32>, _>>();
+        let _ = std::fs::remove_file(&path);
+        Ok(samples?)
+    }
+}
+
+/// SAPI is only available on Windows
+#[cfg(not(windows))]
+pub struct SapiVoices;
+
+#[cfg(not(windows))]
+impl SapiVoices {
+    pub fn new() -> Result<Self> {
+        anyhow::bail!("Windows voices are not available on this platform")
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn synthesize(&self, _voice: &str, _text: &str, _sample_rate: u32) -> Result<Vec<f32>> {
+        anyhow::bail!("Windows voices are not available on this platform")
+    }
+}
+
+impl TtsBackend for SapiVoices {
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn voices(&self) -> Vec<String> {
+        self.names()
+    }
+
+    /// The whole text is rendered up front, as a single chunk
+    fn synth_stream<'a>(&'a self, text: &'a str, voice: &str) -> Result<SampleStream<'a>> {
+        let samples = self.synthesize(voice, text, SAMPLE_RATE)?;
+        Ok(Box::new(std::iter::once(Ok(samples))))
+    }
+}
@@ -1,20 +1,378 @@
 //! Settings persistence and embedded model configuration
 
+use crate::archive::Archive;
+use crate::audio::EqGains;
+use crate::chime::{ChimeMode, Chimes};
+use crate::clipboard::{CopyLimits, FileLimits};
+use crate::export::ExportFormat;
+use crate::filter::TextFilter;
+use crate::hotkey::Hotkey;
+use crate::icon;
+use crate::logging::LogSettings;
+use crate::output::{AudioLatency, OutputPreset};
+use crate::soft::{NightVolume, SoftMode, SoftSchedule};
+use crate::source::{AppFilter, AppFilterMode};
+use crate::text::{CodeMode, EmojiMode, JsonMode, ListMode, TextOptions, UrlMode};
+use crate::tray::ClickAction;
+use crate::tts::{ComputeDevice, Precision};
+use crate::voice_info::{VoiceGrouping, VoiceInfo};
+use image::Rgba;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-/// Application settings persisted to JSON file
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Application settings persisted to JSON file, in sections; the file keeps every
+/// field at the top level, and missing fields fall back to their defaults so older
+/// files keep loading
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
-    pub monitor_enabled: bool,
+    /// Voice, model and how text is read
+    #[serde(flatten)]
+    pub speech: SpeechSettings,
+    /// Playback, chimes and saved speech
+    #[serde(flatten)]
+    pub audio: AudioSettings,
+    /// What's done with copies
+    #[serde(flatten)]
+    pub clipboard: ClipboardSettings,
+    /// Global hotkeys
+    #[serde(flatten)]
+    pub hotkeys: HotkeySettings,
+    /// Which copies are spoken
+    #[serde(flatten)]
+    pub filters: FilterSettings,
+    /// Tray icon, menus and windows
+    #[serde(flatten)]
+    pub ui: UiSettings,
+}
+
+/// Speech settings: voice, model and how text is read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeechSettings {
+    /// Name of the selected voice
     pub current_voice: String,
+    /// Model directory name inside `models/`, or "default" for the models folder itself
+    pub current_model: String,
+    /// Device the model runs on; falls back to the CPU if unavailable
+    pub device: ComputeDevice,
+    /// Precision the model weights are loaded at
+    pub precision: Precision,
+    /// Free the model after this many minutes without speech (0 keeps it loaded)
+    pub unload_after_minutes: u32,
+    /// Size limit of the on-disk cache of generated sentences in MB (0 disables it)
+    pub audio_cache_mb: u32,
+    /// Voices that read sentences in other languages, keyed by ISO 639-1 code (e.g. "fr")
+    pub language_voices: HashMap<String, String>,
+    /// Voices copies from these executables (e.g. "OUTLOOK.EXE") are read in,
+    /// instead of the selected voice
+    pub app_voices: HashMap<String, String>,
+    /// Per-voice speed, gain and pause overrides, keyed by voice name
+    pub voice_prosody: HashMap<String, VoiceProsody>,
+    /// Names voices are listed by instead of their own, keyed by voice name
+    pub voice_aliases: HashMap<String, String>,
+    /// Voices left out of the voice menus and the next voice hotkey
+    pub hidden_voices: Vec<String>,
+    /// How links in copied text are spoken
+    pub url_mode: UrlMode,
+    /// How emoji in copied text are spoken
+    pub emoji_mode: EmojiMode,
+    /// Whether code blocks are read, skipped or announced
+    pub code_mode: CodeMode,
+    /// Whether list items are read as written, paced or numbered
+    pub list_mode: ListMode,
+    /// Whether copied JSON is read, summarized or skipped
+    pub json_mode: JsonMode,
+    /// Speak only the headings and first sentence of each paragraph of long texts
+    pub skim_long_texts: bool,
+}
+
+/// Audio settings: playback, chimes and saved speech
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// Volume speech plays at, in percent; the mouse wheel over the tray icon changes it
+    pub volume_percent: u32,
+    /// Volume during night hours, in percent (100 keeps the usual volume)
+    pub night_volume_percent: u32,
+    /// Hour (0-23) the night volume starts
+    pub night_from_hour: u32,
+    /// Hour (0-23) the night volume ends
+    pub night_until_hour: u32,
+    /// Pitch shift in semitones applied to every voice, on top of its own
+    pub pitch_semitones: i32,
+    /// Bass, mid and treble boost or cut in dB, applied to playback
+    pub equalizer: EqGains,
+    /// Play speech quieter and muffled, always or during night hours
+    pub soft_voice: SoftMode,
+    /// Hour (0-23) the scheduled soft voice starts
+    pub soft_from_hour: u32,
+    /// Hour (0-23) the scheduled soft voice ends
+    pub soft_until_hour: u32,
+    /// Buffer size the audio output device is opened with
+    pub audio_latency: AudioLatency,
+    /// Named output devices offered in the tray
+    pub output_presets: Vec<OutputPreset>,
+    /// Name of the preset speech plays on; the system default device if unset
+    pub output_preset: Option<String>,
+    /// Lower the volume of other applications while speaking
+    pub duck_others: bool,
+    /// Volume other applications are lowered to while speaking, in percent of their own
+    pub duck_volume_percent: u32,
+    /// Play a chime before and/or after each text
+    pub chime: ChimeMode,
+    /// WAV file played before each text instead of the built-in chime
+    pub chime_start_wav: Option<PathBuf>,
+    /// WAV file played after each text instead of the built-in chime
+    pub chime_end_wav: Option<PathBuf>,
+    /// File format used by "Save Last Speech" and the archive
+    pub export_format: ExportFormat,
+    /// Save everything spoken to the archive folder
+    pub archive_enabled: bool,
+    /// Folder spoken texts are archived in; `archive/` next to the executable if unset
+    pub archive_dir: Option<PathBuf>,
+    /// Most archived files kept, the oldest are deleted first (0 keeps all)
+    pub archive_keep: u32,
+}
+
+/// Clipboard settings: what's done with copies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardSettings {
+    /// Speak copied text
+    pub monitor_enabled: bool,
+    /// Stop the current utterance and speak new clipboard text immediately
+    pub interrupt_on_copy: bool,
+    /// Read the text in copied images, such as screenshots
+    pub read_images: bool,
+    /// Show each copy in a notification and only speak it if "Speak" is clicked
+    pub ask_before_speaking: bool,
+    /// Collect copies instead of speaking each, to speak them together later
+    pub collect_copies: bool,
+    /// Copied .txt and .md files up to this size in KB are read aloud (0 never reads
+    /// copied files)
+    pub file_max_kb: u64,
+    /// Copied files bigger than this many KB are only read after asking (0 never asks)
+    pub file_confirm_kb: u64,
+    /// Copying the same text again speaks it again once this many seconds have passed
+    /// since it was last copied (0 never repeats it)
+    pub repeat_after_seconds: u64,
+    /// Copied texts longer than this many characters may be put on the reading list
+    /// instead of being read right away (0 never asks)
+    pub reading_list_chars: usize,
+    /// Log file whose new lines are spoken as they're appended, if any
+    pub follow_log: Option<PathBuf>,
+}
+
+/// Global hotkeys, each a combination such as "Ctrl+Alt+R"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeySettings {
+    /// Global hotkey that reads the selection in any app, e.g. "Ctrl+Alt+R" (None for none)
+    pub read_selection_hotkey: Option<String>,
+    /// Global hotkey that speaks the collected copies (None for none)
+    pub speak_collected_hotkey: Option<String>,
+    /// Global hotkey that switches to the next voice and says its name (None for none)
+    pub next_voice_hotkey: Option<String>,
+    /// Global hotkey that stops speech, shown next to Stop in the tray (None for none)
+    pub stop_hotkey: Option<String>,
+    /// Global hotkey that turns monitoring on or off, shown next to Monitoring (None for none)
+    pub monitor_hotkey: Option<String>,
+    /// Global hotkey that quits, shown next to Quit (None for none)
+    pub quit_hotkey: Option<String>,
+    /// Global hotkey that opens the player window, shown next to Player (None for none)
+    pub player_hotkey: Option<String>,
+}
+
+/// Filter settings: which copies are spoken
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterSettings {
+    /// Whether copies from the denied apps are skipped, or only the allowed apps' spoken
+    pub app_filter: AppFilterMode,
+    /// Executables (e.g. "KeePass.exe") whose copies are never spoken
+    pub denied_apps: Vec<String>,
+    /// Executables whose copies are the only ones spoken, in allow mode
+    pub allowed_apps: Vec<String>,
+    /// Copied text matching any of these regexes is never spoken
+    pub exclude_patterns: Vec<String>,
+    /// If any are set, only copied text matching one of these regexes is spoken
+    pub include_patterns: Vec<String>,
+    /// Skip copied hashes, UUIDs, base64 blobs and long file paths
+    pub skip_machine_strings: bool,
+    /// Executables (e.g. "mstsc.exe") that silence monitoring while they're in the
+    /// foreground, pausing any speech until focus leaves them
+    pub quiet_apps: Vec<String>,
+}
+
+/// Interface settings: tray icon, menus and windows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiSettings {
+    /// Tray icon color as "#RRGGBB"; unset to match the taskbar theme
+    pub icon_color: Option<String>,
+    /// What a left click on the tray icon does
+    pub click_action: ClickAction,
+    /// What a double-click on the tray icon does
+    pub double_click_action: ClickAction,
+    /// What the voices menu is grouped by
+    pub voice_grouping: VoiceGrouping,
+    /// Show the drop target, a small always-on-top window that speaks text and text
+    /// files dropped on it
+    pub drop_window: bool,
+    /// Launch at sign-in, from the current user's Run registry key
+    pub start_with_windows: bool,
+    /// Look for a newer release at startup, downloading it to install on the next start
+    pub check_for_updates: bool,
+    /// How much is logged, and to which file
+    pub log: LogSettings,
+}
+
+/// Prosody overrides applied while a particular voice is selected
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VoiceProsody {
+    /// Speaking rate multiplier (0.5 to 2.0), applied without changing pitch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+    /// Volume multiplier (0.0 to 4.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gain: Option<f32>,
+    /// Pitch shift in semitones (-12 to 12)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pitch: Option<f32>,
+    /// Length of a bare `[pause]` in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pause_ms: Option<u64>,
 }
 
-impl Default for Settings {
+impl VoiceProsody {
+    /// Speed multiplier, clamped to a usable range
+    pub fn speed(&self) -> f32 {
+        self.speed.unwrap_or(1.0).clamp(0.5, 2.0)
+    }
+
+    /// Pitch shift in semitones, clamped to a usable range
+    pub fn pitch(&self) -> f32 {
+        self.pitch.unwrap_or(0.0).clamp(-12.0, 12.0)
+    }
+
+    /// Gain multiplier, clamped to a usable range
+    pub fn gain(&self) -> f32 {
+        self.gain.unwrap_or(1.0).clamp(0.0, 4.0)
+    }
+}
+
+impl Default for SpeechSettings {
     fn default() -> Self {
         Self {
-            monitor_enabled: true,
             current_voice: "alba".to_string(),
+            current_model: DEFAULT_MODEL.to_string(),
+            device: ComputeDevice::default(),
+            precision: Precision::default(),
+            unload_after_minutes: 30,
+            audio_cache_mb: 200,
+            language_voices: HashMap::new(),
+            app_voices: HashMap::new(),
+            voice_prosody: HashMap::new(),
+            voice_aliases: HashMap::new(),
+            hidden_voices: Vec::new(),
+            url_mode: UrlMode::default(),
+            emoji_mode: EmojiMode::default(),
+            code_mode: CodeMode::default(),
+            list_mode: ListMode::default(),
+            json_mode: JsonMode::default(),
+            skim_long_texts: false,
+        }
+    }
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            volume_percent: 100,
+            night_volume_percent: 100,
+            night_from_hour: 22,
+            night_until_hour: 7,
+            pitch_semitones: 0,
+            equalizer: EqGains::default(),
+            soft_voice: SoftMode::default(),
+            soft_from_hour: 22,
+            soft_until_hour: 7,
+            audio_latency: AudioLatency::default(),
+            output_presets: Vec::new(),
+            output_preset: None,
+            duck_others: false,
+            duck_volume_percent: 30,
+            chime: ChimeMode::default(),
+            chime_start_wav: None,
+            chime_end_wav: None,
+            export_format: ExportFormat::default(),
+            archive_enabled: false,
+            archive_dir: None,
+            archive_keep: 500,
+        }
+    }
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        Self {
+            monitor_enabled: true,
+            interrupt_on_copy: false,
+            read_images: false,
+            ask_before_speaking: false,
+            collect_copies: false,
+            file_max_kb: 1024,
+            file_confirm_kb: 64,
+            repeat_after_seconds: 30,
+            reading_list_chars: 5000,
+            follow_log: None,
+        }
+    }
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            read_selection_hotkey: Some("Ctrl+Alt+R".to_string()),
+            speak_collected_hotkey: Some("Ctrl+Alt+K".to_string()),
+            next_voice_hotkey: Some("Ctrl+Alt+N".to_string()),
+            stop_hotkey: None,
+            monitor_hotkey: None,
+            quit_hotkey: None,
+            player_hotkey: None,
+        }
+    }
+}
+
+impl Default for FilterSettings {
+    fn default() -> Self {
+        Self {
+            app_filter: AppFilterMode::default(),
+            denied_apps: Vec::new(),
+            allowed_apps: Vec::new(),
+            exclude_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            skip_machine_strings: true,
+            quiet_apps: Vec::new(),
+        }
+    }
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            icon_color: None,
+            click_action: ClickAction::None,
+            double_click_action: ClickAction::Replay,
+            voice_grouping: VoiceGrouping::default(),
+            drop_window: false,
+            start_with_windows: false,
+            check_for_updates: false,
+            log: LogSettings::default(),
         }
     }
 }
@@ -27,13 +385,311 @@ impl Settings {
         Ok(dir.join("pocket-tray.json"))
     }
 
-    /// Load settings from file or return default
-    pub fn load_or_default() -> Self {
-        Self::config_path()
+    /// Load settings from file, or the defaults if there's none or it can't be read,
+    /// along with the problems found in it
+    /// An unreadable file is copied aside first, as saving the defaults replaces it
+    pub fn load_checked() -> (Self, Vec<String>) {
+        let Ok(path) = Self::config_path() else {
+            return (Self::default(), Vec::new());
+        };
+        if !path.exists() {
+            return (Self::default(), Vec::new());
+        }
+        match Self::load() {
+            Ok(settings) => {
+                let problems = settings.problems();
+                (settings, problems)
+            }
+            Err(e) => {
+                let kept = path.with_extension("invalid.json");
+                let problem = match std::fs::copy(&path, &kept) {
+                    Ok(_) => format!(
+                        "The settings file couldn't be read ({:#}), so the defaults are used; it was kept as {}",
+                        e,
+                        kept.display()
+                    ),
+                    Err(_) => format!("The settings file couldn't be read ({:#}), so the defaults are used", e),
+                };
+                (Self::default(), vec![problem])
+            }
+        }
+    }
+
+    /// Read the settings file, failing if it's missing or invalid
+    pub fn load() -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(Self::config_path()?)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Values that are out of range, or otherwise can't be used as written
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, patterns) in [
+            ("exclude_patterns", &self.filters.exclude_patterns),
+            ("include_patterns", &self.filters.include_patterns),
+        ] {
+            for pattern in patterns {
+                if let Err(e) = Regex::new(pattern) {
+                    // The regex error spans lines, pointing at the offending part
+                    let reason = e.to_string();
+                    let reason = reason.lines().last().unwrap_or_default().trim().to_string();
+                    problems.push(format!(
+                        "{}: '{}' is not a valid regex ({}), it's ignored",
+                        name, pattern, reason
+                    ));
+                }
+            }
+        }
+        for (name, hotkey) in [
+            ("read_selection_hotkey", &self.hotkeys.read_selection_hotkey),
+            ("speak_collected_hotkey", &self.hotkeys.speak_collected_hotkey),
+            ("next_voice_hotkey", &self.hotkeys.next_voice_hotkey),
+            ("stop_hotkey", &self.hotkeys.stop_hotkey),
+            ("monitor_hotkey", &self.hotkeys.monitor_hotkey),
+            ("quit_hotkey", &self.hotkeys.quit_hotkey),
+            ("player_hotkey", &self.hotkeys.player_hotkey),
+        ] {
+            if let Some(Err(e)) = hotkey.as_deref().map(Hotkey::parse) {
+                problems.push(format!("{}: {}, it's not registered", name, e));
+            }
+        }
+        for (voice, prosody) in &self.speech.voice_prosody {
+            for (name, value, min, max) in [
+                ("speed", prosody.speed, 0.5, 2.0),
+                ("gain", prosody.gain, 0.0, 4.0),
+                ("pitch", prosody.pitch, -12.0, 12.0),
+            ] {
+                if let Some(value) = value.filter(|value| !(min..=max).contains(value)) {
+                    problems.push(format!(
+                        "voice_prosody: {} {} for '{}' is outside {} to {}, so {} is used",
+                        name,
+                        value,
+                        voice,
+                        min,
+                        max,
+                        value.clamp(min, max)
+                    ));
+                }
+            }
+        }
+        if !(-12..=12).contains(&self.audio.pitch_semitones) {
+            problems.push(format!(
+                "pitch_semitones: {} is outside -12 to 12",
+                self.audio.pitch_semitones
+            ));
+        }
+        for (name, percent) in [
+            ("volume_percent", self.audio.volume_percent),
+            ("night_volume_percent", self.audio.night_volume_percent),
+            ("duck_volume_percent", self.audio.duck_volume_percent),
+        ] {
+            if percent > 100 {
+                problems.push(format!("{}: {} is over 100, so 100 is used", name, percent));
+            }
+        }
+        for (name, hour) in [
+            ("soft_from_hour", self.audio.soft_from_hour),
+            ("soft_until_hour", self.audio.soft_until_hour),
+            ("night_from_hour", self.audio.night_from_hour),
+            ("night_until_hour", self.audio.night_until_hour),
+        ] {
+            if hour > 23 {
+                problems.push(format!("{}: {} is not an hour from 0 to 23", name, hour));
+            }
+        }
+        if let Some(color) = self
+            .ui
+            .icon_color
+            .as_deref()
+            .filter(|color| icon::parse_color(color).is_none())
+        {
+            problems.push(format!(
+                "icon_color: '{}' is not a \"#RRGGBB\" color, it's ignored",
+                color
+            ));
+        }
+        if let Some(name) = self
+            .audio
+            .output_preset
+            .as_ref()
+            .filter(|_| self.output_device().is_none())
+        {
+            problems.push(format!(
+                "output_preset: there's no preset named '{}', the default device is used",
+                name
+            ));
+        }
+        for language in self.speech.language_voices.keys() {
+            if language.len() != 2 || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+                problems.push(format!(
+                    "language_voices: '{}' is not a two-letter language code, it's never matched",
+                    language
+                ));
+            }
+        }
+        problems
+    }
+
+    /// When the settings file was last written, None if there isn't one
+    pub fn modified() -> Option<SystemTime> {
+        std::fs::metadata(Self::config_path().ok()?)
+            .and_then(|metadata| metadata.modified())
             .ok()
-            .and_then(|p| std::fs::read_to_string(p).ok())
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default()
+    }
+
+    /// Text processing options for the TTS engine
+    pub fn text_options(&self) -> TextOptions {
+        TextOptions {
+            url_mode: self.speech.url_mode,
+            emoji_mode: self.speech.emoji_mode,
+            code_mode: self.speech.code_mode,
+            list_mode: self.speech.list_mode,
+            json_mode: self.speech.json_mode,
+            default_pause: None,
+        }
+    }
+
+    /// How long the model may sit unused before it's unloaded, if at all
+    pub fn idle_unload(&self) -> Option<Duration> {
+        (self.speech.unload_after_minutes > 0)
+            .then(|| Duration::from_secs(self.speech.unload_after_minutes as u64 * 60))
+    }
+
+    /// Sizes of copied text files that are read aloud
+    pub fn file_limits(&self) -> FileLimits {
+        FileLimits {
+            max_bytes: self.clipboard.file_max_kb.saturating_mul(1024),
+            confirm_bytes: self.clipboard.file_confirm_kb.saturating_mul(1024),
+        }
+    }
+
+    /// How long after its last copy the same text is spoken again, if ever
+    pub fn repeat_after(&self) -> Option<Duration> {
+        (self.clipboard.repeat_after_seconds > 0).then(|| Duration::from_secs(self.clipboard.repeat_after_seconds))
+    }
+
+    /// Limits on which copies are spoken, for the clipboard thread
+    pub fn copy_limits(&self) -> CopyLimits {
+        CopyLimits {
+            files: self.file_limits(),
+            repeat_after: self.repeat_after(),
+            reading_threshold: self.clipboard.reading_list_chars,
+        }
+    }
+
+    /// Factor other applications' volume is scaled by while speaking
+    pub fn duck_level(&self) -> f32 {
+        self.audio.duck_volume_percent.min(100) as f32 / 100.0
+    }
+
+    /// Output device of the selected preset (None for the system default)
+    pub fn output_device(&self) -> Option<String> {
+        let name = self.audio.output_preset.as_ref()?;
+        self.audio
+            .output_presets
+            .iter()
+            .find(|preset| &preset.name == name)
+            .map(|preset| preset.device.clone())
+    }
+
+    /// Which applications copies are spoken from, and in which voices
+    pub fn app_filter(&self) -> AppFilter {
+        AppFilter {
+            mode: self.filters.app_filter,
+            denied: self.filters.denied_apps.clone(),
+            allowed: self.filters.allowed_apps.clone(),
+            voices: self.speech.app_voices.clone(),
+        }
+    }
+
+    /// Which copied texts are spoken, by content
+    pub fn text_filter(&self) -> TextFilter {
+        TextFilter::new(
+            &self.filters.exclude_patterns,
+            &self.filters.include_patterns,
+            self.filters.skip_machine_strings,
+        )
+    }
+
+    /// When the soft voice is used
+    pub fn soft_schedule(&self) -> SoftSchedule {
+        SoftSchedule {
+            mode: self.audio.soft_voice,
+            from_hour: self.audio.soft_from_hour,
+            until_hour: self.audio.soft_until_hour,
+        }
+    }
+
+    /// Color the tray icon is drawn in
+    /// Without a valid `icon_color`, the one that stands out on the taskbar
+    pub fn icon_color(&self) -> Rgba<u8> {
+        self.ui
+            .icon_color
+            .as_deref()
+            .and_then(icon::parse_color)
+            .unwrap_or_else(icon::taskbar_color)
+    }
+
+    /// Volume speech plays at, 0 to 1
+    pub fn volume(&self) -> f32 {
+        self.audio.volume_percent.min(100) as f32 / 100.0
+    }
+
+    /// How much quieter speech plays at night
+    pub fn night_volume(&self) -> NightVolume {
+        NightVolume {
+            percent: self.audio.night_volume_percent,
+            from_hour: self.audio.night_from_hour,
+            until_hour: self.audio.night_until_hour,
+        }
+    }
+
+    /// Whether a voice is listed in the voice menus and switched to by the next voice
+    /// hotkey; the selected one always is
+    pub fn is_voice_shown(&self, name: &str, selected: &str) -> bool {
+        name == selected || !self.speech.hidden_voices.iter().any(|hidden| hidden == name)
+    }
+
+    /// A voice as the menus list it, renamed if it has an alias
+    pub fn menu_voice(&self, mut voice: VoiceInfo) -> VoiceInfo {
+        if let Some(alias) = self.speech.voice_aliases.get(&voice.name) {
+            voice.display_name = alias.clone();
+        }
+        voice
+    }
+
+    /// Voices as the menus list them, renamed and without the hidden ones
+    pub fn menu_voices(&self, voices: Vec<VoiceInfo>, selected: &str) -> Vec<VoiceInfo> {
+        voices
+            .into_iter()
+            .filter(|voice| self.is_voice_shown(&voice.name, selected))
+            .map(|voice| self.menu_voice(voice))
+            .collect()
+    }
+
+    /// Chimes played around each text, as configured
+    pub fn chimes(&self) -> Chimes {
+        Chimes::new(
+            self.audio.chime,
+            self.audio.chime_start_wav.as_deref(),
+            self.audio.chime_end_wav.as_deref(),
+        )
+    }
+
+    /// Where spoken texts are archived, if archiving is on
+    pub fn archive(&self) -> Option<Archive> {
+        if !self.audio.archive_enabled {
+            return None;
+        }
+        let dir = match &self.audio.archive_dir {
+            Some(dir) => dir.clone(),
+            None => archive_dir().ok()?,
+        };
+        Some(Archive::new(
+            dir,
+            self.audio.export_format,
+            self.audio.archive_keep as usize,
+        ))
     }
 
     /// Save settings to file
@@ -52,31 +708,195 @@ pub fn models_dir() -> anyhow::Result<PathBuf> {
     Ok(dir.join("models"))
 }
 
-/// List of available voices
-pub const VOICES: &[&str] = &[
-    "alba",
-    "azelma",
-    "cosette",
-    "eponine",
-    "fantine",
-    "javert",
-    "jean",
-    "marius",
-];
+/// Get the directory quantized weights are expanded into for loading (next to executable)
+pub fn expanded_weights_dir() -> anyhow::Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    let dir = exe.parent().ok_or_else(|| anyhow::anyhow!("No parent directory"))?;
+    Ok(dir.join("weights-cache"))
+}
+
+/// Get the directory saved speech is written to (next to executable)
+pub fn recordings_dir() -> anyhow::Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    let dir = exe.parent().ok_or_else(|| anyhow::anyhow!("No parent directory"))?;
+    Ok(dir.join("recordings"))
+}
+
+/// Get the default directory spoken texts are archived in (next to executable)
+pub fn archive_dir() -> anyhow::Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    let dir = exe.parent().ok_or_else(|| anyhow::anyhow!("No parent directory"))?;
+    Ok(dir.join("archive"))
+}
+
+/// Get the directory generated audio is cached in (next to executable)
+pub fn cache_dir() -> anyhow::Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    let dir = exe.parent().ok_or_else(|| anyhow::anyhow!("No parent directory"))?;
+    Ok(dir.join("cache"))
+}
+
+/// Get the directory user-added voice prompts are kept in (next to executable)
+pub fn voices_dir() -> anyhow::Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    let dir = exe.parent().ok_or_else(|| anyhow::anyhow!("No parent directory"))?;
+    Ok(dir.join("voices"))
+}
+
+/// Name given to a model placed directly in the models directory
+pub const DEFAULT_MODEL: &str = "default";
+
+/// Model weights files are named `tts_<hash>.safetensors`
+const MODEL_WEIGHTS_PREFIX: &str = "tts_";
+
+/// A TTS model found in the models directory
+#[derive(Debug, Clone)]
+pub struct ModelInfo {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+impl ModelInfo {
+    /// Path to the model weights for `precision`
+    /// A pre-converted variant (`tts_<hash>_f16.safetensors`, or `tts_<hash>.gguf` for
+    /// quantized weights) is preferred when present, otherwise the full weights are
+    /// converted while loading
+    pub fn weights_path(&self, precision: Precision) -> Option<PathBuf> {
+        if precision == Precision::Quantized {
+            let quantized = files_with_extension(&self.dir, "gguf")
+                .into_iter()
+                .find(|(_, path)| is_weights_file(path));
+            if let Some((_, path)) = quantized {
+                return Some(path);
+            }
+        }
+        let weights: Vec<(String, PathBuf)> = files_with_extension(&self.dir, "safetensors")
+            .into_iter()
+            .filter(|(_, path)| is_weights_file(path))
+            .collect();
+        let is_variant = |stem: &str| Precision::ALL.iter().any(|p| stem.ends_with(&format!("_{}", p.id())));
+
+        weights
+            .iter()
+            .find(|(stem, _)| stem.ends_with(&format!("_{}", precision.id())))
+            .or_else(|| weights.iter().find(|(stem, _)| !is_variant(stem)))
+            .or_else(|| weights.first())
+            .map(|(_, path)| path.clone())
+    }
+
+    /// Path to the tokenizer
+    pub fn tokenizer_path(&self) -> PathBuf {
+        self.dir.join("tokenizer.model")
+    }
+
+    /// Voices shipped with the model as (name, voice path), sorted by name
+    /// Every `*.safetensors` file except the model weights is a voice;
+    /// for Piper models every `*.onnx` file is one
+    pub fn voices(&self) -> Vec<(String, PathBuf)> {
+        if self.is_piper() {
+            return files_with_extension(&self.dir, "onnx");
+        }
+        files_with_extension(&self.dir, "safetensors")
+            .into_iter()
+            .filter(|(_, path)| !is_weights_file(path))
+            .collect()
+    }
+
+    /// Whether this is a folder of Piper voices (`*.onnx` with their `*.onnx.json` config)
+    /// rather than Pocket TTS weights
+    pub fn is_piper(&self) -> bool {
+        self.weights_path(Precision::default()).is_none() && !files_with_extension(&self.dir, "onnx").is_empty()
+    }
+}
+
+/// Models installed next to the executable: the models directory itself (as "default")
+/// if it holds weights, then every `models/<model-name>/` that holds weights or Piper voices
+pub fn installed_models() -> Vec<ModelInfo> {
+    let Ok(root) = models_dir() else {
+        return Vec::new();
+    };
+
+    let mut models = vec![ModelInfo {
+        name: DEFAULT_MODEL.to_string(),
+        dir: root.clone(),
+    }];
+
+    if let Ok(entries) = std::fs::read_dir(&root) {
+        let mut dirs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+        models.extend(dirs.into_iter().filter_map(|dir| {
+            let name = dir.file_name()?.to_str()?.to_string();
+            Some(ModelInfo { name, dir })
+        }));
+    }
+
+    models.retain(|model| {
+        model.weights_path(Precision::default()).is_some()
+            || model.weights_path(Precision::Quantized).is_some()
+            || model.is_piper()
+    });
+    models
+}
+
+/// Find a model by name, falling back to the first installed model
+pub fn find_model(name: &str) -> Option<ModelInfo> {
+    let models = installed_models();
+    models
+        .iter()
+        .find(|model| model.name == name)
+        .or_else(|| models.first())
+        .cloned()
+}
+
+fn is_weights_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with(MODEL_WEIGHTS_PREFIX))
+        .unwrap_or(false)
+}
+
+/// User-added voices as (name, prompt audio path), sorted by name
+pub fn custom_voices() -> Vec<(String, PathBuf)> {
+    voices_dir()
+        .map(|dir| files_with_extension(&dir, "wav"))
+        .unwrap_or_default()
+}
+
+/// Files in `dir` with the given extension as (file stem, path), sorted by stem
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .map(|ext| ext.eq_ignore_ascii_case(extension))
+                .unwrap_or(false)
+        })
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            Some((name, path))
+        })
+        .collect();
+    files.sort();
+    files
+}
 
 /// Create embedded model configuration matching b6369a24.yaml
 /// This avoids needing to ship/parse a YAML file at runtime
-pub fn embedded_config() -> pocket_tts::config::Config {
+pub fn embedded_config(precision: Precision) -> pocket_tts::config::Config {
     pocket_tts::config::Config {
         weights_path: None,
         weights_path_without_voice_cloning: None,
         flow_lm: pocket_tts::config::FlowLMConfig {
-            dtype: "float32".to_string(),
+            dtype: precision.dtype().to_string(),
             weights_path: None,
-            flow: pocket_tts::config::FlowConfig {
-                dim: 512,
-                depth: 6,
-            },
+            flow: pocket_tts::config::FlowConfig { dim: 512, depth: 6 },
             transformer: pocket_tts::config::FlowLMTransformerConfig {
                 d_model: 1024,
                 hidden_scale: 4,
@@ -92,7 +912,7 @@ pub fn embedded_config() -> pocket_tts::config::Config {
             },
         },
         mimi: pocket_tts::config::MimiConfig {
-            dtype: "float32".to_string(),
+            dtype: precision.dtype().to_string(),
             sample_rate: 24000,
             channels: 1,
             frame_rate: 12.5,
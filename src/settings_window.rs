@@ -0,0 +1,775 @@
+//! Settings window, for changing the everyday settings without editing settings.json
+//!
+//! The window runs on a thread of its own and hands the edited settings back
+//! through a channel when OK is pressed; the app applies them from there.
+
+use crate::settings::Settings;
+use std::sync::mpsc::Sender;
+use std::thread::JoinHandle;
+
+/// The settings as the window opened with them and as OK left them; only what
+/// differs is applied, so changes made meanwhile from the tray or the file stay
+#[derive(Debug)]
+pub struct Edit {
+    pub opened: Settings,
+    pub edited: Settings,
+}
+
+/// Open the settings window on its own thread, showing `settings` and offering `voices`
+#[cfg(windows)]
+pub fn open(
+    settings: Settings,
+    voices: Vec<String>,
+    edited_tx: Sender<Edit>,
+) -> std::io::Result<JoinHandle<()>> {
+    std::thread::Builder::new()
+        .name("settings-window".to_string())
+        .spawn(move || win::run(settings, voices, edited_tx))
+}
+
+#[cfg(not(windows))]
+pub fn open(
+    _settings: Settings,
+    _voices: Vec<String>,
+    _edited_tx: Sender<Edit>,
+) -> std::io::Result<JoinHandle<()>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "the settings window is not supported on this platform",
+    ))
+}
+
+#[cfg(windows)]
+mod win {
+    use super::Edit;
+    use crate::hotkey::Hotkey;
+    use crate::settings::Settings;
+    use crate::source::AppFilterMode;
+    use crate::tts::ComputeDevice;
+    use std::cell::RefCell;
+    use std::sync::mpsc::Sender;
+    use windows::core::{w, HSTRING, PCWSTR};
+    use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+    use windows::Win32::Graphics::Gdi::{GetStockObject, COLOR_BTNFACE, DEFAULT_GUI_FONT, HBRUSH};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::Controls::BST_CHECKED;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        AdjustWindowRectEx, CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW,
+        GetDlgItem, GetMessageW, GetWindowTextLengthW, GetWindowTextW, IsDialogMessageW,
+        LoadCursorW, MessageBoxW, PostQuitMessage, RegisterClassW, SendMessageW,
+        SetForegroundWindow, SetWindowTextW, ShowWindow, TranslateMessage, BM_GETCHECK,
+        BM_SETCHECK, BS_AUTOCHECKBOX, BS_DEFPUSHBUTTON, BS_GROUPBOX, BS_PUSHBUTTON, CBN_SELCHANGE,
+        CBS_DROPDOWNLIST, CB_ADDSTRING, CB_GETCURSEL, CB_SETCURSEL, CW_USEDEFAULT, ES_AUTOHSCROLL,
+        ES_AUTOVSCROLL, ES_MULTILINE, ES_NUMBER, ES_WANTRETURN, HMENU, IDC_ARROW, MB_ICONWARNING,
+        MB_OK, MSG, SW_SHOW, WINDOW_EX_STYLE, WINDOW_STYLE, WM_CLOSE, WM_COMMAND, WM_DESTROY,
+        WM_SETFONT, WNDCLASSW, WS_BORDER, WS_CAPTION, WS_CHILD, WS_EX_CLIENTEDGE,
+        WS_EX_DLGMODALFRAME, WS_SYSMENU, WS_TABSTOP, WS_VISIBLE, WS_VSCROLL,
+    };
+
+    const CLASS_NAME: PCWSTR = w!("PocketTraySettings");
+
+    /// Size of the window's client area
+    const WIDTH: i32 = 460;
+    const HEIGHT: i32 = 896;
+
+    /// Where fields start, right of their labels
+    const FIELD_X: i32 = 180;
+
+    // Control IDs; OK and Cancel use the IDs Enter and Escape send
+    const ID_OK: i32 = 1;
+    const ID_CANCEL: i32 = 2;
+    const ID_VOICE: i32 = 100;
+    const ID_SPEED: i32 = 101;
+    const ID_GAIN: i32 = 102;
+    const ID_DEVICE: i32 = 103;
+    const ID_OUTPUT: i32 = 104;
+    const ID_EXCLUDE: i32 = 105;
+    const ID_INCLUDE: i32 = 106;
+    const ID_DENIED_APPS: i32 = 107;
+    const ID_QUIET_APPS: i32 = 108;
+    const ID_SKIP_MACHINE: i32 = 109;
+    const ID_READ_SELECTION: i32 = 110;
+    const ID_SPEAK_COLLECTED: i32 = 111;
+    const ID_NEXT_VOICE: i32 = 112;
+    const ID_STOP: i32 = 113;
+    const ID_MONITOR: i32 = 114;
+    const ID_QUIT: i32 = 115;
+    const ID_PLAYER: i32 = 116;
+    const ID_VOLUME: i32 = 117;
+    const ID_APP_FILTER: i32 = 118;
+    const ID_ALLOWED_APPS: i32 = 119;
+
+    /// What the open window edits, kept for its window procedure
+    struct Form {
+        settings: Settings,
+        voices: Vec<String>,
+        edited_tx: Sender<Edit>,
+    }
+
+    thread_local! {
+        static FORM: RefCell<Option<Form>> = const { RefCell::new(None) };
+    }
+
+    pub(super) fn run(settings: Settings, mut voices: Vec<String>, edited_tx: Sender<Edit>) {
+        if !voices.contains(&settings.speech.current_voice) {
+            voices.insert(0, settings.speech.current_voice.clone());
+        }
+
+        let hwnd = match unsafe { create(&settings, &voices) } {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                log::error!("Failed to open the settings window: {}", e);
+                return;
+            }
+        };
+        FORM.with(|form| {
+            *form.borrow_mut() = Some(Form {
+                settings,
+                voices,
+                edited_tx,
+            })
+        });
+
+        unsafe {
+            let _ = ShowWindow(hwnd, SW_SHOW);
+            let _ = SetForegroundWindow(hwnd);
+
+            let mut msg = MSG::default();
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                // Tab between controls, Enter for OK and Escape for Cancel
+                if !IsDialogMessageW(hwnd, &msg).as_bool() {
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+            }
+        }
+        FORM.with(|form| form.borrow_mut().take());
+    }
+
+    /// Create the window and its controls, filled in from `settings`
+    unsafe fn create(settings: &Settings, voices: &[String]) -> windows::core::Result<HWND> {
+        let instance = GetModuleHandleW(None)?;
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: instance.into(),
+            hCursor: LoadCursorW(None, IDC_ARROW)?,
+            hbrBackground: HBRUSH((COLOR_BTNFACE.0 + 1) as isize as _),
+            lpszClassName: CLASS_NAME,
+            ..Default::default()
+        };
+        // Fails harmlessly when the window was opened before
+        RegisterClassW(&class);
+
+        let style = WS_CAPTION | WS_SYSMENU;
+        let mut rect = RECT {
+            left: 0,
+            top: 0,
+            right: WIDTH,
+            bottom: HEIGHT,
+        };
+        AdjustWindowRectEx(&mut rect, style, false, WS_EX_DLGMODALFRAME)?;
+        let hwnd = CreateWindowExW(
+            WS_EX_DLGMODALFRAME,
+            CLASS_NAME,
+            w!("Pocket-Tray Settings"),
+            style,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            None,
+            None,
+            instance,
+            None,
+        )?;
+
+        let prosody = settings
+            .speech.voice_prosody
+            .get(&settings.speech.current_voice)
+            .cloned()
+            .unwrap_or_default();
+
+        // Voice
+        group(hwnd, "Voice", 12, 128);
+        label(hwnd, "Voice", 34);
+        let voice = combo(hwnd, ID_VOICE, 32, 270);
+        for name in voices {
+            add_item(voice, name);
+        }
+        select(
+            voice,
+            voices
+                .iter()
+                .position(|name| *name == settings.speech.current_voice),
+        );
+        label(hwnd, "Speed (%)", 62);
+        edit(
+            hwnd,
+            ID_SPEED,
+            &percent(prosody.speed),
+            ES_NUMBER,
+            (FIELD_X, 60, 60, 22),
+        );
+        note(hwnd, "50 to 200", 250, 62);
+        label(hwnd, "Voice gain (%)", 88);
+        edit(
+            hwnd,
+            ID_GAIN,
+            &percent(prosody.gain),
+            ES_NUMBER,
+            (FIELD_X, 86, 60, 22),
+        );
+        note(hwnd, "0 to 400", 250, 88);
+        label(hwnd, "Volume (%)", 116);
+        edit(
+            hwnd,
+            ID_VOLUME,
+            &settings.audio.volume_percent.min(100).to_string(),
+            ES_NUMBER,
+            (FIELD_X, 114, 60, 22),
+        );
+        note(hwnd, "0 to 100", 250, 116);
+
+        // Devices
+        group(hwnd, "Devices", 148, 78);
+        label(hwnd, "Run the model on", 172);
+        let device = combo(hwnd, ID_DEVICE, 170, 270);
+        for each in ComputeDevice::ALL {
+            add_item(device, each.label());
+        }
+        select(
+            device,
+            ComputeDevice::ALL
+                .iter()
+                .position(|each| *each == settings.speech.device),
+        );
+        label(hwnd, "Play on", 200);
+        let output = combo(hwnd, ID_OUTPUT, 198, 270);
+        add_item(output, "System Default");
+        for preset in &settings.audio.output_presets {
+            add_item(output, &preset.name);
+        }
+        let preset = settings
+            .audio.output_preset
+            .as_ref()
+            .and_then(|name| {
+                settings
+                    .audio.output_presets
+                    .iter()
+                    .position(|preset| preset.name == *name)
+            })
+            .map_or(0, |i| i + 1);
+        select(output, Some(preset));
+
+        // Filters
+        group(hwnd, "Filters", 234, 366);
+        label(hwnd, "Copies from apps", 258);
+        let app_filter = combo(hwnd, ID_APP_FILTER, 256, 270);
+        for mode in AppFilterMode::ALL {
+            add_item(app_filter, app_filter_label(mode));
+        }
+        select(
+            app_filter,
+            AppFilterMode::ALL
+                .iter()
+                .position(|mode| *mode == settings.filters.app_filter),
+        );
+        let lists = [
+            (
+                "Never speak text matching (one pattern per line)",
+                ID_EXCLUDE,
+                &settings.filters.exclude_patterns,
+                (24, 284, 412),
+            ),
+            (
+                "Only speak text matching (one pattern per line)",
+                ID_INCLUDE,
+                &settings.filters.include_patterns,
+                (24, 356, 412),
+            ),
+            (
+                "Denied apps (one per line)",
+                ID_DENIED_APPS,
+                &settings.filters.denied_apps,
+                (24, 428, 204),
+            ),
+            (
+                "Allowed apps (one per line)",
+                ID_ALLOWED_APPS,
+                &settings.filters.allowed_apps,
+                (232, 428, 204),
+            ),
+            (
+                "Stay quiet while these apps are in front (one per line)",
+                ID_QUIET_APPS,
+                &settings.filters.quiet_apps,
+                (24, 500, 412),
+            ),
+        ];
+        for (text, id, lines, (x, y, width)) in lists {
+            control(
+                hwnd,
+                w!("STATIC"),
+                text,
+                0,
+                WINDOW_EX_STYLE::default(),
+                -1,
+                (x, y, width, 18),
+            );
+            edit(
+                hwnd,
+                id,
+                &lines.join("\r\n"),
+                ES_MULTILINE | ES_AUTOVSCROLL | ES_WANTRETURN,
+                (x, y + 18, width, 48),
+            );
+        }
+        let skip = control(
+            hwnd,
+            w!("BUTTON"),
+            "Skip hashes, UUIDs, base64 blobs and long paths",
+            WS_TABSTOP.0 | BS_AUTOCHECKBOX as u32,
+            WINDOW_EX_STYLE::default(),
+            ID_SKIP_MACHINE,
+            (24, 572, 412, 20),
+        );
+        SendMessageW(
+            skip,
+            BM_SETCHECK,
+            WPARAM(settings.filters.skip_machine_strings as usize),
+            LPARAM(0),
+        );
+
+        // Hotkeys
+        group(hwnd, "Hotkeys", 608, 236);
+        label(hwnd, "Read selection", 632);
+        edit(
+            hwnd,
+            ID_READ_SELECTION,
+            settings
+                .hotkeys.read_selection_hotkey
+                .as_deref()
+                .unwrap_or_default(),
+            ES_AUTOHSCROLL,
+            (FIELD_X, 630, 150, 22),
+        );
+        label(hwnd, "Speak collected copies", 660);
+        edit(
+            hwnd,
+            ID_SPEAK_COLLECTED,
+            settings
+                .hotkeys.speak_collected_hotkey
+                .as_deref()
+                .unwrap_or_default(),
+            ES_AUTOHSCROLL,
+            (FIELD_X, 658, 150, 22),
+        );
+        label(hwnd, "Next voice", 688);
+        edit(
+            hwnd,
+            ID_NEXT_VOICE,
+            settings.hotkeys.next_voice_hotkey.as_deref().unwrap_or_default(),
+            ES_AUTOHSCROLL,
+            (FIELD_X, 686, 150, 22),
+        );
+        label(hwnd, "Stop", 716);
+        edit(
+            hwnd,
+            ID_STOP,
+            settings.hotkeys.stop_hotkey.as_deref().unwrap_or_default(),
+            ES_AUTOHSCROLL,
+            (FIELD_X, 714, 150, 22),
+        );
+        label(hwnd, "Monitoring on/off", 744);
+        edit(
+            hwnd,
+            ID_MONITOR,
+            settings.hotkeys.monitor_hotkey.as_deref().unwrap_or_default(),
+            ES_AUTOHSCROLL,
+            (FIELD_X, 742, 150, 22),
+        );
+        label(hwnd, "Quit", 772);
+        edit(
+            hwnd,
+            ID_QUIT,
+            settings.hotkeys.quit_hotkey.as_deref().unwrap_or_default(),
+            ES_AUTOHSCROLL,
+            (FIELD_X, 770, 150, 22),
+        );
+        label(hwnd, "Player", 800);
+        edit(
+            hwnd,
+            ID_PLAYER,
+            settings.hotkeys.player_hotkey.as_deref().unwrap_or_default(),
+            ES_AUTOHSCROLL,
+            (FIELD_X, 798, 150, 22),
+        );
+        note(
+            hwnd,
+            "Leave empty for none.",
+            24,
+            824,
+        );
+
+        control(
+            hwnd,
+            w!("BUTTON"),
+            "OK",
+            WS_TABSTOP.0 | BS_DEFPUSHBUTTON as u32,
+            WINDOW_EX_STYLE::default(),
+            ID_OK,
+            (276, 858, 80, 26),
+        );
+        control(
+            hwnd,
+            w!("BUTTON"),
+            "Cancel",
+            WS_TABSTOP.0 | BS_PUSHBUTTON as u32,
+            WINDOW_EX_STYLE::default(),
+            ID_CANCEL,
+            (364, 858, 80, 26),
+        );
+
+        Ok(hwnd)
+    }
+
+    unsafe extern "system" fn window_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        match msg {
+            WM_COMMAND => {
+                let id = (wparam.0 & 0xFFFF) as i32;
+                let notification = ((wparam.0 >> 16) & 0xFFFF) as u32;
+                match id {
+                    // Stays open to be corrected when something entered can't be used
+                    ID_OK if !submit(hwnd) => {}
+                    ID_OK | ID_CANCEL => {
+                        let _ = DestroyWindow(hwnd);
+                    }
+                    ID_VOICE if notification == CBN_SELCHANGE => show_voice_prosody(hwnd),
+                    _ => {}
+                }
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                let _ = DestroyWindow(hwnd);
+                LRESULT(0)
+            }
+            WM_DESTROY => {
+                PostQuitMessage(0);
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+
+    /// Show the speed and gain of the voice just picked
+    unsafe fn show_voice_prosody(hwnd: HWND) {
+        FORM.with(|form| {
+            let form = form.borrow();
+            let Some(form) = form.as_ref() else {
+                return;
+            };
+            let Some(voice) = selected(hwnd, ID_VOICE).and_then(|i| form.voices.get(i)) else {
+                return;
+            };
+            let prosody = form
+                .settings
+                .speech.voice_prosody
+                .get(voice)
+                .cloned()
+                .unwrap_or_default();
+            set_text(hwnd, ID_SPEED, &percent(prosody.speed));
+            set_text(hwnd, ID_GAIN, &percent(prosody.gain));
+        });
+    }
+
+    /// Read the controls back into the settings and hand them to the app
+    /// Returns false, after saying why, if something entered can't be used
+    unsafe fn submit(hwnd: HWND) -> bool {
+        let edited = FORM.with(|form| {
+            let form = form.borrow();
+            let form = form.as_ref()?;
+            Some(read_form(hwnd, form).map(|edited| {
+                let edit = Edit {
+                    opened: form.settings.clone(),
+                    edited,
+                };
+                (edit, form.edited_tx.clone())
+            }))
+        });
+        match edited {
+            Some(Ok((edit, edited_tx))) => {
+                let _ = edited_tx.send(edit);
+                true
+            }
+            Some(Err(problem)) => {
+                MessageBoxW(
+                    hwnd,
+                    &HSTRING::from(problem),
+                    w!("Pocket-Tray Settings"),
+                    MB_OK | MB_ICONWARNING,
+                );
+                false
+            }
+            None => true,
+        }
+    }
+
+    unsafe fn read_form(hwnd: HWND, form: &Form) -> Result<Settings, String> {
+        let mut settings = form.settings.clone();
+
+        if let Some(voice) = selected(hwnd, ID_VOICE).and_then(|i| form.voices.get(i)) {
+            settings.speech.current_voice = voice.clone();
+        }
+        let speed = read_percent(hwnd, ID_SPEED, "Speed", 50..=200)?;
+        let gain = read_percent(hwnd, ID_GAIN, "Voice gain", 0..=400)?;
+        let prosody = settings
+            .speech.voice_prosody
+            .entry(settings.speech.current_voice.clone())
+            .or_default();
+        prosody.speed = speed;
+        prosody.gain = gain;
+        settings.audio.volume_percent = read_number(hwnd, ID_VOLUME, "Volume", 0..=100)?;
+
+        if let Some(device) = selected(hwnd, ID_DEVICE).and_then(|i| ComputeDevice::ALL.get(i)) {
+            settings.speech.device = *device;
+        }
+        settings.audio.output_preset = match selected(hwnd, ID_OUTPUT) {
+            Some(i) if i > 0 => settings
+                .audio.output_presets
+                .get(i - 1)
+                .map(|preset| preset.name.clone()),
+            _ => None,
+        };
+
+        settings.filters.exclude_patterns = read_patterns(hwnd, ID_EXCLUDE)?;
+        settings.filters.include_patterns = read_patterns(hwnd, ID_INCLUDE)?;
+        if let Some(mode) = selected(hwnd, ID_APP_FILTER).and_then(|i| AppFilterMode::ALL.get(i)) {
+            settings.filters.app_filter = *mode;
+        }
+        settings.filters.denied_apps = read_lines(hwnd, ID_DENIED_APPS);
+        settings.filters.allowed_apps = read_lines(hwnd, ID_ALLOWED_APPS);
+        settings.filters.quiet_apps = read_lines(hwnd, ID_QUIET_APPS);
+        let skip = SendMessageW(
+            GetDlgItem(hwnd, ID_SKIP_MACHINE).unwrap_or_default(),
+            BM_GETCHECK,
+            WPARAM(0),
+            LPARAM(0),
+        );
+        settings.filters.skip_machine_strings = skip.0 == BST_CHECKED.0 as isize;
+
+        settings.hotkeys.read_selection_hotkey = read_hotkey(hwnd, ID_READ_SELECTION, "Read selection")?;
+        settings.hotkeys.speak_collected_hotkey =
+            read_hotkey(hwnd, ID_SPEAK_COLLECTED, "Speak collected copies")?;
+        settings.hotkeys.next_voice_hotkey = read_hotkey(hwnd, ID_NEXT_VOICE, "Next voice")?;
+        settings.hotkeys.stop_hotkey = read_hotkey(hwnd, ID_STOP, "Stop")?;
+        settings.hotkeys.monitor_hotkey = read_hotkey(hwnd, ID_MONITOR, "Monitoring on/off")?;
+        settings.hotkeys.quit_hotkey = read_hotkey(hwnd, ID_QUIT, "Quit")?;
+        settings.hotkeys.player_hotkey = read_hotkey(hwnd, ID_PLAYER, "Player")?;
+
+        Ok(settings)
+    }
+
+    /// A percentage as a multiplier, None for 100% (the voice as it is)
+    unsafe fn read_percent(
+        hwnd: HWND,
+        id: i32,
+        name: &str,
+        range: std::ops::RangeInclusive<u32>,
+    ) -> Result<Option<f32>, String> {
+        let value = read_number(hwnd, id, name, range)?;
+        Ok((value != 100).then_some(value as f32 / 100.0))
+    }
+
+    /// A whole percentage within `range`
+    unsafe fn read_number(
+        hwnd: HWND,
+        id: i32,
+        name: &str,
+        range: std::ops::RangeInclusive<u32>,
+    ) -> Result<u32, String> {
+        let text = text(hwnd, id);
+        let value: u32 = text
+            .trim()
+            .parse()
+            .map_err(|_| format!("{} should be a percentage, not \"{}\"", name, text.trim()))?;
+        if !range.contains(&value) {
+            return Err(format!(
+                "{} should be from {}% to {}%",
+                name,
+                range.start(),
+                range.end()
+            ));
+        }
+        Ok(value)
+    }
+
+    /// Non-empty lines, each a regular expression that compiles
+    unsafe fn read_patterns(hwnd: HWND, id: i32) -> Result<Vec<String>, String> {
+        let patterns = read_lines(hwnd, id);
+        for pattern in &patterns {
+            regex::Regex::new(pattern)
+                .map_err(|e| format!("\"{}\" is not a valid pattern:\n\n{}", pattern, e))?;
+        }
+        Ok(patterns)
+    }
+
+    unsafe fn read_lines(hwnd: HWND, id: i32) -> Vec<String> {
+        text(hwnd, id)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// A hotkey that parses, or None when left empty
+    unsafe fn read_hotkey(hwnd: HWND, id: i32, name: &str) -> Result<Option<String>, String> {
+        let text = text(hwnd, id).trim().to_string();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        Hotkey::parse(&text).map_err(|e| format!("{} hotkey \"{}\": {}", name, text, e))?;
+        Ok(Some(text))
+    }
+
+    /// What each app filter mode does, as offered in the window
+    fn app_filter_label(mode: AppFilterMode) -> &'static str {
+        match mode {
+            AppFilterMode::Deny => "Never speak copies from the denied apps",
+            AppFilterMode::Allow => "Only speak copies from the allowed apps",
+        }
+    }
+
+    /// A multiplier as a whole percentage
+    fn percent(multiplier: Option<f32>) -> String {
+        format!("{}", (multiplier.unwrap_or(1.0) * 100.0).round() as u32)
+    }
+
+    unsafe fn control(
+        parent: HWND,
+        class: PCWSTR,
+        text: &str,
+        style: u32,
+        ex_style: WINDOW_EX_STYLE,
+        id: i32,
+        (x, y, width, height): (i32, i32, i32, i32),
+    ) -> HWND {
+        let hwnd = CreateWindowExW(
+            ex_style,
+            class,
+            &HSTRING::from(text),
+            WS_CHILD | WS_VISIBLE | WINDOW_STYLE(style),
+            x,
+            y,
+            width,
+            height,
+            parent,
+            HMENU(id as isize as _),
+            None,
+            None,
+        )
+        .unwrap_or_default();
+        // Controls start in the bitmap system font otherwise
+        let font = GetStockObject(DEFAULT_GUI_FONT);
+        SendMessageW(hwnd, WM_SETFONT, WPARAM(font.0 as usize), LPARAM(1));
+        hwnd
+    }
+
+    unsafe fn group(parent: HWND, text: &str, y: i32, height: i32) {
+        control(
+            parent,
+            w!("BUTTON"),
+            text,
+            BS_GROUPBOX as u32,
+            WINDOW_EX_STYLE::default(),
+            -1,
+            (12, y, WIDTH - 24, height),
+        );
+    }
+
+    /// Label left of a field
+    unsafe fn label(parent: HWND, text: &str, y: i32) {
+        note(parent, text, 24, y);
+    }
+
+    unsafe fn note(parent: HWND, text: &str, x: i32, y: i32) {
+        control(
+            parent,
+            w!("STATIC"),
+            text,
+            0,
+            WINDOW_EX_STYLE::default(),
+            -1,
+            (x, y, WIDTH - 12 - x - 12, 18),
+        );
+    }
+
+    unsafe fn edit(
+        parent: HWND,
+        id: i32,
+        text: &str,
+        style: i32,
+        rect: (i32, i32, i32, i32),
+    ) -> HWND {
+        let mut style = WS_TABSTOP.0 | style as u32;
+        if style & ES_MULTILINE as u32 != 0 {
+            style |= WS_VSCROLL.0;
+        }
+        control(parent, w!("EDIT"), text, style, WS_EX_CLIENTEDGE, id, rect)
+    }
+
+    unsafe fn combo(parent: HWND, id: i32, y: i32, width: i32) -> HWND {
+        // The height includes the drop-down list
+        control(
+            parent,
+            w!("COMBOBOX"),
+            "",
+            WS_TABSTOP.0 | WS_VSCROLL.0 | WS_BORDER.0 | CBS_DROPDOWNLIST as u32,
+            WINDOW_EX_STYLE::default(),
+            id,
+            (FIELD_X, y, width, 200),
+        )
+    }
+
+    unsafe fn add_item(combo: HWND, text: &str) {
+        let text = HSTRING::from(text);
+        SendMessageW(
+            combo,
+            CB_ADDSTRING,
+            WPARAM(0),
+            LPARAM(text.as_ptr() as isize),
+        );
+    }
+
+    unsafe fn select(combo: HWND, index: Option<usize>) {
+        if let Some(index) = index {
+            SendMessageW(combo, CB_SETCURSEL, WPARAM(index), LPARAM(0));
+        }
+    }
+
+    unsafe fn selected(hwnd: HWND, id: i32) -> Option<usize> {
+        let combo = GetDlgItem(hwnd, id).ok()?;
+        let index = SendMessageW(combo, CB_GETCURSEL, WPARAM(0), LPARAM(0)).0;
+        usize::try_from(index).ok()
+    }
+
+    unsafe fn text(hwnd: HWND, id: i32) -> String {
+        let Ok(control) = GetDlgItem(hwnd, id) else {
+            return String::new();
+        };
+        let mut buffer = vec![0u16; GetWindowTextLengthW(control) as usize + 1];
+        let len = GetWindowTextW(control, &mut buffer) as usize;
+        String::from_utf16_lossy(&buffer[..len])
+    }
+
+    unsafe fn set_text(hwnd: HWND, id: i32, text: &str) {
+        if let Ok(control) = GetDlgItem(hwnd, id) {
+            let _ = SetWindowTextW(control, &HSTRING::from(text));
+        }
+    }
+}
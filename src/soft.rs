@@ -0,0 +1,167 @@
+//! When the soft voice is used: always, never, or during night hours; and how much
+//! quieter speech plays at night
+
+use serde::{Deserialize, Serialize};
+
+/// When speech is played with the soft voice effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SoftMode {
+    #[default]
+    Off,
+    On,
+    /// Between `soft_from_hour` and `soft_until_hour`
+    Scheduled,
+}
+
+impl SoftMode {
+    /// All options, in menu order
+    pub const ALL: [SoftMode; 3] = [SoftMode::Off, SoftMode::On, SoftMode::Scheduled];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            SoftMode::Off => "off",
+            SoftMode::On => "on",
+            SoftMode::Scheduled => "scheduled",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            SoftMode::Off => "Off",
+            SoftMode::On => "On",
+            SoftMode::Scheduled => "Night Hours Only",
+        }
+    }
+}
+
+/// The soft voice setting, checked at the start of each text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SoftSchedule {
+    pub mode: SoftMode,
+    /// Hour of the day (0-23, local time) scheduled soft speech starts
+    pub from_hour: u32,
+    /// Hour of the day (0-23, local time) scheduled soft speech ends
+    pub until_hour: u32,
+}
+
+impl SoftSchedule {
+    /// Whether speech starting now is softened
+    pub fn is_active(&self) -> bool {
+        match self.mode {
+            SoftMode::Off => false,
+            SoftMode::On => true,
+            SoftMode::Scheduled => self.covers(local_hour()),
+        }
+    }
+
+    fn covers(&self, hour: u32) -> bool {
+        covers(self.from_hour, self.until_hour, hour)
+    }
+}
+
+/// Lower volume during night hours, apart from the soft voice
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NightVolume {
+    /// Volume at night, in percent of the usual (100 leaves it alone)
+    pub percent: u32,
+    /// Hour of the day (0-23, local time) the lower volume starts
+    pub from_hour: u32,
+    /// Hour of the day (0-23, local time) the lower volume ends
+    pub until_hour: u32,
+}
+
+impl Default for NightVolume {
+    fn default() -> Self {
+        Self {
+            percent: 100,
+            from_hour: 22,
+            until_hour: 7,
+        }
+    }
+}
+
+impl NightVolume {
+    /// Gain for speech starting now
+    pub fn gain(&self) -> f32 {
+        if self.percent >= 100 || !covers(self.from_hour, self.until_hour, local_hour()) {
+            return 1.0;
+        }
+        self.percent as f32 / 100.0
+    }
+}
+
+/// Whether `hour` falls in the range from `from` until `until`, which may wrap past
+/// midnight
+fn covers(from: u32, until: u32, hour: u32) -> bool {
+    let (from, until) = (from % 24, until % 24);
+    if from <= until {
+        (from..until).contains(&hour)
+    } else {
+        hour >= from || hour < until
+    }
+}
+
+/// Current hour of the day in local time
+#[cfg(windows)]
+fn local_hour() -> u32 {
+    use windows::Win32::System::SystemInformation::GetLocalTime;
+
+    unsafe { GetLocalTime().wHour as u32 }
+}
+
+/// Current hour of the day (UTC, local time is only looked up on Windows)
+#[cfg(not(windows))]
+fn local_hour() -> u32 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    (seconds / 3600 % 24) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(from_hour: u32, until_hour: u32) -> SoftSchedule {
+        SoftSchedule {
+            mode: SoftMode::Scheduled,
+            from_hour,
+            until_hour,
+        }
+    }
+
+    #[test]
+    fn covers_hours_in_the_day() {
+        let schedule = schedule(9, 17);
+        assert!(schedule.covers(9));
+        assert!(schedule.covers(16));
+        assert!(!schedule.covers(17));
+        assert!(!schedule.covers(3));
+    }
+
+    #[test]
+    fn covers_hours_past_midnight() {
+        let schedule = schedule(22, 7);
+        assert!(schedule.covers(22));
+        assert!(schedule.covers(0));
+        assert!(schedule.covers(6));
+        assert!(!schedule.covers(7));
+        assert!(!schedule.covers(12));
+    }
+
+    #[test]
+    fn covers_nothing_when_the_hours_are_equal() {
+        assert!((0..24).all(|hour| !schedule(5, 5).covers(hour)));
+    }
+
+    #[test]
+    fn wraps_hours_past_23() {
+        assert!(schedule(22, 31).covers(6));
+    }
+}
@@ -0,0 +1,170 @@
+//! Which application a copy came from, whether copies from it are spoken, and in
+//! which voice
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How the apps checked in the tray are treated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AppFilterMode {
+    /// Copies from the denied apps are never spoken
+    #[default]
+    Deny,
+    /// Only copies from the allowed apps are spoken
+    Allow,
+}
+
+impl AppFilterMode {
+    /// All modes, in menu order
+    pub const ALL: [AppFilterMode; 2] = [AppFilterMode::Deny, AppFilterMode::Allow];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            AppFilterMode::Deny => "deny",
+            AppFilterMode::Allow => "allow",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            AppFilterMode::Deny => "Never Speak Copies from Checked Apps",
+            AppFilterMode::Allow => "Only Speak Copies from Checked Apps",
+        }
+    }
+}
+
+/// Per-app allow and deny lists, by executable name (e.g. "KeePass.exe")
+/// Both lists are kept so switching modes doesn't lose either
+#[derive(Debug, Clone, Default)]
+pub struct AppFilter {
+    pub mode: AppFilterMode,
+    pub denied: Vec<String>,
+    pub allowed: Vec<String>,
+    /// Voices copies from these apps are read in, instead of the selected one
+    pub voices: HashMap<String, String>,
+}
+
+impl AppFilter {
+    /// The list the current mode uses
+    pub fn listed(&self) -> &[String] {
+        match self.mode {
+            AppFilterMode::Deny => &self.denied,
+            AppFilterMode::Allow => &self.allowed,
+        }
+    }
+
+    /// Whether `app` is on the list the current mode uses
+    pub fn is_listed(&self, app: &str) -> bool {
+        self.listed().iter().any(|listed| listed.eq_ignore_ascii_case(app))
+    }
+
+    /// Add `app` to the current mode's list, or take it off
+    pub fn toggle(&mut self, app: &str) {
+        let list = match self.mode {
+            AppFilterMode::Deny => &mut self.denied,
+            AppFilterMode::Allow => &mut self.allowed,
+        };
+        let before = list.len();
+        list.retain(|listed| !listed.eq_ignore_ascii_case(app));
+        if list.len() == before {
+            list.push(app.to_string());
+        }
+    }
+
+    /// Whether a copy from `app` (None if it couldn't be told) is spoken
+    /// An empty allow list allows everything, so switching modes never silences all copies
+    pub fn allows(&self, app: Option<&str>) -> bool {
+        match (self.mode, app) {
+            (AppFilterMode::Deny, Some(app)) => !self.is_listed(app),
+            (AppFilterMode::Deny, None) => true,
+            (AppFilterMode::Allow, _) if self.allowed.is_empty() => true,
+            (AppFilterMode::Allow, Some(app)) => self.is_listed(app),
+            (AppFilterMode::Allow, None) => false,
+        }
+    }
+
+    /// Voice copies from `app` are read in, if it has one of its own
+    pub fn voice(&self, app: Option<&str>) -> Option<String> {
+        let app = app?;
+        self.voices
+            .iter()
+            .find(|(listed, _)| listed.eq_ignore_ascii_case(app))
+            .map(|(_, voice)| voice.clone())
+    }
+}
+
+/// Executable name of the application that put the current content on the clipboard
+#[cfg(windows)]
+pub fn source_app() -> Option<String> {
+    use windows::Win32::System::DataExchange::GetClipboardOwner;
+    use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+    // Apps copying without a window of their own leave no owner; the window in
+    // front is then the likeliest source
+    let window = match unsafe { GetClipboardOwner() } {
+        Ok(owner) if !owner.is_invalid() => owner,
+        _ => unsafe { GetForegroundWindow() },
+    };
+    window_app(window)
+}
+
+/// Executable name of the application in the foreground
+#[cfg(windows)]
+pub fn foreground_app() -> Option<String> {
+    use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+    window_app(unsafe { GetForegroundWindow() })
+}
+
+/// Executable name of the process owning `window`
+#[cfg(windows)]
+fn window_app(window: windows::Win32::Foundation::HWND) -> Option<String> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use std::path::PathBuf;
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+    if window.is_invalid() {
+        return None;
+    }
+    unsafe {
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(window, Some(&mut pid));
+        if pid == 0 {
+            return None;
+        }
+
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR::from_raw(buffer.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(process);
+        result.ok()?;
+
+        let path = PathBuf::from(OsString::from_wide(&buffer[..len as usize]));
+        path.file_name()?.to_str().map(str::to_string)
+    }
+}
+
+#[cfg(not(windows))]
+pub fn source_app() -> Option<String> {
+    None
+}
+
+#[cfg(not(windows))]
+pub fn foreground_app() -> Option<String> {
+    None
+}
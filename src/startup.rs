@@ -0,0 +1,59 @@
+//! Start with Windows: a value under the current user's Run key launches the
+//! executable at sign-in
+
+use anyhow::Result;
+
+/// Registry key Windows reads the sign-in programs of the current user from
+#[cfg(windows)]
+const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+
+/// Name of the value under the Run key
+#[cfg(windows)]
+const VALUE_NAME: &str = "Pocket-Tray";
+
+/// Add the running executable to the programs started at sign-in, or remove it
+/// Adding it again updates the path, e.g. after the executable was moved
+#[cfg(windows)]
+pub fn set_enabled(enabled: bool) -> Result<()> {
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::ERROR_FILE_NOT_FOUND;
+    use windows::Win32::System::Registry::{
+        RegDeleteKeyValueW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ,
+    };
+
+    let key = HSTRING::from(RUN_KEY);
+    let name = HSTRING::from(VALUE_NAME);
+
+    if !enabled {
+        let result = unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, &key, &name) };
+        // Nothing to remove is fine
+        if result != ERROR_FILE_NOT_FOUND {
+            result.ok()?;
+        }
+        return Ok(());
+    }
+
+    // Quoted, as the path may contain spaces
+    let exe = std::env::current_exe()?;
+    let command: Vec<u16> = format!("\"{}\"", exe.display())
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            &key,
+            &name,
+            REG_SZ.0,
+            Some(command.as_ptr().cast()),
+            (command.len() * 2) as u32,
+        )
+        .ok()?;
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn set_enabled(_enabled: bool) -> Result<()> {
+    anyhow::bail!("Starting with the system is not supported on this platform")
+}
@@ -0,0 +1,125 @@
+//! Following a text file, like `tail -f`, speaking lines as they're appended
+
+use crate::tts::TTSCommand;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the file is checked for new lines
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Most lines spoken per check; a burst beyond this is summarized
+const MAX_LINES_PER_POLL: usize = 5;
+
+/// A file being followed on its own thread, until dropped; the thread isn't
+/// waited for, it ends by itself at its next check
+pub struct LogTail {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+}
+
+impl LogTail {
+    /// Follow `path`, speaking lines appended from now on
+    pub fn start(path: PathBuf, tts_tx: Sender<TTSCommand>) -> std::io::Result<Self> {
+        // Lines already in the file aren't spoken
+        let offset = std::fs::metadata(&path)?.len();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        {
+            let path = path.clone();
+            let stop = Arc::clone(&stop);
+            std::thread::Builder::new()
+                .name("log-tail".into())
+                .spawn(move || follow(&path, offset, &stop, &tts_tx))?;
+        }
+
+        log::info!("Following {}", path.display());
+        Ok(Self { path, stop })
+    }
+
+    /// The file being followed
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LogTail {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        log::info!("Stopped following {}", self.path.display());
+    }
+}
+
+fn follow(path: &Path, mut offset: u64, stop: &AtomicBool, tts_tx: &Sender<TTSCommand>) {
+    // Bytes after the last newline, completed by a later write
+    let mut partial: Vec<u8> = Vec::new();
+
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let appended = match read_from(path, &mut offset) {
+            Ok(appended) => appended,
+            Err(e) => {
+                // The file may be briefly missing while a logger rotates it
+                log::debug!("Failed to read {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        if appended.is_empty() {
+            continue;
+        }
+
+        partial.extend_from_slice(&appended);
+        let Some(end) = partial.iter().rposition(|&b| b == b'\n') else {
+            continue;
+        };
+        let complete: Vec<u8> = partial.drain(..=end).collect();
+        let complete = String::from_utf8_lossy(&complete);
+        let lines: Vec<&str> = complete
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        let mut texts: Vec<String> = lines
+            .iter()
+            .take(MAX_LINES_PER_POLL)
+            .map(|line| line.to_string())
+            .collect();
+        if lines.len() > MAX_LINES_PER_POLL {
+            texts.push(format!("{} more lines", lines.len() - MAX_LINES_PER_POLL));
+        }
+
+        for text in texts {
+            if tts_tx.send(TTSCommand::Speak { text, voice: None }).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Read what was appended since `offset` and move it past that
+/// A file that shrank was truncated or replaced, and is read from the start
+fn read_from(path: &Path, offset: &mut u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len < *offset {
+        *offset = 0;
+    }
+    if len == *offset {
+        return Ok(Vec::new());
+    }
+
+    file.seek(SeekFrom::Start(*offset))?;
+    let mut bytes = Vec::new();
+    file.take(len - *offset).read_to_end(&mut bytes)?;
+    *offset += bytes.len() as u64;
+    Ok(bytes)
+}
@@ -0,0 +1,45 @@
+//! Removal of citation and footnote markers, the clutter of copied encyclopedia text
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Reference markers, with the space before them: "[1]", "[12][13]", "[note 2]",
+/// "[a]", Wikipedia's "[citation needed]" and the like, and "(ibid.)"
+/// Numbers start from 1, so an index such as "x[0]" isn't taken for one
+static MARKERS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?ix)
+        [\ \t]*
+        (?:
+            \[ (?: [1-9]\d{0,3} (?:\s*[-–,]\s*\d{1,4})* | [a-z] | (?:note|nb|n|fn)\.?\s*\d{1,3} ) \]
+          | \[ (?: citation | clarification | verification | page | pages | dubious | who | whom
+                 | when | where | which | why | how | according\ to\ whom | by\ whom | year
+                 | original\ research | better\ source | failed\ verification | not\ in\ citation
+                 | edit | update | full\ citation | specify | vague | disputed | further\ explanation
+               ) \b [^\]\n]{0,40} \]
+          | \( (?: ibid | ibidem | op\.?\ cit | loc\.?\ cit ) \.? (?: ,?\ [^)\n]{0,20} )? \)
+        )+",
+    )
+    .expect("valid pattern")
+});
+
+/// Take citation and footnote markers out of `text`
+pub fn strip_citations(text: &str) -> String {
+    MARKERS.replace_all(text, "").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_markers() {
+        assert_eq!(strip_citations("France.[1][2] It is large[citation needed]."), "France. It is large.");
+        assert_eq!(strip_citations("As noted (ibid.)."), "As noted.");
+    }
+
+    #[test]
+    fn keeps_indexing() {
+        assert_eq!(strip_citations("x[0]"), "x[0]");
+    }
+}
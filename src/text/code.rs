@@ -0,0 +1,180 @@
+//! Detection of source code in spoken text
+
+use serde::{Deserialize, Serialize};
+
+/// What is said in place of an omitted code block
+const ANNOUNCEMENT: &str = "Code block omitted.";
+
+/// Share of symbol characters above which a line is treated as code
+const SYMBOL_DENSITY: f32 = 0.2;
+
+/// Lines shorter than this (ignoring whitespace) are never judged by density alone
+const MIN_DENSE_LEN: usize = 8;
+
+/// How code blocks in copied text are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeMode {
+    /// Read code like any other text
+    Read,
+    /// Leave code blocks out silently
+    Skip,
+    /// Replace each code block with "code block omitted"
+    #[default]
+    Announce,
+}
+
+impl CodeMode {
+    /// All modes, in menu order
+    pub const ALL: [CodeMode; 3] = [CodeMode::Read, CodeMode::Skip, CodeMode::Announce];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            CodeMode::Read => "read",
+            CodeMode::Skip => "skip",
+            CodeMode::Announce => "announce",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            CodeMode::Read => "Read Code",
+            CodeMode::Skip => "Skip Code Blocks",
+            CodeMode::Announce => "Announce Code Blocks",
+        }
+    }
+}
+
+/// Remove or announce fenced blocks, indented blocks and symbol-dense lines
+pub fn strip_code(text: &str, mode: CodeMode) -> String {
+    if mode == CodeMode::Read {
+        return text.to_string();
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    let is_code = classify(&lines);
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < lines.len() {
+        if !is_code[i] {
+            out.push_str(lines[i]);
+            out.push('\n');
+            i += 1;
+            continue;
+        }
+
+        while i < lines.len() && is_code[i] {
+            i += 1;
+        }
+        if mode == CodeMode::Announce {
+            out.push_str(ANNOUNCEMENT);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Mark which lines belong to code
+fn classify(lines: &[&str]) -> Vec<bool> {
+    let mut is_code = vec![false; lines.len()];
+
+    // Fenced blocks, fences included. An unclosed fence runs to the end.
+    let mut fence: Option<&str> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        match (fence, marker) {
+            (None, Some(m)) => {
+                fence = Some(m);
+                is_code[i] = true;
+            }
+            (Some(open), Some(m)) if open == m => {
+                fence = None;
+                is_code[i] = true;
+            }
+            (Some(_), _) => is_code[i] = true,
+            (None, None) => {}
+        }
+    }
+
+    // Indented blocks that start after a blank line and mostly look like code
+    let mut i = 0;
+    while i < lines.len() {
+        let starts_block = is_indented(lines[i])
+            && !is_code[i]
+            && (i == 0 || lines[i - 1].trim().is_empty());
+        if !starts_block {
+            i += 1;
+            continue;
+        }
+        let end = (i..lines.len())
+            .find(|&j| is_code[j] || !(is_indented(lines[j]) || lines[j].trim().is_empty()))
+            .unwrap_or(lines.len());
+        let block = &lines[i..end];
+        let non_blank = block.iter().filter(|l| !l.trim().is_empty()).count();
+        let code_like = block.iter().filter(|l| looks_like_code(l)).count();
+        if code_like * 2 >= non_blank {
+            is_code[i..end].fill(true);
+        }
+        i = end;
+    }
+
+    // Stray lines dense with symbols
+    for (i, line) in lines.iter().enumerate() {
+        if !is_code[i] && is_symbol_dense(line) {
+            is_code[i] = true;
+        }
+    }
+
+    // Blank lines between two code lines belong to the same block
+    let mut last_code: Option<usize> = None;
+    for i in 0..lines.len() {
+        if !is_code[i] {
+            continue;
+        }
+        if let Some(prev) = last_code {
+            if lines[prev + 1..i].iter().all(|l| l.trim().is_empty()) {
+                is_code[prev + 1..i].fill(true);
+            }
+        }
+        last_code = Some(i);
+    }
+
+    is_code
+}
+
+fn is_indented(line: &str) -> bool {
+    (line.starts_with("    ") || line.starts_with('\t')) && !line.trim().is_empty()
+}
+
+/// Lines that end or open a statement the way code does
+fn looks_like_code(line: &str) -> bool {
+    let trimmed = line.trim();
+    is_symbol_dense(line) || trimmed.ends_with([';', '{', '}', '(', ')', ','])
+}
+
+fn is_symbol_dense(line: &str) -> bool {
+    let mut total = 0usize;
+    let mut symbols = 0usize;
+    // Slashes, plus and percent signs are left out so dates and sums read as prose,
+    // and hashes so hashtags and "#1" do
+    for c in line.chars().filter(|c| !c.is_whitespace()) {
+        total += 1;
+        if matches!(
+            c,
+            '{' | '}' | '[' | ']' | '(' | ')' | '<' | '>' | ';' | '=' | '*' | '\\' | '|' | '&'
+                | '^' | '@' | '~' | '`'
+        ) {
+            symbols += 1;
+        }
+    }
+
+    // A line of nothing but braces closes a block
+    if total > 0 && total == symbols && line.trim().chars().all(|c| "{}[]();".contains(c)) {
+        return true;
+    }
+    total >= MIN_DENSE_LEN && symbols as f32 / total as f32 >= SYMBOL_DENSITY
+}
@@ -0,0 +1,124 @@
+//! Emoji handling in spoken text
+
+use serde::{Deserialize, Serialize};
+
+/// How emoji in copied text are spoken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmojiMode {
+    /// Leave emoji out
+    #[default]
+    Skip,
+    /// Speak the emoji's Unicode name ("fire emoji")
+    Name,
+    /// Say "emoji"
+    Placeholder,
+}
+
+impl EmojiMode {
+    /// All modes, in menu order
+    pub const ALL: [EmojiMode; 3] = [EmojiMode::Skip, EmojiMode::Name, EmojiMode::Placeholder];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            EmojiMode::Skip => "skip",
+            EmojiMode::Name => "name",
+            EmojiMode::Placeholder => "placeholder",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            EmojiMode::Skip => "Skip Emoji",
+            EmojiMode::Name => "Speak Emoji Names",
+            EmojiMode::Placeholder => "Say \"Emoji\"",
+        }
+    }
+}
+
+/// Replace emoji in `text` according to `mode`
+pub fn rewrite_emoji(text: &str, mode: EmojiMode) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if is_modifier(c) {
+            // Stray joiner or variation selector outside a sequence
+            continue;
+        }
+        if !is_emoji(c) {
+            out.push(c);
+            continue;
+        }
+
+        // Swallow the rest of the sequence (skin tones, ZWJ families, flag pairs)
+        // so it's spoken once, named after its first character
+        let is_flag = is_regional_indicator(c);
+        let mut joined = false;
+        while let Some(&next) = chars.peek() {
+            if is_modifier(next) {
+                joined = next == '\u{200D}';
+                chars.next();
+            } else if joined && is_emoji(next) {
+                joined = false;
+                chars.next();
+            } else if is_flag && is_regional_indicator(next) {
+                chars.next();
+                break;
+            } else {
+                break;
+            }
+        }
+
+        let spoken = match mode {
+            EmojiMode::Skip => continue,
+            EmojiMode::Placeholder => "emoji".to_string(),
+            EmojiMode::Name if is_flag => "flag emoji".to_string(),
+            EmojiMode::Name => match unicode_names2::name(c) {
+                Some(name) => format!("{} emoji", name.to_string().to_lowercase()),
+                None => "emoji".to_string(),
+            },
+        };
+
+        // Keep the words apart from their neighbours
+        if !out.is_empty() && !out.ends_with(char::is_whitespace) {
+            out.push(' ');
+        }
+        out.push_str(&spoken);
+        if chars.peek().is_some_and(|next| next.is_alphanumeric()) {
+            out.push(' ');
+        }
+    }
+
+    out
+}
+
+/// Characters that start an emoji (pictographs, symbols, dingbats, flags)
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF // pictographs, emoticons, transport, symbols, flags
+            | 0x2600..=0x27BF // misc symbols and dingbats
+            | 0x2300..=0x23FF // misc technical (watch, hourglass, alarm clock)
+            | 0x2B00..=0x2BFF // arrows and stars
+            | 0x3030 | 0x303D | 0x3297 | 0x3299
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1F1E6..=0x1F1FF)
+}
+
+/// Characters that only modify a preceding emoji
+fn is_modifier(c: char) -> bool {
+    matches!(
+        c as u32,
+        0xFE0E | 0xFE0F // variation selectors
+            | 0x200D // zero width joiner
+            | 0x20E3 // combining keycap
+            | 0x1F3FB..=0x1F3FF // skin tones
+            | 0xE0020..=0xE007F // tag sequences
+    )
+}
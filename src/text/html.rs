@@ -0,0 +1,304 @@
+//! Readable text from HTML copied out of a browser
+
+/// Elements whose content is page furniture or code, not text to read
+const SKIPPED: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "nav", "header", "footer", "aside",
+    "form", "button", "select", "iframe",
+];
+
+/// Elements whose content is raw text, ended only by their closing tag
+const RAW_TEXT: &[&str] = &["script", "style", "template"];
+
+/// Elements that start and end a paragraph
+const PARAGRAPHS: &[&str] = &[
+    "p", "h1", "h2", "h3", "h4", "h5", "h6", "blockquote", "pre", "table", "ul", "ol", "dl",
+    "figure", "section", "article",
+];
+
+/// Elements that start and end a line
+const LINES: &[&str] = &[
+    "br", "div", "li", "tr", "dt", "dd", "hr", "figcaption", "main", "caption",
+];
+
+/// Elements without content or a closing tag
+const VOID: &[&str] = &[
+    "br", "hr", "img", "input", "meta", "link", "area", "base", "col", "embed", "source",
+    "track", "wbr",
+];
+
+/// Turn an HTML fragment into plain text: tags, scripts and navigation are dropped,
+/// link targets left out, entities decoded, block elements put on lines of their
+/// own and other whitespace collapsed as a browser would
+pub fn html_to_text(html: &str) -> String {
+    let mut out = Text::default();
+    let mut skip_depth = 0usize;
+    let mut pre_depth = 0usize;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            if skip_depth == 0 {
+                out.push_text(rest, pre_depth > 0);
+            }
+            break;
+        };
+        if skip_depth == 0 {
+            out.push_text(&rest[..start], pre_depth > 0);
+        }
+        rest = &rest[start..];
+
+        // Comments, doctypes and processing instructions
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+
+        let Some(tag) = Tag::parse(rest) else {
+            // A lone '<' is text
+            if skip_depth == 0 {
+                out.push_text("<", pre_depth > 0);
+            }
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[tag.len..];
+
+        let name = tag.name.as_str();
+        if !tag.closing && RAW_TEXT.contains(&name) {
+            rest = skip_raw_text(rest, name);
+            continue;
+        }
+
+        if SKIPPED.contains(&name) {
+            if tag.closing {
+                skip_depth = skip_depth.saturating_sub(1);
+            } else if !tag.self_closing {
+                skip_depth += 1;
+            }
+            continue;
+        }
+        if skip_depth > 0 {
+            continue;
+        }
+
+        if name == "pre" {
+            if tag.closing {
+                pre_depth = pre_depth.saturating_sub(1);
+            } else if !tag.self_closing {
+                pre_depth += 1;
+            }
+        }
+        if PARAGRAPHS.contains(&name) {
+            out.break_paragraph();
+        } else if LINES.contains(&name) {
+            out.break_line();
+        } else if matches!(name, "td" | "th") && !tag.closing {
+            // Table cells on a row are read one after another
+            out.push_text(" ", false);
+        }
+    }
+
+    out.finish()
+}
+
+/// A start or end tag
+struct Tag {
+    /// Lowercase element name
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    /// Bytes from '<' through '>'
+    len: usize,
+}
+
+impl Tag {
+    /// Parse the tag at the start of `html`, which begins with '<'
+    fn parse(html: &str) -> Option<Tag> {
+        let body = &html[1..];
+        let (closing, body) = match body.strip_prefix('/') {
+            Some(body) => (true, body),
+            None => (false, body),
+        };
+        let name_len = body
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+            .unwrap_or(body.len());
+        if name_len == 0 || !body.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let name = body[..name_len].to_ascii_lowercase();
+
+        // Find the closing '>', stepping over quoted attribute values
+        let mut quote = None;
+        let mut end = None;
+        for (i, c) in body.char_indices().skip(name_len) {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '>') => {
+                    end = Some(i);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let end = end?;
+
+        let self_closing = body[..end].trim_end().ends_with('/') || VOID.contains(&name.as_str());
+        let len = 1 + usize::from(closing) + end + 1;
+        Some(Tag {
+            name,
+            closing,
+            self_closing,
+            len,
+        })
+    }
+}
+
+/// Skip past the closing tag of a raw text element like `<script>`
+fn skip_raw_text<'a>(html: &'a str, name: &str) -> &'a str {
+    let closing = format!("</{}", name);
+    let lower = html.to_ascii_lowercase();
+    match lower.find(&closing) {
+        Some(start) => {
+            let rest = &html[start..];
+            rest.find('>').map_or("", |end| &rest[end + 1..])
+        }
+        None => "",
+    }
+}
+
+/// Plain text being built, with whitespace collapsed as it's added
+#[derive(Default)]
+struct Text {
+    text: String,
+    /// Line breaks wanted before the next text, 0 to 2
+    pending_breaks: usize,
+}
+
+impl Text {
+    fn push_text(&mut self, html: &str, preformatted: bool) {
+        let decoded = decode_entities(html);
+        for c in decoded.chars() {
+            if preformatted && c == '\n' {
+                self.break_line();
+                continue;
+            }
+            if c.is_whitespace() {
+                // Runs of whitespace read as one space, and none at line starts
+                let at_line_start = self.text.is_empty() || self.text.ends_with('\n');
+                let collapsed = !preformatted && self.text.ends_with(' ');
+                if self.pending_breaks == 0 && !at_line_start && !collapsed {
+                    self.text.push(' ');
+                }
+                continue;
+            }
+            self.flush_breaks();
+            self.text.push(c);
+        }
+    }
+
+    fn break_line(&mut self) {
+        self.pending_breaks = self.pending_breaks.max(1);
+    }
+
+    fn break_paragraph(&mut self) {
+        self.pending_breaks = 2;
+    }
+
+    fn flush_breaks(&mut self) {
+        if self.pending_breaks == 0 {
+            return;
+        }
+        let trimmed = self.text.trim_end_matches([' ', '\t']).len();
+        self.text.truncate(trimmed);
+        if !self.text.is_empty() {
+            let existing = self.text.len() - self.text.trim_end_matches('\n').len();
+            for _ in existing..self.pending_breaks {
+                self.text.push('\n');
+            }
+        }
+        self.pending_breaks = 0;
+    }
+
+    fn finish(self) -> String {
+        self.text
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string()
+    }
+}
+
+/// Decode character references like `&amp;`, `&#8217;` and `&#x2014;`
+pub(super) fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = rest[1..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '#')
+            .map_or(rest.len(), |i| i + 1);
+        let name = &rest[1..end];
+        match decode_entity(name) {
+            Some(c) => {
+                decoded.push(c);
+                // The ';' is optional in practice
+                rest = rest[end..].strip_prefix(';').unwrap_or(&rest[end..]);
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "hellip" => '\u{2026}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "ldquo" => '\u{201C}',
+        "rdquo" => '\u{201D}',
+        "laquo" => '\u{AB}',
+        "raquo" => '\u{BB}',
+        "bull" => '\u{2022}',
+        "middot" => '\u{B7}',
+        "copy" => '\u{A9}',
+        "reg" => '\u{AE}',
+        "trade" => '\u{2122}',
+        "deg" => '\u{B0}',
+        "times" => '\u{D7}',
+        "euro" => '\u{20AC}',
+        "pound" => '\u{A3}',
+        _ => return None,
+    };
+    Some(c)
+}
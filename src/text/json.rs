@@ -0,0 +1,280 @@
+//! Spoken summaries of copied JSON, in place of its braces and quotes
+
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+
+/// Keys or items described before the rest are only counted
+const MAX_ENTRIES: usize = 8;
+
+/// Strings longer than this are cut short
+const MAX_STRING_CHARS: usize = 80;
+
+/// How JSON in copied text is spoken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonMode {
+    /// Read JSON like any other text
+    Read,
+    /// Describe its keys and values
+    #[default]
+    Summarize,
+    /// Leave JSON out
+    Skip,
+}
+
+impl JsonMode {
+    /// All modes, in menu order
+    pub const ALL: [JsonMode; 3] = [JsonMode::Read, JsonMode::Summarize, JsonMode::Skip];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            JsonMode::Read => "read",
+            JsonMode::Summarize => "summarize",
+            JsonMode::Skip => "skip",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            JsonMode::Read => "Read JSON",
+            JsonMode::Summarize => "Summarize JSON",
+            JsonMode::Skip => "Skip JSON",
+        }
+    }
+}
+
+/// Replace text that is a JSON object or array as a whole with a summary of it,
+/// or nothing when skipping; other text is returned as it is
+pub fn rewrite_json(text: &str, mode: JsonMode) -> String {
+    if mode == JsonMode::Read {
+        return text.to_string();
+    }
+    let trimmed = text.trim();
+    // Only objects and lists; a bare number or string is just text
+    if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        return text.to_string();
+    }
+    let Ok(value) = serde_json::from_str::<Json>(trimmed) else {
+        return text.to_string();
+    };
+
+    match mode {
+        JsonMode::Skip => String::new(),
+        _ => {
+            let mut summary = describe(&value, 0);
+            summary.push('.');
+            capitalize(&summary)
+        }
+    }
+}
+
+/// A JSON value with its keys in the order they were written, which
+/// `serde_json::Value` doesn't keep
+enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    List(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl<'de> Deserialize<'de> for Json {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonVisitor)
+    }
+}
+
+struct JsonVisitor;
+
+impl<'de> Visitor<'de> for JsonVisitor {
+    type Value = Json;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Json, E> {
+        Ok(Json::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Json, E> {
+        Ok(Json::Number(value.to_string()))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Json, E> {
+        Ok(Json::Number(value.to_string()))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Json, E> {
+        Ok(Json::Number(value.to_string()))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Json, E> {
+        Ok(Json::String(value.to_string()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Json, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Json::List(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Json, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Json::Object(entries))
+    }
+}
+
+/// A value as a phrase, e.g. "an object with 2 keys: name is Ada, age is 36"
+/// Objects and lists inside others are only counted
+fn describe(value: &Json, depth: usize) -> String {
+    match value {
+        Json::Null => "empty".to_string(),
+        Json::Bool(true) => "true".to_string(),
+        Json::Bool(false) => "false".to_string(),
+        Json::Number(number) => number.clone(),
+        Json::String(text) => shorten(text),
+        Json::Object(entries) => describe_object(entries, depth),
+        Json::List(items) => describe_list(items, depth),
+    }
+}
+
+fn describe_object(entries: &[(String, Json)], depth: usize) -> String {
+    if entries.is_empty() {
+        return "an empty object".to_string();
+    }
+    let count = counted(entries.len(), "key", "keys");
+    if depth > 0 {
+        return format!("an object with {}", count);
+    }
+
+    let described: Vec<String> = entries
+        .iter()
+        .take(MAX_ENTRIES)
+        .map(|(key, value)| format!("{} is {}", spoken_key(key), describe(value, depth + 1)))
+        .collect();
+    format!("an object with {}: {}{}", count, described.join(", "), more(entries.len()))
+}
+
+fn describe_list(items: &[Json], depth: usize) -> String {
+    if items.is_empty() {
+        return "an empty list".to_string();
+    }
+    // Lists of objects are usually records of one shape; the first stands for the rest
+    if items.iter().all(|item| matches!(item, Json::Object(_))) {
+        let objects = counted(items.len(), "object", "objects");
+        if depth > 0 {
+            return format!("a list of {}", objects);
+        }
+        return format!("a list of {}, the first {}", objects, describe(&items[0], depth));
+    }
+    let count = counted(items.len(), "item", "items");
+    if depth > 0 {
+        return format!("a list of {}", count);
+    }
+
+    let described: Vec<String> = items
+        .iter()
+        .take(MAX_ENTRIES)
+        .map(|item| describe(item, depth + 1))
+        .collect();
+    format!("a list of {}: {}{}", count, described.join(", "), more(items.len()))
+}
+
+/// "1 key", "3 keys"
+fn counted(count: usize, one: &str, many: &str) -> String {
+    format!("{} {}", count, if count == 1 { one } else { many })
+}
+
+/// ", and 4 more" past the entries described
+fn more(count: usize) -> String {
+    if count > MAX_ENTRIES {
+        format!(", and {} more", count - MAX_ENTRIES)
+    } else {
+        String::new()
+    }
+}
+
+/// "first_name" and "first-name" as "first name"
+fn spoken_key(key: &str) -> String {
+    key.replace(['_', '-'], " ").trim().to_string()
+}
+
+fn shorten(text: &str) -> String {
+    let text = text.trim();
+    if text.is_empty() {
+        return "an empty string".to_string();
+    }
+    if text.chars().count() > MAX_STRING_CHARS {
+        let cut: String = text.chars().take(MAX_STRING_CHARS).collect();
+        format!("{}, and so on", cut.trim_end())
+    } else {
+        text.to_string()
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_an_object() {
+        assert_eq!(
+            rewrite_json(r#"{"name":"Ada","status":"active"}"#, JsonMode::Summarize),
+            "An object with 2 keys: name is Ada, status is active."
+        );
+    }
+
+    #[test]
+    fn summarizes_a_list_by_its_first_item() {
+        assert_eq!(
+            rewrite_json(r#"[{"a":1},{"a":2}]"#, JsonMode::Summarize),
+            "A list of 2 objects, the first an object with 1 key: a is 1."
+        );
+    }
+
+    #[test]
+    fn counts_nested_values() {
+        let summary = rewrite_json(r#"{"user":{"id":1},"tags":["a","b"]}"#, JsonMode::Summarize);
+        assert!(summary.contains("user is an object with 1 key"), "{}", summary);
+        assert!(summary.contains("tags is a list of 2 items"), "{}", summary);
+    }
+
+    #[test]
+    fn leaves_out_entries_past_the_first_few() {
+        let json = r#"{"a":1,"b":2,"c":3,"d":4,"e":5,"f":6,"g":7,"h":8,"i":9,"j":10}"#;
+        assert!(rewrite_json(json, JsonMode::Summarize).ends_with(", and 2 more."));
+    }
+
+    #[test]
+    fn skips_json() {
+        assert_eq!(rewrite_json(r#"{"a":1}"#, JsonMode::Skip), "");
+    }
+
+    #[test]
+    fn leaves_other_text_alone() {
+        assert_eq!(rewrite_json(r#"{"a":1}"#, JsonMode::Read), r#"{"a":1}"#);
+        assert_eq!(rewrite_json("[see above]", JsonMode::Summarize), "[see above]");
+        assert_eq!(rewrite_json("42", JsonMode::Skip), "42");
+    }
+}
@@ -0,0 +1,83 @@
+//! Rough language identification, used to read each language with its own voice
+//!
+//! Non-Latin scripts are recognized by their characters; Latin-script languages
+//! by their most common short words. Short or ambiguous text is left undecided.
+
+/// Common words that are rare in the other listed languages, per ISO 639-1 code
+const STOPWORDS: [(&str, &[&str]); 7] = [
+    ("en", &["the", "and", "is", "are", "was", "of", "to", "with", "this", "that", "you", "have", "it", "for", "not", "be"]),
+    ("fr", &["le", "la", "les", "et", "est", "une", "des", "du", "je", "vous", "nous", "avec", "pour", "pas", "dans", "sur", "qui", "ce"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "ich", "sie", "mit", "ein", "eine", "auf", "für", "auch", "wir", "sind", "zu"]),
+    ("es", &["el", "los", "las", "y", "es", "una", "por", "con", "para", "que", "del", "muy", "pero", "está", "como", "yo"]),
+    ("it", &["il", "gli", "e", "è", "di", "che", "non", "per", "una", "con", "sono", "della", "questo", "anche", "io", "lo"]),
+    ("pt", &["o", "os", "as", "e", "é", "um", "uma", "não", "com", "para", "que", "do", "da", "em", "você", "eu"]),
+    ("nl", &["de", "het", "een", "en", "is", "niet", "ik", "je", "met", "van", "op", "voor", "dat", "zijn", "ook", "wij"]),
+];
+
+/// Minimum stopword hits before a Latin-script language is trusted
+const MIN_HITS: usize = 2;
+
+/// Identify the language of `text` as an ISO 639-1 code, if it's clear enough
+pub fn detect(text: &str) -> Option<&'static str> {
+    detect_script(text).or_else(|| detect_latin(text))
+}
+
+/// Languages written in their own script
+fn detect_script(text: &str) -> Option<&'static str> {
+    let mut counts: [(&'static str, usize); 8] = [
+        ("ru", 0),
+        ("el", 0),
+        ("ar", 0),
+        ("he", 0),
+        ("ja", 0),
+        ("zh", 0),
+        ("ko", 0),
+        ("latin", 0),
+    ];
+    for c in text.chars() {
+        let index = match c {
+            '\u{0400}'..='\u{04FF}' => 0,
+            '\u{0370}'..='\u{03FF}' => 1,
+            '\u{0600}'..='\u{06FF}' => 2,
+            '\u{0590}'..='\u{05FF}' => 3,
+            '\u{3040}'..='\u{30FF}' => 4,
+            '\u{4E00}'..='\u{9FFF}' => 5,
+            '\u{AC00}'..='\u{D7AF}' => 6,
+            c if c.is_alphabetic() => 7,
+            _ => continue,
+        };
+        counts[index].1 += 1;
+    }
+
+    // Kanji appear in Japanese too, any kana makes it Japanese
+    if counts[4].1 > 0 {
+        counts[4].1 += counts[5].1;
+        counts[5].1 = 0;
+    }
+
+    let (language, count) = counts.into_iter().max_by_key(|&(_, count)| count)?;
+    (count > 0 && language != "latin").then_some(language)
+}
+
+/// Latin-script languages, by stopword counts
+fn detect_latin(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let mut scores: Vec<(&'static str, usize)> = STOPWORDS
+        .iter()
+        .map(|(language, stopwords)| {
+            let hits = words.iter().filter(|word| stopwords.contains(&word.as_str())).count();
+            (*language, hits)
+        })
+        .collect();
+    scores.sort_by_key(|&(_, hits)| std::cmp::Reverse(hits));
+
+    match scores.as_slice() {
+        [(language, best), (_, second), ..] if *best >= MIN_HITS && best > second => Some(language),
+        _ => None,
+    }
+}
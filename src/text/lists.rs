@@ -0,0 +1,171 @@
+//! Pacing of bulleted and numbered lists, so their items aren't run together
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+/// Markup for the silence placed before each list item
+const ITEM_PAUSE: &str = "[pause 400ms]";
+
+/// A list item: indentation, marker (bullet, "3.", "3)", "(3)", "c)" or "(c)"),
+/// an optional task box, and the item's text
+static ITEM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\s*)(?:[-*+•◦▪‣]|\d{1,3}[.)]|\(\d{1,3}\)|\(?[a-z]\))\s+(?:\[[ xX]\]\s+)?(\S.*)$")
+        .expect("valid pattern")
+});
+
+/// How lists in copied text are spoken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListMode {
+    /// Read lists like any other text
+    Read,
+    /// Drop the markers and pause between items
+    #[default]
+    Pause,
+    /// Pause between items and say "item 3:" before each
+    Number,
+}
+
+impl ListMode {
+    /// All modes, in menu order
+    pub const ALL: [ListMode; 3] = [ListMode::Read, ListMode::Pause, ListMode::Number];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            ListMode::Read => "read",
+            ListMode::Pause => "pause",
+            ListMode::Number => "number",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            ListMode::Read => "Read Lists as Written",
+            ListMode::Pause => "Pause Between Items",
+            ListMode::Number => "Pause and Number Items",
+        }
+    }
+}
+
+/// Put a pause before each item of the lists in `text`, ending each item as a
+/// sentence and numbering the top-level ones if asked to
+/// Only runs of two or more items count as a list; fenced code blocks are left alone
+pub fn pace_lists(text: &str, mode: ListMode) -> String {
+    if mode == ListMode::Read {
+        return text.to_string();
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    let is_item = items(&lines);
+
+    let mut out = String::with_capacity(text.len());
+    // Indentation of the current list's top-level items, and how many there were so far
+    let mut list: Option<(usize, usize)> = None;
+
+    for (line, item) in lines.iter().zip(&is_item) {
+        let captures = item.then(|| ITEM.captures(line)).flatten();
+        let Some(captures) = captures else {
+            // Blank lines and indented continuations don't end a list
+            if !line.trim().is_empty() && !line.starts_with(char::is_whitespace) {
+                list = None;
+            }
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+
+        let indent = captures[1].len();
+        let (base, count) = list.get_or_insert((indent, 0));
+        // On a line of its own, so it doesn't make short items look like code
+        out.push_str(ITEM_PAUSE);
+        out.push('\n');
+        if indent <= *base {
+            *base = indent;
+            *count += 1;
+            if mode == ListMode::Number {
+                out.push_str(&format!("Item {}: ", count));
+            }
+        }
+        let content = captures[2].trim_end();
+        out.push_str(content);
+        // A full stop, so each item is said as a sentence of its own
+        if content.ends_with(|c: char| c.is_alphanumeric() || matches!(c, ')' | '"' | '\'' | '*' | '_' | '`')) {
+            out.push('.');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Which lines are items of a list of two or more, outside code fences
+fn items(lines: &[&str]) -> Vec<bool> {
+    let mut is_item = vec![false; lines.len()];
+    let mut in_fence = false;
+    // Start of the run of items being looked at, and how many items it has
+    let mut run: Option<(usize, usize)> = None;
+
+    let close = |run: &mut Option<(usize, usize)>, end: usize, is_item: &mut Vec<bool>| {
+        if let Some((start, count)) = run.take() {
+            if count >= 2 {
+                for (i, line) in lines.iter().enumerate().take(end).skip(start) {
+                    is_item[i] = ITEM.is_match(line);
+                }
+            }
+        }
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            close(&mut run, i, &mut is_item);
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        if ITEM.is_match(line) {
+            let (_, count) = run.get_or_insert((i, 0));
+            *count += 1;
+        } else if !line.trim().is_empty() && !line.starts_with(char::is_whitespace) {
+            close(&mut run, i, &mut is_item);
+        }
+    }
+    close(&mut run, lines.len(), &mut is_item);
+
+    is_item
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pauses_between_items() {
+        assert_eq!(
+            pace_lists("Shopping:\n- eggs\n- milk", ListMode::Pause),
+            "Shopping:\n[pause 400ms]\neggs.\n[pause 400ms]\nmilk.\n"
+        );
+    }
+
+    #[test]
+    fn numbers_items() {
+        let paced = pace_lists("- one\n- two", ListMode::Number);
+        assert!(paced.contains("Item 1: one."), "{}", paced);
+        assert!(paced.contains("Item 2: two."), "{}", paced);
+    }
+
+    #[test]
+    fn leaves_single_items_alone() {
+        assert_eq!(pace_lists("- only one", ListMode::Pause), "- only one\n");
+    }
+
+    #[test]
+    fn reads_lists_as_written() {
+        assert_eq!(pace_lists("- eggs\n- milk", ListMode::Read), "- eggs\n- milk");
+    }
+}
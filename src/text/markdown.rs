@@ -0,0 +1,138 @@
+//! Removal of Markdown syntax, so copied READMEs and chat replies read as prose
+
+use super::markup;
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+/// Inline syntax and what's kept of it, applied in order
+static INLINE: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    [
+        // Images and links keep their text, not their targets
+        (r"!\[([^\]]*)\]\([^)]*\)", "$1"),
+        (r"\[([^\]]+)\]\([^)]*\)", "$1"),
+        (r"`+([^`]+)`+", "$1"),
+        (r"\*\*(\S(?:[^*]*?\S)?)\*\*", "$1"),
+        (r"__(\S(?:[^_]*?\S)?)__", "$1"),
+        (r"~~(\S(?:[^~]*?\S)?)~~", "$1"),
+        // Single markers need text right inside them, so "5 * 3 * 2" is left alone,
+        // and underscores need a word boundary outside, so snake_case is too
+        (r"\*(\S(?:[^*]*?\S)?)\*", "$1"),
+        (r"(^|[^\w])_(\S(?:[^_]*?\S)?)_([^\w]|$)", "$1$2$3"),
+    ]
+    .into_iter()
+    .map(|(pattern, replacement)| (Regex::new(pattern).expect("valid pattern"), replacement))
+    .collect()
+});
+
+/// Reference links, `[text][label]`, which keep their text
+static REFERENCE_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\]]+)\]\[([^\]]*)\]").expect("valid pattern"));
+
+/// Lines that are only markup: rules, setext underlines, table alignment rows and
+/// link reference definitions
+static MARKUP_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:(?:[-*_=]\s*){3,}|\|?\s*:?-+:?\s*(?:\|\s*:?-+:?\s*)*\|?|\[[^\]]+\]:\s+\S+.*)$")
+        .expect("valid pattern")
+});
+
+/// Headings, with any closing hashes
+static HEADING: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s{0,3}#{1,6}\s+(.*?)(?:\s+#+)?\s*$").expect("valid pattern")
+});
+
+/// Line prefixes: block quotes, bullets and task list boxes
+static LINE_PREFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:(?:>\s?)+|[-*+]\s+(?:\[[ xX]\]\s+)?)").expect("valid pattern")
+});
+
+/// Strip Markdown syntax, keeping the text it marks up
+/// Fenced code blocks are left as they are, for code handling to deal with
+pub fn strip_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut fence: Option<&str> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        match (fence, marker) {
+            (None, Some(m)) => fence = Some(m),
+            (Some(open), Some(m)) if open == m => fence = None,
+            _ => {}
+        }
+        if fence.is_some() || marker.is_some() {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+
+        if MARKUP_LINE.is_match(line) {
+            continue;
+        }
+        let line = HEADING.replace(line, "$1");
+        let mut line = LINE_PREFIX.replace(&line, "").into_owned();
+        // Table cells read as a list
+        if line.trim_start().starts_with('|') {
+            let cells: Vec<&str> = line
+                .split('|')
+                .map(str::trim)
+                .filter(|cell| !cell.is_empty())
+                .collect();
+            line = cells.join(", ");
+        }
+        // Markup tags side by side, as in "[em]word[/em][pause]", look the same
+        line = REFERENCE_LINK
+            .replace_all(&line, |caps: &Captures| {
+                if markup::is_tag(&caps[1]) || markup::is_tag(&caps[2]) {
+                    caps[0].to_string()
+                } else {
+                    caps[1].to_string()
+                }
+            })
+            .into_owned();
+        for (pattern, replacement) in INLINE.iter() {
+            line = pattern.replace_all(&line, *replacement).into_owned();
+        }
+
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_headings_and_emphasis() {
+        assert_eq!(strip_markdown("# Title\nSome **bold** and *italic* text"), "Title\nSome bold and italic text\n");
+    }
+
+    #[test]
+    fn keeps_link_text() {
+        assert_eq!(strip_markdown("See [the docs](https://example.com)."), "See the docs.\n");
+        assert_eq!(strip_markdown("See [the docs][1]."), "See the docs.\n");
+    }
+
+    #[test]
+    fn keeps_asterisks_and_underscores_that_are_not_markup() {
+        assert_eq!(strip_markdown("snake_case_name"), "snake_case_name\n");
+        assert_eq!(strip_markdown("5 * 3 * 2"), "5 * 3 * 2\n");
+    }
+
+    #[test]
+    fn keeps_speech_markup() {
+        assert_eq!(strip_markdown("[em]word[/em][pause]"), "[em]word[/em][pause]\n");
+    }
+
+    #[test]
+    fn drops_rules_and_bullets() {
+        assert_eq!(strip_markdown("one\n---\n- item"), "one\nitem\n");
+    }
+
+    #[test]
+    fn leaves_code_blocks_alone() {
+        let code = "```\nlet x = **y**;\n```\n";
+        assert_eq!(strip_markdown(code), code);
+    }
+}
@@ -0,0 +1,245 @@
+//! SSML-lite markup parsing
+//!
+//! Supported tags:
+//! - `[pause]`, `[pause 500ms]`, `[pause 2s]` - insert silence
+//! - `[spell]API[/spell]` - read the enclosed text letter by letter
+//! - `[em]word[/em]` or `[emphasis]word[/emphasis]` - stress the enclosed text
+//!
+//! Anything that doesn't parse as a tag is spoken literally.
+
+use std::time::Duration;
+
+/// Pause length for a bare `[pause]`
+const DEFAULT_PAUSE: Duration = Duration::from_millis(500);
+
+/// Longest pause a tag may request
+const MAX_PAUSE: Duration = Duration::from_secs(10);
+
+/// Short pause placed around emphasized text to set it apart
+const EMPHASIS_PAUSE: Duration = Duration::from_millis(150);
+
+/// Names of the tags
+const TAG_NAMES: [&str; 4] = ["pause", "spell", "em", "emphasis"];
+
+/// A piece of an utterance
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    /// Text to synthesize, with its language (ISO 639-1 code) if recognized
+    Speech {
+        text: String,
+        emphasized: bool,
+        language: Option<&'static str>,
+        /// Paragraph of the text it's in, counted from 0
+        paragraph: usize,
+    },
+    /// Silence
+    Pause(Duration),
+}
+
+/// A recognized markup tag
+enum Tag {
+    Pause(Duration),
+    Spell(String),
+    Emphasis(String),
+}
+
+/// Parse markup into speech and pause segments
+/// A bare `[pause]` lasts `default_pause`, or half a second if not given
+pub fn parse(text: &str, default_pause: Option<Duration>) -> Vec<Segment> {
+    let default_pause = default_pause.unwrap_or(DEFAULT_PAUSE);
+    let mut segments = Vec::new();
+    let mut speech = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('[') {
+        speech.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+
+        match parse_tag(candidate, default_pause) {
+            Some((Tag::Pause(duration), consumed)) => {
+                flush_speech(&mut segments, &mut speech);
+                segments.push(Segment::Pause(duration));
+                rest = &candidate[consumed..];
+            }
+            Some((Tag::Spell(content), consumed)) => {
+                speech.push_str(&spell_out(&content));
+                rest = &candidate[consumed..];
+            }
+            Some((Tag::Emphasis(content), consumed)) => {
+                flush_speech(&mut segments, &mut speech);
+                if !content.trim().is_empty() {
+                    segments.push(Segment::Pause(EMPHASIS_PAUSE));
+                    segments.push(Segment::Speech {
+                        text: content.trim().to_string(),
+                        emphasized: true,
+                        language: None,
+                        paragraph: 0,
+                    });
+                    segments.push(Segment::Pause(EMPHASIS_PAUSE));
+                }
+                rest = &candidate[consumed..];
+            }
+            None => {
+                // Not a tag, keep the bracket as text
+                speech.push('[');
+                rest = &candidate[1..];
+            }
+        }
+    }
+
+    speech.push_str(rest);
+    flush_speech(&mut segments, &mut speech);
+    segments
+}
+
+/// Move accumulated plain text into a speech segment
+fn flush_speech(segments: &mut Vec<Segment>, speech: &mut String) {
+    let text = speech.trim();
+    if !text.is_empty() {
+        segments.push(Segment::Speech {
+            text: text.to_string(),
+            emphasized: false,
+            language: None,
+            paragraph: 0,
+        });
+    }
+    speech.clear();
+}
+
+/// Whether what's inside a pair of brackets, such as "pause 2s" or "/em", is one
+/// of the tags, for passes before parsing to leave alone
+pub(super) fn is_tag(inner: &str) -> bool {
+    let name = inner.trim().trim_start_matches('/');
+    let name = name.split_whitespace().next().unwrap_or_default();
+    TAG_NAMES.iter().any(|tag| name.eq_ignore_ascii_case(tag))
+}
+
+/// Try to parse a tag at the start of `text` (which begins with '[')
+fn parse_tag(text: &str, default_pause: Duration) -> Option<(Tag, usize)> {
+    let end = text.find(']')?;
+    let inner = text[1..end].trim();
+    let (name, argument) = match inner.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (inner, ""),
+    };
+    let body = &text[end + 1..];
+
+    if name.eq_ignore_ascii_case("pause") {
+        let duration = if argument.is_empty() {
+            default_pause
+        } else {
+            parse_duration(argument)?
+        };
+        return Some((Tag::Pause(duration.min(MAX_PAUSE)), end + 1));
+    }
+
+    if !argument.is_empty() {
+        return None;
+    }
+
+    let closing = format!("[/{}]", name);
+    let close_at = find_ignore_ascii_case(body, &closing)?;
+    let content = body[..close_at].to_string();
+    let consumed = end + 1 + close_at + closing.len();
+
+    if name.eq_ignore_ascii_case("spell") {
+        Some((Tag::Spell(content), consumed))
+    } else if name.eq_ignore_ascii_case("em") || name.eq_ignore_ascii_case("emphasis") {
+        Some((Tag::Emphasis(content), consumed))
+    } else {
+        None
+    }
+}
+
+/// Parse "500ms", "2s", "1.5s" or a bare number of milliseconds
+fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim().to_ascii_lowercase();
+    let (number, scale) = if let Some(ms) = text.strip_suffix("ms") {
+        (ms, 0.001)
+    } else if let Some(s) = text.strip_suffix('s') {
+        (s, 1.0)
+    } else {
+        (text.as_str(), 0.001)
+    };
+
+    let value: f64 = number.trim().parse().ok()?;
+    if !value.is_finite() || value < 0.0 {
+        return None;
+    }
+    Some(Duration::from_secs_f64(value * scale))
+}
+
+/// Byte offset of the first ASCII-case-insensitive match of `needle`
+fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.char_indices().map(|(i, _)| i).find(|&i| {
+        haystack
+            .get(i..i + needle.len())
+            .is_some_and(|candidate| candidate.eq_ignore_ascii_case(needle))
+    })
+}
+
+/// Turn "API" into "A, P, I" so each character is read on its own
+fn spell_out(text: &str) -> String {
+    let letters: Vec<String> = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_string())
+        .collect();
+    letters.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speech(text: &str, emphasized: bool) -> Segment {
+        Segment::Speech {
+            text: text.to_string(),
+            emphasized,
+            language: None,
+            paragraph: 0,
+        }
+    }
+
+    #[test]
+    fn splits_at_pauses() {
+        assert_eq!(
+            parse("One[pause 2s]two[pause]", Some(Duration::from_millis(300))),
+            vec![
+                speech("One", false),
+                Segment::Pause(Duration::from_secs(2)),
+                speech("two", false),
+                Segment::Pause(Duration::from_millis(300)),
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_pauses() {
+        assert_eq!(parse("[pause 60s]", None), vec![Segment::Pause(MAX_PAUSE)]);
+    }
+
+    #[test]
+    fn spells_letters_out() {
+        assert_eq!(parse("[spell]API[/spell]", None), vec![speech("A, P, I", false)]);
+    }
+
+    #[test]
+    fn sets_emphasis_apart() {
+        assert_eq!(
+            parse("a [em]big[/em] deal", None),
+            vec![
+                speech("a", false),
+                Segment::Pause(EMPHASIS_PAUSE),
+                speech("big", true),
+                Segment::Pause(EMPHASIS_PAUSE),
+                speech("deal", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_other_brackets() {
+        assert_eq!(parse("x[0] and [note]", None), vec![speech("x[0] and [note]", false)]);
+    }
+}
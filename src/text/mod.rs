@@ -0,0 +1,265 @@
+//! Text processing - turns copied text into segments the TTS engine speaks
+
+mod citations;
+mod code;
+mod emoji;
+mod html;
+mod json;
+mod language;
+mod lists;
+mod markdown;
+mod markup;
+mod normalize;
+mod skim;
+mod typography;
+mod urls;
+mod wraps;
+
+pub use code::CodeMode;
+pub use emoji::EmojiMode;
+pub use html::html_to_text;
+pub use json::{rewrite_json, JsonMode};
+pub use lists::ListMode;
+pub use markup::Segment;
+pub use skim::skim;
+pub use urls::UrlMode;
+
+use regex::Regex;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Blank lines, which end a paragraph
+static PARAGRAPH_BREAK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\n[ \t]*\n\s*").expect("valid pattern"));
+
+/// User-configurable text processing options
+#[derive(Debug, Clone, Default)]
+pub struct TextOptions {
+    pub url_mode: UrlMode,
+    pub emoji_mode: EmojiMode,
+    pub code_mode: CodeMode,
+    pub list_mode: ListMode,
+    pub json_mode: JsonMode,
+    /// Length of a bare `[pause]`, if the voice overrides it
+    pub default_pause: Option<Duration>,
+}
+
+/// Summarize JSON, decode entities and typographic characters, rejoin hard-wrapped lines,
+/// pace lists, strip Markdown, drop code blocks, remove citation markers, parse markup,
+/// normalize speech into plain words and split it into sentence-sized segments tagged with
+/// their language
+pub fn segment(text: &str, options: &TextOptions) -> Vec<Segment> {
+    let mut segments = Vec::new();
+
+    // Sentences too short to tell keep the language of the one before
+    let mut current_language = None;
+    // Paragraph of the next sentence, and whether a blank line came since the last one
+    let mut paragraph = 0;
+    let mut paragraph_break = false;
+    let mut spoken = false;
+
+    // Copied JSON as a whole is summarized before anything takes its symbols apart;
+    // long texts have had theirs summarized before they were split
+    let text = json::rewrite_json(text, options.json_mode);
+    let text = typography::normalize_typography(&text);
+    // Lines wrapped by the page, as in PDFs, are joined back into their sentences
+    let text = wraps::join_wrapped_lines(&text);
+    // Lists go next, while their bullets are still there to find them by
+    let text = lists::pace_lists(&text, options.list_mode);
+    // Markdown next, its symbols would otherwise make lines look like code
+    let text = markdown::strip_markdown(&text);
+    let text = code::strip_code(&text, options.code_mode);
+    let text = citations::strip_citations(&text);
+    for segment in markup::parse(&text, options.default_pause) {
+        match segment {
+            Segment::Speech { text, emphasized, .. } => {
+                for (i, text) in PARAGRAPH_BREAK.split(&text).enumerate() {
+                    paragraph_break |= i > 0;
+                    let text = urls::rewrite_urls(text, options.url_mode);
+                    let text = emoji::rewrite_emoji(&text, options.emoji_mode);
+                    for sentence in split_sentences(&normalize::normalize(&text)) {
+                        if std::mem::take(&mut paragraph_break) && spoken {
+                            paragraph += 1;
+                        }
+                        spoken = true;
+                        current_language = language::detect(&sentence).or(current_language);
+                        segments.push(Segment::Speech {
+                            text: sentence,
+                            emphasized,
+                            language: current_language,
+                            paragraph,
+                        });
+                    }
+                }
+            }
+            pause @ Segment::Pause(_) => segments.push(pause),
+        }
+    }
+
+    segments
+}
+
+/// Split a long text into parts of up to about `max_chars`, at paragraph breaks where
+/// possible and between sentences otherwise, so it can be queued part by part
+/// Fenced code blocks are kept whole, so code handling still sees them
+pub fn split_parts(text: &str, max_chars: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+
+    for paragraph in split_paragraphs(text) {
+        let pieces = if paragraph.chars().count() > max_chars && !paragraph.starts_with("```") {
+            split_sentences(&paragraph)
+        } else {
+            vec![paragraph]
+        };
+        // Paragraph breaks are kept, sentences of a split paragraph go on their own lines
+        let mut separator = "\n\n";
+        for piece in pieces {
+            if !current.is_empty()
+                && current.chars().count() + separator.len() + piece.chars().count() > max_chars
+            {
+                parts.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push_str(separator);
+            }
+            current.push_str(&piece);
+            separator = "\n";
+        }
+    }
+
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Split text at blank lines outside fenced code blocks
+fn split_paragraphs(text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        if line.trim().is_empty() && !in_fence {
+            if !current.is_empty() {
+                paragraphs.push(current.join("\n").trim().to_string());
+                current.clear();
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join("\n").trim().to_string());
+    }
+
+    paragraphs
+}
+
+/// Split text into sentences at terminal punctuation followed by whitespace,
+/// and at line breaks
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        current.push(c);
+
+        if !matches!(c, '.' | '!' | '?' | '\n') {
+            continue;
+        }
+
+        // Keep closing quotes and brackets with the sentence they end
+        while let Some(&next) = chars.peek() {
+            if matches!(next, '"' | '\'' | ')' | ']' | '\u{201D}' | '\u{2019}') {
+                current.push(next);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        // "3.14" or "e.g." mid-word is not a sentence end
+        if c == '\n' || chars.peek().map(|next| next.is_whitespace()).unwrap_or(true) {
+            let sentence = current.trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence.to_string());
+            }
+            current.clear();
+        }
+    }
+
+    let sentence = current.trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence.to_string());
+    }
+
+    sentences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Text and paragraph of each speech segment
+    fn sentences(segments: &[Segment]) -> Vec<(&str, usize)> {
+        segments
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Speech { text, paragraph, .. } => Some((text.as_str(), *paragraph)),
+                Segment::Pause(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_into_sentences_and_paragraphs() {
+        let segments = segment("Hello there. How are you?\n\nFine, thanks.", &TextOptions::default());
+        assert_eq!(
+            sentences(&segments),
+            vec![("Hello there.", 0), ("How are you?", 0), ("Fine, thanks.", 1)]
+        );
+    }
+
+    #[test]
+    fn runs_the_passes_before_splitting() {
+        let segments = segment("# Notes\nSee **this**.[1]", &TextOptions::default());
+        assert_eq!(sentences(&segments), vec![("Notes", 0), ("See this.", 0)]);
+    }
+
+    #[test]
+    fn keeps_pauses() {
+        let segments = segment("One.[pause 1s]Two.", &TextOptions::default());
+        assert!(segments.contains(&Segment::Pause(Duration::from_secs(1))));
+        assert_eq!(sentences(&segments), vec![("One.", 0), ("Two.", 0)]);
+    }
+
+    #[test]
+    fn sentences_end_at_terminal_punctuation_followed_by_space() {
+        assert_eq!(split_sentences("Pi is 3.14. \"Really?\" Yes"), vec!["Pi is 3.14.", "\"Really?\"", "Yes"]);
+    }
+
+    #[test]
+    fn parts_break_at_paragraphs() {
+        assert_eq!(split_parts("First.\n\nSecond.", 10), vec!["First.", "Second."]);
+        assert_eq!(split_parts("First.\n\nSecond.", 100), vec!["First.\n\nSecond."]);
+    }
+
+    #[test]
+    fn long_paragraphs_break_between_sentences() {
+        assert_eq!(
+            split_parts("One two. Three four. Five six.", 20),
+            vec!["One two.\nThree four.", "Five six."]
+        );
+    }
+
+    #[test]
+    fn parts_keep_code_blocks_whole() {
+        let code = "```\nfn a() {}\n\nfn b() {}\n```";
+        assert_eq!(split_parts(code, 5), vec![code]);
+    }
+}
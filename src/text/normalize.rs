@@ -0,0 +1,424 @@
+//! Normalization of numbers, dates, currency and abbreviations into words
+//!
+//! The model reads plain words well but mangles things like "3/14/2024",
+//! "$5.99" or "v2.0", so these are spelled out before synthesis.
+
+const ONES: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const SCALES: [(u64, &str); 4] = [
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September",
+    "October", "November", "December",
+];
+
+/// Abbreviations expanded wherever they appear (matched case-sensitively)
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("Dr.", "Doctor"),
+    ("Mr.", "Mister"),
+    ("Mrs.", "Missus"),
+    ("Ms.", "Miz"),
+    ("Prof.", "Professor"),
+    ("St.", "Saint"),
+    ("Jr.", "Junior"),
+    ("Sr.", "Senior"),
+    ("vs.", "versus"),
+    ("e.g.", "for example"),
+    ("i.e.", "that is"),
+    ("etc.", "et cetera."),
+    ("approx.", "approximately"),
+    ("Inc.", "Incorporated"),
+    ("Ltd.", "Limited"),
+    ("Jan.", "January"),
+    ("Feb.", "February"),
+    ("Mar.", "March"),
+    ("Apr.", "April"),
+    ("Aug.", "August"),
+    ("Sep.", "September"),
+    ("Sept.", "September"),
+    ("Oct.", "October"),
+    ("Nov.", "November"),
+    ("Dec.", "December"),
+];
+
+/// Longest digit run read as a number; longer runs are read digit by digit
+const MAX_NUMBER_DIGITS: usize = 15;
+
+/// Expand numbers, dates, times, currency, versions and abbreviations into words
+pub fn normalize(text: &str) -> String {
+    // Words with the whitespace that follows them, so spacing and line breaks survive
+    let pieces: Vec<(&str, &str)> = text
+        .split_inclusive(char::is_whitespace)
+        .map(|piece| {
+            let word_len = piece.trim_end_matches(char::is_whitespace).len();
+            piece.split_at(word_len)
+        })
+        .collect();
+
+    let mut out = String::with_capacity(text.len());
+    for (i, (word, space)) in pieces.iter().enumerate() {
+        let next = pieces[i + 1..]
+            .iter()
+            .map(|(word, _)| *word)
+            .find(|word| !word.is_empty());
+        out.push_str(&normalize_word(word, next));
+        out.push_str(space);
+    }
+    out
+}
+
+/// Normalize a single whitespace-delimited word
+fn normalize_word(word: &str, next: Option<&str>) -> String {
+    // Split off surrounding punctuation that isn't part of the token
+    let start = word.find(|c: char| !is_leading_punct(c)).unwrap_or(word.len());
+    let (lead, rest) = word.split_at(start);
+    let core_len = rest.trim_end_matches(is_trailing_punct).len();
+    let (core, trail) = rest.split_at(core_len);
+
+    if core.is_empty() {
+        return word.to_string();
+    }
+
+    // Abbreviations keep their own trailing period
+    if let Some(after) = trail.strip_prefix('.') {
+        let candidate = &rest[..core_len + 1];
+        if candidate == "No." && next.is_some_and(|n| n.starts_with(|c: char| c.is_ascii_digit())) {
+            return format!("{}number{}", lead, after);
+        }
+        if let Some((_, expansion)) = ABBREVIATIONS.iter().find(|(abbr, _)| *abbr == candidate) {
+            return format!("{}{}{}", lead, expansion, after);
+        }
+    }
+
+    match expand(core) {
+        Some(words) => format!("{}{}{}", lead, words, trail),
+        None => word.to_string(),
+    }
+}
+
+fn is_leading_punct(c: char) -> bool {
+    matches!(c, '(' | '[' | '"' | '\'' | '\u{201C}' | '\u{2018}')
+}
+
+fn is_trailing_punct(c: char) -> bool {
+    matches!(
+        c,
+        '.' | ',' | ';' | ':' | '!' | '?' | ')' | ']' | '"' | '\'' | '\u{201D}' | '\u{2019}'
+    )
+}
+
+/// Try each known token shape in turn
+fn expand(token: &str) -> Option<String> {
+    slash_date_words(token)
+        .or_else(|| iso_date_words(token))
+        .or_else(|| time_words(token))
+        .or_else(|| currency_words(token))
+        .or_else(|| version_words(token))
+        .or_else(|| percent_words(token))
+        .or_else(|| ordinal_words(token))
+        .or_else(|| year_token_words(token))
+        .or_else(|| number_words(token))
+}
+
+/// "3/14/2024" or "3/14/24" (US month/day/year order)
+fn slash_date_words(token: &str) -> Option<String> {
+    let mut parts = token.split('/');
+    let (month, day, year) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() || !all_digits(month) || !all_digits(day) || !all_digits(year) {
+        return None;
+    }
+
+    let year: u64 = match year.len() {
+        2 => {
+            let short: u64 = year.parse().ok()?;
+            if short < 50 {
+                2000 + short
+            } else {
+                1900 + short
+            }
+        }
+        4 => year.parse().ok()?,
+        _ => return None,
+    };
+    date_words(month.parse().ok()?, day.parse().ok()?, year)
+}
+
+/// "2024-03-14"
+fn iso_date_words(token: &str) -> Option<String> {
+    let mut parts = token.split('-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some()
+        || year.len() != 4
+        || month.len() != 2
+        || day.len() != 2
+        || !all_digits(year)
+        || !all_digits(month)
+        || !all_digits(day)
+    {
+        return None;
+    }
+    date_words(month.parse().ok()?, day.parse().ok()?, year.parse().ok()?)
+}
+
+fn date_words(month: usize, day: u64, year: u64) -> Option<String> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(format!("{} {}, {}", MONTHS[month - 1], ordinal(day), year_words(year)))
+}
+
+/// "3:30", "14:05", "9:00pm"
+fn time_words(token: &str) -> Option<String> {
+    let lower = token.to_ascii_lowercase();
+    let (clock, suffix) = if let Some(clock) = lower.strip_suffix("am") {
+        (clock, " a m")
+    } else if let Some(clock) = lower.strip_suffix("pm") {
+        (clock, " p m")
+    } else {
+        (lower.as_str(), "")
+    };
+
+    let (hours, minutes) = clock.split_once(':')?;
+    if hours.is_empty() || hours.len() > 2 || minutes.len() != 2 || !all_digits(hours) || !all_digits(minutes) {
+        return None;
+    }
+    let hours: u64 = hours.parse().ok()?;
+    let minutes: u64 = minutes.parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    let minutes = match minutes {
+        0 if hours <= 12 => "o'clock".to_string(),
+        0 => "hundred".to_string(),
+        1..=9 => format!("oh {}", cardinal(minutes)),
+        _ => cardinal(minutes),
+    };
+    Some(format!("{} {}{}", cardinal(hours), minutes, suffix))
+}
+
+/// "$5.99", "€20", "£1,000"
+fn currency_words(token: &str) -> Option<String> {
+    let mut chars = token.chars();
+    let (unit, units, sub, subs) = match chars.next()? {
+        '$' => ("dollar", "dollars", "cent", "cents"),
+        '\u{20AC}' => ("euro", "euros", "cent", "cents"),
+        '\u{00A3}' => ("pound", "pounds", "penny", "pence"),
+        _ => return None,
+    };
+
+    let amount = chars.as_str();
+    let (whole, fraction) = match amount.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (amount, None),
+    };
+
+    let whole_digits = strip_thousands(whole)?;
+    if whole_digits.len() > MAX_NUMBER_DIGITS {
+        return None;
+    }
+    let whole: u64 = whole_digits.parse().ok()?;
+    let cents: u64 = match fraction {
+        Some(f) if f.len() == 2 && all_digits(f) => f.parse().ok()?,
+        Some(_) => return None,
+        None => 0,
+    };
+
+    let mut parts = Vec::new();
+    if whole > 0 || cents == 0 {
+        parts.push(format!("{} {}", cardinal(whole), if whole == 1 { unit } else { units }));
+    }
+    if cents > 0 {
+        parts.push(format!("{} {}", cardinal(cents), if cents == 1 { sub } else { subs }));
+    }
+    Some(parts.join(" and "))
+}
+
+/// "v2.0" or "V1.2.3"
+fn version_words(token: &str) -> Option<String> {
+    let version = token.strip_prefix(['v', 'V'])?;
+    let parts: Vec<&str> = version.split('.').collect();
+    if parts.iter().any(|part| !all_digits(part) || part.len() > MAX_NUMBER_DIGITS) {
+        return None;
+    }
+    let words: Vec<String> = parts.iter().map(|part| integer_words(part)).collect();
+    Some(format!("version {}", words.join(" point ")))
+}
+
+/// "50%" or "3.5%"
+fn percent_words(token: &str) -> Option<String> {
+    let number = token.strip_suffix('%')?;
+    Some(format!("{} percent", number_words(number)?))
+}
+
+/// "1st", "22nd", "103rd", "4th"
+fn ordinal_words(token: &str) -> Option<String> {
+    let lower = token.to_ascii_lowercase();
+    let digits = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| lower.strip_suffix(suffix))?;
+    let digits = strip_thousands(digits)?;
+    if digits.len() > MAX_NUMBER_DIGITS {
+        return None;
+    }
+    Some(ordinal(digits.parse().ok()?))
+}
+
+/// Bare four-digit numbers in the usual year range are read as years
+fn year_token_words(token: &str) -> Option<String> {
+    if token.len() != 4 || !all_digits(token) {
+        return None;
+    }
+    let year: u64 = token.parse().ok()?;
+    (1100..=2099).contains(&year).then(|| year_words(year))
+}
+
+/// "42", "-7", "1,234,567", "3.14"
+fn number_words(token: &str) -> Option<String> {
+    let (negative, unsigned) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    let whole = strip_thousands(whole)?;
+    let mut words = integer_words(&whole);
+    if let Some(fraction) = fraction {
+        if !all_digits(fraction) {
+            return None;
+        }
+        words = format!("{} point {}", words, digits_words(fraction));
+    }
+    if negative {
+        words = format!("minus {}", words);
+    }
+    Some(words)
+}
+
+/// Read a digit string as a number, or digit by digit if it's too long or zero-padded
+fn integer_words(digits: &str) -> String {
+    if digits.len() > MAX_NUMBER_DIGITS || (digits.len() > 1 && digits.starts_with('0')) {
+        return digits_words(digits);
+    }
+    digits.parse().map(cardinal).unwrap_or_else(|_| digits_words(digits))
+}
+
+/// "314" -> "three one four"
+fn digits_words(digits: &str) -> String {
+    let words: Vec<&str> = digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| ONES[d as usize])
+        .collect();
+    words.join(" ")
+}
+
+/// Validate and remove thousands separators ("1,234" -> "1234")
+fn strip_thousands(digits: &str) -> Option<String> {
+    if !digits.contains(',') {
+        return all_digits(digits).then(|| digits.to_string());
+    }
+
+    let mut groups = digits.split(',');
+    let first = groups.next()?;
+    if first.is_empty() || first.len() > 3 || !all_digits(first) {
+        return None;
+    }
+    let mut result = first.to_string();
+    for group in groups {
+        if group.len() != 3 || !all_digits(group) {
+            return None;
+        }
+        result.push_str(group);
+    }
+    Some(result)
+}
+
+fn all_digits(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Spell out a whole number ("1234" -> "one thousand two hundred thirty-four")
+fn cardinal(n: u64) -> String {
+    if n < 20 {
+        return ONES[n as usize].to_string();
+    }
+    if n < 100 {
+        let tens = TENS[(n / 10) as usize];
+        return match n % 10 {
+            0 => tens.to_string(),
+            ones => format!("{}-{}", tens, ONES[ones as usize]),
+        };
+    }
+    if n < 1000 {
+        let hundreds = format!("{} hundred", ONES[(n / 100) as usize]);
+        return match n % 100 {
+            0 => hundreds,
+            rest => format!("{} {}", hundreds, cardinal(rest)),
+        };
+    }
+
+    let (scale, name) = SCALES
+        .iter()
+        .copied()
+        .find(|&(scale, _)| n >= scale)
+        .unwrap_or(SCALES[SCALES.len() - 1]);
+    let head = format!("{} {}", cardinal(n / scale), name);
+    match n % scale {
+        0 => head,
+        rest => format!("{} {}", head, cardinal(rest)),
+    }
+}
+
+/// Spell out an ordinal number (3 -> "third", 21 -> "twenty-first")
+fn ordinal(n: u64) -> String {
+    let words = cardinal(n);
+    let (head, last) = match words.rfind([' ', '-']) {
+        Some(i) => words.split_at(i + 1),
+        None => ("", words.as_str()),
+    };
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        word if word.ends_with('y') => format!("{}ieth", &word[..word.len() - 1]),
+        word => format!("{}th", word),
+    };
+    format!("{}{}", head, last)
+}
+
+/// Read a year the way people say it ("1984" -> "nineteen eighty-four")
+fn year_words(year: u64) -> String {
+    // 2001-2009 read naturally as plain numbers ("two thousand five")
+    if !(1000..=9999).contains(&year) || (2001..=2009).contains(&year) {
+        return cardinal(year);
+    }
+
+    let century = cardinal(year / 100);
+    match (year / 100 % 10, year % 100) {
+        (0, 0) => cardinal(year),
+        (_, 0) => format!("{} hundred", century),
+        (_, rest @ 1..=9) => format!("{} oh {}", century, cardinal(rest)),
+        (_, rest) => format!("{} {}", century, cardinal(rest)),
+    }
+}
@@ -0,0 +1,32 @@
+//! Skimming of long texts: the headings and first sentence of each paragraph, enough
+//! to tell whether the whole text is worth hearing
+
+use super::{split_paragraphs, split_sentences, wraps};
+
+/// The first sentence of each paragraph of `text`, with the headings above it
+/// Fenced code blocks are left out
+pub fn skim(text: &str) -> String {
+    let mut out = String::new();
+
+    for paragraph in split_paragraphs(text) {
+        if paragraph.starts_with("```") {
+            continue;
+        }
+        // Rejoined first, or a hard-wrapped paragraph's first sentence is its first line
+        let paragraph = wraps::join_wrapped_lines(&paragraph);
+        let mut lines = paragraph.lines().peekable();
+
+        let mut kept: Vec<String> = Vec::new();
+        while let Some(heading) = lines.next_if(|line| line.trim_start().starts_with('#')) {
+            kept.push(heading.trim().to_string());
+        }
+        let rest: Vec<&str> = lines.collect();
+        kept.extend(split_sentences(&rest.join("\n")).into_iter().next());
+
+        if !kept.is_empty() {
+            out.push_str(&kept.join("\n"));
+            out.push_str("\n\n");
+        }
+    }
+    out.trim_end().to_string()
+}
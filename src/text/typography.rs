@@ -0,0 +1,67 @@
+//! Decoding of HTML entities and plain-text stand-ins for typographic characters,
+//! so web text isn't read as "ampersand a m p semicolon" and curly quotes don't
+//! trip the voice
+
+use super::html::decode_entities;
+
+/// Decode entities such as `&amp;`, `&nbsp;` and `&#8217;`, then straighten quotes,
+/// space out dashes and drop invisible characters
+pub fn normalize_typography(text: &str) -> String {
+    // Text without entities or special characters, the usual case, is left as it is
+    if text.is_ascii() && !text.contains('&') {
+        return text.to_string();
+    }
+
+    let text = decode_entities(text);
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => out.push('\''),
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' | '\u{AB}' | '\u{BB}' => {
+                out.push('"')
+            }
+            '\u{2026}' => out.push_str("..."),
+            '\u{2212}' => out.push('-'),
+            '\u{A0}' | '\u{2007}' | '\u{2009}' | '\u{200A}' | '\u{202F}' => out.push(' '),
+            // Soft hyphens and zero-width spaces; zero-width joiners hold emoji together
+            '\u{AD}' | '\u{200B}' | '\u{FEFF}' => {}
+            '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2015}' => {
+                let before = i.checked_sub(1).map(|j| chars[j]);
+                let after = chars.get(i + 1).copied();
+                // Ranges such as "1990–2000" keep a plain hyphen
+                if before.is_some_and(|b| b.is_ascii_digit()) && after.is_some_and(|a| a.is_ascii_digit()) {
+                    out.push('-');
+                } else {
+                    // Set apart as a pause, not joined to the words either side
+                    if !out.ends_with(char::is_whitespace) && !out.is_empty() {
+                        out.push(' ');
+                    }
+                    out.push('-');
+                    if after.is_some_and(|a| !a.is_whitespace()) {
+                        out.push(' ');
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_entities_and_straightens_quotes() {
+        assert_eq!(normalize_typography("Tom &amp; \u{201C}Jerry\u{201D}"), "Tom & \"Jerry\"");
+    }
+
+    #[test]
+    fn reads_dashes() {
+        assert_eq!(normalize_typography("1990\u{2013}2000"), "1990-2000");
+        assert_eq!(normalize_typography("wait\u{2014}what"), "wait - what");
+    }
+}
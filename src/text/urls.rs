@@ -0,0 +1,97 @@
+//! Handling of web links in spoken text
+
+use serde::{Deserialize, Serialize};
+
+/// How links in copied text are spoken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlMode {
+    /// Read the whole link
+    Read,
+    /// Say "link to example dot com"
+    #[default]
+    Domain,
+    /// Leave links out entirely
+    Skip,
+}
+
+impl UrlMode {
+    /// All modes, in menu order
+    pub const ALL: [UrlMode; 3] = [UrlMode::Read, UrlMode::Domain, UrlMode::Skip];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            UrlMode::Read => "read",
+            UrlMode::Domain => "domain",
+            UrlMode::Skip => "skip",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            UrlMode::Read => "Read Full Links",
+            UrlMode::Domain => "Say Domain Only",
+            UrlMode::Skip => "Skip Links",
+        }
+    }
+}
+
+/// Replace links in `text` according to `mode`
+pub fn rewrite_urls(text: &str, mode: UrlMode) -> String {
+    if mode == UrlMode::Read {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let word_len = piece.trim_end_matches(char::is_whitespace).len();
+        let (word, space) = piece.split_at(word_len);
+
+        // Keep sentence punctuation that trails the link
+        let url_len = word.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '"', '\'']).len();
+        let (url, trail) = word.split_at(url_len);
+        let url_start = url.find(|c: char| !matches!(c, '(' | '[' | '"' | '\'' | '<')).unwrap_or(0);
+        let (lead, url) = url.split_at(url_start);
+        let url = url.trim_end_matches('>');
+
+        match domain_of(url) {
+            Some(domain) if mode == UrlMode::Domain => {
+                out.push_str(lead);
+                out.push_str("link to ");
+                out.push_str(&domain.replace('.', " dot "));
+                out.push_str(trail);
+            }
+            Some(_) => {
+                // Keep only sentence-ending punctuation so the sentence still ends
+                out.extend(trail.chars().filter(|c| matches!(c, '.' | '!' | '?')));
+            }
+            None => out.push_str(word),
+        }
+        out.push_str(space);
+    }
+    out
+}
+
+/// Extract the host name from something that looks like a web link
+fn domain_of(word: &str) -> Option<&str> {
+    let lower = word.to_ascii_lowercase();
+    let rest = if lower.starts_with("http://") || lower.starts_with("https://") {
+        &word[word.find("://")? + 3..]
+    } else if lower.starts_with("www.") {
+        word
+    } else {
+        return None;
+    };
+
+    let host = rest.split(['/', '?', '#']).next()?;
+    let host = host.rsplit('@').next()?; // drop user info
+    let host = host.split(':').next()?; // drop port
+    let host = host.strip_prefix("www.").unwrap_or(host);
+
+    if host.is_empty() || !host.contains('.') {
+        return None;
+    }
+    Some(host)
+}
@@ -0,0 +1,99 @@
+//! Rejoining of lines broken by hard wraps, as in text copied from PDFs, so a
+//! paragraph isn't read line by line and "environ- ment" is read as one word
+
+/// Lines shorter than this are taken to end where the author ended them (titles,
+/// verse, addresses) rather than where the page did
+const MIN_WRAPPED_LEN: usize = 30;
+
+/// Words after which a trailing hyphen is kept, as in "pre- and post-war"
+const SUSPENDED_HYPHEN_NEXT: [&str; 4] = ["and", "or", "to", "nor"];
+
+/// Join lines that a paragraph was wrapped at, dropping the hyphens words were
+/// split with; fenced code blocks are left alone
+pub fn join_wrapped_lines(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_fence = false;
+    let mut lines = text.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+
+        if in_fence {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        let line = join_spaced_hyphens(line);
+        let line = line.as_str();
+
+        let joined = lines.peek().copied().filter(|next| continues(line, next));
+        match joined {
+            Some(next) if ends_split_word(line, next) => {
+                out.push_str(line.trim_end().trim_end_matches('-'));
+            }
+            Some(_) => {
+                out.push_str(line.trim_end());
+                out.push(' ');
+            }
+            None => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// Whether `next` carries on the sentence `line` was wrapped in
+fn continues(line: &str, next: &str) -> bool {
+    let line = line.trim_end();
+    // Indented lines, Markdown structure and list items keep their own lines
+    let structural = |l: &str| {
+        l.starts_with(char::is_whitespace) || l.starts_with(['#', '>', '|', '-', '*', '+', '['])
+    };
+    if line.chars().count() < MIN_WRAPPED_LEN || structural(line) || structural(next) {
+        return false;
+    }
+    let ends_open = line
+        .chars()
+        .last()
+        .is_some_and(|c| c.is_alphanumeric() || matches!(c, ',' | '-' | '\u{2013}' | '\u{2014}'));
+    ends_open && next.chars().next().is_some_and(char::is_lowercase)
+}
+
+/// Whether `line` ends in the first part of a word hyphenated across to `next`
+fn ends_split_word(line: &str, next: &str) -> bool {
+    let line = line.trim_end();
+    let Some(stem) = line.strip_suffix('-') else {
+        return false;
+    };
+    let next_word: String = next.chars().take_while(|c| c.is_alphabetic()).collect();
+    stem.chars().last().is_some_and(char::is_alphabetic)
+        && !SUSPENDED_HYPHEN_NEXT.contains(&next_word.as_str())
+}
+
+/// "environ- ment", where a viewer already joined the lines with a space, as "environment"
+fn join_spaced_hyphens(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(at) = rest.find("- ") {
+        let (before, after) = rest.split_at(at);
+        let after = &after[2..];
+        let next_word: String = after.chars().take_while(|c| c.is_alphabetic()).collect();
+        let splits_word = before.chars().last().is_some_and(char::is_lowercase)
+            && next_word.chars().next().is_some_and(char::is_lowercase)
+            && !SUSPENDED_HYPHEN_NEXT.contains(&next_word.as_str());
+
+        out.push_str(before);
+        if !splits_word {
+            out.push_str("- ");
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,155 @@
+//! Windows toast notifications asking whether to speak a copied text, and telling
+//! of errors and updates
+
+use anyhow::Result;
+
+/// App identity toasts are shown under, registered for the current user
+#[cfg(windows)]
+const APP_ID: &str = "PocketTray.TTS";
+
+#[cfg(windows)]
+/// Characters of the copied text shown in the toast
+const PREVIEW_CHARS: usize = 120;
+
+/// A toast offering to speak a copied text, hidden when dropped if still showing
+pub struct SpeakPrompt {
+    #[cfg(windows)]
+    notifier: windows::UI::Notifications::ToastNotifier,
+    #[cfg(windows)]
+    notification: windows::UI::Notifications::ToastNotification,
+}
+
+impl SpeakPrompt {
+    /// Show a toast with the first line of `text` and "Speak" and "Dismiss" buttons;
+    /// `on_speak` runs, on a thread of its own, if "Speak" is clicked
+    #[cfg(windows)]
+    pub fn show(text: &str, on_speak: impl FnOnce() + Send + 'static) -> Result<Self> {
+        use std::sync::Mutex;
+        use windows::core::{IInspectable, Interface, HSTRING};
+        use windows::Data::Xml::Dom::XmlDocument;
+        use windows::Foundation::TypedEventHandler;
+        use windows::UI::Notifications::{
+            ToastActivatedEventArgs, ToastNotification, ToastNotificationManager,
+        };
+
+        register_app_id()?;
+
+        // Silent, so the prompt itself doesn't make the sound it's there to avoid
+        let xml = format!(
+            r#"<toast><visual><binding template="ToastGeneric"><text>Speak copied text?</text><text>{}</text></binding></visual><actions><action content="Speak" arguments="speak"/><action content="Dismiss" arguments="dismiss"/></actions><audio silent="true"/></toast>"#,
+            escape_xml(&preview(text))
+        );
+        let document = XmlDocument::new()?;
+        document.LoadXml(&HSTRING::from(xml))?;
+        let notification = ToastNotification::CreateToastNotification(&document)?;
+
+        let on_speak = Mutex::new(Some(on_speak));
+        notification.Activated(&TypedEventHandler::new(
+            move |_: &Option<ToastNotification>, args: &Option<IInspectable>| {
+                let clicked = args
+                    .as_ref()
+                    .and_then(|args| args.cast::<ToastActivatedEventArgs>().ok())
+                    .and_then(|args| args.Arguments().ok());
+                if clicked.is_some_and(|arguments| arguments == "speak") {
+                    if let Some(on_speak) = on_speak.lock().ok().and_then(|mut f| f.take()) {
+                        on_speak();
+                    }
+                }
+                Ok(())
+            },
+        ))?;
+
+        let notifier = ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))?;
+        notifier.Show(&notification)?;
+        Ok(Self { notifier, notification })
+    }
+
+    #[cfg(not(windows))]
+    pub fn show(_text: &str, _on_speak: impl FnOnce() + Send + 'static) -> Result<Self> {
+        anyhow::bail!("Notifications are not supported on this platform")
+    }
+}
+
+#[cfg(windows)]
+impl Drop for SpeakPrompt {
+    fn drop(&mut self) {
+        // Fails harmlessly once the toast is gone
+        let _ = self.notifier.Hide(&self.notification);
+    }
+}
+
+/// Show a toast telling of an error, which stays in the notification center once it
+/// times out
+pub fn show_error(message: &str) -> Result<()> {
+    show_notice("Pocket-Tray TTS error", message)
+}
+
+/// Show a toast with a title and a message, kept in the notification center like errors
+#[cfg(windows)]
+pub fn show_notice(title: &str, message: &str) -> Result<()> {
+    use windows::core::HSTRING;
+    use windows::Data::Xml::Dom::XmlDocument;
+    use windows::UI::Notifications::{ToastNotification, ToastNotificationManager};
+
+    register_app_id()?;
+
+    let xml = format!(
+        r#"<toast><visual><binding template="ToastGeneric"><text>{}</text><text>{}</text></binding></visual></toast>"#,
+        escape_xml(title),
+        escape_xml(message)
+    );
+    let document = XmlDocument::new()?;
+    document.LoadXml(&HSTRING::from(xml))?;
+    let notification = ToastNotification::CreateToastNotification(&document)?;
+    ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))?.Show(&notification)?;
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn show_notice(_title: &str, _message: &str) -> Result<()> {
+    anyhow::bail!("Notifications are not supported on this platform")
+}
+
+#[cfg(windows)]
+/// First line of `text`, shortened to fit a toast
+fn preview(text: &str) -> String {
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("");
+    if line.chars().count() > PREVIEW_CHARS {
+        let cut: String = line.chars().take(PREVIEW_CHARS).collect();
+        format!("{}…", cut.trim_end())
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(windows)]
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Register the app identity under HKCU so Windows shows toasts from an unpackaged app
+#[cfg(windows)]
+fn register_app_id() -> Result<()> {
+    use windows::core::HSTRING;
+    use windows::Win32::System::Registry::{RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ};
+
+    let path = HSTRING::from(format!(r"Software\Classes\AppUserModelId\{}", APP_ID));
+    let name: Vec<u16> = "Pocket-Tray TTS".encode_utf16().chain(std::iter::once(0)).collect();
+
+    // Creates the key if it isn't there yet
+    unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            &path,
+            &HSTRING::from("DisplayName"),
+            REG_SZ.0,
+            Some(name.as_ptr().cast()),
+            (name.len() * 2) as u32,
+        )
+        .ok()?;
+    }
+    Ok(())
+}
@@ -1,23 +1,432 @@
 //! System tray icon and menu management
 
-use crate::icon::IconAnimator;
-use crate::settings::VOICES;
+use crate::audio::EqBand;
+use crate::chime::ChimeMode;
+use crate::export::ExportFormat;
+use crate::icon::{icon_size_for_dpi, parse_color, taskbar_dpi, IconAlert, IconAnimator, ICON_COLOR_PRESETS};
+use crate::output::AudioLatency;
+use crate::reading::ReadingItem;
+use crate::settings::{custom_voices, find_model, installed_models, HotkeySettings, Settings};
+use crate::soft::SoftMode;
+use crate::source::{AppFilter, AppFilterMode};
+use crate::text::{CodeMode, EmojiMode, JsonMode, ListMode, UrlMode};
+use crate::tts::{ComputeDevice, Precision, QueuedText};
+use crate::voice_info::{describe_voices, group_voices, VoiceGrouping, VoiceInfo};
 use anyhow::Result;
+use image::Rgba;
 use muda::{accelerator::Accelerator, CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
 use tray_icon::{TrayIcon, TrayIconBuilder};
 
+/// How long a flashing icon stays on, then off
+const FLASH_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Times (in minutes) monitoring can be paused for
+const PAUSE_MONITOR_CHOICES: [u32; 3] = [10, 30, 60];
+
+/// Idle times (in minutes) offered for unloading the model; 0 means never
+const UNLOAD_AFTER_CHOICES: [u32; 5] = [0, 5, 15, 30, 60];
+
+/// Pitch shifts (in semitones) offered for all voices
+const PITCH_CHOICES: [i32; 5] = [-4, -2, 0, 2, 4];
+
+/// Boosts and cuts (in dB) offered for each equalizer band
+const EQ_CHOICES: [i32; 5] = [-6, -3, 0, 3, 6];
+
+/// What clicking or double-clicking the tray icon does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClickAction {
+    #[default]
+    None,
+    /// Show the menu, as right-clicking does (single click only)
+    Menu,
+    /// Speak the last text again
+    Replay,
+    Stop,
+    PauseResume,
+    /// Turn clipboard monitoring on or off
+    ToggleMonitor,
+}
+
 /// Menu item IDs
 pub mod menu_ids {
+    use crate::audio::EqBand;
+    use crate::chime::ChimeMode;
+    use crate::export::ExportFormat;
+    use crate::output::AudioLatency;
+    use crate::soft::SoftMode;
+    use crate::source::AppFilterMode;
+    use crate::text::{CodeMode, EmojiMode, JsonMode, ListMode, UrlMode};
+    use crate::tts::{ComputeDevice, Precision};
+    use crate::voice_info::VoiceGrouping;
     use muda::MenuId;
 
     pub fn monitor() -> MenuId {
         MenuId::new("monitor")
     }
 
+    pub fn pause_monitor(minutes: u32) -> MenuId {
+        MenuId::new(format!("pause_monitor_{}", minutes))
+    }
+
+    pub fn pause_monitor_from_id(id: &MenuId) -> Option<u32> {
+        super::PAUSE_MONITOR_CHOICES
+            .into_iter()
+            .find(|&minutes| pause_monitor(minutes) == *id)
+    }
+
+    pub fn interrupt() -> MenuId {
+        MenuId::new("interrupt")
+    }
+
+    pub fn read_images() -> MenuId {
+        MenuId::new("read_images")
+    }
+
+    pub fn ask_first() -> MenuId {
+        MenuId::new("ask_first")
+    }
+
+    pub fn collect() -> MenuId {
+        MenuId::new("collect")
+    }
+
+    pub fn speak_collected() -> MenuId {
+        MenuId::new("speak_collected")
+    }
+
+    pub fn clear_collected() -> MenuId {
+        MenuId::new("clear_collected")
+    }
+
+    pub fn skim() -> MenuId {
+        MenuId::new("skim")
+    }
+
+    pub fn read_in_full() -> MenuId {
+        MenuId::new("read_in_full")
+    }
+
+    pub fn duck() -> MenuId {
+        MenuId::new("duck")
+    }
+
+    pub fn app_filter(mode: AppFilterMode) -> MenuId {
+        MenuId::new(format!("app_filter_{}", mode.id()))
+    }
+
+    pub fn app_filter_from_id(id: &MenuId) -> Option<AppFilterMode> {
+        AppFilterMode::ALL.into_iter().find(|&mode| app_filter(mode) == *id)
+    }
+
+    pub fn source_app(app: &str) -> MenuId {
+        MenuId::new(format!("source_app_{}", app))
+    }
+
+    pub fn source_app_from_id(id: &MenuId) -> Option<String> {
+        id.as_ref().strip_prefix("source_app_").map(str::to_string)
+    }
+
     pub fn stop() -> MenuId {
         MenuId::new("stop")
     }
 
+    pub fn pause_resume() -> MenuId {
+        MenuId::new("pause_resume")
+    }
+
+    pub fn next_sentence() -> MenuId {
+        MenuId::new("next_sentence")
+    }
+
+    pub fn previous_sentence() -> MenuId {
+        MenuId::new("previous_sentence")
+    }
+
+    pub fn show_player() -> MenuId {
+        MenuId::new("show_player")
+    }
+
+    pub fn drop_window() -> MenuId {
+        MenuId::new("drop_window")
+    }
+
+    pub fn clear_queue() -> MenuId {
+        MenuId::new("clear_queue")
+    }
+
+    pub fn queued_front(id: u64) -> MenuId {
+        MenuId::new(format!("queued_front_{}", id))
+    }
+
+    pub fn queued_front_from_id(id: &MenuId) -> Option<u64> {
+        id.as_ref().strip_prefix("queued_front_")?.parse().ok()
+    }
+
+    pub fn queued_remove(id: u64) -> MenuId {
+        MenuId::new(format!("queued_remove_{}", id))
+    }
+
+    pub fn queued_remove_from_id(id: &MenuId) -> Option<u64> {
+        id.as_ref().strip_prefix("queued_remove_")?.parse().ok()
+    }
+
+    pub fn open_file() -> MenuId {
+        MenuId::new("open_file")
+    }
+
+    pub fn save_last() -> MenuId {
+        MenuId::new("save_last")
+    }
+
+    pub fn reading_start(id: u64) -> MenuId {
+        MenuId::new(format!("reading_start_{}", id))
+    }
+
+    pub fn reading_start_from_id(id: &MenuId) -> Option<u64> {
+        id.as_ref().strip_prefix("reading_start_")?.parse().ok()
+    }
+
+    pub fn reading_resume(id: u64) -> MenuId {
+        MenuId::new(format!("reading_resume_{}", id))
+    }
+
+    pub fn reading_resume_from_id(id: &MenuId) -> Option<u64> {
+        id.as_ref().strip_prefix("reading_resume_")?.parse().ok()
+    }
+
+    pub fn reading_delete(id: u64) -> MenuId {
+        MenuId::new(format!("reading_delete_{}", id))
+    }
+
+    pub fn reading_delete_from_id(id: &MenuId) -> Option<u64> {
+        id.as_ref().strip_prefix("reading_delete_")?.parse().ok()
+    }
+
+    pub fn follow_log() -> MenuId {
+        MenuId::new("follow_log")
+    }
+
+    pub fn archive() -> MenuId {
+        MenuId::new("archive")
+    }
+
+    pub fn clear_cache() -> MenuId {
+        MenuId::new("clear_cache")
+    }
+
+    pub fn chime(mode: ChimeMode) -> MenuId {
+        MenuId::new(format!("chime_{}", mode.id()))
+    }
+
+    pub fn chime_from_id(id: &MenuId) -> Option<ChimeMode> {
+        ChimeMode::ALL.into_iter().find(|&mode| chime(mode) == *id)
+    }
+
+    pub fn export_format(format: ExportFormat) -> MenuId {
+        MenuId::new(format!("format_{}", format.extension()))
+    }
+
+    pub fn export_format_from_id(id: &MenuId) -> Option<ExportFormat> {
+        ExportFormat::ALL
+            .into_iter()
+            .find(|&format| export_format(format) == *id)
+    }
+
+    pub fn equalizer(band: EqBand, db: i32) -> MenuId {
+        MenuId::new(format!("eq_{}_{}", band.id(), db))
+    }
+
+    pub fn equalizer_from_id(id: &MenuId) -> Option<(EqBand, i32)> {
+        EqBand::ALL.into_iter().find_map(|band| {
+            super::EQ_CHOICES
+                .into_iter()
+                .find(|&db| equalizer(band, db) == *id)
+                .map(|db| (band, db))
+        })
+    }
+
+    pub fn soft_voice(mode: SoftMode) -> MenuId {
+        MenuId::new(format!("soft_{}", mode.id()))
+    }
+
+    pub fn soft_voice_from_id(id: &MenuId) -> Option<SoftMode> {
+        SoftMode::ALL.into_iter().find(|&mode| soft_voice(mode) == *id)
+    }
+
+    pub fn audio_latency(latency: AudioLatency) -> MenuId {
+        MenuId::new(format!("latency_{}", latency.id()))
+    }
+
+    pub fn audio_latency_from_id(id: &MenuId) -> Option<AudioLatency> {
+        AudioLatency::ALL
+            .into_iter()
+            .find(|&latency| audio_latency(latency) == *id)
+    }
+
+    pub fn output_preset(name: Option<&str>) -> MenuId {
+        match name {
+            Some(name) => MenuId::new(format!("output_preset_{}", name)),
+            None => MenuId::new("output_default"),
+        }
+    }
+
+    pub fn output_preset_from_id(id: &MenuId) -> Option<Option<String>> {
+        if *id == output_preset(None) {
+            return Some(None);
+        }
+        id.as_ref()
+            .strip_prefix("output_preset_")
+            .map(|name| Some(name.to_string()))
+    }
+
+    pub fn icon_color(color: Option<&str>) -> MenuId {
+        match color {
+            Some(color) => MenuId::new(format!("icon_color_{}", color)),
+            None => MenuId::new("icon_color_taskbar"),
+        }
+    }
+
+    pub fn icon_color_from_id(id: &MenuId) -> Option<Option<String>> {
+        if *id == icon_color(None) {
+            return Some(None);
+        }
+        id.as_ref()
+            .strip_prefix("icon_color_")
+            .map(|color| Some(color.to_string()))
+    }
+
+    pub fn device(device: ComputeDevice) -> MenuId {
+        MenuId::new(format!("device_{}", device.id()))
+    }
+
+    pub fn device_from_id(id: &MenuId) -> Option<ComputeDevice> {
+        ComputeDevice::ALL.into_iter().find(|&device| self::device(device) == *id)
+    }
+
+    pub fn precision(precision: Precision) -> MenuId {
+        MenuId::new(format!("precision_{}", precision.id()))
+    }
+
+    pub fn precision_from_id(id: &MenuId) -> Option<Precision> {
+        Precision::ALL
+            .into_iter()
+            .find(|&precision| self::precision(precision) == *id)
+    }
+
+    pub fn unload_after(minutes: u32) -> MenuId {
+        MenuId::new(format!("unload_{}", minutes))
+    }
+
+    pub fn unload_after_from_id(id: &MenuId) -> Option<u32> {
+        super::UNLOAD_AFTER_CHOICES
+            .into_iter()
+            .find(|&minutes| unload_after(minutes) == *id)
+    }
+
+    pub fn pitch(semitones: i32) -> MenuId {
+        MenuId::new(format!("pitch_{}", semitones))
+    }
+
+    pub fn pitch_from_id(id: &MenuId) -> Option<i32> {
+        super::PITCH_CHOICES
+            .into_iter()
+            .find(|&semitones| pitch(semitones) == *id)
+    }
+
+    pub fn url_mode(mode: UrlMode) -> MenuId {
+        MenuId::new(format!("links_{}", mode.id()))
+    }
+
+    pub fn url_mode_from_id(id: &MenuId) -> Option<UrlMode> {
+        UrlMode::ALL.into_iter().find(|&mode| url_mode(mode) == *id)
+    }
+
+    pub fn emoji_mode(mode: EmojiMode) -> MenuId {
+        MenuId::new(format!("emoji_{}", mode.id()))
+    }
+
+    pub fn emoji_mode_from_id(id: &MenuId) -> Option<EmojiMode> {
+        EmojiMode::ALL.into_iter().find(|&mode| emoji_mode(mode) == *id)
+    }
+
+    pub fn code_mode(mode: CodeMode) -> MenuId {
+        MenuId::new(format!("code_{}", mode.id()))
+    }
+
+    pub fn code_mode_from_id(id: &MenuId) -> Option<CodeMode> {
+        CodeMode::ALL.into_iter().find(|&mode| code_mode(mode) == *id)
+    }
+
+    pub fn list_mode(mode: ListMode) -> MenuId {
+        MenuId::new(format!("lists_{}", mode.id()))
+    }
+
+    pub fn list_mode_from_id(id: &MenuId) -> Option<ListMode> {
+        ListMode::ALL.into_iter().find(|&mode| list_mode(mode) == *id)
+    }
+
+    pub fn json_mode(mode: JsonMode) -> MenuId {
+        MenuId::new(format!("json_{}", mode.id()))
+    }
+
+    pub fn json_mode_from_id(id: &MenuId) -> Option<JsonMode> {
+        JsonMode::ALL.into_iter().find(|&mode| json_mode(mode) == *id)
+    }
+
+    pub fn preview_voice(name: &str) -> MenuId {
+        MenuId::new(format!("preview_{}", name))
+    }
+
+    pub fn preview_voice_name_from_id(id: &MenuId) -> Option<String> {
+        id.as_ref().strip_prefix("preview_").map(str::to_string)
+    }
+
+    pub fn replay_with_voice(name: &str) -> MenuId {
+        MenuId::new(format!("replay_with_{}", name))
+    }
+
+    pub fn replay_with_voice_name_from_id(id: &MenuId) -> Option<String> {
+        id.as_ref().strip_prefix("replay_with_").map(str::to_string)
+    }
+
+    pub fn voice_grouping(grouping: VoiceGrouping) -> MenuId {
+        MenuId::new(format!("group_voices_{}", grouping.id()))
+    }
+
+    pub fn voice_grouping_from_id(id: &MenuId) -> Option<VoiceGrouping> {
+        VoiceGrouping::ALL
+            .into_iter()
+            .find(|&grouping| voice_grouping(grouping) == *id)
+    }
+
+    pub fn model(name: &str) -> MenuId {
+        MenuId::new(format!("model_{}", name))
+    }
+
+    pub fn model_name_from_id(id: &MenuId) -> Option<String> {
+        id.as_ref().strip_prefix("model_").map(str::to_string)
+    }
+
+    pub fn add_voice() -> MenuId {
+        MenuId::new("add_voice")
+    }
+
+    pub fn start_with_windows() -> MenuId {
+        MenuId::new("start_with_windows")
+    }
+
+    pub fn check_for_updates() -> MenuId {
+        MenuId::new("check_for_updates")
+    }
+
+    pub fn settings() -> MenuId {
+        MenuId::new("settings")
+    }
+
     pub fn quit() -> MenuId {
         MenuId::new("quit")
     }
@@ -33,64 +442,688 @@ pub mod menu_ids {
         } else {
             None
         }
-    }
-}
+    }
+}
+
+/// Tray icon and menu manager
+pub struct TrayManager {
+    tray_icon: TrayIcon,
+    monitor_item: CheckMenuItem,
+    interrupt_item: CheckMenuItem,
+    read_images_item: CheckMenuItem,
+    ask_first_item: CheckMenuItem,
+    collect_item: CheckMenuItem,
+    speak_collected_item: MenuItem,
+    clear_collected_item: MenuItem,
+    stop_item: MenuItem,
+    pause_resume_item: MenuItem,
+    show_player_item: MenuItem,
+    quit_item: MenuItem,
+    duck_item: CheckMenuItem,
+    skim_item: CheckMenuItem,
+    app_filter_items: Vec<(AppFilterMode, CheckMenuItem)>,
+    source_apps_menu: Submenu,
+    /// One item per recently seen or listed app
+    source_app_items: Vec<CheckMenuItem>,
+    archive_item: CheckMenuItem,
+    start_with_windows_item: CheckMenuItem,
+    follow_log_item: CheckMenuItem,
+    drop_window_item: CheckMenuItem,
+    queue_menu: Submenu,
+    /// One submenu per text waiting in the queue
+    queue_items: Vec<Submenu>,
+    reading_menu: Submenu,
+    /// One submenu per reading list text
+    reading_items: Vec<Submenu>,
+    voices_menu: Submenu,
+    preview_menu: Submenu,
+    /// Voices of the engine in use, with their details
+    voices: Vec<VoiceInfo>,
+    selected_voice: String,
+    voice_grouping: VoiceGrouping,
+    voice_grouping_items: Vec<(VoiceGrouping, CheckMenuItem)>,
+    /// One submenu per group of voices, none while they're listed flat
+    voice_groups: Vec<Submenu>,
+    voice_items: Vec<CheckMenuItem>,
+    preview_items: Vec<MenuItem>,
+    replay_with_menu: Submenu,
+    replay_with_items: Vec<MenuItem>,
+    model_items: Vec<CheckMenuItem>,
+    unload_items: Vec<(u32, CheckMenuItem)>,
+    pitch_items: Vec<(i32, CheckMenuItem)>,
+    device_items: Vec<(ComputeDevice, CheckMenuItem)>,
+    active_device_item: MenuItem,
+    precision_items: Vec<(Precision, CheckMenuItem)>,
+    format_items: Vec<(ExportFormat, CheckMenuItem)>,
+    chime_items: Vec<(ChimeMode, CheckMenuItem)>,
+    latency_items: Vec<(AudioLatency, CheckMenuItem)>,
+    output_items: Vec<(Option<String>, CheckMenuItem)>,
+    icon_color_items: Vec<(Option<String>, CheckMenuItem)>,
+    equalizer_items: Vec<(EqBand, i32, CheckMenuItem)>,
+    soft_voice_items: Vec<(SoftMode, CheckMenuItem)>,
+    url_mode_items: Vec<(UrlMode, CheckMenuItem)>,
+    emoji_mode_items: Vec<(EmojiMode, CheckMenuItem)>,
+    code_mode_items: Vec<(CodeMode, CheckMenuItem)>,
+    list_mode_items: Vec<(ListMode, CheckMenuItem)>,
+    json_mode_items: Vec<(JsonMode, CheckMenuItem)>,
+    animator: IconAnimator,
+    /// Tooltip set last, shown again once a flashed one is over
+    tooltip: RefCell<String>,
+    /// When the flashed tooltip gives way to the usual one
+    flash_until: Cell<Option<Instant>>,
+    /// When a flashing icon stops flashing
+    icon_flash_until: Option<Instant>,
+    /// When a flashing icon last turned on or off, and whether it's off
+    icon_flash_toggled: (Instant, bool),
+}
+
+impl TrayManager {
+    /// Create the tray icon and menu
+    pub fn new(settings: &Settings) -> Result<Self> {
+        // Create icon animator
+        let animator = IconAnimator::new(settings.icon_color(), icon_size_for_dpi(taskbar_dpi()))?;
+        let icon = animator.static_icon().clone();
+
+        // Build menu
+        let menu = Menu::new();
+
+        // Monitor toggle
+        let monitor_item = CheckMenuItem::with_id(
+            menu_ids::monitor(),
+            "&Monitoring",
+            true,
+            settings.clipboard.monitor_enabled,
+            menu_accelerator(settings.hotkeys.monitor_hotkey.as_deref()),
+        );
+
+        // Pause monitoring submenu
+        let pause_monitor_menu = Submenu::new("Pause Monitoring", true);
+        for minutes in PAUSE_MONITOR_CHOICES {
+            let item = MenuItem::with_id(
+                menu_ids::pause_monitor(minutes),
+                format!("For {} Minutes", minutes),
+                true,
+                None::<Accelerator>,
+            );
+            pause_monitor_menu.append(&item)?;
+        }
+
+        // Interrupt-on-copy toggle
+        let interrupt_item = CheckMenuItem::with_id(
+            menu_ids::interrupt(),
+            "Interrupt on New Copy",
+            true,
+            settings.clipboard.interrupt_on_copy,
+            None::<Accelerator>,
+        );
+
+        // Image text recognition toggle
+        let read_images_item = CheckMenuItem::with_id(
+            menu_ids::read_images(),
+            "Read Text in Copied Images",
+            true,
+            settings.clipboard.read_images,
+            None::<Accelerator>,
+        );
+
+        // Confirm-before-speaking toggle
+        let ask_first_item = CheckMenuItem::with_id(
+            menu_ids::ask_first(),
+            "Ask Before Speaking",
+            true,
+            settings.clipboard.ask_before_speaking,
+            None::<Accelerator>,
+        );
+
+        // Collected copies submenu
+        let collect_item = CheckMenuItem::with_id(
+            menu_ids::collect(),
+            "Collect Copies Instead of Speaking",
+            true,
+            settings.clipboard.collect_copies,
+            None::<Accelerator>,
+        );
+        let speak_collected_item = MenuItem::with_id(
+            menu_ids::speak_collected(),
+            collected_label(0),
+            false,
+            None::<Accelerator>,
+        );
+        let clear_collected_item = MenuItem::with_id(
+            menu_ids::clear_collected(),
+            "Clear Collected",
+            false,
+            None::<Accelerator>,
+        );
+        let collect_menu = Submenu::new("Collect Copies", true);
+        collect_menu.append(&collect_item)?;
+        collect_menu.append(&speak_collected_item)?;
+        collect_menu.append(&clear_collected_item)?;
+
+        // Ducking toggle
+        let duck_item = CheckMenuItem::with_id(
+            menu_ids::duck(),
+            "Lower Other Audio While Speaking",
+            true,
+            settings.audio.duck_others,
+            None::<Accelerator>,
+        );
+
+        // Source apps submenu; the apps themselves are filled in by set_source_apps
+        let source_apps_menu = Submenu::new("Source Apps", true);
+        let mut app_filter_items = Vec::new();
+        for mode in AppFilterMode::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::app_filter(mode),
+                mode.label(),
+                true,
+                mode == settings.filters.app_filter,
+                None::<Accelerator>,
+            );
+            source_apps_menu.append(&item)?;
+            app_filter_items.push((mode, item));
+        }
+        source_apps_menu.append(&PredefinedMenuItem::separator())?;
+
+        // Chime submenu
+        let chime_menu = Submenu::new("Chime", true);
+        let mut chime_items = Vec::new();
+        for mode in ChimeMode::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::chime(mode),
+                mode.label(),
+                true,
+                mode == settings.audio.chime,
+                None::<Accelerator>,
+            );
+            chime_menu.append(&item)?;
+            chime_items.push((mode, item));
+        }
+
+        // Stop button
+        let stop_item = MenuItem::with_id(
+            menu_ids::stop(),
+            "&Stop",
+            true,
+            menu_accelerator(settings.hotkeys.stop_hotkey.as_deref()),
+        );
+
+        // Pause / Resume button, labelled for what it does next
+        let pause_resume_item =
+            MenuItem::with_id(menu_ids::pause_resume(), "Pause", true, None::<Accelerator>);
+
+        // Sentence skip buttons
+        let previous_sentence_item = MenuItem::with_id(
+            menu_ids::previous_sentence(),
+            "Previous Sentence",
+            true,
+            None::<Accelerator>,
+        );
+        let next_sentence_item =
+            MenuItem::with_id(menu_ids::next_sentence(), "Next Sentence", true, None::<Accelerator>);
+
+        // Player window, with the same controls always in view
+        let show_player_item = MenuItem::with_id(
+            menu_ids::show_player(),
+            "&Player...",
+            true,
+            menu_accelerator(settings.hotkeys.player_hotkey.as_deref()),
+        );
+
+        // Drop target toggle
+        let drop_window_item = CheckMenuItem::with_id(
+            menu_ids::drop_window(),
+            "Drop Target",
+            true,
+            settings.ui.drop_window,
+            None::<Accelerator>,
+        );
+
+        // Clear queue button
+        let clear_queue_item =
+            MenuItem::with_id(menu_ids::clear_queue(), "Clear Queue", true, None::<Accelerator>);
+
+        // Queue submenu, filled in by set_queue
+        let queue_menu = Submenu::new("Queue", false);
+
+        // Open text file button
+        let open_file_item =
+            MenuItem::with_id(menu_ids::open_file(), "Open Text File...", true, None::<Accelerator>);
+
+        // Save last speech button
+        let save_last_item =
+            MenuItem::with_id(menu_ids::save_last(), "Save Last Speech", true, None::<Accelerator>);
+
+        let clear_cache_item = MenuItem::with_id(
+            menu_ids::clear_cache(),
+            "Clear Audio Cache",
+            true,
+            None::<Accelerator>,
+        );
+
+        let follow_log_item = CheckMenuItem::with_id(
+            menu_ids::follow_log(),
+            "Follow Log File...",
+            true,
+            settings.clipboard.follow_log.is_some(),
+            None::<Accelerator>,
+        );
+
+        // Reading list submenu, filled in by set_reading_list
+        let reading_menu = Submenu::new("Reading List", false);
+
+        let archive_item = CheckMenuItem::with_id(
+            menu_ids::archive(),
+            "Archive Everything Spoken",
+            true,
+            settings.audio.archive_enabled,
+            None::<Accelerator>,
+        );
+
+        // Save format submenu
+        let format_menu = Submenu::new("Save Format", true);
+        let mut format_items = Vec::new();
+        for format in ExportFormat::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::export_format(format),
+                format.label(),
+                true,
+                format == settings.audio.export_format,
+                None::<Accelerator>,
+            );
+            format_menu.append(&item)?;
+            format_items.push((format, item));
+        }
+
+        // Soft voice submenu; the scheduled option shows its hours
+        let soft_voice_menu = Submenu::new("Soft Voice", true);
+        let mut soft_voice_items = Vec::new();
+        for mode in SoftMode::ALL {
+            let label = match mode {
+                SoftMode::Scheduled => format!(
+                    "{} ({:02}:00-{:02}:00)",
+                    mode.label(),
+                    settings.audio.soft_from_hour % 24,
+                    settings.audio.soft_until_hour % 24
+                ),
+                _ => mode.label().to_string(),
+            };
+            let item = CheckMenuItem::with_id(
+                menu_ids::soft_voice(mode),
+                label,
+                true,
+                mode == settings.audio.soft_voice,
+                None::<Accelerator>,
+            );
+            soft_voice_menu.append(&item)?;
+            soft_voice_items.push((mode, item));
+        }
+
+        // Equalizer submenu, one submenu per band
+        let equalizer_menu = Submenu::new("Equalizer", true);
+        let mut equalizer_items = Vec::new();
+        for band in EqBand::ALL {
+            let band_menu = Submenu::new(band.label(), true);
+            for db in EQ_CHOICES {
+                let label = match db {
+                    0 => "Flat".to_string(),
+                    db if db > 0 => format!("+{} dB", db),
+                    db => format!("{} dB", db),
+                };
+                let item = CheckMenuItem::with_id(
+                    menu_ids::equalizer(band, db),
+                    label,
+                    true,
+                    db == settings.audio.equalizer.get(band),
+                    None::<Accelerator>,
+                );
+                band_menu.append(&item)?;
+                equalizer_items.push((band, db, item));
+            }
+            equalizer_menu.append(&band_menu)?;
+        }
+
+        // Audio latency submenu
+        let latency_menu = Submenu::new("Audio Latency", true);
+        let mut latency_items = Vec::new();
+        for latency in AudioLatency::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::audio_latency(latency),
+                latency.label(),
+                true,
+                latency == settings.audio.audio_latency,
+                None::<Accelerator>,
+            );
+            latency_menu.append(&item)?;
+            latency_items.push((latency, item));
+        }
+
+        // Output device submenu: the system default, then the user's presets
+        let output_menu = Submenu::new("Output Device", true);
+        let mut output_items = Vec::new();
+        // A preset that was since removed plays on the default device
+        let selected_preset = settings
+            .audio.output_preset
+            .clone()
+            .filter(|name| settings.audio.output_presets.iter().any(|preset| &preset.name == name));
+        let presets = std::iter::once(None).chain(
+            settings
+                .audio.output_presets
+                .iter()
+                .map(|preset| Some(preset.name.clone())),
+        );
+        for preset in presets {
+            let item = CheckMenuItem::with_id(
+                menu_ids::output_preset(preset.as_deref()),
+                preset.as_deref().unwrap_or("System Default"),
+                true,
+                preset == selected_preset,
+                None::<Accelerator>,
+            );
+            output_menu.append(&item)?;
+            output_items.push((preset, item));
+        }
+
+        // Icon color submenu: matching the taskbar, then the presets
+        let icon_color_menu = Submenu::new("Icon Color", true);
+        let mut icon_color_items = Vec::new();
+        let colors = std::iter::once((None, "Match Taskbar"))
+            .chain(ICON_COLOR_PRESETS.iter().map(|&(name, hex)| (Some(hex.to_string()), name)));
+        for (color, label) in colors {
+            let item = CheckMenuItem::with_id(
+                menu_ids::icon_color(color.as_deref()),
+                label,
+                true,
+                same_color(color.as_deref(), settings.ui.icon_color.as_deref()),
+                None::<Accelerator>,
+            );
+            icon_color_menu.append(&item)?;
+            icon_color_items.push((color, item));
+        }
+
+        // Link handling submenu
+        let links_menu = Submenu::new("Links", true);
+        let mut url_mode_items = Vec::new();
+        for mode in UrlMode::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::url_mode(mode),
+                mode.label(),
+                true,
+                mode == settings.speech.url_mode,
+                None::<Accelerator>,
+            );
+            links_menu.append(&item)?;
+            url_mode_items.push((mode, item));
+        }
+
+        // Emoji handling submenu
+        let emoji_menu = Submenu::new("Emoji", true);
+        let mut emoji_mode_items = Vec::new();
+        for mode in EmojiMode::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::emoji_mode(mode),
+                mode.label(),
+                true,
+                mode == settings.speech.emoji_mode,
+                None::<Accelerator>,
+            );
+            emoji_menu.append(&item)?;
+            emoji_mode_items.push((mode, item));
+        }
+
+        // Code handling submenu
+        let code_menu = Submenu::new("Code", true);
+        let mut code_mode_items = Vec::new();
+        for mode in CodeMode::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::code_mode(mode),
+                mode.label(),
+                true,
+                mode == settings.speech.code_mode,
+                None::<Accelerator>,
+            );
+            code_menu.append(&item)?;
+            code_mode_items.push((mode, item));
+        }
+
+        // List pacing submenu
+        let lists_menu = Submenu::new("Lists", true);
+        let mut list_mode_items = Vec::new();
+        for mode in ListMode::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::list_mode(mode),
+                mode.label(),
+                true,
+                mode == settings.speech.list_mode,
+                None::<Accelerator>,
+            );
+            lists_menu.append(&item)?;
+            list_mode_items.push((mode, item));
+        }
+
+        // JSON handling submenu
+        let json_menu = Submenu::new("JSON", true);
+        let mut json_mode_items = Vec::new();
+        for mode in JsonMode::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::json_mode(mode),
+                mode.label(),
+                true,
+                mode == settings.speech.json_mode,
+                None::<Accelerator>,
+            );
+            json_menu.append(&item)?;
+            json_mode_items.push((mode, item));
+        }
+
+        // Device submenu, topped by the device actually in use
+        let device_menu = Submenu::new("Device", true);
+        let active_device_item = MenuItem::new("In use: (model not loaded)", false, None::<Accelerator>);
+        device_menu.append(&active_device_item)?;
+        device_menu.append(&PredefinedMenuItem::separator())?;
+        let mut device_items = Vec::new();
+        for device in ComputeDevice::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::device(device),
+                device.label(),
+                true,
+                device == settings.speech.device,
+                None::<Accelerator>,
+            );
+            device_menu.append(&item)?;
+            device_items.push((device, item));
+        }
 
-/// Tray icon and menu manager
-pub struct TrayManager {
-    tray_icon: TrayIcon,
-    monitor_item: CheckMenuItem,
-    voice_items: Vec<CheckMenuItem>,
-    animator: IconAnimator,
-}
+        // Precision submenu
+        let precision_menu = Submenu::new("Precision", true);
+        let mut precision_items = Vec::new();
+        for precision in Precision::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::precision(precision),
+                precision.label(),
+                true,
+                precision == settings.speech.precision,
+                None::<Accelerator>,
+            );
+            precision_menu.append(&item)?;
+            precision_items.push((precision, item));
+        }
 
-impl TrayManager {
-    /// Create the tray icon and menu
-    pub fn new(monitor_enabled: bool, current_voice: &str) -> Result<Self> {
-        // Create icon animator
-        let animator = IconAnimator::new()?;
-        let icon = animator.static_icon().clone();
+        // Unload-when-idle submenu
+        let unload_menu = Submenu::new("Unload Model When Idle", true);
+        let mut unload_items = Vec::new();
+        for minutes in UNLOAD_AFTER_CHOICES {
+            let label = if minutes == 0 {
+                "Never".to_string()
+            } else {
+                format!("After {} Minutes", minutes)
+            };
+            let item = CheckMenuItem::with_id(
+                menu_ids::unload_after(minutes),
+                label,
+                true,
+                minutes == settings.speech.unload_after_minutes,
+                None::<Accelerator>,
+            );
+            unload_menu.append(&item)?;
+            unload_items.push((minutes, item));
+        }
 
-        // Build menu
-        let menu = Menu::new();
+        // Pitch submenu
+        let pitch_menu = Submenu::new("Pitch", true);
+        let mut pitch_items = Vec::new();
+        for semitones in PITCH_CHOICES {
+            let label = match semitones {
+                0 => "Normal".to_string(),
+                s if s > 0 => format!("Higher (+{} Semitones)", s),
+                s => format!("Lower ({} Semitones)", s),
+            };
+            let item = CheckMenuItem::with_id(
+                menu_ids::pitch(semitones),
+                label,
+                true,
+                semitones == settings.audio.pitch_semitones,
+                None::<Accelerator>,
+            );
+            pitch_menu.append(&item)?;
+            pitch_items.push((semitones, item));
+        }
 
-        // Monitor toggle
-        let monitor_item = CheckMenuItem::with_id(
-            menu_ids::monitor(),
-            "Monitoring",
+        // Voices submenu, filled from the model's voices (refreshed once the model loads)
+        let voices_menu = Submenu::new("Voices", true);
+        let preview_menu = Submenu::new("Preview", true);
+        let replay_with_menu = Submenu::new("Speak Last With", true);
+        voices_menu.append(&PredefinedMenuItem::separator())?;
+        voices_menu.append(&preview_menu)?;
+        voices_menu.append(&replay_with_menu)?;
+        let voice_grouping_menu = Submenu::new("Group By", true);
+        let mut voice_grouping_items = Vec::new();
+        for grouping in VoiceGrouping::ALL {
+            let item = CheckMenuItem::with_id(
+                menu_ids::voice_grouping(grouping),
+                grouping.label(),
+                true,
+                grouping == settings.ui.voice_grouping,
+                None::<Accelerator>,
+            );
+            voice_grouping_menu.append(&item)?;
+            voice_grouping_items.push((grouping, item));
+        }
+        voices_menu.append(&voice_grouping_menu)?;
+        voices_menu.append(&MenuItem::with_id(
+            menu_ids::add_voice(),
+            "Add Voice from Audio...",
             true,
-            monitor_enabled,
             None::<Accelerator>,
-        );
-
-        // Stop button
-        let stop_item = MenuItem::with_id(menu_ids::stop(), "Stop", true, None::<Accelerator>);
+        ))?;
 
-        // Voices submenu
-        let voices_menu = Submenu::new("Voices", true);
-        let mut voice_items = Vec::new();
-        for &name in VOICES {
-            let checked = name == current_voice;
+        // Models submenu
+        let models_menu = Submenu::new("Models", true);
+        let mut model_items = Vec::new();
+        for model in installed_models() {
             let item = CheckMenuItem::with_id(
-                menu_ids::voice(name),
-                name,
+                menu_ids::model(&model.name),
+                &model.name,
                 true,
-                checked,
+                model.name == settings.speech.current_model,
                 None::<Accelerator>,
             );
-            voices_menu.append(&item)?;
-            voice_items.push(item);
+            models_menu.append(&item)?;
+            model_items.push(item);
         }
 
+        // Skim toggle
+        let skim_item = CheckMenuItem::with_id(
+            menu_ids::skim(),
+            "Skim Long Texts",
+            true,
+            settings.speech.skim_long_texts,
+            None::<Accelerator>,
+        );
+        let read_in_full_item = MenuItem::with_id(
+            menu_ids::read_in_full(),
+            "Read Last Text in Full",
+            true,
+            None::<Accelerator>,
+        );
+
+        // Start with Windows toggle
+        let start_with_windows_item = CheckMenuItem::with_id(
+            menu_ids::start_with_windows(),
+            "Start with Windows",
+            true,
+            settings.ui.start_with_windows,
+            None::<Accelerator>,
+        );
+
+        // Update check
+        let check_for_updates_item = MenuItem::with_id(
+            menu_ids::check_for_updates(),
+            "Check for Updates",
+            true,
+            None::<Accelerator>,
+        );
+
+        // Settings window
+        let settings_item = MenuItem::with_id(menu_ids::settings(), "Settings...", true, None::<Accelerator>);
+
         // Quit
-        let quit_item = MenuItem::with_id(menu_ids::quit(), "Quit", true, None::<Accelerator>);
+        let quit_item = MenuItem::with_id(
+            menu_ids::quit(),
+            "&Quit",
+            true,
+            menu_accelerator(settings.hotkeys.quit_hotkey.as_deref()),
+        );
 
         // Assemble menu
         menu.append(&monitor_item)?;
+        menu.append(&pause_monitor_menu)?;
+        menu.append(&interrupt_item)?;
+        menu.append(&read_images_item)?;
+        menu.append(&ask_first_item)?;
+        menu.append(&collect_menu)?;
+        menu.append(&duck_item)?;
+        menu.append(&source_apps_menu)?;
+        menu.append(&chime_menu)?;
         menu.append(&stop_item)?;
+        menu.append(&pause_resume_item)?;
+        menu.append(&previous_sentence_item)?;
+        menu.append(&next_sentence_item)?;
+        menu.append(&show_player_item)?;
+        menu.append(&drop_window_item)?;
+        menu.append(&queue_menu)?;
+        menu.append(&clear_queue_item)?;
+        menu.append(&PredefinedMenuItem::separator())?;
+        menu.append(&open_file_item)?;
+        menu.append(&follow_log_item)?;
+        menu.append(&reading_menu)?;
+        menu.append(&save_last_item)?;
+        menu.append(&format_menu)?;
+        menu.append(&archive_item)?;
         menu.append(&PredefinedMenuItem::separator())?;
         menu.append(&voices_menu)?;
+        menu.append(&pitch_menu)?;
+        menu.append(&equalizer_menu)?;
+        menu.append(&soft_voice_menu)?;
+        menu.append(&models_menu)?;
+        menu.append(&device_menu)?;
+        menu.append(&precision_menu)?;
+        menu.append(&unload_menu)?;
+        menu.append(&clear_cache_item)?;
+        menu.append(&latency_menu)?;
+        menu.append(&output_menu)?;
+        menu.append(&links_menu)?;
+        menu.append(&emoji_menu)?;
+        menu.append(&code_menu)?;
+        menu.append(&lists_menu)?;
+        menu.append(&json_menu)?;
+        menu.append(&skim_item)?;
+        menu.append(&read_in_full_item)?;
+        menu.append(&icon_color_menu)?;
         menu.append(&PredefinedMenuItem::separator())?;
+        menu.append(&start_with_windows_item)?;
+        menu.append(&check_for_updates_item)?;
+        menu.append(&settings_item)?;
         menu.append(&quit_item)?;
 
         // Create tray icon
@@ -98,14 +1131,91 @@ impl TrayManager {
             .with_menu(Box::new(menu))
             .with_tooltip("Pocket-Tray TTS")
             .with_icon(icon)
+            .with_menu_on_left_click(settings.ui.click_action == ClickAction::Menu)
             .build()?;
 
-        Ok(Self {
+        let mut tray = Self {
             tray_icon,
             monitor_item,
-            voice_items,
+            interrupt_item,
+            read_images_item,
+            ask_first_item,
+            collect_item,
+            speak_collected_item,
+            clear_collected_item,
+            stop_item,
+            pause_resume_item,
+            show_player_item,
+            quit_item,
+            duck_item,
+            skim_item,
+            app_filter_items,
+            source_apps_menu,
+            source_app_items: Vec::new(),
+            archive_item,
+            start_with_windows_item,
+            follow_log_item,
+            drop_window_item,
+            queue_menu,
+            queue_items: Vec::new(),
+            reading_menu,
+            reading_items: Vec::new(),
+            voices_menu,
+            preview_menu,
+            voices: Vec::new(),
+            selected_voice: settings.speech.current_voice.clone(),
+            voice_grouping: settings.ui.voice_grouping,
+            voice_grouping_items,
+            voice_groups: Vec::new(),
+            voice_items: Vec::new(),
+            preview_items: Vec::new(),
+            replay_with_menu,
+            replay_with_items: Vec::new(),
+            model_items,
+            unload_items,
+            pitch_items,
+            device_items,
+            active_device_item,
+            precision_items,
+            format_items,
+            chime_items,
+            latency_items,
+            output_items,
+            icon_color_items,
+            equalizer_items,
+            soft_voice_items,
+            url_mode_items,
+            emoji_mode_items,
+            code_mode_items,
+            list_mode_items,
+            json_mode_items,
             animator,
-        })
+            tooltip: RefCell::new("Pocket-Tray TTS".to_string()),
+            flash_until: Cell::new(None),
+            icon_flash_until: None,
+            icon_flash_toggled: (Instant::now(), false),
+        };
+
+        // Voices of the selected model, plus the user's own (Piper voices can't be cloned)
+        let model = find_model(&settings.speech.current_model);
+        let custom = match &model {
+            Some(model) if model.is_piper() => Vec::new(),
+            _ => custom_voices(),
+        };
+        let voice_names: Vec<String> = model
+            .map(|model| model.voices())
+            .unwrap_or_default()
+            .into_iter()
+            .chain(custom)
+            .map(|(name, _)| name)
+            .collect();
+        let voices = describe_voices(&voice_names, &settings.speech.current_model);
+        tray.set_voices(
+            &settings.menu_voices(voices, &settings.speech.current_voice),
+            &settings.speech.current_voice,
+        )?;
+
+        Ok(tray)
     }
 
     /// Update the monitor checkbox state
@@ -113,24 +1223,436 @@ impl TrayManager {
         self.monitor_item.set_checked(checked);
     }
 
+    /// Update the interrupt-on-copy checkbox state
+    pub fn set_interrupt_checked(&self, checked: bool) {
+        self.interrupt_item.set_checked(checked);
+    }
+
+    /// Update the read-images checkbox state
+    pub fn set_read_images_checked(&self, checked: bool) {
+        self.read_images_item.set_checked(checked);
+    }
+
+    /// Update the ask-before-speaking checkbox state
+    pub fn set_ask_first_checked(&self, checked: bool) {
+        self.ask_first_item.set_checked(checked);
+    }
+
+    /// Update the collect-copies checkbox state
+    pub fn set_collect_checked(&self, checked: bool) {
+        self.collect_item.set_checked(checked);
+    }
+
+    /// Show how many copies are collected, with nothing to speak or clear if none
+    pub fn set_collected_count(&self, count: usize) {
+        self.speak_collected_item.set_text(collected_label(count));
+        self.speak_collected_item.set_enabled(count > 0);
+        self.clear_collected_item.set_enabled(count > 0);
+    }
+
+    /// Offer "Resume" while speech is paused, "Pause" otherwise
+    pub fn set_paused(&self, paused: bool) {
+        self.pause_resume_item.set_text(if paused { "Resume" } else { "Pause" });
+    }
+
+    /// Update the archive checkbox state
+    pub fn set_archive_checked(&self, checked: bool) {
+        self.archive_item.set_checked(checked);
+    }
+
+    /// Update the start-with-Windows checkbox state
+    pub fn set_start_with_windows_checked(&self, checked: bool) {
+        self.start_with_windows_item.set_checked(checked);
+    }
+
+    /// Replace the queue submenu's entries; it's disabled while nothing is waiting
+    pub fn set_queue(&mut self, texts: &[QueuedText]) -> Result<()> {
+        for item in self.queue_items.drain(..) {
+            self.queue_menu.remove(&item)?;
+        }
+        for (index, text) in texts.iter().enumerate() {
+            let item_menu = Submenu::new(&text.label, true);
+            item_menu.append(&MenuItem::with_id(
+                menu_ids::queued_front(text.id),
+                "Move to Front",
+                index > 0,
+                None::<Accelerator>,
+            ))?;
+            item_menu.append(&MenuItem::with_id(
+                menu_ids::queued_remove(text.id),
+                "Remove",
+                true,
+                None::<Accelerator>,
+            ))?;
+            self.queue_menu.append(&item_menu)?;
+            self.queue_items.push(item_menu);
+        }
+        self.queue_menu.set_enabled(!texts.is_empty());
+        Ok(())
+    }
+
+    /// Replace the reading list submenu's entries; it's disabled while empty
+    pub fn set_reading_list(&mut self, items: &[ReadingItem]) -> Result<()> {
+        for item in self.reading_items.drain(..) {
+            self.reading_menu.remove(&item)?;
+        }
+        for item in items {
+            let item_menu = Submenu::new(item.label(), true);
+            item_menu.append(&MenuItem::with_id(
+                menu_ids::reading_start(item.id),
+                "Read from Start",
+                true,
+                None::<Accelerator>,
+            ))?;
+            item_menu.append(&MenuItem::with_id(
+                menu_ids::reading_resume(item.id),
+                "Resume",
+                item.part > 0,
+                None::<Accelerator>,
+            ))?;
+            item_menu.append(&MenuItem::with_id(
+                menu_ids::reading_delete(item.id),
+                "Delete",
+                true,
+                None::<Accelerator>,
+            ))?;
+            self.reading_menu.append(&item_menu)?;
+            self.reading_items.push(item_menu);
+        }
+        self.reading_menu.set_enabled(!items.is_empty());
+        Ok(())
+    }
+
+    /// Update which app filter mode is selected
+    pub fn set_app_filter_checked(&self, selected: AppFilterMode) {
+        for (mode, item) in &self.app_filter_items {
+            item.set_checked(*mode == selected);
+        }
+    }
+
+    /// Replace the apps in the source apps submenu, checking those on the filter's
+    /// current list
+    pub fn set_source_apps(&mut self, apps: &[String], filter: &AppFilter) -> Result<()> {
+        for item in self.source_app_items.drain(..) {
+            self.source_apps_menu.remove(&item)?;
+        }
+        for app in apps {
+            let item = CheckMenuItem::with_id(
+                menu_ids::source_app(app),
+                app,
+                true,
+                filter.is_listed(app),
+                None::<Accelerator>,
+            );
+            self.source_apps_menu.append(&item)?;
+            self.source_app_items.push(item);
+        }
+        Ok(())
+    }
+
+    /// Update the follow-log checkbox state
+    pub fn set_follow_log_checked(&self, checked: bool) {
+        self.follow_log_item.set_checked(checked);
+    }
+
+    /// Show the hotkeys next to the menu items they stand in for
+    pub fn set_hotkeys(&self, hotkeys: &HotkeySettings) {
+        let shown = [
+            self.monitor_item.set_accelerator(menu_accelerator(hotkeys.monitor_hotkey.as_deref())),
+            self.stop_item.set_accelerator(menu_accelerator(hotkeys.stop_hotkey.as_deref())),
+            self.show_player_item.set_accelerator(menu_accelerator(hotkeys.player_hotkey.as_deref())),
+            self.quit_item.set_accelerator(menu_accelerator(hotkeys.quit_hotkey.as_deref())),
+        ];
+        for result in shown {
+            if let Err(e) = result {
+                log::warn!("Failed to show a hotkey in the menu: {}", e);
+            }
+        }
+    }
+
+    /// Show the menu on a left click, or leave that click to `action`
+    pub fn set_click_action(&self, action: ClickAction) {
+        self.tray_icon.set_show_menu_on_left_click(action == ClickAction::Menu);
+    }
+
+    /// Update the drop target checkbox state
+    pub fn set_drop_window_checked(&self, checked: bool) {
+        self.drop_window_item.set_checked(checked);
+    }
+
+    /// Update the skim checkbox state
+    pub fn set_skim_checked(&self, checked: bool) {
+        self.skim_item.set_checked(checked);
+    }
+
+    /// Update the ducking checkbox state
+    pub fn set_duck_checked(&self, checked: bool) {
+        self.duck_item.set_checked(checked);
+    }
+
     /// Update which voice is selected
-    pub fn set_voice_checked(&self, voice_name: &str) {
+    pub fn set_voice_checked(&mut self, voice_name: &str) {
+        self.selected_voice = voice_name.to_string();
         for item in &self.voice_items {
-            let is_selected = item.text() == voice_name;
-            item.set_checked(is_selected);
+            item.set_checked(item.id() == &menu_ids::voice(voice_name));
+        }
+    }
+
+    /// Add a voice to the voices submenu, above "Add Voice from Audio..."
+    pub fn add_voice(&mut self, voice: VoiceInfo) -> Result<()> {
+        self.voices.push(voice);
+        self.rebuild_voices()
+    }
+
+    /// Replace the voices in the voices submenu (after loading another model)
+    pub fn set_voices(&mut self, voices: &[VoiceInfo], selected: &str) -> Result<()> {
+        self.voices = voices.to_vec();
+        self.selected_voice = selected.to_string();
+        self.rebuild_voices()
+    }
+
+    /// Group the voices submenu by another detail
+    pub fn set_voice_grouping(&mut self, grouping: VoiceGrouping) -> Result<()> {
+        for (option, item) in &self.voice_grouping_items {
+            item.set_checked(*option == grouping);
+        }
+        self.voice_grouping = grouping;
+        self.rebuild_voices()
+    }
+
+    /// Fill the voices submenu, grouped or flat, and the Preview and Speak Last With
+    /// submenus from `self.voices`
+    fn rebuild_voices(&mut self) -> Result<()> {
+        if self.voice_groups.is_empty() {
+            for item in &self.voice_items {
+                self.voices_menu.remove(item)?;
+            }
+        }
+        for group in self.voice_groups.drain(..) {
+            self.voices_menu.remove(&group)?;
+        }
+        self.voice_items.clear();
+        for item in self.preview_items.drain(..) {
+            self.preview_menu.remove(&item)?;
+        }
+        for item in self.replay_with_items.drain(..) {
+            self.replay_with_menu.remove(&item)?;
+        }
+
+        let voice_item = |voice: &VoiceInfo| {
+            CheckMenuItem::with_id(
+                menu_ids::voice(&voice.name),
+                voice.label(),
+                true,
+                voice.name == self.selected_voice,
+                None::<Accelerator>,
+            )
+        };
+        let mut voice_items = Vec::new();
+        match group_voices(&self.voices, self.voice_grouping) {
+            Some(groups) => {
+                for (position, (name, voices)) in groups.into_iter().enumerate() {
+                    let group = Submenu::new(&name, true);
+                    for voice in voices {
+                        let item = voice_item(voice);
+                        group.append(&item)?;
+                        voice_items.push(item);
+                    }
+                    self.voices_menu.insert(&group, position)?;
+                    self.voice_groups.push(group);
+                }
+            }
+            None => {
+                for (position, voice) in self.voices.iter().enumerate() {
+                    let item = voice_item(voice);
+                    self.voices_menu.insert(&item, position)?;
+                    voice_items.push(item);
+                }
+            }
+        }
+        self.voice_items = voice_items;
+
+        for voice in &self.voices {
+            let preview_item = MenuItem::with_id(
+                menu_ids::preview_voice(&voice.name),
+                voice.preview_label(),
+                true,
+                None::<Accelerator>,
+            );
+            self.preview_menu.append(&preview_item)?;
+            self.preview_items.push(preview_item);
+
+            let replay_with_item = MenuItem::with_id(
+                menu_ids::replay_with_voice(&voice.name),
+                voice.label(),
+                true,
+                None::<Accelerator>,
+            );
+            self.replay_with_menu.append(&replay_with_item)?;
+            self.replay_with_items.push(replay_with_item);
+        }
+        self.preview_menu.set_enabled(!self.voices.is_empty());
+        self.replay_with_menu.set_enabled(!self.voices.is_empty());
+        Ok(())
+    }
+
+    /// Update which model is selected
+    pub fn set_model_checked(&self, model_name: &str) {
+        for item in &self.model_items {
+            item.set_checked(item.text() == model_name);
+        }
+    }
+
+    /// Update which level is selected for an equalizer band
+    pub fn set_equalizer_checked(&self, selected_band: EqBand, selected_db: i32) {
+        for (band, db, item) in &self.equalizer_items {
+            if *band == selected_band {
+                item.set_checked(*db == selected_db);
+            }
+        }
+    }
+
+    /// Update which soft voice option is selected
+    pub fn set_soft_voice_checked(&self, selected: SoftMode) {
+        for (mode, item) in &self.soft_voice_items {
+            item.set_checked(*mode == selected);
+        }
+    }
+
+    /// Update which audio latency is selected
+    pub fn set_audio_latency_checked(&self, selected: AudioLatency) {
+        for (latency, item) in &self.latency_items {
+            item.set_checked(*latency == selected);
+        }
+    }
+
+    /// Update which output preset is selected (None for the system default)
+    pub fn set_output_preset_checked(&self, selected: Option<&str>) {
+        for (preset, item) in &self.output_items {
+            item.set_checked(preset.as_deref() == selected);
+        }
+    }
+
+    /// Update which icon color is selected (None for matching the taskbar)
+    pub fn set_icon_color_checked(&self, selected: Option<&str>) {
+        for (color, item) in &self.icon_color_items {
+            item.set_checked(same_color(color.as_deref(), selected));
+        }
+    }
+
+    /// Update which device is selected
+    pub fn set_device_checked(&self, selected: ComputeDevice) {
+        for (device, item) in &self.device_items {
+            item.set_checked(*device == selected);
+        }
+    }
+
+    /// Show which device the loaded model runs on (None when no model is loaded)
+    pub fn set_active_device(&self, device: Option<ComputeDevice>) {
+        let text = match device {
+            Some(device) => format!("In use: {}", device.label()),
+            None => "In use: (model not loaded)".to_string(),
+        };
+        self.active_device_item.set_text(text);
+    }
+
+    /// Update which precision is selected
+    pub fn set_precision_checked(&self, selected: Precision) {
+        for (precision, item) in &self.precision_items {
+            item.set_checked(*precision == selected);
+        }
+    }
+
+    /// Update which idle unload time is selected
+    pub fn set_unload_after_checked(&self, selected: u32) {
+        for (minutes, item) in &self.unload_items {
+            item.set_checked(*minutes == selected);
+        }
+    }
+
+    /// Update which pitch shift is selected
+    pub fn set_pitch_checked(&self, selected: i32) {
+        for (semitones, item) in &self.pitch_items {
+            item.set_checked(*semitones == selected);
+        }
+    }
+
+    /// Update which chime option is selected
+    pub fn set_chime_checked(&self, selected: ChimeMode) {
+        for (mode, item) in &self.chime_items {
+            item.set_checked(*mode == selected);
+        }
+    }
+
+    /// Update which export format is selected
+    pub fn set_export_format_checked(&self, selected: ExportFormat) {
+        for (format, item) in &self.format_items {
+            item.set_checked(*format == selected);
+        }
+    }
+
+    /// Update which link handling mode is selected
+    pub fn set_url_mode_checked(&self, selected: UrlMode) {
+        for (mode, item) in &self.url_mode_items {
+            item.set_checked(*mode == selected);
+        }
+    }
+
+    /// Update which emoji handling mode is selected
+    pub fn set_emoji_mode_checked(&self, selected: EmojiMode) {
+        for (mode, item) in &self.emoji_mode_items {
+            item.set_checked(*mode == selected);
+        }
+    }
+
+    /// Update which code handling mode is selected
+    pub fn set_code_mode_checked(&self, selected: CodeMode) {
+        for (mode, item) in &self.code_mode_items {
+            item.set_checked(*mode == selected);
+        }
+    }
+
+    /// Update which list pacing mode is selected
+    pub fn set_list_mode_checked(&self, selected: ListMode) {
+        for (mode, item) in &self.list_mode_items {
+            item.set_checked(*mode == selected);
+        }
+    }
+
+    /// Update which JSON handling mode is selected
+    pub fn set_json_mode_checked(&self, selected: JsonMode) {
+        for (mode, item) in &self.json_mode_items {
+            item.set_checked(*mode == selected);
         }
     }
 
     /// Update the tooltip
     pub fn set_tooltip(&self, tooltip: &str) {
+        *self.tooltip.borrow_mut() = tooltip.to_string();
+        if self.flash_until.get().is_none() {
+            let _ = self.tray_icon.set_tooltip(Some(tooltip));
+        }
+    }
+
+    /// Show `tooltip` for a moment instead of the usual one
+    pub fn flash_tooltip(&self, tooltip: &str, duration: Duration) {
+        self.flash_until.set(Some(Instant::now() + duration));
         let _ = self.tray_icon.set_tooltip(Some(tooltip));
     }
 
+    /// Put the usual tooltip back once a flashed one is over
+    pub fn end_tooltip_flash(&self) {
+        if self.flash_until.get().is_some_and(|until| Instant::now() >= until) {
+            self.flash_until.set(None);
+            let _ = self.tray_icon.set_tooltip(Some(self.tooltip.borrow().as_str()));
+        }
+    }
+
     /// Start the icon animation (call when speaking starts)
     pub fn start_animation(&mut self) {
         self.animator.start_animation();
-        // Set the first animation frame
-        if let Some(frame) = self.animator.next_frame() {
+        // Set the first animation frame, nothing is playing yet
+        if let Some(frame) = self.animator.next_frame(0.0) {
             let _ = self.tray_icon.set_icon(Some(frame.clone()));
         }
     }
@@ -142,14 +1664,39 @@ impl TrayManager {
         let _ = self.tray_icon.set_icon(Some(self.animator.static_icon().clone()));
     }
 
+    /// Show the loading animation (call when the model starts loading)
+    pub fn start_loading(&mut self) {
+        self.animator.start_loading();
+        if let Some(frame) = self.animator.next_frame(0.0) {
+            let _ = self.tray_icon.set_icon(Some(frame.clone()));
+        }
+    }
+
+    /// Stop the loading animation (call when the model is loaded or failed to load)
+    pub fn stop_loading(&mut self) {
+        self.animator.stop_loading();
+        let _ = self.tray_icon.set_icon(Some(self.animator.current_icon().clone()));
+    }
+
+    /// Freeze the icon animation on its current frame (call when speech is paused)
+    pub fn pause_animation(&mut self) {
+        self.animator.pause_animation();
+    }
+
+    /// Continue a frozen icon animation (call when speech is resumed)
+    pub fn resume_animation(&mut self) {
+        self.animator.resume_animation();
+    }
+
     /// Advance to the next animation frame (call every ~120ms when animating)
+    /// `level` is the RMS level of the audio playing
     /// Returns true if animation is active, false otherwise
-    pub fn tick_animation(&mut self) -> bool {
+    pub fn tick_animation(&mut self, level: f32) -> bool {
         if !self.animator.is_animating() {
             return false;
         }
 
-        if let Some(frame) = self.animator.next_frame() {
+        if let Some(frame) = self.animator.next_frame(level) {
             let _ = self.tray_icon.set_icon(Some(frame.clone()));
             true
         } else {
@@ -161,30 +1708,325 @@ impl TrayManager {
     pub fn is_animating(&self) -> bool {
         self.animator.is_animating()
     }
+
+    /// Grey out the icon while copies aren't being spoken
+    pub fn set_monitoring(&mut self, monitoring: bool) -> Result<()> {
+        if monitoring != self.animator.is_dimmed() {
+            return Ok(());
+        }
+        self.animator.set_dimmed(!monitoring)?;
+        let _ = self.tray_icon.set_icon(Some(self.animator.current_icon().clone()));
+        Ok(())
+    }
+
+    /// Draw the icon in red or amber while something is wrong, or clear that with None
+    pub fn set_alert(&mut self, alert: Option<IconAlert>) -> Result<()> {
+        if alert == self.animator.alert() {
+            return Ok(());
+        }
+        self.animator.set_alert(alert)?;
+        let _ = self.tray_icon.set_icon(Some(self.animator.current_icon().clone()));
+        Ok(())
+    }
+
+    /// What the icon warns about, if anything
+    pub fn alert(&self) -> Option<IconAlert> {
+        self.animator.alert()
+    }
+
+    /// Flash the icon on and off for `duration`, so an alert is noticed
+    pub fn flash_icon(&mut self, duration: Duration) {
+        self.icon_flash_until = Some(Instant::now() + duration);
+        self.icon_flash_toggled = (Instant::now(), false);
+    }
+
+    /// Turn a flashing icon on or off when it's time
+    /// Flashing stops early once speech animates the icon again
+    pub fn tick_icon_flash(&mut self) {
+        let Some(until) = self.icon_flash_until else {
+            return;
+        };
+        let now = Instant::now();
+        if now >= until || self.animator.is_animating() {
+            self.icon_flash_until = None;
+            if self.icon_flash_toggled.1 {
+                let _ = self.tray_icon.set_icon(Some(self.animator.current_icon().clone()));
+            }
+            return;
+        }
+
+        let (toggled, blank) = self.icon_flash_toggled;
+        if now.duration_since(toggled) < FLASH_INTERVAL {
+            return;
+        }
+        let icon = if blank {
+            Ok(self.animator.current_icon().clone())
+        } else {
+            self.animator.blank_icon()
+        };
+        match icon {
+            Ok(icon) => {
+                let _ = self.tray_icon.set_icon(Some(icon));
+                self.icon_flash_toggled = (now, !blank);
+            }
+            Err(e) => {
+                log::warn!("Failed to flash the tray icon: {}", e);
+                self.icon_flash_until = None;
+            }
+        }
+    }
+
+    /// Draw the icon in another color, e.g. after the taskbar theme changed
+    pub fn set_icon_color(&mut self, color: Rgba<u8>) -> Result<()> {
+        if color == self.animator.color() {
+            return Ok(());
+        }
+        self.animator.set_color(color)?;
+        let _ = self.tray_icon.set_icon(Some(self.animator.current_icon().clone()));
+        Ok(())
+    }
+
+    /// Draw the icon at the size suiting a display scaling of `dpi`, so it isn't
+    /// blurred by Windows scaling a smaller one up
+    pub fn set_icon_dpi(&mut self, dpi: u32) -> Result<()> {
+        let size = icon_size_for_dpi(dpi);
+        if size == self.animator.size() {
+            return Ok(());
+        }
+        self.animator.set_size(size)?;
+        let _ = self.tray_icon.set_icon(Some(self.animator.current_icon().clone()));
+        Ok(())
+    }
+}
+
+/// Longest time between the clicks of a double-click
+#[cfg(windows)]
+pub fn double_click_time() -> Duration {
+    use windows::Win32::UI::Input::KeyboardAndMouse::GetDoubleClickTime;
+
+    Duration::from_millis(unsafe { GetDoubleClickTime() } as u64)
+}
+
+#[cfg(not(windows))]
+pub fn double_click_time() -> Duration {
+    Duration::from_millis(500)
 }
 
 /// Menu event handler results
 pub enum MenuAction {
     ToggleMonitor,
+    PauseMonitoring(u32),
+    ToggleInterrupt,
+    ToggleReadImages,
+    ToggleAskFirst,
+    ToggleCollect,
+    SpeakCollected,
+    ClearCollected,
+    ToggleDucking,
+    ToggleSkim,
+    /// Speak the last copied text again without skimming it
+    ReadLastInFull,
+    ChangeAppFilter(AppFilterMode),
+    ToggleSourceApp(String),
     Stop,
+    TogglePause,
+    NextSentence,
+    PreviousSentence,
+    ClearQueue,
+    MoveQueuedToFront(u64),
+    RemoveQueued(u64),
+    OpenFile,
+    SaveLast,
+    ToggleArchive,
+    ToggleFollowLog,
+    ReadFromStart(u64),
+    ResumeReading(u64),
+    DeleteReading(u64),
+    ClearCache,
+    ChangeExportFormat(ExportFormat),
+    ChangeChime(ChimeMode),
+    ChangeAudioLatency(AudioLatency),
+    ChangeOutputPreset(Option<String>),
+    ChangeIconColor(Option<String>),
+    ChangeUnloadAfter(u32),
+    ChangePitch(i32),
+    ChangeEqualizer(EqBand, i32),
+    ChangeSoftVoice(SoftMode),
+    ChangeDevice(ComputeDevice),
+    ChangePrecision(Precision),
+    ChangeUrlMode(UrlMode),
+    ChangeEmojiMode(EmojiMode),
+    ChangeCodeMode(CodeMode),
+    ChangeListMode(ListMode),
+    ChangeJsonMode(JsonMode),
     ChangeVoice(String),
+    ChangeModel(String),
+    PreviewVoice(String),
+    ReplayLastWith(String),
+    AddVoice,
+    ToggleStartWithWindows,
+    ChangeVoiceGrouping(VoiceGrouping),
+    CheckForUpdates,
+    OpenSettings,
+    ShowPlayer,
+    ToggleDropWindow,
     Quit,
     Unknown,
 }
 
+/// Label of the item speaking the collected copies
+fn collected_label(count: usize) -> String {
+    match count {
+        1 => "Speak 1 Collected Copy".to_string(),
+        n => format!("Speak {} Collected Copies", n),
+    }
+}
+
 /// Process a menu event and return the corresponding action
 pub fn process_menu_event(event: &MenuEvent) -> MenuAction {
     let id = event.id();
 
     if id == &menu_ids::monitor() {
         MenuAction::ToggleMonitor
+    } else if id == &menu_ids::interrupt() {
+        MenuAction::ToggleInterrupt
+    } else if id == &menu_ids::read_images() {
+        MenuAction::ToggleReadImages
+    } else if id == &menu_ids::ask_first() {
+        MenuAction::ToggleAskFirst
+    } else if id == &menu_ids::collect() {
+        MenuAction::ToggleCollect
+    } else if id == &menu_ids::speak_collected() {
+        MenuAction::SpeakCollected
+    } else if id == &menu_ids::clear_collected() {
+        MenuAction::ClearCollected
+    } else if id == &menu_ids::duck() {
+        MenuAction::ToggleDucking
+    } else if id == &menu_ids::skim() {
+        MenuAction::ToggleSkim
+    } else if id == &menu_ids::read_in_full() {
+        MenuAction::ReadLastInFull
+    } else if id == &menu_ids::show_player() {
+        MenuAction::ShowPlayer
+    } else if id == &menu_ids::drop_window() {
+        MenuAction::ToggleDropWindow
     } else if id == &menu_ids::stop() {
         MenuAction::Stop
+    } else if id == &menu_ids::pause_resume() {
+        MenuAction::TogglePause
+    } else if id == &menu_ids::next_sentence() {
+        MenuAction::NextSentence
+    } else if id == &menu_ids::previous_sentence() {
+        MenuAction::PreviousSentence
+    } else if id == &menu_ids::clear_queue() {
+        MenuAction::ClearQueue
+    } else if id == &menu_ids::open_file() {
+        MenuAction::OpenFile
+    } else if id == &menu_ids::save_last() {
+        MenuAction::SaveLast
+    } else if id == &menu_ids::add_voice() {
+        MenuAction::AddVoice
+    } else if id == &menu_ids::archive() {
+        MenuAction::ToggleArchive
+    } else if id == &menu_ids::follow_log() {
+        MenuAction::ToggleFollowLog
+    } else if id == &menu_ids::clear_cache() {
+        MenuAction::ClearCache
+    } else if id == &menu_ids::start_with_windows() {
+        MenuAction::ToggleStartWithWindows
+    } else if id == &menu_ids::check_for_updates() {
+        MenuAction::CheckForUpdates
+    } else if id == &menu_ids::settings() {
+        MenuAction::OpenSettings
     } else if id == &menu_ids::quit() {
         MenuAction::Quit
+    } else if let Some(minutes) = menu_ids::pause_monitor_from_id(id) {
+        MenuAction::PauseMonitoring(minutes)
+    } else if let Some(mode) = menu_ids::app_filter_from_id(id) {
+        MenuAction::ChangeAppFilter(mode)
+    } else if let Some(app) = menu_ids::source_app_from_id(id) {
+        MenuAction::ToggleSourceApp(app)
+    } else if let Some(mode) = menu_ids::chime_from_id(id) {
+        MenuAction::ChangeChime(mode)
+    } else if let Some(text) = menu_ids::queued_front_from_id(id) {
+        MenuAction::MoveQueuedToFront(text)
+    } else if let Some(text) = menu_ids::queued_remove_from_id(id) {
+        MenuAction::RemoveQueued(text)
+    } else if let Some(item) = menu_ids::reading_start_from_id(id) {
+        MenuAction::ReadFromStart(item)
+    } else if let Some(item) = menu_ids::reading_resume_from_id(id) {
+        MenuAction::ResumeReading(item)
+    } else if let Some(item) = menu_ids::reading_delete_from_id(id) {
+        MenuAction::DeleteReading(item)
+    } else if let Some(format) = menu_ids::export_format_from_id(id) {
+        MenuAction::ChangeExportFormat(format)
+    } else if let Some(latency) = menu_ids::audio_latency_from_id(id) {
+        MenuAction::ChangeAudioLatency(latency)
+    } else if let Some(preset) = menu_ids::output_preset_from_id(id) {
+        MenuAction::ChangeOutputPreset(preset)
+    } else if let Some(color) = menu_ids::icon_color_from_id(id) {
+        MenuAction::ChangeIconColor(color)
+    } else if let Some(device) = menu_ids::device_from_id(id) {
+        MenuAction::ChangeDevice(device)
+    } else if let Some(precision) = menu_ids::precision_from_id(id) {
+        MenuAction::ChangePrecision(precision)
+    } else if let Some(minutes) = menu_ids::unload_after_from_id(id) {
+        MenuAction::ChangeUnloadAfter(minutes)
+    } else if let Some((band, db)) = menu_ids::equalizer_from_id(id) {
+        MenuAction::ChangeEqualizer(band, db)
+    } else if let Some(mode) = menu_ids::soft_voice_from_id(id) {
+        MenuAction::ChangeSoftVoice(mode)
+    } else if let Some(semitones) = menu_ids::pitch_from_id(id) {
+        MenuAction::ChangePitch(semitones)
+    } else if let Some(mode) = menu_ids::url_mode_from_id(id) {
+        MenuAction::ChangeUrlMode(mode)
+    } else if let Some(mode) = menu_ids::emoji_mode_from_id(id) {
+        MenuAction::ChangeEmojiMode(mode)
+    } else if let Some(mode) = menu_ids::code_mode_from_id(id) {
+        MenuAction::ChangeCodeMode(mode)
+    } else if let Some(mode) = menu_ids::list_mode_from_id(id) {
+        MenuAction::ChangeListMode(mode)
+    } else if let Some(mode) = menu_ids::json_mode_from_id(id) {
+        MenuAction::ChangeJsonMode(mode)
+    } else if let Some(model) = menu_ids::model_name_from_id(id) {
+        MenuAction::ChangeModel(model)
+    } else if let Some(grouping) = menu_ids::voice_grouping_from_id(id) {
+        MenuAction::ChangeVoiceGrouping(grouping)
+    } else if let Some(voice) = menu_ids::preview_voice_name_from_id(id) {
+        MenuAction::PreviewVoice(voice)
+    } else if let Some(voice) = menu_ids::replay_with_voice_name_from_id(id) {
+        MenuAction::ReplayLastWith(voice)
     } else if let Some(voice) = menu_ids::voice_name_from_id(id) {
         MenuAction::ChangeVoice(voice)
     } else {
         MenuAction::Unknown
     }
 }
+
+/// A global hotkey as shown next to the menu item it stands in for
+/// The keys are handled by the hotkey thread; the menu only shows them
+fn menu_accelerator(hotkey: Option<&str>) -> Option<Accelerator> {
+    let hotkey = hotkey?;
+    // muda calls the Windows key Super
+    let text = hotkey
+        .split('+')
+        .map(|part| {
+            let part = part.trim();
+            if part.eq_ignore_ascii_case("win") {
+                "Super"
+            } else {
+                part
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("+");
+    text.parse()
+        .map_err(|e| log::warn!("Can't show hotkey {} in the menu: {}", hotkey, e))
+        .ok()
+}
+
+/// Whether two icon color settings draw the same color, whatever their case or "#"
+/// A color that doesn't parse matches the taskbar, as it does when drawing
+fn same_color(a: Option<&str>, b: Option<&str>) -> bool {
+    a.and_then(parse_color) == b.and_then(parse_color)
+}
@@ -1,149 +1,793 @@
 //! TTS Engine wrapper - handles model loading and audio generation
 
-use crate::settings::{embedded_config, models_dir, VOICES};
+use crate::archive::Archive;
+use crate::audio::{
+    resample, EqGains, Equalizer, Limiter, OutputConverter, OutputLevel, PitchShifter, SilenceTrimmer,
+    SoftEffect,
+};
+use crate::backend::{SampleStream, TtsBackend};
+use crate::cache::AudioCache;
+use crate::chime::Chimes;
+use crate::export::{write_audio, ExportFormat};
+use crate::onecore::OneCoreVoices;
+use crate::output::{AudioLatency, AudioOutput};
+use crate::piper::PiperBackend;
+use crate::pocket::PocketBackend;
+use crate::reading;
+use crate::sapi::SapiVoices;
+use crate::settings::{cache_dir, find_model, models_dir, ModelInfo, VoiceProsody};
+use crate::soft::{NightVolume, SoftSchedule};
+use crate::text::{self, JsonMode, Segment, TextOptions};
+use crate::voice_info::{describe_voice, voice_languages};
 use anyhow::Result;
-use pocket_tts::{ModelState, TTSModel};
-use rodio::{buffer::SamplesBuffer, OutputStream, OutputStreamHandle, Sink};
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use serde::{Deserialize, Serialize};
+use rodio::Sink;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, RecvError, RecvTimeoutError, Sender, TryRecvError};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often to check for commands while waiting on queued audio
+const PLAYBACK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Audio buffered before playback starts (or restarts after running dry), so
+/// uneven generation speed doesn't cause gaps mid-sentence
+const PREFETCH_TARGET: Duration = Duration::from_millis(1500);
+
+/// Buffered audio below which playback is held until the buffer refills
+const PREFETCH_LOW: Duration = Duration::from_millis(200);
+
+/// Most audio generated ahead of playback; beyond this generation waits
+const PREFETCH_LIMIT: Duration = Duration::from_secs(30);
+
+/// How often the main thread is told where playback is
+const PLAYING_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Speaking rate assumed until some of a text was generated, in characters per
+/// second at normal speed
+const DEFAULT_CHARS_PER_SECOND: f64 = 15.0;
+
+/// Gain applied to emphasized speech
+const EMPHASIS_GAIN: f32 = 1.2;
+
+/// Texts longer than this are split and queued as parts of about this length
+const LONG_TEXT_PART_CHARS: usize = 2000;
+
+/// Source of the IDs that tell queued texts apart
+static NEXT_UTTERANCE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Hardware the model runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComputeDevice {
+    #[default]
+    Cpu,
+    /// NVIDIA GPU (needs a build with the `cuda` feature)
+    Cuda,
+    /// Any DirectX 12 GPU, through onnxruntime for Piper voices (needs a build with
+    /// the `directml` feature); Pocket TTS models run on the CPU instead
+    DirectMl,
+}
+
+impl ComputeDevice {
+    /// All devices, in menu order
+    pub const ALL: [ComputeDevice; 3] = [ComputeDevice::Cpu, ComputeDevice::Cuda, ComputeDevice::DirectMl];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            ComputeDevice::Cpu => "cpu",
+            ComputeDevice::Cuda => "cuda",
+            ComputeDevice::DirectMl => "directml",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            ComputeDevice::Cpu => "CPU",
+            ComputeDevice::Cuda => "CUDA (NVIDIA GPU)",
+            ComputeDevice::DirectMl => "DirectML",
+        }
+    }
+
+    /// The candle device to load the model on
+    pub fn to_candle(self) -> Result<candle_core::Device> {
+        match self {
+            ComputeDevice::Cpu => Ok(candle_core::Device::Cpu),
+            ComputeDevice::Cuda => Ok(candle_core::Device::new_cuda(0)?),
+            ComputeDevice::DirectMl => anyhow::bail!("candle has no DirectML support, only Piper voices run on it"),
+        }
+    }
+}
+
+/// Numeric precision of the model weights
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Precision {
+    #[default]
+    Float32,
+    /// Half the memory, slightly lower quality; fastest on GPUs
+    Float16,
+    /// Half the memory with float32's range; better than float16 on most CPUs
+    BFloat16,
+    /// 8- or 4-bit GGUF weights, a fraction of the download and disk size, expanded
+    /// to bfloat16 when loaded
+    Quantized,
+}
+
+impl Precision {
+    /// All precisions, in menu order
+    pub const ALL: [Precision; 4] = [
+        Precision::Float32,
+        Precision::Float16,
+        Precision::BFloat16,
+        Precision::Quantized,
+    ];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            Precision::Float32 => "f32",
+            Precision::Float16 => "f16",
+            Precision::BFloat16 => "bf16",
+            Precision::Quantized => "gguf",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            Precision::Float32 => "Full (float32)",
+            Precision::Float16 => "Half (float16)",
+            Precision::BFloat16 => "Half (bfloat16)",
+            Precision::Quantized => "Quantized (GGUF)",
+        }
+    }
+
+    /// Model config dtype name
+    pub fn dtype(self) -> &'static str {
+        match self {
+            Precision::Float32 => "float32",
+            Precision::Float16 => "float16",
+            Precision::BFloat16 | Precision::Quantized => "bfloat16",
+        }
+    }
+
+    /// The candle dtype the model runs at
+    pub fn to_candle(self) -> candle_core::DType {
+        match self {
+            Precision::Float32 => candle_core::DType::F32,
+            Precision::Float16 => candle_core::DType::F16,
+            Precision::BFloat16 | Precision::Quantized => candle_core::DType::BF16,
+        }
+    }
+}
 
 /// Commands sent to the TTS thread
 #[derive(Debug)]
 pub enum TTSCommand {
-    Speak { text: String },
+    /// Queue a text, read in `voice` instead of the current one if it's set and the
+    /// engine has it
+    Speak { text: String, voice: Option<String> },
+    /// Stop whatever is playing and speak this text right away
+    SpeakNow { text: String, voice: Option<String> },
+    /// Stop whatever is playing and read a reading list text, from one of its parts
+    /// (counted from 0)
+    Read { id: u64, text: String, from_part: usize },
     Stop,
+    Pause,
+    Resume,
+    NextSentence,
+    PreviousSentence,
+    ClearQueue,
+    /// Drop the queued texts other than the rest of the one being spoken
+    ClearQueueAfterCurrent,
+    /// Drop a text waiting in the queue, with all its parts if it's a long one
+    RemoveQueued { id: u64 },
+    /// Speak a text waiting in the queue before the others
+    MoveQueuedToFront { id: u64 },
     ChangeVoice { voice: String },
+    /// Load another model and its voices
+    ChangeModel { model: String },
+    /// Speak a short sample with a voice without selecting it
+    PreviewVoice { voice: String },
+    /// Say the name of the voice just changed to, then start the current text over
+    /// with it
+    AnnounceVoice { voice: String },
+    /// Clone a new voice from prompt audio and switch to it
+    AddVoice { name: String, path: PathBuf },
+    SetTextOptions(TextOptions),
+    /// Speak only the headings and first sentence of each paragraph of long texts
+    /// queued from now on
+    SetSkim(bool),
+    /// Speak the last text queued again in full, right away, skimmed or not before
+    ReadLastInFull,
+    /// Unload the model after this long without commands (None keeps it loaded)
+    SetIdleUnload(Option<Duration>),
+    /// Run the model on another device, reloading it if loaded
+    SetDevice(ComputeDevice),
+    /// Load the weights at another precision, reloading the model if loaded
+    SetPrecision(Precision),
+    /// Limit the on-disk audio cache to this many megabytes (0 disables it)
+    SetCacheSize(u32),
+    /// Shift the pitch of every voice by this many semitones
+    SetPitch(i32),
+    /// Per-voice speed, gain and pause overrides, keyed by voice name
+    SetVoiceProsody(HashMap<String, VoiceProsody>),
+    /// Boost or cut bass, mid and treble of playback
+    SetEqualizer(EqGains),
+    /// When playback is softened
+    SetSoftVoice(SoftSchedule),
+    /// How much quieter speech plays during night hours
+    SetNightVolume(NightVolume),
+    /// Volume speech plays at, 0 to 1, heard at once
+    SetVolume(f32),
+    /// Voices that read text in other languages, keyed by ISO 639-1 code
+    SetLanguageVoices(HashMap<String, String>),
+    /// Reopen the output device with another buffer size
+    SetAudioLatency(AudioLatency),
+    /// Play on the named output device (None for the system default)
+    SetOutputDevice(Option<String>),
+    /// Archive every spoken text (None stops archiving)
+    SetArchive(Option<Archive>),
+    /// Chimes played before and after each text
+    SetChimes(Chimes),
+    /// Delete all cached audio
+    ClearCache,
+    /// Speak the last text again, or start the one being spoken over
+    ReplayLast,
+    /// Speak the last text again with another voice, or start the one being spoken
+    /// over with it
+    ReplayLastWith { voice: String },
+    /// Write the last spoken utterance to an audio file
+    SaveLast { path: PathBuf, format: ExportFormat },
     Shutdown,
 }
 
 /// Events sent from the TTS thread
 #[derive(Debug)]
 pub enum TTSEvent {
-    ModelLoaded,
+    /// Model weights are being loaded (on first use or after switching models)
+    ModelLoading { model: String },
+    /// The model was freed after sitting idle; it reloads on the next text
+    ModelUnloaded,
+    /// A model finished loading, with the voices it offers and the voice now in use
+    ModelLoaded {
+        model: String,
+        voices: Vec<String>,
+        voice: String,
+        /// Device the model actually runs on (CPU if the preferred one failed)
+        device: ComputeDevice,
+    },
+    /// The model couldn't be loaded, Windows voices are used instead
+    FallbackLoaded { voices: Vec<String>, voice: String },
     StartedSpeaking,
-    FinishedSpeaking,
+    /// Started on another part of a long text (counted from 1), which may be
+    /// a reading list text
+    Progress {
+        part: usize,
+        total: usize,
+        reading: Option<u64>,
+    },
+    /// Where playback is in the text being spoken: the paragraph (counted from 1)
+    /// of how many, and about how long is left of the text (or of its part), the
+    /// sentence playing and how far into the text it is (0 to 1)
+    Playing {
+        paragraph: usize,
+        paragraphs: usize,
+        remaining: Duration,
+        sentence: String,
+        progress: f32,
+    },
+    /// The last part of a reading list text was read to the end
+    ReadingFinished { id: u64 },
+    Paused,
+    Resumed,
+    /// Done with a text, or with a part of one when `more_parts` of it are next
+    FinishedSpeaking { more_parts: bool },
+    /// The texts waiting after the one being spoken changed
+    QueueChanged(Vec<QueuedText>),
+    /// A voice was cloned from prompt audio and is now selected
+    VoiceAdded(String),
     Error(String),
 }
 
+/// A text waiting in the queue, as shown in the tray
+#[derive(Debug, Clone)]
+pub struct QueuedText {
+    /// Unique while the engine runs, used in menu IDs
+    pub id: u64,
+    /// First words of the text, and which part it is of a long one
+    pub label: String,
+}
+
+/// A text waiting to be spoken
+#[derive(Clone)]
+pub struct Utterance {
+    /// Tells queued texts apart, the parts of a long text each have their own
+    id: u64,
+    text: String,
+    /// Voice to use instead of the current one
+    voice: Option<String>,
+    /// Voice of the app the text was copied from, used instead of the current one
+    /// if the engine has it
+    app_voice: Option<String>,
+    /// Which part of a long text this is, and of how many (counted from 1)
+    part: Option<(usize, usize)>,
+    /// ID shared by the parts of one long text, the first part's; a whole text's own
+    whole: u64,
+    /// Reading list text this is part of
+    reading: Option<u64>,
+}
+
+impl Utterance {
+    fn new(text: String) -> Self {
+        let id = NEXT_UTTERANCE_ID.fetch_add(1, Ordering::Relaxed);
+        Self {
+            id,
+            text,
+            voice: None,
+            app_voice: None,
+            part: None,
+            whole: id,
+            reading: None,
+        }
+    }
+
+    /// Number each of `parts` of one text (from 1), the first one's ID shared by all
+    fn numbered(parts: Vec<Self>) -> Vec<Self> {
+        let total = parts.len();
+        let whole = parts.first().map_or(0, |first| first.id);
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(index, part)| Self {
+                part: Some((index + 1, total)),
+                whole,
+                ..part
+            })
+            .collect()
+    }
+
+    /// A copied text, split into parts of about `LONG_TEXT_PART_CHARS` if it's longer,
+    /// and only skimmed if `skim` is set
+    fn parts(text: String, app_voice: Option<String>, json_mode: JsonMode, skim: bool) -> Vec<Self> {
+        let new = |text| Self {
+            app_voice: app_voice.clone(),
+            ..Self::new(text)
+        };
+        // Copied JSON is only recognized whole, before splitting breaks it apart
+        let text = text::rewrite_json(&text, json_mode);
+        let chars = text.chars().count();
+        if chars <= LONG_TEXT_PART_CHARS {
+            return vec![new(text)];
+        }
+        let text = if skim {
+            let skimmed = text::skim(&text);
+            let skimmed_chars = skimmed.chars().count();
+            log::info!("Skimming long text ({} of {} chars)", skimmed_chars, chars);
+            if skimmed_chars <= LONG_TEXT_PART_CHARS {
+                return vec![new(skimmed)];
+            }
+            skimmed
+        } else {
+            text
+        };
+        let parts = text::split_parts(&text, LONG_TEXT_PART_CHARS);
+        log::info!("Long text ({} chars) split into {} parts", text.chars().count(), parts.len());
+        Self::numbered(parts.into_iter().map(new).collect())
+    }
+
+    /// The parts of a reading list text from `from_part` (counted from 0) on
+    fn reading(id: u64, text: &str, from_part: usize) -> Vec<Self> {
+        let parts = text::split_parts(text, LONG_TEXT_PART_CHARS);
+        let total = parts.len();
+        let parts = Self::numbered(
+            parts
+                .into_iter()
+                .map(|text| Self {
+                    reading: Some(id),
+                    ..Self::new(text)
+                })
+                .collect(),
+        );
+        parts.into_iter().skip(from_part.min(total.saturating_sub(1))).collect()
+    }
+
+    /// A short sample spoken with `voice`: its own sample text, if it has one
+    fn preview(voice: String, sample_text: Option<String>) -> Self {
+        let text = sample_text
+            .unwrap_or_else(|| format!("Hi, I'm {}. This is how I sound reading your text.", voice));
+        Self {
+            voice: Some(voice),
+            ..Self::new(text)
+        }
+    }
+
+    /// The name of `voice`, spoken with it
+    fn announcement(voice: String) -> Self {
+        Self {
+            voice: Some(voice.clone()),
+            ..Self::new(format!("{}.", voice))
+        }
+    }
+
+    /// How the text is listed in the tray's queue
+    fn queued_text(&self) -> QueuedText {
+        let title = reading::title(&self.text);
+        QueuedText {
+            id: self.id,
+            label: match self.part {
+                Some((part, total)) => format!("{} (part {} of {})", title, part, total),
+                None => title,
+            },
+        }
+    }
+}
+
+/// What's left of an engine whose thread panicked, to start a new one from
+pub struct CrashedEngine {
+    /// Panic message
+    pub error: String,
+    /// Voice in use when it crashed
+    pub voice: String,
+    /// Texts still waiting to be spoken, with their voices and parts; the one being
+    /// spoken is dropped in case it caused the crash
+    pub queue: VecDeque<Utterance>,
+    /// Commands sent meanwhile are still waiting here
+    pub cmd_rx: Receiver<TTSCommand>,
+}
+
 /// TTS Engine running in a dedicated thread
 pub struct TTSEngine {
-    model: TTSModel,
-    voice_states: HashMap<String, ModelState>,
+    /// The model, loaded on first use, or Windows voices if it can't be loaded
+    backend: Option<Box<dyn TtsBackend>>,
+    /// Windows OneCore voices, offered alongside the backend's
+    onecore: Option<OneCoreVoices>,
+    current_model: String,
+    /// Preferred device and the one the loaded model runs on
+    device: ComputeDevice,
+    active_device: ComputeDevice,
+    precision: Precision,
     current_voice: String,
+    text_options: TextOptions,
+    /// Whether long texts are skimmed
+    skim: bool,
+    /// Last text queued and the voice of the app it came from, for reading it in full
+    last_queued: Option<(String, Option<String>)>,
+    voice_prosody: HashMap<String, VoiceProsody>,
+    idle_unload: Option<Duration>,
+    cache: Option<AudioCache>,
+    archive: Option<Archive>,
+    /// Chimes played before and after each text
+    chimes: Chimes,
+    /// Pitch shift in semitones, added to the voice's own
+    pitch_semitones: i32,
+    /// Equalizer applied to playback
+    equalizer: EqGains,
+    /// When playback is softened, checked at the start of each text
+    soft_voice: SoftSchedule,
+    night_volume: NightVolume,
+    /// Volume of the output, 0 to 1
+    volume: f32,
+    /// Voice per language (ISO 639-1 code) for mixed-language text
+    language_voices: HashMap<String, String>,
+    /// Language codes of the voices of the loaded model whose language is known, for
+    /// the languages without a voice of their own in `language_voices`
+    voice_languages: HashMap<String, String>,
+    queue: VecDeque<Utterance>,
+    /// IDs of the queued texts the main thread was last told about
+    reported_queue: Vec<u64>,
+    /// Last text spoken, for replaying it
+    last_utterance: Option<Utterance>,
+    last_samples: Vec<f32>,
+    last_sample_rate: u32,
+    /// `whole` of the last text spoken, telling the next part of a long text from a new text
+    spoken_whole: Option<u64>,
+    /// Audio of the long text being spoken so far, with the text of its first part,
+    /// archived in one file once its last part is done
+    long_text_audio: (String, Vec<f32>),
     is_speaking: Arc<AtomicBool>,
+    /// Loudness of what's playing, for the tray animation
+    output_level: OutputLevel,
     cmd_rx: Receiver<TTSCommand>,
     event_tx: Sender<TTSEvent>,
-    _stream: OutputStream,
-    stream_handle: OutputStreamHandle,
+    /// Audio is converted to the device's format before playback
+    output: AudioOutput,
+    latency: AudioLatency,
+    output_device: Option<String>,
 }
 
 impl TTSEngine {
     /// Create a new TTS engine
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
+        initial_model: &str,
         initial_voice: &str,
+        text_options: TextOptions,
+        voice_prosody: HashMap<String, VoiceProsody>,
         is_speaking: Arc<AtomicBool>,
+        output_level: OutputLevel,
         cmd_rx: Receiver<TTSCommand>,
         event_tx: Sender<TTSEvent>,
     ) -> Result<Self> {
         let models_path = models_dir()?;
 
-        // Verify models directory exists
+        // Without models, Windows voices are used once something is spoken
         if !models_path.exists() {
-            anyhow::bail!(
-                "Models directory not found at: {}. Please place the models folder next to the executable.",
+            log::warn!(
+                "Models directory not found at: {}, falling back to Windows voices",
                 models_path.display()
             );
         }
 
-        let weights_path = models_path.join("tts_b6369a24.safetensors");
-        let tokenizer_path = models_path.join("tokenizer.model");
-
-        // Verify required files exist
-        if !weights_path.exists() {
-            anyhow::bail!("Model weights not found at: {}", weights_path.display());
-        }
-        if !tokenizer_path.exists() {
-            anyhow::bail!("Tokenizer not found at: {}", tokenizer_path.display());
-        }
-
-        log::info!("Loading TTS model from: {}", models_path.display());
-
-        // Load model using offline method
-        let config = embedded_config();
-        let model = TTSModel::load_offline(&weights_path, &tokenizer_path, config)?;
-
-        log::info!("Model loaded successfully");
-
-        // Pre-load all voice states
-        let mut voice_states = HashMap::new();
-        for voice_name in VOICES {
-            let voice_path = models_path.join(format!("{}.safetensors", voice_name));
-            if voice_path.exists() {
-                match model.get_voice_state_from_prompt_file(&voice_path) {
-                    Ok(state) => {
-                        log::info!("Loaded voice: {}", voice_name);
-                        voice_states.insert(voice_name.to_string(), state);
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to load voice '{}': {}", voice_name, e);
-                    }
-                }
-            } else {
-                log::warn!("Voice file not found: {}", voice_path.display());
-            }
-        }
-
-        if voice_states.is_empty() {
-            anyhow::bail!("No voice files found in models directory");
-        }
-
         // Initialize audio output
-        let (_stream, stream_handle) = OutputStream::try_default()?;
+        let output = AudioOutput::open(AudioLatency::default(), None)?;
 
-        // Use initial voice if available, otherwise use first available
-        let current_voice = if voice_states.contains_key(initial_voice) {
-            initial_voice.to_string()
-        } else {
-            voice_states.keys().next().unwrap().clone()
-        };
+        let onecore = OneCoreVoices::new()
+            .map_err(|e| log::info!("OneCore voices unavailable: {}", e))
+            .ok();
+
+        Ok(Self::with_output(
+            initial_model,
+            initial_voice,
+            text_options,
+            voice_prosody,
+            is_speaking,
+            output_level,
+            cmd_rx,
+            event_tx,
+            output,
+            onecore,
+        ))
+    }
 
-        log::info!("Using voice: {}", current_voice);
+    /// An engine playing on `output`
+    #[allow(clippy::too_many_arguments)]
+    fn with_output(
+        initial_model: &str,
+        initial_voice: &str,
+        text_options: TextOptions,
+        voice_prosody: HashMap<String, VoiceProsody>,
+        is_speaking: Arc<AtomicBool>,
+        output_level: OutputLevel,
+        cmd_rx: Receiver<TTSCommand>,
+        event_tx: Sender<TTSEvent>,
+        output: AudioOutput,
+        onecore: Option<OneCoreVoices>,
+    ) -> Self {
+        // The model itself is loaded when it's first needed
+        let model_name = find_model(initial_model)
+            .map(|info| info.name)
+            .unwrap_or_else(|| initial_model.to_string());
 
-        Ok(Self {
-            model,
-            voice_states,
-            current_voice,
+        Self {
+            backend: None,
+            onecore,
+            current_model: model_name,
+            device: ComputeDevice::default(),
+            active_device: ComputeDevice::default(),
+            precision: Precision::default(),
+            current_voice: initial_voice.to_string(),
+            text_options,
+            skim: false,
+            last_queued: None,
+            voice_prosody,
+            idle_unload: None,
+            cache: None,
+            archive: None,
+            chimes: Chimes::default(),
+            pitch_semitones: 0,
+            equalizer: EqGains::default(),
+            soft_voice: SoftSchedule::default(),
+            night_volume: NightVolume::default(),
+            volume: 1.0,
+            language_voices: HashMap::new(),
+            voice_languages: HashMap::new(),
+            queue: VecDeque::new(),
+            reported_queue: Vec::new(),
+            last_utterance: None,
+            last_samples: Vec::new(),
+            last_sample_rate: 0,
+            spoken_whole: None,
+            long_text_audio: (String::new(), Vec::new()),
             is_speaking,
+            output_level,
             cmd_rx,
             event_tx,
-            _stream,
-            stream_handle,
-        })
+            output,
+            latency: AudioLatency::default(),
+            output_device: None,
+        }
     }
 
     /// Run the TTS engine loop
     pub fn run(&mut self) {
-        // Notify that model is loaded
-        let _ = self.event_tx.send(TTSEvent::ModelLoaded);
-
         loop {
-            match self.cmd_rx.recv() {
-                Ok(TTSCommand::Speak { text }) => {
-                    self.speak(&text);
+            // Wait for a command; if the model stays unused for too long, free it
+            let command = match (&self.backend, self.idle_unload) {
+                // Texts handed over from a crashed engine are spoken once the commands
+                // already waiting, such as its settings, are handled
+                _ if !self.queue.is_empty() => match self.cmd_rx.try_recv() {
+                    Ok(command) => Ok(command),
+                    Err(TryRecvError::Empty) => {
+                        if !self.speak_queue() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Err(TryRecvError::Disconnected) => Err(RecvError),
+                },
+                (Some(_), Some(timeout)) => match self.cmd_rx.recv_timeout(timeout) {
+                    Ok(command) => Ok(command),
+                    Err(RecvTimeoutError::Timeout) => {
+                        self.unload_model();
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => Err(RecvError),
+                },
+                _ => self.cmd_rx.recv(),
+            };
+
+            match command {
+                Ok(TTSCommand::Speak { text, voice }) => {
+                    self.queue.extend(copied_parts(&mut self.last_queued, text, voice, self.text_options.json_mode, self.skim));
+                    if !self.speak_queue() {
+                        break;
+                    }
+                }
+                Ok(TTSCommand::SpeakNow { text, voice }) => {
+                    queue_next(&mut self.queue, copied_parts(&mut self.last_queued, text, voice, self.text_options.json_mode, self.skim));
+                    if !self.speak_queue() {
+                        break;
+                    }
+                }
+                Ok(TTSCommand::ReadLastInFull) => match self.last_queued.clone() {
+                    Some((text, voice)) => {
+                        log::info!("Reading the last text in full");
+                        queue_next(&mut self.queue, copied_parts(&mut self.last_queued, text, voice, self.text_options.json_mode, false));
+                        if !self.speak_queue() {
+                            break;
+                        }
+                    }
+                    None => log::info!("Nothing queued yet to read in full"),
+                },
+                Ok(TTSCommand::Read { id, text, from_part }) => {
+                    queue_reading(&mut self.queue, id, &text, from_part);
+                    if !self.speak_queue() {
+                        break;
+                    }
                 }
                 Ok(TTSCommand::Stop) => {
                     self.is_speaking.store(false, Ordering::SeqCst);
                 }
+                Ok(TTSCommand::Pause)
+                | Ok(TTSCommand::Resume)
+                | Ok(TTSCommand::NextSentence)
+                | Ok(TTSCommand::PreviousSentence)
+                | Ok(TTSCommand::ClearQueue)
+                | Ok(TTSCommand::ClearQueueAfterCurrent)
+                | Ok(TTSCommand::RemoveQueued { .. })
+                | Ok(TTSCommand::MoveQueuedToFront { .. }) => {
+                    // Nothing is playing or queued
+                }
                 Ok(TTSCommand::ChangeVoice { voice }) => {
-                    if self.voice_states.contains_key(&voice) {
+                    // Before the model is loaded any voice is accepted and checked on load
+                    if self.backend.is_none() || self.has_voice(&voice) {
                         self.current_voice = voice;
                         log::info!("Voice changed to: {}", self.current_voice);
                     } else {
                         log::warn!("Voice '{}' not available", voice);
                     }
                 }
+                Ok(TTSCommand::ChangeModel { model }) => {
+                    self.change_model(&model);
+                }
+                Ok(TTSCommand::PreviewVoice { voice }) => {
+                    let sample_text = describe_voice(&voice, &self.current_model).sample_text;
+                    self.queue.push_front(Utterance::preview(voice, sample_text));
+                    if !self.speak_queue() {
+                        break;
+                    }
+                }
+                Ok(TTSCommand::AnnounceVoice { voice }) => {
+                    self.queue.push_front(Utterance::announcement(voice));
+                    if !self.speak_queue() {
+                        break;
+                    }
+                }
+                Ok(TTSCommand::AddVoice { name, path }) => {
+                    self.add_voice(name, &path);
+                }
+                Ok(TTSCommand::SetTextOptions(options)) => {
+                    self.text_options = options;
+                }
+                Ok(TTSCommand::SetSkim(skim)) => {
+                    self.skim = skim;
+                }
+                Ok(TTSCommand::SetIdleUnload(timeout)) => {
+                    self.idle_unload = timeout;
+                }
+                Ok(TTSCommand::SetDevice(device)) => {
+                    self.device = device;
+                    if self.backend.is_some() {
+                        self.change_model(&self.current_model.clone());
+                    }
+                }
+                Ok(TTSCommand::SetCacheSize(megabytes)) => {
+                    self.cache = audio_cache(megabytes);
+                }
+                Ok(TTSCommand::SetPitch(semitones)) => {
+                    self.pitch_semitones = semitones;
+                }
+                Ok(TTSCommand::SetVoiceProsody(prosody)) => {
+                    self.voice_prosody = prosody;
+                }
+                Ok(TTSCommand::SetEqualizer(gains)) => {
+                    self.equalizer = gains;
+                }
+                Ok(TTSCommand::SetSoftVoice(schedule)) => {
+                    self.soft_voice = schedule;
+                }
+                Ok(TTSCommand::SetNightVolume(night_volume)) => {
+                    self.night_volume = night_volume;
+                }
+                Ok(TTSCommand::SetVolume(volume)) => {
+                    self.volume = volume;
+                }
+                Ok(TTSCommand::SetLanguageVoices(voices)) => {
+                    self.language_voices = voices;
+                }
+                Ok(TTSCommand::SetAudioLatency(latency)) => {
+                    self.reopen_output(latency, self.output_device.clone());
+                }
+                Ok(TTSCommand::SetOutputDevice(device)) => {
+                    self.reopen_output(self.latency, device);
+                }
+                Ok(TTSCommand::SetArchive(archive)) => {
+                    self.archive = archive;
+                }
+                Ok(TTSCommand::SetChimes(chimes)) => {
+                    self.chimes = chimes;
+                }
+                Ok(TTSCommand::ClearCache) => {
+                    self.clear_cache();
+                }
+                Ok(TTSCommand::SetPrecision(precision)) => {
+                    self.precision = precision;
+                    if self.backend.is_some() {
+                        self.change_model(&self.current_model.clone());
+                    }
+                }
+                Ok(TTSCommand::SaveLast { path, format }) => {
+                    self.save_last(&path, format);
+                }
+                Ok(TTSCommand::ReplayLast) => match self.last_utterance.clone() {
+                    Some(utterance) => {
+                        log::info!("Replaying the last text");
+                        self.queue.push_front(utterance);
+                        if !self.speak_queue() {
+                            break;
+                        }
+                    }
+                    None => log::info!("Nothing spoken yet to replay"),
+                },
+                Ok(TTSCommand::ReplayLastWith { voice }) => match self.last_utterance.clone() {
+                    Some(utterance) => {
+                        log::info!("Replaying the last text with voice {}", voice);
+                        self.queue.push_front(Utterance {
+                            voice: Some(voice),
+                            ..utterance
+                        });
+                        if !self.speak_queue() {
+                            break;
+                        }
+                    }
+                    None => log::info!("Nothing spoken yet to replay"),
+                },
                 Ok(TTSCommand::Shutdown) | Err(_) => {
                     log::info!("TTS engine shutting down");
                     break;
@@ -152,127 +796,1235 @@ impl TTSEngine {
         }
     }
 
+    /// Keep what a new engine needs after this one panicked, leaving voice previews out of the queue
+    fn into_crashed(self, error: String) -> CrashedEngine {
+        self.is_speaking.store(false, Ordering::SeqCst);
+        CrashedEngine {
+            error,
+            voice: self.current_voice,
+            queue: self.queue.into_iter().filter(|utterance| utterance.voice.is_none()).collect(),
+            cmd_rx: self.cmd_rx,
+        }
+    }
+
+    /// Speak queued texts in order until the queue is empty
+    /// Returns false if a shutdown was requested
+    fn speak_queue(&mut self) -> bool {
+        while let Some(utterance) = self.queue.pop_front() {
+            report_queue(&self.queue, &mut self.reported_queue, &self.event_tx);
+            if !self.speak(&utterance) {
+                return false;
+            }
+        }
+        report_queue(&self.queue, &mut self.reported_queue, &self.event_tx);
+        true
+    }
+
     /// Speak the given text
-    fn speak(&mut self, text: &str) {
-        let voice_state = match self.voice_states.get(&self.current_voice) {
-            Some(s) => s,
-            None => {
-                let _ = self.event_tx.send(TTSEvent::Error(format!(
-                    "Voice '{}' not loaded",
-                    self.current_voice
-                )));
-                return;
+    /// Returns false if a shutdown was requested
+    fn speak(&mut self, utterance: &Utterance) -> bool {
+        // OneCore voices don't need the model
+        let voice = utterance
+            .voice
+            .as_ref()
+            .or(utterance.app_voice.as_ref())
+            .unwrap_or(&self.current_voice);
+        let system_voice = self.onecore.as_ref().is_some_and(|onecore| onecore.has_voice(voice));
+        if !system_voice && !self.ensure_model() {
+            // Don't retry the load for every queued text
+            self.queue.clear();
+            return true;
+        }
+        let text = utterance.text.as_str();
+        let voice = match (&utterance.voice, &utterance.app_voice) {
+            (Some(voice), _) => voice.clone(),
+            (None, Some(app_voice)) if self.has_voice(app_voice) => app_voice.clone(),
+            (None, Some(app_voice)) => {
+                log::warn!("No voice '{}' for the app copied from, using {}", app_voice, self.current_voice);
+                self.current_voice.clone()
             }
+            (None, None) => self.current_voice.clone(),
+        };
+
+        let prosody = self
+            .voice_prosody
+            .get(&voice)
+            .cloned()
+            .unwrap_or_default();
+        let options = TextOptions {
+            default_pause: prosody.pause_ms.map(Duration::from_millis),
+            ..self.text_options.clone()
+        };
+
+        let segments = text::segment(text, &options);
+        if segments.is_empty() {
+            return true;
+        }
+
+        // The backend's voices and OneCore voices may be mixed in one text
+        let backend = self.backend.as_deref();
+        let onecore = self.onecore.as_ref();
+        let Some(sample_rate) = backend_for(backend, onecore, &voice).map(|source| source.sample_rate()) else {
+            let _ = self.event_tx.send(TTSEvent::Error(format!("Voice '{}' not loaded", voice)));
+            return true;
         };
 
         // Create a new sink for this speech
-        let sink = match Sink::try_new(&self.stream_handle) {
+        let sink = match self.output.new_sink() {
             Ok(s) => s,
             Err(e) => {
                 let _ = self.event_tx.send(TTSEvent::Error(format!("Audio error: {}", e)));
-                return;
+                return true;
             }
         };
 
+        sink.set_volume(self.volume);
+        // Held until the first chunks are buffered
+        sink.pause();
+        let mut buffering = true;
+
         self.is_speaking.store(true, Ordering::SeqCst);
         let _ = self.event_tx.send(TTSEvent::StartedSpeaking);
+        if let Some((part, total)) = utterance.part {
+            let _ = self.event_tx.send(TTSEvent::Progress {
+                part,
+                total,
+                reading: utterance.reading,
+            });
+        }
 
         log::info!("Speaking: {}", text);
 
-        // Stream generation
-        for chunk_result in self.model.generate_stream_long(text, voice_state) {
-            // Check for stop command (non-blocking)
-            match self.cmd_rx.try_recv() {
-                Ok(TTSCommand::Stop) => {
-                    log::info!("Speech stopped by user");
-                    sink.stop();
-                    break;
+        // Stream generation segment by segment (sentences and pauses), then keep
+        // polling for commands until playback drains
+        let mut segment_index = 0;
+        let mut chunks: Option<SampleStream> = None;
+        let mut generating = true;
+        let mut paused = false;
+
+        // Generated audio per segment, kept for saving the utterance afterwards
+        let mut segment_audio: Vec<Vec<f32>> = vec![Vec::new(); segments.len()];
+
+        // Segment index of every chunk appended to the sink, used to find out
+        // which segment is audible right now (generation runs ahead of playback)
+        let mut chunk_segments: Vec<usize> = Vec::new();
+
+        // Sample count of every chunk appended to the sink, to know how much is buffered
+        let mut chunk_lengths: Vec<usize> = Vec::new();
+
+        // Model output of the sentence being generated, cached once complete
+        let mut generated: Vec<f32> = Vec::new();
+        let mut trimmer = SilenceTrimmer::new(sample_rate);
+
+        // Speed and pitch are changed independently; cached audio is stored unchanged
+        let speed = prosody.speed();
+        let semitones = prosody.pitch() + self.pitch_semitones as f32;
+        let mut shifter = PitchShifter::new(speed, semitones, sample_rate);
+        let mut soft = SoftEffect::new(self.soft_voice.is_active(), sample_rate);
+        let night_gain = self.night_volume.gain();
+        let mut equalizer = Equalizer::new(self.equalizer, sample_rate);
+        let mut limiter = Limiter::new(sample_rate);
+        let mut converter = OutputConverter::new(sample_rate, self.output.format(), self.output_level.clone());
+        let model_key = format!("{}:{}", self.current_model, self.precision.id());
+
+        // Voices added and model requested while speaking
+        let mut added_voices: Vec<(String, PathBuf)> = Vec::new();
+        let mut new_model: Option<String> = None;
+        let mut new_latency: Option<AudioLatency> = None;
+        let mut new_output_device: Option<Option<String>> = None;
+
+        // Chimes mark texts read on their own, previews go without; a long text's
+        // parts are chimed as one, at its start and end
+        let starts_text = utterance.part.is_none() || self.spoken_whole != Some(utterance.whole);
+        self.spoken_whole = Some(utterance.whole);
+        let (mut start_chime, mut end_chime) = match utterance.voice {
+            Some(_) => (None, None),
+            None => (
+                self.chimes.start(sample_rate).filter(|_| starts_text),
+                self.chimes.end(sample_rate),
+            ),
+        };
+        for chime in [&mut start_chime, &mut end_chime].into_iter().flatten() {
+            apply_gain(chime, night_gain);
+        }
+        if let Some(chime) = start_chime {
+            // Plays once playback starts, ahead of the first sentence
+            sink.append(converter.buffer(&chime));
+        }
+
+        // Set once the text played to its end, rather than being cut short
+        let mut completed = false;
+
+        // Paragraph of each segment, pauses counting with the sentence before them
+        let mut paragraph_of = Vec::with_capacity(segments.len());
+        for segment in &segments {
+            let previous = paragraph_of.last().copied().unwrap_or(0);
+            paragraph_of.push(match segment {
+                Segment::Speech { paragraph, .. } => *paragraph,
+                Segment::Pause(_) => previous,
+            });
+        }
+        let paragraphs = paragraph_of.last().map_or(1, |last| last + 1);
+        let mut last_playing_update = Instant::now();
+        let mut last_playing_segment = None;
+
+        'speech: loop {
+            let mut jump_to: Option<usize> = None;
+
+            // Drain pending commands; while paused, block so generation is suspended too
+            loop {
+                let command = if paused {
+                    self.cmd_rx.recv().ok()
+                } else {
+                    match self.cmd_rx.try_recv() {
+                        Ok(command) => Some(command),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => None,
+                    }
+                };
+
+                match command {
+                    Some(TTSCommand::Stop) => {
+                        log::info!("Speech stopped by user");
+                        sink.stop();
+                        self.queue.clear();
+                        break 'speech;
+                    }
+                    Some(TTSCommand::Pause) => {
+                        if !paused {
+                            log::info!("Speech paused");
+                            sink.pause();
+                            paused = true;
+                            let _ = self.event_tx.send(TTSEvent::Paused);
+                        }
+                    }
+                    Some(TTSCommand::Resume) => {
+                        if paused {
+                            log::info!("Speech resumed");
+                            if !buffering {
+                                sink.play();
+                            }
+                            paused = false;
+                            let _ = self.event_tx.send(TTSEvent::Resumed);
+                        }
+                    }
+                    Some(TTSCommand::NextSentence) => {
+                        let current = playing_segment(&chunk_segments, &sink, segment_index);
+                        jump_to = Some(current + 1);
+                    }
+                    Some(TTSCommand::PreviousSentence) => {
+                        let current = playing_segment(&chunk_segments, &sink, segment_index);
+                        jump_to = Some(current.saturating_sub(1));
+                    }
+                    Some(TTSCommand::ChangeVoice { voice }) => {
+                        if self.has_voice(&voice) {
+                            self.current_voice = voice;
+                        }
+                    }
+                    Some(TTSCommand::ChangeModel { model }) => {
+                        // Loaded once this text is done, generation still uses the current model
+                        new_model = Some(model);
+                    }
+                    Some(TTSCommand::AddVoice { name, path }) => {
+                        // Cloned once this text is done, the current voice is still in use
+                        added_voices.push((name, path));
+                    }
+                    Some(TTSCommand::SetTextOptions(options)) => {
+                        // Applies from the next text on
+                        self.text_options = options;
+                    }
+                    Some(TTSCommand::SetSkim(skim)) => {
+                        self.skim = skim;
+                    }
+                    Some(TTSCommand::SetIdleUnload(timeout)) => {
+                        self.idle_unload = timeout;
+                    }
+                    Some(TTSCommand::SetDevice(device)) => {
+                        // Reloaded once this text is done
+                        self.device = device;
+                        new_model = Some(self.current_model.clone());
+                    }
+                    Some(TTSCommand::SetCacheSize(megabytes)) => {
+                        self.cache = audio_cache(megabytes);
+                    }
+                    Some(TTSCommand::SetPitch(semitones)) => {
+                        // Applies from the next text on
+                        self.pitch_semitones = semitones;
+                    }
+                    Some(TTSCommand::SetVoiceProsody(prosody)) => {
+                        // Applies from the next text on
+                        self.voice_prosody = prosody;
+                    }
+                    Some(TTSCommand::SetEqualizer(gains)) => {
+                        // Applies from the next text on
+                        self.equalizer = gains;
+                    }
+                    Some(TTSCommand::SetSoftVoice(schedule)) => {
+                        // Applies from the next text on
+                        self.soft_voice = schedule;
+                    }
+                    Some(TTSCommand::SetNightVolume(night_volume)) => {
+                        // Applies from the next text on
+                        self.night_volume = night_volume;
+                    }
+                    Some(TTSCommand::SetVolume(volume)) => {
+                        self.volume = volume;
+                        sink.set_volume(volume);
+                    }
+                    Some(TTSCommand::SetLanguageVoices(voices)) => {
+                        self.language_voices = voices;
+                    }
+                    Some(TTSCommand::SetAudioLatency(latency)) => {
+                        // The device is reopened once this text is done
+                        new_latency = Some(latency);
+                    }
+                    Some(TTSCommand::SetOutputDevice(device)) => {
+                        // Switching devices mid-sentence would cut it off
+                        new_output_device = Some(device);
+                    }
+                    Some(TTSCommand::SetArchive(archive)) => {
+                        // This text is archived too once done
+                        self.archive = archive;
+                    }
+                    Some(TTSCommand::SetChimes(chimes)) => {
+                        // Applies from the next text on
+                        self.chimes = chimes;
+                    }
+                    Some(TTSCommand::ClearCache) => {
+                        self.clear_cache();
+                    }
+                    Some(TTSCommand::SetPrecision(precision)) => {
+                        // Reloaded once this text is done
+                        self.precision = precision;
+                        new_model = Some(self.current_model.clone());
+                    }
+                    Some(TTSCommand::SaveLast { path, format }) => {
+                        self.save_last(&path, format);
+                    }
+                    Some(TTSCommand::ReplayLast) => {
+                        log::info!("Starting the current text over");
+                        sink.stop();
+                        self.queue.push_front(utterance.clone());
+                        break 'speech;
+                    }
+                    Some(TTSCommand::ReplayLastWith { voice }) => {
+                        log::info!("Starting the current text over with voice {}", voice);
+                        sink.stop();
+                        self.queue.push_front(Utterance {
+                            voice: Some(voice),
+                            ..utterance.clone()
+                        });
+                        break 'speech;
+                    }
+                    Some(TTSCommand::ClearQueue) => {
+                        log::info!("Cleared {} queued text(s)", self.queue.len());
+                        self.queue.clear();
+                        report_queue(&self.queue, &mut self.reported_queue, &self.event_tx);
+                    }
+                    Some(TTSCommand::ClearQueueAfterCurrent) => {
+                        // The later parts of a long text are still to come
+                        self.queue.retain(|queued| queued.whole == utterance.whole);
+                        log::info!("Cleared the queue after the current text ({} part(s) left)", self.queue.len());
+                        report_queue(&self.queue, &mut self.reported_queue, &self.event_tx);
+                    }
+                    Some(TTSCommand::RemoveQueued { id }) => {
+                        // All the parts of a long text
+                        if let Some(whole) = queued_whole(&self.queue, id) {
+                            self.queue.retain(|utterance| utterance.whole != whole);
+                            log::info!("Removed queued text ({} pending)", self.queue.len());
+                        }
+                        report_queue(&self.queue, &mut self.reported_queue, &self.event_tx);
+                    }
+                    Some(TTSCommand::MoveQueuedToFront { id }) => {
+                        // All the parts of a long text, in order
+                        if let Some(whole) = queued_whole(&self.queue, id) {
+                            let (moved, rest): (VecDeque<_>, VecDeque<_>) =
+                                self.queue.drain(..).partition(|utterance| utterance.whole == whole);
+                            self.queue = moved.into_iter().chain(rest).collect();
+                            log::info!("Moved queued text to the front");
+                        }
+                        report_queue(&self.queue, &mut self.reported_queue, &self.event_tx);
+                    }
+                    Some(TTSCommand::Speak { text, voice }) => {
+                        // Speak after the current text finishes
+                        self.queue.extend(copied_parts(&mut self.last_queued, text, voice, self.text_options.json_mode, self.skim));
+                        log::info!("Queued text ({} pending)", self.queue.len());
+                        report_queue(&self.queue, &mut self.reported_queue, &self.event_tx);
+                    }
+                    Some(TTSCommand::SpeakNow { text, voice }) => {
+                        // Cut the current text short and speak the new one next
+                        log::info!("Speech interrupted by new text");
+                        sink.stop();
+                        queue_next(&mut self.queue, copied_parts(&mut self.last_queued, text, voice, self.text_options.json_mode, self.skim));
+                        break 'speech;
+                    }
+                    Some(TTSCommand::ReadLastInFull) => match self.last_queued.clone() {
+                        Some((text, voice)) => {
+                            log::info!("Speech interrupted to read the last text in full");
+                            sink.stop();
+                            queue_next(&mut self.queue, copied_parts(&mut self.last_queued, text, voice, self.text_options.json_mode, false));
+                            break 'speech;
+                        }
+                        None => log::info!("Nothing queued yet to read in full"),
+                    },
+                    Some(TTSCommand::Read { id, text, from_part }) => {
+                        log::info!("Speech interrupted by reading list text");
+                        sink.stop();
+                        queue_reading(&mut self.queue, id, &text, from_part);
+                        break 'speech;
+                    }
+                    Some(TTSCommand::PreviewVoice { voice }) => {
+                        // Play the sample now, then start the current text over
+                        log::info!("Speech interrupted by voice preview");
+                        sink.stop();
+                        self.queue.push_front(utterance.clone());
+                        let sample_text = describe_voice(&voice, &self.current_model).sample_text;
+                        self.queue.push_front(Utterance::preview(voice, sample_text));
+                        break 'speech;
+                    }
+                    Some(TTSCommand::AnnounceVoice { voice }) => {
+                        // Say the new voice's name, then start the current text over with it
+                        log::info!("Speech interrupted to announce voice {}", voice);
+                        sink.stop();
+                        self.queue.push_front(utterance.clone());
+                        self.queue.push_front(Utterance::announcement(voice));
+                        break 'speech;
+                    }
+                    Some(TTSCommand::Shutdown) | None => {
+                        sink.stop();
+                        self.is_speaking.store(false, Ordering::SeqCst);
+                        return false;
+                    }
                 }
-                Ok(TTSCommand::Shutdown) => {
+            }
+
+            // Skip to another segment: drop queued audio and restart generation there
+            if let Some(target) = jump_to {
+                if target < segments.len() {
+                    log::info!("Skipping to segment {}/{}", target + 1, segments.len());
                     sink.stop();
-                    self.is_speaking.store(false, Ordering::SeqCst);
-                    return;
+                    sink.pause();
+                    buffering = true;
+                    chunk_segments.clear();
+                    chunk_lengths.clear();
+                    segment_index = target;
+                    // Segments generated ahead are generated again from here on
+                    for audio in &mut segment_audio[target..] {
+                        audio.clear();
+                    }
+                    generated.clear();
+                    trimmer.reset();
+                    shifter.reset();
+                    soft.reset();
+                    equalizer.reset();
+                    limiter.reset();
+                    converter.reset();
+                    chunks = None;
+                    generating = true;
                 }
-                Ok(TTSCommand::ChangeVoice { voice }) => {
-                    if self.voice_states.contains_key(&voice) {
-                        self.current_voice = voice;
+            }
+
+            // Generate ahead of playback, up to a limit
+            let ahead_limit = (PREFETCH_LIMIT.as_secs_f64() * sample_rate as f64) as usize;
+            if generating && buffered_samples(&chunk_lengths, &sink) < ahead_limit {
+                let mut segment_done = false;
+
+                match &segments[segment_index] {
+                    Segment::Pause(duration) => {
+                        // Silence needs no generation, queue it in one go
+                        let seconds = duration.as_secs_f64() / speed as f64;
+                        let mut samples = vec![0.0; (seconds * sample_rate as f64) as usize];
+                        segment_audio[segment_index].extend_from_slice(&samples);
+                        chunk_lengths.push(samples.len());
+                        soft.process(&mut samples);
+                        equalizer.process(&mut samples);
+                        limiter.process(&mut samples);
+                        sink.append(converter.buffer(&samples));
+                        chunk_segments.push(segment_index);
+                        segment_done = true;
+                    }
+                    Segment::Speech {
+                        text,
+                        emphasized,
+                        language,
+                        ..
+                    } => {
+                        let gain = if *emphasized {
+                            prosody.gain() * EMPHASIS_GAIN
+                        } else {
+                            prosody.gain()
+                        } * night_gain;
+
+                        // Sentences in another language are read by a voice of it, unless a
+                        // specific voice was asked for (previews)
+                        let segment_voice = language
+                            .filter(|_| utterance.voice.is_none())
+                            .and_then(|language| self.language_voice(language, &voice))
+                            .unwrap_or(&voice);
+                        let cache_key = AudioCache::key(&model_key, segment_voice, text);
+
+                        // A sentence spoken before plays straight from the cache
+                        let cached = match (&chunks, &self.cache) {
+                            (None, Some(cache)) => cache.get(cache_key, sample_rate),
+                            _ => None,
+                        };
+                        if let Some(samples) = cached {
+                            let mut samples = [shifter.push(&samples), shifter.finish()].concat();
+                            apply_gain(&mut samples, gain);
+                            segment_audio[segment_index].extend_from_slice(&samples);
+                            chunk_lengths.push(samples.len());
+                            soft.process(&mut samples);
+                            equalizer.process(&mut samples);
+                            limiter.process(&mut samples);
+                            sink.append(converter.buffer(&samples));
+                            chunk_segments.push(segment_index);
+                            segment_done = true;
+                        } else {
+                            let stream = chunks.get_or_insert_with(|| {
+                                let Some(source) = backend_for(backend, onecore, segment_voice) else {
+                                    let error = anyhow::anyhow!("Voice '{}' not loaded", segment_voice);
+                                    return Box::new(std::iter::once(Err(error)));
+                                };
+                                let source_rate = source.sample_rate();
+                                match source.synth_stream(text, segment_voice) {
+                                    Ok(stream) if source_rate == sample_rate => stream,
+                                    Ok(stream) => Box::new(stream.map(move |chunk| {
+                                        chunk.map(|samples| resample(&samples, source_rate, sample_rate))
+                                    })),
+                                    Err(e) => Box::new(std::iter::once(Err(e))),
+                                }
+                            });
+                            let (samples, finished) = match stream.next() {
+                                // Drop dead air before playing
+                                Some(Ok(chunk)) => (trimmer.push(&chunk), false),
+                                Some(Err(e)) => {
+                                    log::error!("TTS generation error: {}", e);
+                                    let _ = self
+                                        .event_tx
+                                        .send(TTSEvent::Error(format!("Generation error: {}", e)));
+                                    generating = false;
+                                    (Vec::new(), false)
+                                }
+                                None => (trimmer.finish(), true),
+                            };
+
+                            generated.extend_from_slice(&samples);
+                            let mut samples = shifter.push(&samples);
+                            if finished {
+                                samples.extend(shifter.finish());
+                            }
+                            apply_gain(&mut samples, gain);
+                            if !samples.is_empty() {
+                                segment_audio[segment_index].extend_from_slice(&samples);
+                                chunk_lengths.push(samples.len());
+                                soft.process(&mut samples);
+                                equalizer.process(&mut samples);
+                                limiter.process(&mut samples);
+                                sink.append(converter.buffer(&samples));
+                                chunk_segments.push(segment_index);
+                            }
+
+                            if finished {
+                                if let Some(cache) = &self.cache {
+                                    cache.put(cache_key, &generated, sample_rate);
+                                }
+                                segment_done = true;
+                            }
+                        }
                     }
                 }
-                Ok(TTSCommand::Speak { .. }) => {
-                    // Ignore new speech requests while speaking
+
+                // Move on to the next segment, if any
+                if segment_done {
+                    chunks = None;
+                    generated.clear();
+                    if segment_index + 1 < segments.len() {
+                        segment_index += 1;
+                    } else {
+                        generating = false;
+                    }
                 }
-                Err(TryRecvError::Empty) => {
-                    // No command, continue
+            } else if !generating && sink.empty() {
+                // Chime once the text is done, still taking commands while it plays
+                if self.queue.front().is_some_and(|next| next.whole == utterance.whole) {
+                    end_chime = None;
                 }
-                Err(TryRecvError::Disconnected) => {
-                    sink.stop();
-                    self.is_speaking.store(false, Ordering::SeqCst);
-                    return;
+                match end_chime.take() {
+                    Some(chime) => sink.append(converter.buffer(&chime)),
+                    None => {
+                        completed = true;
+                        break;
+                    }
                 }
+            } else {
+                std::thread::sleep(PLAYBACK_POLL_INTERVAL);
             }
 
-            match chunk_result {
-                Ok(chunk) => {
-                    // Convert tensor to samples
-                    let samples = self.tensor_to_samples(&chunk);
-                    if !samples.is_empty() {
-                        let buffer = SamplesBuffer::new(
-                            1,                                   // channels
-                            self.model.sample_rate as u32,       // 24000
-                            samples,
-                        );
-                        sink.append(buffer);
-                    }
-                }
-                Err(e) => {
-                    log::error!("TTS generation error: {}", e);
-                    let _ = self.event_tx.send(TTSEvent::Error(format!("Generation error: {}", e)));
-                    break;
+            // Start playback once enough audio is buffered; if it runs low while
+            // generation is behind, hold it until the buffer refills
+            let buffered = buffered_samples(&chunk_lengths, &sink);
+            let to_samples = |duration: Duration| (duration.as_secs_f64() * sample_rate as f64) as usize;
+            if buffering && (!generating || buffered >= to_samples(PREFETCH_TARGET)) {
+                buffering = false;
+                if !paused {
+                    sink.play();
                 }
+            } else if !buffering && generating && buffered < to_samples(PREFETCH_LOW) {
+                log::debug!("Playback buffer ran low, rebuffering");
+                buffering = true;
+                sink.pause();
+            }
+
+            // Also sent as soon as another sentence starts, for the player window
+            let playing = playing_segment(&chunk_segments, &sink, segment_index);
+            if !buffering
+                && (last_playing_update.elapsed() >= PLAYING_UPDATE_INTERVAL
+                    || last_playing_segment != Some(playing))
+            {
+                last_playing_update = Instant::now();
+                last_playing_segment = Some(playing);
+                let ungenerated = generating.then_some(segment_index);
+                let remaining = buffered as f64 / sample_rate as f64
+                    + ungenerated_seconds(&segments, &segment_audio, ungenerated, speed, sample_rate);
+                let _ = self.event_tx.send(TTSEvent::Playing {
+                    paragraph: paragraph_of.get(playing).map_or(paragraphs, |p| p + 1),
+                    paragraphs,
+                    // Unknown rather than a panic should the estimate come out odd
+                    remaining: Duration::try_from_secs_f64(remaining.max(0.0)).unwrap_or_default(),
+                    sentence: sentence_at(&segments, playing),
+                    progress: playing.min(segments.len()) as f32 / segments.len() as f32,
+                });
             }
         }
 
-        // Wait for playback to finish (unless stopped)
-        if self.is_speaking.load(Ordering::SeqCst) {
-            sink.sleep_until_end();
+        // The generation stream borrows the current voice state
+        drop(chunks);
+        for (name, path) in added_voices {
+            self.add_voice(name, &path);
+        }
+        if let Some(model) = new_model {
+            self.change_model(&model);
+        }
+        if new_latency.is_some() || new_output_device.is_some() {
+            let latency = new_latency.unwrap_or(self.latency);
+            let device = new_output_device.unwrap_or_else(|| self.output_device.clone());
+            self.reopen_output(latency, device);
+        }
+
+        let more_parts = utterance.part.is_some() && self.queue.front().is_some_and(|next| next.whole == utterance.whole);
+        let samples = segment_audio.concat();
+        // Voice previews aren't worth keeping
+        if let (Some(archive), None) = (&self.archive, &utterance.voice) {
+            let (archived_text, audio) = &mut self.long_text_audio;
+            if starts_text {
+                archived_text.clone_from(&utterance.text);
+                audio.clear();
+            }
+            audio.extend_from_slice(&samples);
+            if !more_parts && !audio.is_empty() {
+                archive.save(audio, sample_rate, archived_text);
+                audio.clear();
+            }
+        }
+        if !samples.is_empty() {
+            self.last_samples = samples;
+            self.last_sample_rate = sample_rate;
+        }
+        // Previews and announcements aren't what's wanted again; a replayed reading
+        // list part doesn't move the reading list on
+        if utterance.voice.is_none() {
+            self.last_utterance = Some(Utterance {
+                reading: None,
+                ..utterance.clone()
+            });
+        }
+
+        if let (true, Some(id), Some((part, total))) = (completed, utterance.reading, utterance.part) {
+            if part == total {
+                let _ = self.event_tx.send(TTSEvent::ReadingFinished { id });
+            }
         }
 
         self.is_speaking.store(false, Ordering::SeqCst);
-        let _ = self.event_tx.send(TTSEvent::FinishedSpeaking);
+        // The meter stops with the audio, so it would hold the last level
+        self.output_level.set(0.0);
+        let _ = self.event_tx.send(TTSEvent::FinishedSpeaking { more_parts });
         log::info!("Speech finished");
+        true
     }
 
-    /// Convert a tensor to f32 samples
-    fn tensor_to_samples(&self, tensor: &candle_core::Tensor) -> Vec<f32> {
-        // Tensor is [B, C, T] - squeeze batch and channel to get [T]
-        let squeezed = tensor
-            .squeeze(0)
-            .and_then(|t| t.squeeze(0))
-            .unwrap_or_else(|_| tensor.clone());
+    /// Load the current model if it isn't loaded yet, falling back to Windows voices
+    /// if it can't be
+    /// Returns false if neither is available
+    fn ensure_model(&mut self) -> bool {
+        if self.backend.is_some() {
+            return true;
+        }
+        let loaded = match find_model(&self.current_model) {
+            Some(info) => self.load(info),
+            None => {
+                log::error!("Model '{}' not found", self.current_model);
+                false
+            }
+        };
+        loaded || self.start_fallback()
+    }
 
-        squeezed.to_vec1::<f32>().unwrap_or_default()
+    /// Switch to Windows voices after the model failed to load
+    fn start_fallback(&mut self) -> bool {
+        match SapiVoices::new() {
+            Ok(sapi) => {
+                self.backend = Some(Box::new(sapi));
+                if !self.has_voice(&self.current_voice) {
+                    self.current_voice = self.voice_names()[0].clone();
+                }
+                log::info!("Using Windows voice: {}", self.current_voice);
+                self.voice_languages = voice_languages(&self.voice_names(), &self.current_model);
+                let _ = self.event_tx.send(TTSEvent::FallbackLoaded {
+                    voices: self.voice_names(),
+                    voice: self.current_voice.clone(),
+                });
+                true
+            }
+            Err(e) => {
+                log::error!("Windows voices unavailable: {}", e);
+                let _ = self.event_tx.send(TTSEvent::Error(format!(
+                    "Model '{}' could not be loaded",
+                    self.current_model
+                )));
+                false
+            }
+        }
+    }
+
+    /// Voice sentences in `language` are read by instead of `voice`: the one mapped to
+    /// it in the settings, or else one whose details say it speaks it, if `voice` is
+    /// known not to
+    fn language_voice(&self, language: &str, voice: &str) -> Option<&String> {
+        if let Some(mapped) = self.language_voices.get(language).filter(|name| self.has_voice(name)) {
+            return Some(mapped);
+        }
+        if self.voice_languages.get(voice).is_none_or(|code| code == language) {
+            return None;
+        }
+        self.voice_languages
+            .iter()
+            .filter(|(name, code)| *code == language && self.has_voice(name))
+            .map(|(name, _)| name)
+            .min()
     }
+
+    /// Whether a voice of the backend or a OneCore voice exists
+    fn has_voice(&self, name: &str) -> bool {
+        backend_for(self.backend.as_deref(), self.onecore.as_ref(), name).is_some()
+    }
+
+    /// Switch to another model
+    fn change_model(&mut self, name: &str) {
+        let Some(info) = find_model(name).filter(|info| info.name == name) else {
+            let _ = self.event_tx.send(TTSEvent::Error(format!("Model '{}' not found", name)));
+            return;
+        };
+
+        log::info!("Switching to model: {}", name);
+        self.load(info);
+    }
+
+    /// Replace the loaded model and its voices, keeping the current voice if the model has it
+    /// Returns false if loading failed, in which case the previous model stays loaded
+    fn load(&mut self, info: ModelInfo) -> bool {
+        let _ = self.event_tx.send(TTSEvent::ModelLoading {
+            model: info.name.clone(),
+        });
+
+        let loaded: Result<(Box<dyn TtsBackend>, ComputeDevice)> = if info.is_piper() {
+            PiperBackend::load(&info, self.device).map(|(backend, device)| (Box::new(backend) as _, device))
+        } else {
+            PocketBackend::load(&info, self.device, self.precision)
+                .map(|(backend, device)| (Box::new(backend) as _, device))
+        };
+
+        match loaded {
+            Ok((backend, device)) => {
+                self.backend = Some(backend);
+                self.active_device = device;
+                self.current_model = info.name;
+                if !self.has_voice(&self.current_voice) {
+                    self.current_voice = self.voice_names()[0].clone();
+                }
+                log::info!("Using voice: {}", self.current_voice);
+                self.voice_languages = voice_languages(&self.voice_names(), &self.current_model);
+                let _ = self.event_tx.send(self.model_loaded_event());
+                true
+            }
+            Err(e) => {
+                log::error!("Failed to load model '{}': {}", info.name, e);
+                let _ = self.event_tx.send(TTSEvent::Error(format!("Model load failed: {}", e)));
+                false
+            }
+        }
+    }
+
+    /// Reopen the output device, keeping the current one if that fails
+    fn reopen_output(&mut self, latency: AudioLatency, device: Option<String>) {
+        if latency == self.latency && device == self.output_device {
+            return;
+        }
+        match AudioOutput::open(latency, device.as_deref()) {
+            Ok(output) => {
+                self.output = output;
+                self.latency = latency;
+                self.output_device = device;
+            }
+            Err(e) => {
+                log::error!("Failed to reopen audio output: {}", e);
+                let _ = self.event_tx.send(TTSEvent::Error(format!("Audio error: {}", e)));
+            }
+        }
+    }
+
+    fn clear_cache(&self) {
+        if let Ok(dir) = cache_dir() {
+            AudioCache::new(dir, 0).clear();
+            log::info!("Audio cache cleared");
+        }
+    }
+
+    /// Drop the model and voice states to free memory; they reload on demand
+    fn unload_model(&mut self) {
+        if self.backend.take().is_none() {
+            return;
+        }
+        log::info!("Model '{}' unloaded after being idle", self.current_model);
+        let _ = self.event_tx.send(TTSEvent::ModelUnloaded);
+    }
+
+    /// Voices of the backend, then the OneCore voices
+    fn voice_names(&self) -> Vec<String> {
+        let mut names = self
+            .backend
+            .as_ref()
+            .map(|backend| backend.voices())
+            .unwrap_or_default();
+        if let Some(onecore) = &self.onecore {
+            names.extend(onecore.names());
+        }
+        names
+    }
+
+    fn model_loaded_event(&self) -> TTSEvent {
+        TTSEvent::ModelLoaded {
+            model: self.current_model.clone(),
+            voices: self.voice_names(),
+            voice: self.current_voice.clone(),
+            device: self.active_device,
+        }
+    }
+
+    /// Clone a voice from prompt audio and make it the current voice
+    fn add_voice(&mut self, name: String, path: &Path) {
+        if !self.ensure_model() {
+            return;
+        }
+        let Some(backend) = &mut self.backend else {
+            return;
+        };
+
+        match backend.add_voice(&name, path) {
+            Ok(()) => {
+                log::info!("Added voice '{}' from {}", name, path.display());
+                self.current_voice = name.clone();
+                let _ = self.event_tx.send(TTSEvent::VoiceAdded(name));
+            }
+            Err(e) => {
+                log::error!("Failed to clone voice from {}: {}", path.display(), e);
+                // Don't keep a prompt that will fail again on every start
+                let _ = std::fs::remove_file(path);
+                let _ = self.event_tx.send(TTSEvent::Error(format!("Voice cloning failed: {}", e)));
+            }
+        }
+    }
+
+    /// Save the last spoken utterance as an audio file
+    fn save_last(&self, path: &Path, format: ExportFormat) {
+        if self.last_samples.is_empty() {
+            log::warn!("Nothing spoken yet, not saving");
+            return;
+        }
+
+        match write_audio(path, &self.last_samples, self.last_sample_rate, format) {
+            Ok(()) => log::info!("Saved last speech to: {}", path.display()),
+            Err(e) => {
+                log::error!("Failed to save speech: {}", e);
+                let _ = self.event_tx.send(TTSEvent::Error(format!("Save failed: {}", e)));
+            }
+        }
+    }
+}
+
+/// An audio cache limited to `megabytes`, or none if that's 0
+fn audio_cache(megabytes: u32) -> Option<AudioCache> {
+    match cache_dir() {
+        Ok(dir) if megabytes > 0 => Some(AudioCache::new(dir, megabytes as u64 * 1024 * 1024)),
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("Failed to locate audio cache directory: {}", e);
+            None
+        }
+    }
+}
+
+/// Scale samples by `gain`
+fn apply_gain(samples: &mut [f32], gain: f32) {
+    if gain != 1.0 {
+        samples.iter_mut().for_each(|s| *s *= gain);
+    }
+}
+
+/// The backend speaking `voice`: the main one, or the OneCore voices for theirs
+fn backend_for<'a>(
+    backend: Option<&'a dyn TtsBackend>,
+    onecore: Option<&'a OneCoreVoices>,
+    voice: &str,
+) -> Option<&'a dyn TtsBackend> {
+    match backend.filter(|backend| backend.has_voice(voice)) {
+        Some(backend) => Some(backend),
+        None => onecore
+            .filter(|onecore| onecore.has_voice(voice))
+            .map(|onecore| onecore as &dyn TtsBackend),
+    }
+}
+
+/// Samples appended to the sink that haven't finished playing
+fn buffered_samples(chunk_lengths: &[usize], sink: &Sink) -> usize {
+    let pending = sink.len().min(chunk_lengths.len());
+    chunk_lengths[chunk_lengths.len() - pending..].iter().sum()
+}
+
+/// About how long the segments from `from` on will play for, once generated (0 if
+/// all are); speech is timed at the rate the text was spoken at so far
+fn ungenerated_seconds(
+    segments: &[Segment],
+    segment_audio: &[Vec<f32>],
+    from: Option<usize>,
+    speed: f32,
+    sample_rate: u32,
+) -> f64 {
+    let Some(from) = from else {
+        return 0.0;
+    };
+    let seconds = |audio: &Vec<f32>| audio.len() as f64 / sample_rate as f64;
+
+    let (mut chars, mut spoken) = (0, 0.0);
+    for (segment, audio) in segments[..from].iter().zip(segment_audio) {
+        if let Segment::Speech { text, .. } = segment {
+            chars += text.chars().count();
+            spoken += seconds(audio);
+        }
+    }
+    let chars_per_second = if spoken >= 1.0 && chars > 0 {
+        chars as f64 / spoken
+    } else {
+        DEFAULT_CHARS_PER_SECOND * speed as f64
+    };
+
+    segments[from..]
+        .iter()
+        .zip(&segment_audio[from..])
+        .map(|(segment, audio)| match segment {
+            // The segment being generated may be partly done
+            Segment::Speech { text, .. } => {
+                (text.chars().count() as f64 / chars_per_second - seconds(audio)).max(0.0)
+            }
+            Segment::Pause(duration) => (duration.as_secs_f64() / speed as f64 - seconds(audio)).max(0.0),
+        })
+        .sum()
+}
+
+/// Tell the main thread which texts are waiting, if that changed since it was last
+/// told (`reported` holds their IDs); voice previews and announcements aren't listed
+fn report_queue(queue: &VecDeque<Utterance>, reported: &mut Vec<u64>, event_tx: &Sender<TTSEvent>) {
+    let queued: Vec<&Utterance> = queue.iter().filter(|utterance| utterance.voice.is_none()).collect();
+    if queued.iter().map(|utterance| utterance.id).eq(reported.iter().copied()) {
+        return;
+    }
+    *reported = queued.iter().map(|utterance| utterance.id).collect();
+    let texts = queued.iter().map(|utterance| utterance.queued_text()).collect();
+    let _ = event_tx.send(TTSEvent::QueueChanged(texts));
 }
 
-/// Spawn the TTS engine in a separate thread
+/// `whole` of the queued text with the given ID
+fn queued_whole(queue: &VecDeque<Utterance>, id: u64) -> Option<u64> {
+    queue.iter().find(|utterance| utterance.id == id).map(|utterance| utterance.whole)
+}
+
+/// The parts a copied text is queued as, kept in `last_queued` for reading it in full
+fn copied_parts(
+    last_queued: &mut Option<(String, Option<String>)>,
+    text: String,
+    app_voice: Option<String>,
+    json_mode: JsonMode,
+    skim: bool,
+) -> Vec<Utterance> {
+    *last_queued = Some((text.clone(), app_voice.clone()));
+    Utterance::parts(text, app_voice, json_mode, skim)
+}
+
+/// Queue the parts of a text ahead of everything else
+fn queue_next(queue: &mut VecDeque<Utterance>, parts: Vec<Utterance>) {
+    for utterance in parts.into_iter().rev() {
+        queue.push_front(utterance);
+    }
+}
+
+/// Queue the parts of a reading list text ahead of everything else, replacing
+/// any of its parts already queued
+fn queue_reading(queue: &mut VecDeque<Utterance>, id: u64, text: &str, from_part: usize) {
+    queue.retain(|utterance| utterance.reading != Some(id));
+    for utterance in Utterance::reading(id, text, from_part).into_iter().rev() {
+        queue.push_front(utterance);
+    }
+}
+
+/// Find the segment currently audible, given the segment of every chunk
+/// appended to the sink; falls back to the segment being generated once
+/// everything appended has been played
+fn playing_segment(chunk_segments: &[usize], sink: &Sink, generating: usize) -> usize {
+    let playing_chunk = chunk_segments.len().saturating_sub(sink.len());
+    chunk_segments.get(playing_chunk).copied().unwrap_or(generating)
+}
+
+/// Text of the sentence at `index`, or of the last one before it for a pause
+fn sentence_at(segments: &[Segment], index: usize) -> String {
+    segments[..(index + 1).min(segments.len())]
+        .iter()
+        .rev()
+        .find_map(|segment| match segment {
+            Segment::Speech { text, .. } => Some(text.clone()),
+            Segment::Pause(_) => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Run an engine until it shuts down
+/// Returns what's left of it if it panicked
+fn run_engine(mut engine: TTSEngine) -> Option<CrashedEngine> {
+    let run = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| engine.run()));
+    let payload = run.err()?;
+    let error = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown error".to_string());
+    log::error!("TTS engine crashed: {}", error);
+    Some(engine.into_crashed(error))
+}
+
+/// Spawn the TTS engine in a separate thread, speaking `queue` first (texts a crashed
+/// engine left)
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_tts_thread(
+    initial_model: String,
     initial_voice: String,
+    text_options: TextOptions,
+    voice_prosody: HashMap<String, VoiceProsody>,
     is_speaking: Arc<AtomicBool>,
+    output_level: OutputLevel,
+    queue: VecDeque<Utterance>,
     cmd_rx: Receiver<TTSCommand>,
     event_tx: Sender<TTSEvent>,
-) -> std::thread::JoinHandle<()> {
+) -> std::thread::JoinHandle<Option<CrashedEngine>> {
     std::thread::Builder::new()
         .name("tts-engine".into())
         .spawn(move || {
-            match TTSEngine::new(&initial_voice, is_speaking, cmd_rx, event_tx.clone()) {
+            match TTSEngine::new(
+                &initial_model,
+                &initial_voice,
+                text_options,
+                voice_prosody,
+                is_speaking,
+                output_level,
+                cmd_rx,
+                event_tx.clone(),
+            ) {
                 Ok(mut engine) => {
-                    engine.run();
+                    engine.queue = queue;
+                    run_engine(engine)
                 }
                 Err(e) => {
                     log::error!("Failed to initialize TTS engine: {}", e);
                     let _ = event_tx.send(TTSEvent::Error(format!("Init failed: {}", e)));
+                    None
                 }
             }
         })
         .expect("Failed to spawn TTS thread")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+
+    fn speech(text: &str) -> Segment {
+        Segment::Speech {
+            text: text.to_string(),
+            emphasized: false,
+            language: None,
+            paragraph: 0,
+        }
+    }
+
+    fn audio(backend: &MockBackend, text: &str) -> Vec<f32> {
+        backend
+            .synth_stream(text, "alba")
+            .and_then(|stream| stream.collect::<Result<Vec<_>>>())
+            .expect("mock synthesizes")
+            .concat()
+    }
+
+    #[test]
+    fn short_texts_are_one_part() {
+        let parts = Utterance::parts("Hello.".to_string(), Some("alba".to_string()), JsonMode::Read, false);
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].text, "Hello.");
+        assert_eq!(parts[0].part, None);
+        assert_eq!(parts[0].whole, parts[0].id);
+        assert_eq!(parts[0].app_voice.as_deref(), Some("alba"));
+    }
+
+    #[test]
+    fn long_texts_are_numbered_parts_of_one_whole() {
+        let paragraph = "A sentence of some length. ".repeat(40);
+        let text = [paragraph.trim(); 3].join("\n\n");
+        let parts = Utterance::parts(text, None, JsonMode::Read, false);
+        assert_eq!(parts.len(), 3);
+        for (index, part) in parts.iter().enumerate() {
+            assert_eq!(part.part, Some((index + 1, 3)));
+            assert_eq!(part.whole, parts[0].id);
+            assert!(part.text.chars().count() <= LONG_TEXT_PART_CHARS);
+        }
+    }
+
+    #[test]
+    fn json_is_summarized_before_splitting() {
+        let json = format!("[{}]", vec!["1"; 1500].join(","));
+        let parts = Utterance::parts(json, None, JsonMode::Summarize, false);
+        assert_eq!(parts.len(), 1);
+        assert!(parts[0].text.starts_with("A list of 1500 items: 1, 1,"), "{}", parts[0].text);
+    }
+
+    #[test]
+    fn queue_survives_a_crash_in_order() {
+        let (_cmd_tx, cmd_rx) = std::sync::mpsc::channel();
+        let (event_tx, _event_rx) = std::sync::mpsc::channel();
+        let mut engine = TTSEngine::with_output(
+            "default",
+            "alba",
+            TextOptions::default(),
+            HashMap::new(),
+            Arc::default(),
+            OutputLevel::default(),
+            cmd_rx,
+            event_tx,
+            AudioOutput::silent(),
+            None,
+        );
+        engine.backend = Some(Box::new(MockBackend::new(&["alba", "marius"]).panicking_on("crash")));
+
+        let paragraph = "A sentence of some length. ".repeat(40);
+        let long = [paragraph.trim(); 3].join("\n\n");
+        let queued: Vec<Utterance> = Utterance::parts("Short one.".to_string(), None, JsonMode::Read, false)
+            .into_iter()
+            .chain(Utterance::parts(long, Some("marius".to_string()), JsonMode::Read, false))
+            .collect();
+        engine.queue.push_back(Utterance::new("This one makes it crash.".to_string()));
+        engine.queue.push_back(Utterance::preview("marius".to_string(), None));
+        engine.queue.extend(queued.iter().cloned());
+
+        let crashed = run_engine(engine).expect("the backend panicked");
+        assert_eq!(crashed.voice, "alba");
+        assert!(crashed.error.contains("crash"), "{}", crashed.error);
+        let summary = |utterance: &Utterance| {
+            (utterance.id, utterance.app_voice.clone(), utterance.whole, utterance.part)
+        };
+        assert_eq!(
+            crashed.queue.iter().map(summary).collect::<Vec<_>>(),
+            queued.iter().map(summary).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn voices_come_from_the_backend_that_has_them() {
+        let backend = MockBackend::new(&["alba"]);
+        assert!(backend_for(Some(&backend), None, "alba").is_some());
+        assert!(backend_for(Some(&backend), None, "marius").is_none());
+        assert!(backend_for(None, None, "alba").is_none());
+    }
+
+    #[test]
+    fn ungenerated_speech_is_timed_at_the_rate_so_far() {
+        let backend = MockBackend::new(&["alba"]);
+        let segments = [
+            speech("aaaaaaaaaa"),
+            speech("bbbbbbbbbbbbbbbbbbbb"),
+            Segment::Pause(Duration::from_secs(1)),
+        ];
+        let segment_audio = [audio(&backend, "aaaaaaaaaa"), Vec::new(), Vec::new()];
+        let seconds = ungenerated_seconds(&segments, &segment_audio, Some(1), 1.0, MockBackend::SAMPLE_RATE);
+        assert!((seconds - 3.0).abs() < 1e-9, "{}", seconds);
+        assert_eq!(ungenerated_seconds(&segments, &segment_audio, None, 1.0, MockBackend::SAMPLE_RATE), 0.0);
+    }
+
+    #[test]
+    fn skipping_back_keeps_each_segment_once() {
+        let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
+        let (event_tx, event_rx) = std::sync::mpsc::channel();
+        let mut engine = TTSEngine::with_output(
+            "default",
+            "alba",
+            TextOptions::default(),
+            HashMap::new(),
+            Arc::default(),
+            OutputLevel::default(),
+            cmd_rx,
+            event_tx,
+            AudioOutput::silent(),
+            None,
+        );
+        let backend = MockBackend::new(&["alba"]);
+        // Shorter than the prefetch target, so all of it is generated before it plays
+        let sentences = "One. Two. Three.";
+        let expected: usize = text::segment(sentences, &TextOptions::default())
+            .iter()
+            .map(|segment| match segment {
+                Segment::Speech { text, .. } => audio(&backend, text).len(),
+                Segment::Pause(duration) => (duration.as_secs_f64() * MockBackend::SAMPLE_RATE as f64) as usize,
+            })
+            .sum();
+        engine.backend = Some(Box::new(backend));
+        engine.queue.push_back(Utterance::new(sentences.to_string()));
+
+        // The silent output never plays anything, so the text is stopped once it's
+        // been generated again after the skip
+        let commands = std::thread::spawn(move || {
+            let mut playing = event_rx.iter().filter(|event| matches!(event, TTSEvent::Playing { .. }));
+            playing.next();
+            let _ = cmd_tx.send(TTSCommand::PreviousSentence);
+            playing.next();
+            let _ = cmd_tx.send(TTSCommand::Stop);
+            let _ = cmd_tx.send(TTSCommand::Shutdown);
+        });
+        engine.run();
+        commands.join().expect("commands sent");
+        assert_eq!(engine.last_samples.len(), expected);
+    }
+
+    #[test]
+    fn generated_audio_is_not_counted_twice() {
+        let segments = [speech("short")];
+        let segment_audio = [vec![0.0; 1000]];
+        let seconds = ungenerated_seconds(&segments, &segment_audio, Some(0), 1.0, MockBackend::SAMPLE_RATE);
+        assert_eq!(seconds, 0.0);
+    }
+}
@@ -0,0 +1,407 @@
+//! Updates: the latest GitHub release is checked for a newer version, whose
+//! executable is downloaded next to the running one, checked for a valid
+//! signature by the same publisher, and swapped in when Pocket-Tray next starts
+
+use crate::toast;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// GitHub API address of the newest release
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/jame25/pocket-tray/releases/latest";
+
+/// Version of the running executable
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How long a started update has to show it runs before it's trusted to
+const STARTUP_CHECK: Duration = Duration::from_secs(5);
+
+/// A newer release, with the executable to download
+#[derive(Debug)]
+pub struct Release {
+    /// Version number, without the tag's "v"
+    pub version: String,
+    download_url: String,
+    /// Size of the executable in bytes, as GitHub lists it
+    size: u64,
+}
+
+/// The parts of GitHub's release JSON that are used
+#[derive(Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    assets: Vec<GitHubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GitHubAsset {
+    name: String,
+    browser_download_url: String,
+    size: u64,
+}
+
+/// Check for updates in a background thread, then download one if there is
+/// `manual` checks say in a notification when there's nothing new or the check failed;
+/// either way a downloaded update is announced
+pub fn spawn_update_check(manual: bool) -> std::io::Result<JoinHandle<()>> {
+    std::thread::Builder::new()
+        .name("update".into())
+        .spawn(move || run_check(manual))
+}
+
+fn run_check(manual: bool) {
+    let release = match check() {
+        Ok(Some(release)) => release,
+        Ok(None) => {
+            log::info!("Pocket-Tray {} is up to date", CURRENT_VERSION);
+            if manual {
+                notify(
+                    "Pocket-Tray is up to date",
+                    &format!("Version {} is the latest.", CURRENT_VERSION),
+                );
+            }
+            return;
+        }
+        Err(e) => {
+            log::warn!("Update check failed: {:#}", e);
+            if manual {
+                notify_error(&format!("Update check failed: {:#}", e));
+            }
+            return;
+        }
+    };
+
+    log::info!("Downloading Pocket-Tray {}", release.version);
+    match download(&release) {
+        Ok(()) => {
+            log::info!(
+                "Pocket-Tray {} downloaded, installed on next start",
+                release.version
+            );
+            notify(
+                "Pocket-Tray update ready",
+                &format!(
+                    "Version {} was downloaded and is installed the next time Pocket-Tray starts.",
+                    release.version
+                ),
+            );
+        }
+        Err(e) => {
+            log::error!("Update download failed: {:#}", e);
+            notify_error(&format!("Update download failed: {:#}", e));
+        }
+    }
+}
+
+fn notify(title: &str, message: &str) {
+    if let Err(e) = toast::show_notice(title, message) {
+        log::warn!("Failed to show update notification: {}", e);
+    }
+}
+
+fn notify_error(message: &str) {
+    if let Err(e) = toast::show_error(message) {
+        log::warn!("Failed to show update notification: {}", e);
+    }
+}
+
+/// The latest release, if it's newer than the running version
+pub fn check() -> Result<Option<Release>> {
+    let json = fetch_string(LATEST_RELEASE_URL)?;
+    let release: GitHubRelease = serde_json::from_str(&json).context("Unexpected release data")?;
+
+    let version = release.tag_name.trim_start_matches('v');
+    if !is_newer(version, CURRENT_VERSION) {
+        return Ok(None);
+    }
+    let asset = release
+        .assets
+        .into_iter()
+        .find(|asset| asset.name.to_ascii_lowercase().ends_with(".exe"))
+        .ok_or_else(|| anyhow!("Release {} has no executable", release.tag_name))?;
+
+    Ok(Some(Release {
+        version: version.to_string(),
+        download_url: asset.browser_download_url,
+        size: asset.size,
+    }))
+}
+
+/// Download a release's executable and, once its signature checks out, put it
+/// where the next start picks it up
+pub fn download(release: &Release) -> Result<()> {
+    let bytes = fetch_bytes(&release.download_url)?;
+    if bytes.len() as u64 != release.size {
+        anyhow::bail!("Downloaded {} of {} bytes", bytes.len(), release.size);
+    }
+
+    let exe = std::env::current_exe()?;
+    let partial = sibling(&exe, ".update.part");
+    std::fs::write(&partial, &bytes)?;
+    if let Err(e) = verify_signature(&partial) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e.context("The download isn't validly signed"));
+    }
+    std::fs::rename(&partial, sibling(&exe, ".update"))?;
+    Ok(())
+}
+
+/// An update swapped in for the running executable, which is kept until the
+/// update has started
+pub struct Installed {
+    exe: PathBuf,
+    old: PathBuf,
+}
+
+impl Installed {
+    /// Start the updated executable; if it can't be started, or exits with an
+    /// error right away, the executable it replaced is put back
+    pub fn start(self) -> Result<()> {
+        let result = self.start_checked();
+        if result.is_err() {
+            self.roll_back()?;
+        }
+        result
+    }
+
+    fn start_checked(&self) -> Result<()> {
+        let mut child = std::process::Command::new(&self.exe)
+            .spawn()
+            .context("Failed to start the updated executable")?;
+        let deadline = Instant::now() + STARTUP_CHECK;
+        while Instant::now() < deadline {
+            if let Some(status) = child.try_wait()? {
+                if !status.success() {
+                    anyhow::bail!("The updated executable exited with {}", status);
+                }
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        Ok(())
+    }
+
+    /// Put the replaced executable back; the update is dropped
+    fn roll_back(&self) -> Result<()> {
+        std::fs::remove_file(&self.exe).context("Failed to remove the update")?;
+        std::fs::rename(&self.old, &self.exe).context("Failed to restore the replaced executable")?;
+        Ok(())
+    }
+}
+
+/// Swap a downloaded update in for the running executable
+/// Returns it if it was, in which case it should be started instead
+pub fn apply_staged() -> Result<Option<Installed>> {
+    let exe = std::env::current_exe()?;
+    let old = sibling(&exe, ".old");
+    // Left from the last update; it may still be running if that was just now
+    let _ = std::fs::remove_file(&old);
+
+    let staged = sibling(&exe, ".update");
+    if !staged.exists() {
+        return Ok(None);
+    }
+    // Windows lets a running executable be renamed, not replaced
+    std::fs::rename(&exe, &old)?;
+    if let Err(e) = std::fs::rename(&staged, &exe) {
+        let _ = std::fs::rename(&old, &exe);
+        return Err(e.into());
+    }
+    Ok(Some(Installed { exe, old }))
+}
+
+/// `path` with `suffix` added to its file name
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Whether version `candidate` comes after `current`, comparing their numbers
+/// ("0.10.0" is newer than "0.9.2"; "1.2" and "1.2.0" are the same)
+fn is_newer(candidate: &str, current: &str) -> bool {
+    version_numbers(candidate) > version_numbers(current)
+}
+
+fn version_numbers(version: &str) -> Vec<u64> {
+    let mut numbers: Vec<u64> = version
+        .trim()
+        .split('.')
+        .map(|part| {
+            let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().unwrap_or(0)
+        })
+        .collect();
+    while numbers.last() == Some(&0) {
+        numbers.pop();
+    }
+    numbers
+}
+
+/// An HTTP client that names itself, which GitHub's API requires
+#[cfg(windows)]
+fn http_client() -> Result<windows::Web::Http::HttpClient> {
+    use windows::core::HSTRING;
+    use windows::Web::Http::HttpClient;
+
+    let client = HttpClient::new()?;
+    client
+        .DefaultRequestHeaders()?
+        .UserAgent()?
+        .TryParseAdd(&HSTRING::from(format!("Pocket-Tray/{}", CURRENT_VERSION)))?;
+    Ok(client)
+}
+
+#[cfg(windows)]
+fn fetch_string(url: &str) -> Result<String> {
+    use windows::core::HSTRING;
+    use windows::Foundation::Uri;
+
+    let uri = Uri::CreateUri(&HSTRING::from(url))?;
+    let text = http_client()?.GetStringAsync(&uri)?.get()?;
+    Ok(text.to_string())
+}
+
+#[cfg(windows)]
+fn fetch_bytes(url: &str) -> Result<Vec<u8>> {
+    use windows::core::HSTRING;
+    use windows::Foundation::Uri;
+    use windows::Storage::Streams::DataReader;
+
+    // Redirects to the download servers are followed
+    let uri = Uri::CreateUri(&HSTRING::from(url))?;
+    let buffer = http_client()?.GetBufferAsync(&uri)?.get()?;
+    let mut bytes = vec![0u8; buffer.Length()? as usize];
+    DataReader::FromBuffer(&buffer)?.ReadBytes(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Check the file's Authenticode signature, down to a trusted root and with
+/// revocation checks, and that it's signed with the same certificate as the
+/// running executable, so a release can't be swapped for another publisher's
+/// signed program
+#[cfg(windows)]
+fn verify_signature(path: &Path) -> Result<()> {
+    let exe = std::env::current_exe()?;
+    let expected = signer_thumbprint(&exe).context("The running executable isn't validly signed")?;
+    if signer_thumbprint(path)? != expected {
+        anyhow::bail!("It's signed by a different certificate than the running executable");
+    }
+    Ok(())
+}
+
+/// SHA-1 thumbprint of the certificate a validly signed file is signed with
+#[cfg(windows)]
+fn signer_thumbprint(path: &Path) -> Result<Vec<u8>> {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Security::Cryptography::{CertGetCertificateContextProperty, CERT_SHA1_HASH_PROP_ID};
+    use windows::Win32::Security::WinTrust::{
+        WTHelperGetProvSignerFromChain, WTHelperProvDataFromStateData, WinVerifyTrust,
+        WINTRUST_ACTION_GENERIC_VERIFY_V2, WINTRUST_DATA, WINTRUST_DATA_0, WINTRUST_FILE_INFO,
+        WTD_CHOICE_FILE, WTD_REVOKE_WHOLECHAIN, WTD_STATEACTION_CLOSE, WTD_STATEACTION_VERIFY,
+        WTD_UI_NONE,
+    };
+
+    let path = HSTRING::from(path.as_os_str());
+    let mut file = WINTRUST_FILE_INFO {
+        cbStruct: std::mem::size_of::<WINTRUST_FILE_INFO>() as u32,
+        pcwszFilePath: PCWSTR(path.as_ptr()),
+        ..Default::default()
+    };
+    let mut data = WINTRUST_DATA {
+        cbStruct: std::mem::size_of::<WINTRUST_DATA>() as u32,
+        dwUIChoice: WTD_UI_NONE,
+        fdwRevocationChecks: WTD_REVOKE_WHOLECHAIN,
+        dwUnionChoice: WTD_CHOICE_FILE,
+        Anonymous: WINTRUST_DATA_0 { pFile: &mut file },
+        dwStateAction: WTD_STATEACTION_VERIFY,
+        ..Default::default()
+    };
+    let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+
+    let status = unsafe {
+        WinVerifyTrust(
+            HWND::default(),
+            &mut action,
+            (&mut data as *mut WINTRUST_DATA).cast(),
+        )
+    };
+    // The certificate is read from what the check kept, before it's freed
+    let thumbprint = if status == 0 {
+        unsafe { leaf_thumbprint(&data) }
+    } else {
+        Err(anyhow!("Signature check failed (0x{:08X})", status as u32))
+    };
+    data.dwStateAction = WTD_STATEACTION_CLOSE;
+    unsafe {
+        WinVerifyTrust(
+            HWND::default(),
+            &mut action,
+            (&mut data as *mut WINTRUST_DATA).cast(),
+        )
+    };
+    return thumbprint;
+
+    unsafe fn leaf_thumbprint(data: &WINTRUST_DATA) -> Result<Vec<u8>> {
+        let provider = WTHelperProvDataFromStateData(data.hWVTStateData);
+        if provider.is_null() {
+            anyhow::bail!("The signature check kept no signer");
+        }
+        let signer = WTHelperGetProvSignerFromChain(provider, 0, false, 0);
+        if signer.is_null() || (*signer).csCertChain == 0 || (*signer).pasCertChain.is_null() {
+            anyhow::bail!("The signature has no certificate");
+        }
+        let cert = (*(*signer).pasCertChain).pCert;
+        let mut len = 0;
+        CertGetCertificateContextProperty(cert, CERT_SHA1_HASH_PROP_ID, None, &mut len)?;
+        let mut thumbprint = vec![0u8; len as usize];
+        CertGetCertificateContextProperty(cert, CERT_SHA1_HASH_PROP_ID, Some(thumbprint.as_mut_ptr().cast()), &mut len)?;
+        thumbprint.truncate(len as usize);
+        Ok(thumbprint)
+    }
+}
+
+#[cfg(not(windows))]
+fn fetch_string(_url: &str) -> Result<String> {
+    anyhow::bail!("Updates are not supported on this platform")
+}
+
+#[cfg(not(windows))]
+fn fetch_bytes(_url: &str) -> Result<Vec<u8>> {
+    anyhow::bail!("Updates are not supported on this platform")
+}
+
+#[cfg(not(windows))]
+fn verify_signature(_path: &Path) -> Result<()> {
+    anyhow::bail!("Signature checks are not supported on this platform")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_version_numbers() {
+        assert!(is_newer("0.10.0", "0.9.2"));
+        assert!(is_newer("1.0", "0.99.99"));
+        assert!(is_newer("1.2.1", "1.2"));
+        assert!(!is_newer("0.9.2", "0.10.0"));
+    }
+
+    #[test]
+    fn trailing_zeros_are_the_same_version() {
+        assert!(!is_newer("1.2.0", "1.2"));
+        assert!(!is_newer("1.2", "1.2.0"));
+        assert!(!is_newer(" 1.2 ", "1.2"));
+    }
+
+    #[test]
+    fn ignores_suffixes() {
+        assert!(!is_newer("1.2.0-beta", "1.2.0"));
+        assert!(is_newer("1.3.0-rc1", "1.2.0"));
+    }
+}
@@ -0,0 +1,438 @@
+//! Voice details: friendly names and the language, gender and style of voices,
+//! used to label and group them in the voices menu
+//!
+//! Details come from the voices shipped with Pocket TTS, Piper's `<voice>.onnx.json`
+//! config, or the names Windows gives its voices. A `voices.json` manifest in the
+//! models directory, a model's folder or the voices folder overrides any of them for
+//! the voices it lists, and a `<voice>.json` file next to the voice overrides that.
+
+use crate::settings::{find_model, models_dir, voices_dir};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Details of several voices, keyed by voice name
+const MANIFEST_FILE: &str = "voices.json";
+
+/// Voices shipped with Pocket TTS: (name, accent, gender), all English
+const POCKET_VOICES: [(&str, Option<&str>, Gender); 8] = [
+    ("alba", Some("Scottish"), Gender::Female),
+    ("marius", None, Gender::Male),
+    ("javert", None, Gender::Male),
+    ("jean", None, Gender::Male),
+    ("fantine", None, Gender::Female),
+    ("cosette", None, Gender::Female),
+    ("eponine", None, Gender::Female),
+    ("azelma", None, Gender::Female),
+];
+
+/// Menu group of voices without the detail grouped by
+const OTHER_GROUP: &str = "Other";
+
+/// What the voices menu is grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoiceGrouping {
+    /// One flat list
+    None,
+    #[default]
+    Language,
+    Gender,
+    Style,
+}
+
+impl VoiceGrouping {
+    /// All options, in menu order
+    pub const ALL: [VoiceGrouping; 4] = [
+        VoiceGrouping::None,
+        VoiceGrouping::Language,
+        VoiceGrouping::Gender,
+        VoiceGrouping::Style,
+    ];
+
+    /// Stable identifier used in menu IDs
+    pub fn id(self) -> &'static str {
+        match self {
+            VoiceGrouping::None => "none",
+            VoiceGrouping::Language => "language",
+            VoiceGrouping::Gender => "gender",
+            VoiceGrouping::Style => "style",
+        }
+    }
+
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            VoiceGrouping::None => "Don't Group",
+            VoiceGrouping::Language => "Language",
+            VoiceGrouping::Gender => "Gender",
+            VoiceGrouping::Style => "Style",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Gender {
+    Female,
+    Male,
+}
+
+impl Gender {
+    /// Display name for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            Gender::Female => "Female",
+            Gender::Male => "Male",
+        }
+    }
+}
+
+/// A voice with what's known about it
+#[derive(Debug, Clone)]
+pub struct VoiceInfo {
+    /// Name the engine knows the voice by (its file stem, or the Windows voice name)
+    pub name: String,
+    pub display_name: String,
+    /// E.g. "English" or "English (Great Britain)"
+    pub language: Option<String>,
+    /// ISO 639-1 code of the language, e.g. "en", matched against the language of
+    /// each sentence
+    pub language_code: Option<String>,
+    /// E.g. "Scottish", shown instead of the language
+    pub accent: Option<String>,
+    pub gender: Option<Gender>,
+    /// E.g. "calm" or "narration"
+    pub style: Option<String>,
+    /// A sentence or two about the voice, shown when previewing it
+    pub description: Option<String>,
+    /// What the voice says when previewed, instead of introducing itself
+    pub sample_text: Option<String>,
+}
+
+impl VoiceInfo {
+    /// A voice nothing is known about, shown by its name
+    fn plain(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            display_name: title_case(name),
+            language: None,
+            language_code: None,
+            accent: None,
+            gender: None,
+            style: None,
+            description: None,
+            sample_text: None,
+        }
+    }
+
+    /// Menu label: the display name and its details, e.g. "Alba — Scottish, female"
+    pub fn label(&self) -> String {
+        let details: Vec<String> = [
+            self.accent.clone().or_else(|| self.language.clone()),
+            self.gender.map(|gender| gender.label().to_lowercase()),
+            self.style.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if details.is_empty() {
+            self.display_name.clone()
+        } else {
+            format!("{} — {}", self.display_name, details.join(", "))
+        }
+    }
+
+    /// Preview menu label: the display name and description, if there's one
+    pub fn preview_label(&self) -> String {
+        match &self.description {
+            Some(description) => format!("{} — {}", self.display_name, description),
+            None => self.label(),
+        }
+    }
+
+    /// The group this voice is listed under, None if it's not known
+    fn group(&self, grouping: VoiceGrouping) -> Option<String> {
+        match grouping {
+            VoiceGrouping::None => None,
+            VoiceGrouping::Language => self.language.clone(),
+            VoiceGrouping::Gender => self.gender.map(|gender| gender.label().to_string()),
+            VoiceGrouping::Style => self.style.clone(),
+        }
+    }
+}
+
+/// Voices sorted into named groups, in menu order with voices of unknown
+/// details last
+/// A single group (or `VoiceGrouping::None`) gives None, as grouping wouldn't help
+pub fn group_voices(
+    voices: &[VoiceInfo],
+    grouping: VoiceGrouping,
+) -> Option<Vec<(String, Vec<&VoiceInfo>)>> {
+    let mut groups: BTreeMap<String, Vec<&VoiceInfo>> = BTreeMap::new();
+    let mut other = Vec::new();
+    for voice in voices {
+        match voice.group(grouping) {
+            Some(group) => groups.entry(group).or_default().push(voice),
+            None => other.push(voice),
+        }
+    }
+
+    let mut groups: Vec<(String, Vec<&VoiceInfo>)> = groups.into_iter().collect();
+    if !other.is_empty() {
+        groups.push((OTHER_GROUP.to_string(), other));
+    }
+    (groups.len() > 1).then_some(groups)
+}
+
+/// Details of voices of the model named `model` (and the user's own voices)
+pub fn describe_voices(names: &[String], model: &str) -> Vec<VoiceInfo> {
+    let dirs = voice_dirs(model);
+    let manifest = read_manifests(&dirs);
+    names.iter().map(|name| describe(name, &dirs, &manifest)).collect()
+}
+
+/// Details of one voice of the model named `model`
+pub fn describe_voice(name: &str, model: &str) -> VoiceInfo {
+    let dirs = voice_dirs(model);
+    describe(name, &dirs, &read_manifests(&dirs))
+}
+
+/// Language codes of those voices of the model named `model` whose language is known,
+/// keyed by voice name
+pub fn voice_languages(names: &[String], model: &str) -> HashMap<String, String> {
+    describe_voices(names, model)
+        .into_iter()
+        .filter_map(|voice| Some((voice.name, voice.language_code?)))
+        .collect()
+}
+
+/// Folders voice files are found in: the model's, then the user's own voices
+fn voice_dirs(model: &str) -> Vec<PathBuf> {
+    find_model(model)
+        .map(|model| model.dir)
+        .into_iter()
+        .chain(voices_dir().ok())
+        .collect()
+}
+
+fn describe(name: &str, dirs: &[PathBuf], manifest: &HashMap<String, VoiceDetails>) -> VoiceInfo {
+    let mut info = pocket_voice(name)
+        .or_else(|| piper_voice(name, dirs))
+        .or_else(|| windows_voice(name))
+        .unwrap_or_else(|| VoiceInfo::plain(name));
+
+    if let Some(details) = manifest.get(name) {
+        details.clone().apply(&mut info);
+    }
+    if let Some(details) = dirs
+        .iter()
+        .find_map(|dir| read_details(&dir.join(format!("{}.json", name))))
+    {
+        details.apply(&mut info);
+    }
+    info
+}
+
+/// A voice's entry in `voices.json`, or a `<voice>.json` file next to it, all fields
+/// optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct VoiceDetails {
+    display_name: Option<String>,
+    language: Option<String>,
+    language_code: Option<String>,
+    accent: Option<String>,
+    gender: Option<Gender>,
+    style: Option<String>,
+    description: Option<String>,
+    sample_text: Option<String>,
+}
+
+impl VoiceDetails {
+    /// Override what's known about `info` with the details that are set
+    fn apply(self, info: &mut VoiceInfo) {
+        if let Some(display_name) = self.display_name {
+            info.display_name = display_name;
+        }
+        info.language = self.language.or(info.language.take());
+        info.language_code = self
+            .language_code
+            .map(|code| code.to_lowercase())
+            .or(info.language_code.take());
+        info.accent = self.accent.or(info.accent.take());
+        info.gender = self.gender.or(info.gender);
+        info.style = self.style.or(info.style.take());
+        info.description = self.description.or(info.description.take());
+        info.sample_text = self.sample_text.or(info.sample_text.take());
+    }
+}
+
+fn read_details(path: &Path) -> Option<VoiceDetails> {
+    let json = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json)
+        .map_err(|e| log::warn!("Ignoring voice details in {}: {}", path.display(), e))
+        .ok()
+}
+
+/// The voices listed in the `voices.json` manifests of `dirs` and the models directory;
+/// a voice listed in more than one has the details of the first
+fn read_manifests(dirs: &[PathBuf]) -> HashMap<String, VoiceDetails> {
+    let mut voices = HashMap::new();
+    for dir in dirs.iter().cloned().chain(models_dir().ok()) {
+        let path = dir.join(MANIFEST_FILE);
+        let Ok(json) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        match serde_json::from_str::<HashMap<String, VoiceDetails>>(&json) {
+            Ok(manifest) => {
+                for (name, details) in manifest {
+                    voices.entry(name).or_insert(details);
+                }
+            }
+            Err(e) => log::warn!("Ignoring voice manifest {}: {}", path.display(), e),
+        }
+    }
+    voices
+}
+
+fn pocket_voice(name: &str) -> Option<VoiceInfo> {
+    let &(_, accent, gender) = POCKET_VOICES.iter().find(|(voice, _, _)| *voice == name)?;
+    Some(VoiceInfo {
+        language: Some("English".to_string()),
+        language_code: Some("en".to_string()),
+        accent: accent.map(str::to_string),
+        gender: Some(gender),
+        ..VoiceInfo::plain(name)
+    })
+}
+
+/// The parts of a Piper `<voice>.onnx.json` config describing the voice
+#[derive(Debug, Deserialize)]
+struct PiperDetails {
+    language: Option<PiperLanguage>,
+    dataset: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PiperLanguage {
+    /// ISO 639-1 code, e.g. "en"
+    family: Option<String>,
+    name_english: String,
+    country_english: Option<String>,
+}
+
+/// A Piper voice such as "en_GB-alba-medium": named after its dataset, in the
+/// config's language
+fn piper_voice(name: &str, dirs: &[PathBuf]) -> Option<VoiceInfo> {
+    let details: PiperDetails = dirs.iter().find_map(|dir| {
+        let json = std::fs::read_to_string(dir.join(format!("{}.onnx.json", name))).ok()?;
+        serde_json::from_str(&json).ok()
+    })?;
+    let language_code = details.language.as_ref().and_then(|language| language.family.clone());
+    let language = details
+        .language
+        .map(|language| match language.country_english {
+            Some(country) => format!("{} ({})", language.name_english, country),
+            None => language.name_english,
+        });
+    Some(VoiceInfo {
+        display_name: details
+            .dataset
+            .map(|dataset| title_case(&dataset))
+            .unwrap_or_else(|| title_case(name)),
+        language,
+        language_code,
+        ..VoiceInfo::plain(name)
+    })
+}
+
+/// A Windows voice whose name ends in its language, as in
+/// "Microsoft Zira Desktop - English (United States)"
+fn windows_voice(name: &str) -> Option<VoiceInfo> {
+    let (display_name, language) = name.rsplit_once(" - ")?;
+    Some(VoiceInfo {
+        display_name: display_name.trim().to_string(),
+        language: Some(language.trim().to_string()),
+        ..VoiceInfo::plain(name)
+    })
+}
+
+/// "alba" and "en_gb_vctk" as "Alba" and "En Gb Vctk"; names with capitals are kept
+fn title_case(name: &str) -> String {
+    if name.chars().any(char::is_uppercase) {
+        return name.to_string();
+    }
+    name.split(['_', '-', ' '])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voice(name: &str, language: Option<&str>, gender: Option<Gender>) -> VoiceInfo {
+        VoiceInfo {
+            language: language.map(str::to_string),
+            gender,
+            ..VoiceInfo::plain(name)
+        }
+    }
+
+    /// Group names and the names of their voices
+    fn names<'a>(groups: &[(String, Vec<&'a VoiceInfo>)]) -> Vec<(String, Vec<&'a str>)> {
+        groups
+            .iter()
+            .map(|(group, voices)| (group.clone(), voices.iter().map(|voice| voice.name.as_str()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn groups_by_language_with_unknown_last() {
+        let voices = [
+            voice("hans", Some("German"), Some(Gender::Male)),
+            voice("alba", Some("English"), Some(Gender::Female)),
+            voice("mine", None, None),
+            voice("jean", Some("English"), Some(Gender::Male)),
+        ];
+        let groups = group_voices(&voices, VoiceGrouping::Language).expect("several groups");
+        assert_eq!(
+            names(&groups),
+            vec![
+                ("English".to_string(), vec!["alba", "jean"]),
+                ("German".to_string(), vec!["hans"]),
+                (OTHER_GROUP.to_string(), vec!["mine"]),
+            ]
+        );
+
+        let groups = group_voices(&voices, VoiceGrouping::Gender).expect("several groups");
+        assert_eq!(
+            names(&groups),
+            vec![
+                ("Female".to_string(), vec!["alba"]),
+                ("Male".to_string(), vec!["hans", "jean"]),
+                (OTHER_GROUP.to_string(), vec!["mine"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_single_group_isnt_grouped() {
+        let voices = [
+            voice("alba", Some("English"), None),
+            voice("jean", Some("English"), None),
+        ];
+        assert!(group_voices(&voices, VoiceGrouping::Language).is_none());
+        assert!(group_voices(&voices, VoiceGrouping::Style).is_none());
+        assert!(group_voices(&voices, VoiceGrouping::None).is_none());
+    }
+}
@@ -0,0 +1,173 @@
+//! Mouse wheel over the tray icon, which the notification area doesn't pass on to
+//! icons: while the cursor is over the icon, a low-level mouse hook watches for the
+//! wheel turning and tells the main thread by how many notches
+
+use crate::clipboard::ClipboardEvent;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+/// Screen rectangle of the tray icon in physical pixels (left, top, right, bottom),
+/// set by the main thread while the cursor is over the icon
+#[derive(Debug, Default)]
+pub struct IconArea {
+    area: Mutex<Option<(i32, i32, i32, i32)>>,
+    changed: Condvar,
+}
+
+impl IconArea {
+    /// Where the icon is, None once the cursor has left it
+    pub fn set(&self, area: Option<(i32, i32, i32, i32)>) {
+        if let Ok(mut current) = self.area.lock() {
+            *current = area;
+            self.changed.notify_all();
+        }
+    }
+
+    pub fn get(&self) -> Option<(i32, i32, i32, i32)> {
+        self.area.lock().ok().and_then(|area| *area)
+    }
+
+    /// Wait for the cursor to be over the icon
+    #[cfg(windows)]
+    fn wait(&self) {
+        let Ok(area) = self.area.lock() else {
+            return;
+        };
+        drop(self.changed.wait_while(area, |area| area.is_none()));
+    }
+}
+
+/// Spawn the thread watching the mouse wheel; turns over `icon_area` are sent on
+/// `events_tx`, and kept from whatever is under the icon
+#[cfg(windows)]
+pub fn spawn_wheel_thread(
+    icon_area: Arc<IconArea>,
+    events_tx: Sender<ClipboardEvent>,
+) -> std::io::Result<JoinHandle<()>> {
+    std::thread::Builder::new()
+        .name("wheel".into())
+        .spawn(move || win::listen(icon_area, events_tx))
+}
+
+#[cfg(not(windows))]
+pub fn spawn_wheel_thread(
+    _icon_area: Arc<IconArea>,
+    _events_tx: Sender<ClipboardEvent>,
+) -> std::io::Result<JoinHandle<()>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "the mouse wheel over the tray icon is not supported on this platform",
+    ))
+}
+
+#[cfg(windows)]
+mod win {
+    use super::IconArea;
+    use crate::clipboard::ClipboardEvent;
+    use std::cell::RefCell;
+    use std::sync::mpsc::Sender;
+    use std::sync::Arc;
+    use windows::Win32::Foundation::{LPARAM, LRESULT, POINT, WPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, GetMessageW, KillTimer, SetTimer, SetWindowsHookExW, UnhookWindowsHookEx,
+        HC_ACTION, MSG, MSLLHOOKSTRUCT, WHEEL_DELTA, WH_MOUSE_LL, WM_MOUSEWHEEL,
+    };
+
+    /// How often the cursor is checked for having left the icon while hooked
+    const LEAVE_CHECK_MS: u32 = 250;
+
+    /// What the hook procedure needs, on the thread it runs on
+    struct Hook {
+        icon_area: Arc<IconArea>,
+        events_tx: Sender<ClipboardEvent>,
+        /// Turns smaller than a notch, as from high-resolution wheels, until they add up
+        remainder: i32,
+    }
+
+    impl Hook {
+        fn is_over_icon(&self, point: POINT) -> bool {
+            self.icon_area
+                .get()
+                .is_some_and(|(left, top, right, bottom)| {
+                    (left..right).contains(&point.x) && (top..bottom).contains(&point.y)
+                })
+        }
+
+        /// Add a turn of the wheel, sending the whole notches it makes
+        fn turn(&mut self, delta: i32) {
+            self.remainder += delta;
+            let notches = self.remainder / WHEEL_DELTA as i32;
+            if notches != 0 {
+                self.remainder -= notches * WHEEL_DELTA as i32;
+                let _ = self.events_tx.send(ClipboardEvent::Wheel(notches));
+            }
+        }
+    }
+
+    thread_local! {
+        static HOOK: RefCell<Option<Hook>> = const { RefCell::new(None) };
+    }
+
+    /// Each time the cursor comes over the icon, hook the mouse and wait in
+    /// GetMessageW, which low-level hooks are called from, until it leaves; the
+    /// thread isn't joined, it ends with the process
+    pub(super) fn listen(icon_area: Arc<IconArea>, events_tx: Sender<ClipboardEvent>) {
+        HOOK.with(|hook| {
+            *hook.borrow_mut() = Some(Hook {
+                icon_area: Arc::clone(&icon_area),
+                events_tx,
+                remainder: 0,
+            })
+        });
+        loop {
+            icon_area.wait();
+            unsafe {
+                let hook = match SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_proc), None, 0) {
+                    Ok(hook) => hook,
+                    Err(e) => {
+                        log::warn!("Mouse wheel over the tray icon not available: {}", e);
+                        return;
+                    }
+                };
+                // Wakes GetMessageW to check on the cursor
+                let timer = SetTimer(None, 0, LEAVE_CHECK_MS, None);
+                let mut msg = MSG::default();
+                let mut running = true;
+                while icon_area.get().is_some() {
+                    // 0 is WM_QUIT, -1 an error
+                    if GetMessageW(&mut msg, None, 0, 0).0 <= 0 {
+                        running = false;
+                        break;
+                    }
+                }
+                let _ = KillTimer(None, timer);
+                let _ = UnhookWindowsHookEx(hook);
+                if !running {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Runs for every mouse event in the session, so it does little else than
+    /// pass them on
+    unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code == HC_ACTION as i32 && wparam.0 as u32 == WM_MOUSEWHEEL {
+            let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+            // The high word is the signed distance turned, away from the user
+            let delta = (info.mouseData >> 16) as u16 as i16 as i32;
+            let handled = HOOK.with(|hook| match hook.borrow_mut().as_mut() {
+                Some(hook) if hook.is_over_icon(info.pt) => {
+                    hook.turn(delta);
+                    true
+                }
+                _ => false,
+            });
+            if handled {
+                return LRESULT(1);
+            }
+        }
+        CallNextHookEx(None, code, wparam, lparam)
+    }
+}